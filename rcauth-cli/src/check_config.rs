@@ -0,0 +1,132 @@
+use rcauth_core::bootstrap::Config as BootstrapConfig;
+use rcauth_core::error::{Error, ErrorCode};
+
+use crate::config::{self, ConfigFile};
+
+/// Outcome of loading and validating a single configuration section.
+pub struct SectionReport {
+    pub section: &'static str,
+    pub result: Result<(), String>,
+}
+
+/// Loads and validates one configuration section, capturing the first error as text.
+fn check_section<T>(
+    section: &'static str,
+    loaded: Result<T, figment::Error>,
+    validate: impl FnOnce(&T) -> Result<(), Box<dyn std::error::Error>>,
+) -> SectionReport {
+    let result = loaded
+        .map_err(|e| e.to_string())
+        .and_then(|config| validate(&config).map_err(|e| e.to_string()));
+
+    SectionReport { section, result }
+}
+
+/// Loads the store, server, logger, JWT, and password hashing configurations from `file`, and the bootstrap admin from the environment, and validates each one.
+///
+/// Environment variable overrides are applied exactly as for the other subcommands. No database connection is made.
+pub fn check(file: &ConfigFile) -> Vec<SectionReport> {
+    vec![
+        check_section("store", config::load_store_config(file), |c| c.validate()),
+        check_section("server", config::load_server_config(file), |c| c.validate()),
+        check_section("logger", config::load_logger_config(file), |c| c.validate()),
+        check_section("jwt", config::load_jwt_config(file), |c| c.validate()),
+        check_section("password", config::load_password_config(file), |c| {
+            c.validate()
+        }),
+        check_section("bootstrap", BootstrapConfig::new(), |c| c.validate()),
+    ]
+}
+
+/// Checks the configuration file, with its selected profile, and prints a report for each section.
+///
+/// # Errors
+///
+/// Returns a `ConfigurationError` if any section fails to load or validate.
+///
+/// # Examples
+///
+/// ```sh
+/// cargo run check-config
+/// ```
+pub fn run(file: &ConfigFile) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Checking configuration from {}", file);
+
+    let reports = check(file);
+    for report in &reports {
+        match &report.result {
+            Ok(()) => println!("✅ {}: valid", report.section),
+            Err(e) => println!("❌ {}: {}", report.section, e),
+        }
+    }
+
+    let failures = reports.iter().filter(|r| r.result.is_err()).count();
+    if failures > 0 {
+        return Err(Box::new(Error::new_simple(
+            ErrorCode::ConfigurationError,
+            format!("{} configuration section(s) are invalid", failures),
+        )));
+    }
+
+    println!("Configuration is valid");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn config_file(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn valid_config_passes() {
+        let file = config_file(
+            r#"
+            api_server_port = 8000
+            management_server_port = 8001
+            host = "localhost"
+            user = "rcauth"
+            password = "secret"
+            database = "rcauth"
+            log_level = "info"
+            log_format = "json"
+            jwt_secret = "0123456789abcdef0123456789abcdef"
+            "#,
+        );
+        let path = ConfigFile::new(file.path().to_str().unwrap());
+
+        assert!(check(&path).iter().all(|r| r.result.is_ok()));
+        assert!(run(&path).is_ok());
+    }
+
+    #[test]
+    fn invalid_config_reports_each_failing_section() {
+        let file = config_file(
+            r#"
+            api_server_port = 8000
+            management_server_port = 8000
+            host = "localhost"
+            user = "rcauth"
+            password = "secret"
+            database = "rcauth"
+            log_format = "xml"
+            jwt_secret = "0123456789abcdef0123456789abcdef"
+            "#,
+        );
+        let path = ConfigFile::new(file.path().to_str().unwrap());
+
+        let reports = check(&path);
+        let failed: Vec<_> = reports
+            .iter()
+            .filter(|r| r.result.is_err())
+            .map(|r| r.section)
+            .collect();
+        assert_eq!(failed, vec!["server", "logger"]);
+        assert!(run(&path).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+use rcauth_core::error::Result;
+use rcauth_store::{cleanup, config::Config};
+use tracing::info;
+
+pub async fn run(config: Config, refresh_token_ttl: Duration) -> Result<()> {
+    info!("Purging expired tokens");
+
+    let store = rcauth_store::store::new(config).await?;
+
+    // Close the connections whether or not the purge succeeds
+    let purged = store.purge_expired(refresh_token_ttl).await;
+    store.close().await;
+    cleanup::log_purge(&purged?);
+
+    info!("✅ Expired tokens purged successfully");
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use std::io::Write;
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use crate::Cli;
+
+/// Writes the completion script for `shell` to `out`.
+fn generate(shell: Shell, out: &mut dyn Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+/// Prints the completion script for `shell` to stdout.
+///
+/// # Examples
+///
+/// ```sh
+/// cargo run completions bash > /etc/bash_completion.d/rcauth-cli
+/// ```
+pub fn run(shell: Shell) {
+    generate(shell, &mut std::io::stdout());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bash_completions_list_subcommands() {
+        let mut out = Vec::new();
+        generate(Shell::Bash, &mut out);
+        let script = String::from_utf8(out).unwrap();
+
+        assert!(!script.is_empty());
+        assert!(script.contains("serve"));
+        assert!(script.contains("migrate"));
+    }
+}
@@ -0,0 +1,122 @@
+use rcauth_core::bootstrap::ADMIN_ROLE;
+use rcauth_core::email::EmailNormalizer;
+use rcauth_core::password::Hasher;
+use rcauth_core::user::{self, USER_ROLE, UserProfile};
+use rcauth_store::config::Config;
+use rcauth_store::store::PgStore;
+use tracing::info;
+
+use crate::config::{self, ConfigFile};
+
+/// The account `create-user` makes.
+pub struct NewAccount<'a> {
+    /// Slug of the tenant the user joins, which is created too if it does not exist
+    pub tenant: &'a str,
+    pub email: &'a str,
+    pub password: &'a str,
+    /// Whether the user gets the admin role rather than the user role
+    pub admin: bool,
+}
+
+/// Creates a verified password user, e.g. the first admin of a fresh install.
+///
+/// The email is normalized as configured for the server, and the password must meet the configured policy.
+///
+/// # Errors
+///
+/// Returns an error if the configuration is invalid, the database is unreachable, the email or password is rejected, or a user with the email already exists in the tenant.
+pub async fn run(
+    config_file: &ConfigFile,
+    store_config: Config,
+    account: NewAccount<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let emails = config::load_server_config(config_file)?.email_normalizer();
+    let password_config = config::load_password_config(config_file)?;
+    password_config.validate()?;
+    let hasher = Hasher::new(&password_config)?;
+
+    let store = rcauth_store::store::new(store_config).await?;
+    // Close the connections whether or not the user is created
+    let created = create(&store, &hasher, &emails, &account).await;
+    store.close().await;
+    let user = created?;
+
+    info!(
+        id = user.id,
+        email = user.email,
+        tenant = account.tenant,
+        admin = account.admin,
+        "👤 Created user"
+    );
+    Ok(())
+}
+
+async fn create(
+    store: &PgStore,
+    hasher: &Hasher,
+    emails: &EmailNormalizer,
+    account: &NewAccount<'_>,
+) -> rcauth_core::error::Result<UserProfile> {
+    let tenant_id = store.ensure_tenant(account.tenant).await?;
+    let role = if account.admin { ADMIN_ROLE } else { USER_ROLE };
+    user::create_user(
+        store,
+        hasher,
+        emails,
+        &tenant_id,
+        account.email,
+        account.password,
+        role,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcauth_core::error::ErrorCode;
+    use rcauth_core::password::{self, Verification};
+    use rcauth_core::user::UserRepository;
+
+    /// Requires a reachable, migrated Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn created_user_can_log_in() {
+        let store = rcauth_store::store::new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        let hasher = Hasher::new(&password::Config {
+            password_hash_memory_kib: 8,
+            password_hash_iterations: 1,
+            password_hash_parallelism: 1,
+            ..password::Config::default()
+        })
+        .unwrap();
+        let tenant = format!("create-user-{}", rand::random::<u32>());
+        let account = NewAccount {
+            tenant: &tenant,
+            email: "Admin@Example.com",
+            password: "correct horse battery staple",
+            admin: true,
+        };
+
+        let admin = create(&store, &hasher, &EmailNormalizer::default(), &account)
+            .await
+            .unwrap();
+        assert_eq!(admin.email, "admin@example.com");
+        let hash = store.password_hash(&admin.id).await.unwrap();
+        assert_eq!(
+            hasher
+                .verify_login(account.password, hash.as_deref())
+                .unwrap(),
+            Verification::Valid
+        );
+
+        let err = create(&store, &hasher, &EmailNormalizer::default(), &account)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+
+        store.close().await;
+    }
+}
@@ -0,0 +1,182 @@
+use std::{fmt, path::Path};
+
+use rcauth_core::{
+    error::{Error, ErrorCode},
+    jwt::Config as JwtConfig,
+    logger::Config as LoggerConfig,
+    password::Config as PasswordConfig,
+};
+use rcauth_server::Config as ServerConfig;
+use rcauth_store::config::Config as StoreConfig;
+use serde::{
+    Deserialize, Serialize,
+    de::{self, Visitor},
+};
+
+use crate::gen_secret;
+
+/// Deserializer that records the field names of the struct it is asked to build and then bails out.
+///
+/// This lets the generated file list every key a config struct accepts, including optional keys whose default is unset and therefore absent from the serialized defaults.
+struct FieldNames(&'static [&'static str]);
+
+#[derive(Debug)]
+struct Stop;
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("field introspection")
+    }
+}
+
+impl std::error::Error for Stop {}
+
+impl de::Error for Stop {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        Stop
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut FieldNames {
+    type Error = Stop;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Stop> {
+        Err(Stop)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Stop> {
+        self.0 = fields;
+        Err(Stop)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Returns the field names accepted by the config struct `T`.
+fn field_names<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let mut names = FieldNames(&[]);
+    let _ = T::deserialize(&mut names);
+    names.0
+}
+
+/// Renders one commented section listing every key of `T` with its default value.
+///
+/// Keys without a default value (optional settings that are unset) are written commented out.
+fn render_section<'de, T>(title: &str, defaults: &T) -> Result<String, Box<dyn std::error::Error>>
+where
+    T: Serialize + Deserialize<'de>,
+{
+    let values = toml::Table::try_from(defaults)?;
+
+    let mut section = format!("# {}\n", title);
+    for key in field_names::<T>() {
+        match values.get(*key) {
+            Some(value) => section.push_str(&format!("{} = {}\n", key, value)),
+            None => section.push_str(&format!("# {} = (optional, unset by default)\n", key)),
+        }
+    }
+
+    Ok(section)
+}
+
+/// Renders the contents of a `rcauth.toml` holding the default store, server, logger, JWT, and password hashing settings, with a freshly generated JWT secret.
+///
+/// # Examples
+///
+/// ```ignore
+/// let contents = render().unwrap();
+/// assert!(contents.contains("api_server_port = 8000"));
+/// ```
+pub fn render() -> Result<String, Box<dyn std::error::Error>> {
+    let sections = [
+        render_section("Database Configuration", &StoreConfig::default())?,
+        render_section("Server Configuration", &ServerConfig::default())?,
+        render_section("Logger Configuration", &LoggerConfig::default())?,
+        // Every generated file gets its own signing secret rather than a shared default
+        render_section(
+            "JWT Configuration",
+            &JwtConfig {
+                jwt_secret: gen_secret::hs256_secret(64),
+                ..JwtConfig::default()
+            },
+        )?,
+        render_section("Password Hashing Configuration", &PasswordConfig::default())?,
+    ];
+
+    Ok(format!(
+        "# RedCardinal Auth Server Configuration\n#\n# Generated with default values. Every key can also be set through the environment\n# (RCAUTH_POSTGRES_*, RCAUTH_SERVER_*, RCAUTH_LOGGER_*, RCAUTH_JWT_*, and RCAUTH_PASSWORD_* respectively).\n\n{}",
+        sections.join("\n")
+    ))
+}
+
+/// Writes a default configuration file to `path`.
+///
+/// # Errors
+///
+/// Returns a `ConfigurationError` if `path` already exists and `force` is not set, or an error if the file cannot be written.
+///
+/// # Examples
+///
+/// ```sh
+/// cargo run gen-config --output rcauth.toml --force
+/// ```
+pub fn run(path: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if Path::new(path).exists() && !force {
+        return Err(Box::new(Error::new_simple(
+            ErrorCode::ConfigurationError,
+            format!("{} already exists; pass --force to overwrite it", path),
+        )));
+    }
+
+    std::fs::write(path, render()?)?;
+    println!("✅ Wrote default configuration to {}", path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_config;
+    use crate::config::ConfigFile;
+
+    #[test]
+    fn generated_config_parses_into_valid_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rcauth.toml");
+        let path = path.to_str().unwrap();
+
+        run(path, false).unwrap();
+
+        let reports = check_config::check(&ConfigFile::new(path));
+        assert!(reports.iter().all(|r| r.result.is_ok()));
+
+        let contents = std::fs::read_to_string(path).unwrap();
+        for key in field_names::<LoggerConfig>() {
+            assert!(contents.contains(key), "missing key {}", key);
+        }
+        assert!(contents.contains("# log_file ="));
+    }
+
+    #[test]
+    fn refuses_to_overwrite_without_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rcauth.toml");
+        std::fs::write(&path, "# existing").unwrap();
+        let path = path.to_str().unwrap();
+
+        assert!(run(path, false).is_err());
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "# existing");
+
+        run(path, true).unwrap();
+        assert!(std::fs::read_to_string(path).unwrap().contains("log_level"));
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use rcauth_core::error::{Error, ErrorCode};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::config::{self, ConfigFile};
+
+/// Maps a wildcard listen address to the loopback address a local probe should connect to.
+fn probe_host(host: &str) -> &str {
+    match host {
+        "0.0.0.0" | "" => "127.0.0.1",
+        "::" | "[::]" => "[::1]",
+        other => other,
+    }
+}
+
+/// Sends an HTTP GET for `path` to `addr` and returns the response status code.
+async fn get_status(addr: &str, path: &str) -> Result<u16, Box<dyn std::error::Error>> {
+    let mut stream = TcpStream::connect(addr).await?;
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, addr
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+
+    let response = String::from_utf8_lossy(&response);
+    let status = response
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or("Malformed HTTP response from server")?;
+
+    Ok(status)
+}
+
+/// Probes `path` on `addr`, failing unless the server answers with a 2xx status within `timeout`.
+///
+/// # Errors
+///
+/// Returns an `Unavailable` error if the request times out, the connection fails, or the status is not successful.
+pub async fn check(addr: &str, path: &str, timeout: Duration) -> Result<u16, Error> {
+    let status = tokio::time::timeout(timeout, get_status(addr, path))
+        .await
+        .map_err(|_| {
+            Error::new_simple(
+                ErrorCode::Unavailable,
+                format!("Health check to {}{} timed out", addr, path),
+            )
+        })?
+        .map_err(|e| {
+            Error::new_simple(
+                ErrorCode::Unavailable,
+                format!("Health check to {}{} failed: {}", addr, path, e),
+            )
+        })?;
+
+    if !(200..300).contains(&status) {
+        return Err(Error::new_simple(
+            ErrorCode::Unavailable,
+            format!("Health check to {}{} returned {}", addr, path, status),
+        ));
+    }
+
+    Ok(status)
+}
+
+/// Probes the configured API server and prints the result, for use in container `HEALTHCHECK` directives.
+///
+/// The host, port, and base path come from the server configuration in `config_file`; wildcard listen addresses are probed over loopback. `path` overrides the default `<api_base_path>/health` endpoint.
+///
+/// # Errors
+///
+/// Returns an error if the configuration cannot be loaded or the server is unhealthy, so the process exits non-zero.
+///
+/// # Examples
+///
+/// ```sh
+/// cargo run healthcheck --timeout 3
+/// ```
+pub async fn run(
+    config_file: &ConfigFile,
+    path: Option<&str>,
+    timeout: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let server_config = config::load_server_config(config_file)?;
+    let addr = format!(
+        "{}:{}",
+        probe_host(&server_config.api_server_host),
+        server_config.api_server_port
+    );
+
+    let path = path
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}/health", server_config.api_base_path));
+
+    match check(&addr, &path, timeout).await {
+        Ok(status) => {
+            println!("✅ {}{} is healthy ({})", addr, path, status);
+            Ok(())
+        }
+        Err(e) => {
+            println!("❌ {}", e);
+            Err(Box::new(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Starts a server that answers every request with `status_line` and returns its address.
+    async fn stub_server(status_line: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0; 1024];
+                let _ = socket.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nOK",
+                    status_line
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn healthy_server_passes() {
+        let addr = stub_server("200 OK").await;
+        let status = check(&addr, "/api/v1/health", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(status, 200);
+    }
+
+    #[tokio::test]
+    async fn unavailable_server_fails() {
+        let addr = stub_server("503 Service Unavailable").await;
+        let err = check(&addr, "/api/v1/health", Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unavailable);
+        assert!(err.to_string().contains("503"));
+    }
+
+    #[test]
+    fn wildcard_hosts_probe_loopback() {
+        assert_eq!(probe_host("0.0.0.0"), "127.0.0.1");
+        assert_eq!(probe_host("10.0.0.5"), "10.0.0.5");
+    }
+}
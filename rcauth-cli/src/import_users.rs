@@ -0,0 +1,155 @@
+use rcauth_core::email::EmailNormalizer;
+use rcauth_core::user::{NewUser, USER_ROLE, UserRepository};
+use rcauth_store::config::Config;
+use serde::Deserialize;
+use std::io::Read;
+use std::path::Path;
+use tracing::info;
+
+use crate::config::{self, ConfigFile};
+
+/// Users handed to each `bulk_create` call, so a large file is never held in memory at once.
+const BATCH_SIZE: usize = 5000;
+
+/// One line of the imported CSV, after its header.
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+struct Row {
+    email: String,
+    /// Password hash from the old system, or empty for users who only sign in through a provider
+    password_hash: Option<String>,
+    /// Role of the user, or empty for the user role
+    role: Option<String>,
+    /// Whether the old system had verified the email, or empty for unverified
+    email_verified: Option<bool>,
+}
+
+/// Imports users from a CSV file with the header `email,password_hash,role,email_verified`, skipping those whose email is already taken in the tenant.
+///
+/// Emails are normalized as configured for the server. Users are created in batches, each in its own transaction, so a failure part way through leaves the earlier batches in place; re-running the import skips them.
+///
+/// # Errors
+///
+/// Returns an error if the configuration is invalid, the file cannot be read or has a malformed line, an email is rejected, or the database is unreachable.
+pub async fn run(
+    config_file: &ConfigFile,
+    store_config: Config,
+    file: &Path,
+    tenant: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let emails = config::load_server_config(config_file)?.email_normalizer();
+    let reader =
+        std::fs::File::open(file).map_err(|e| format!("Failed to open {}: {e}", file.display()))?;
+
+    let store = rcauth_store::store::new(store_config).await?;
+    // Close the connections whether or not the import succeeds
+    let imported = async {
+        let tenant_id = store.ensure_tenant(tenant).await?;
+        import(&store, &emails, &tenant_id, reader).await
+    }
+    .await;
+    store.close().await;
+    let (inserted, skipped) = imported?;
+
+    info!(inserted, skipped, tenant, file = %file.display(), "👥 Imported users");
+    Ok(())
+}
+
+/// Streams the CSV in `reader` into `users` in batches, returning how many users were inserted and skipped.
+async fn import(
+    users: &dyn UserRepository,
+    emails: &EmailNormalizer,
+    tenant_id: &str,
+    reader: impl Read,
+) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let (mut inserted, mut skipped) = (0, 0);
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut rows = reader.deserialize::<Row>().peekable();
+    while let Some(row) = rows.next() {
+        batch.push(new_user(row?, emails, tenant_id)?);
+        if batch.len() == BATCH_SIZE || rows.peek().is_none() {
+            let report = users.bulk_create(&batch).await?;
+            inserted += report.inserted;
+            skipped += report.skipped;
+            batch.clear();
+        }
+    }
+    Ok((inserted, skipped))
+}
+
+fn new_user(
+    row: Row,
+    emails: &EmailNormalizer,
+    tenant_id: &str,
+) -> Result<NewUser, Box<dyn std::error::Error>> {
+    let email = emails
+        .normalize(&row.email)
+        .map_err(|e| format!("Invalid email {:?}: {e}", row.email))?;
+    Ok(NewUser {
+        tenant_id: tenant_id.to_string(),
+        email,
+        password_hash: row.password_hash,
+        role: row.role.unwrap_or_else(|| USER_ROLE.to_string()),
+        email_verified: row.email_verified.unwrap_or(false),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcauth_core::testing::{InMemoryUsers, StoredUser};
+
+    const CSV: &str = "\
+email,password_hash,role,email_verified
+Ada@Example.com,$argon2id$v=19$hash-a,admin,true
+grace@example.com,,,
+ADA@example.com,$argon2id$v=19$hash-b,,false
+taken@example.com,$argon2id$v=19$hash-c,,true
+";
+
+    #[tokio::test]
+    async fn import_parses_rows_and_counts_skipped_emails() {
+        let users = InMemoryUsers::default();
+        users.insert(StoredUser::new("user-0", "taken@example.com"));
+
+        let (inserted, skipped) = import(
+            &users,
+            &EmailNormalizer::default(),
+            "tenant-1",
+            CSV.as_bytes(),
+        )
+        .await
+        .unwrap();
+        assert_eq!((inserted, skipped), (2, 2));
+
+        let ada = users.get("user-2").unwrap();
+        assert_eq!(ada.profile.email, "ada@example.com");
+        assert_eq!(ada.role, "admin");
+        assert!(ada.profile.email_verified);
+        assert_eq!(ada.password_hash.as_deref(), Some("$argon2id$v=19$hash-a"));
+
+        let grace = users.get("user-3").unwrap();
+        assert_eq!(grace.role, USER_ROLE);
+        assert!(!grace.profile.email_verified);
+        assert_eq!(grace.password_hash, None);
+    }
+
+    #[tokio::test]
+    async fn import_rejects_malformed_lines() {
+        let users = InMemoryUsers::default();
+        for csv in [
+            "email,password_hash,role,email_verified\nnot-an-email,,,\n",
+            "email,password_hash,role,email_verified\na@example.com,,,maybe\n",
+        ] {
+            let result = import(
+                &users,
+                &EmailNormalizer::default(),
+                "tenant-1",
+                csv.as_bytes(),
+            )
+            .await;
+            assert!(result.is_err(), "{csv}");
+        }
+        assert!(users.is_empty());
+    }
+}
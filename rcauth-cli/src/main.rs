@@ -1,17 +1,37 @@
+mod check_config;
+mod cleanup_expired;
+mod completions;
 mod config;
+mod create_user;
+mod gen_config;
+mod gen_secret;
+mod healthcheck;
+mod import_users;
 mod migrate;
 mod serve;
 
 use clap::{Parser, Subcommand};
-use dotenvy;
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::info;
 
-use crate::config::{load_logger_config, load_store_config};
+use crate::config::{
+    ConfigFile, config_file_path, load_jwt_config, load_logger_config, load_store_config,
+    log_effective_config,
+};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
+    /// Path to the configuration file [default: rcauth.toml]
+    #[arg(long, global = true, env = "RCAUTH_CONFIG_FILE")]
+    config: Option<String>,
+
+    /// Profile whose `[<env>.<section>]` tables override the base settings, e.g. `prod`
+    #[arg(long, global = true, env = "RCAUTH_ENV")]
+    env: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -21,13 +41,108 @@ enum Commands {
     /// Run database migrations
     Migrate,
 
+    /// Delete expired refresh tokens and idempotency keys, and clear stale confirmation and recovery tokens
+    CleanupExpired,
+
+    /// Create a verified password user, e.g. the first admin of a fresh install
+    CreateUser {
+        /// Email of the user
+        #[arg(long)]
+        email: String,
+
+        /// Password of the user; set RCAUTH_CREATE_USER_PASSWORD instead to keep it out of the process list
+        #[arg(long, env = "RCAUTH_CREATE_USER_PASSWORD", hide_env_values = true)]
+        password: String,
+
+        /// Give the user the admin role
+        #[arg(long)]
+        admin: bool,
+
+        /// Slug of the tenant the user joins, which is created if it does not exist
+        #[arg(long, default_value = "default")]
+        tenant: String,
+    },
+
+    /// Import users from a CSV file with the header email,password_hash,role,email_verified, skipping emails already taken
+    ImportUsers {
+        /// Path of the CSV file
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Slug of the tenant the users join, which is created if it does not exist
+        #[arg(long, default_value = "default")]
+        tenant: String,
+    },
+
     /// Start the authentication & management server
-    Serve,
+    Serve {
+        /// Run only the public API server
+        #[arg(long, conflicts_with = "management_only")]
+        api_only: bool,
+
+        /// Run only the management server
+        #[arg(long)]
+        management_only: bool,
+
+        /// Stop every server as soon as one of them fails (default)
+        #[arg(long, conflicts_with = "keep_alive")]
+        fail_fast: bool,
+
+        /// Keep the surviving server running when the other one fails
+        #[arg(long)]
+        keep_alive: bool,
+    },
+
+    /// Validate the configuration without connecting to the database
+    CheckConfig,
+
+    /// Write a configuration file with every key set to its default value
+    GenConfig {
+        /// Path to write the configuration to (defaults to the configured config file path)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Generate a JWT signing secret (HS256) or keypair (RS256)
+    GenSecret {
+        /// Generate an RS256 PEM keypair instead of an HS256 secret
+        #[arg(long)]
+        rsa: bool,
+
+        /// Length in bytes of the HS256 secret
+        #[arg(long, default_value_t = gen_secret::DEFAULT_SECRET_LENGTH)]
+        length: usize,
+
+        /// Modulus size in bits of the RS256 keypair
+        #[arg(long, default_value_t = gen_secret::DEFAULT_RSA_BITS)]
+        bits: usize,
+    },
+
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+
+    /// Probe the configured API server and exit non-zero if it is unhealthy
+    Healthcheck {
+        /// Endpoint path to probe [default: <api_base_path>/health]
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Seconds to wait for a response
+        #[arg(long, default_value_t = 5)]
+        timeout: u64,
+    },
 }
 
 /// Entry point for the command-line application.
 ///
-/// Loads environment variables, initializes logging, parses command-line arguments, loads database configuration, and executes the selected subcommand (`Migrate` or `Serve`). Propagates any errors encountered during initialization or command execution.
+/// Loads environment variables, initializes logging, parses command-line arguments, loads database configuration, and executes the selected subcommand (`Migrate`, `CleanupExpired`, `CreateUser`, `ImportUsers`, `Serve`, `CheckConfig`, `Completions`, `GenConfig`, `GenSecret`, or `Healthcheck`). Propagates any errors encountered during initialization or command execution.
 ///
 /// # Errors
 ///
@@ -41,29 +156,133 @@ enum Commands {
 /// cargo run migrate
 /// ```
 ///
+/// Purging expired tokens once, e.g. from a cron job when the server's cleanup is disabled:
+///
+/// ```sh
+/// cargo run cleanup-expired
+/// ```
+///
+/// Creating the first admin of a fresh install:
+///
+/// ```sh
+/// RCAUTH_CREATE_USER_PASSWORD=... cargo run create-user --email admin@example.com --admin
+/// ```
+///
+/// Importing users from another system, with their existing password hashes:
+///
+/// ```sh
+/// cargo run import-users --file users.csv
+/// ```
+///
 /// Running the application with the `serve` subcommand:
 ///
 /// ```sh
 /// cargo run serve
 /// ```
+///
+/// Running only the management server:
+///
+/// ```sh
+/// cargo run serve --management-only
+/// ```
+///
+/// Reading the configuration from a mounted file:
+///
+/// ```sh
+/// cargo run -- --config /etc/rcauth/rcauth.toml serve
+/// ```
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables from .env file if present
     dotenvy::dotenv().ok();
 
-    // Initialize logging
-    load_logger_config()?.init();
-
     // Parse command line arguments
     let cli = Cli::parse();
+    let config_path = config_file_path(cli.config.clone());
+    let config_file = ConfigFile::new(config_path.clone()).with_profile(cli.env.clone());
+
+    // Configuration commands run before logging is set up, so they work with a missing or invalid config
+    match &cli.command {
+        Commands::CheckConfig => return check_config::run(&config_file),
+        Commands::GenConfig { output, force } => {
+            return gen_config::run(output.as_deref().unwrap_or(&config_path), *force);
+        }
+        Commands::Completions { shell } => {
+            completions::run(*shell);
+            return Ok(());
+        }
+        Commands::GenSecret { rsa, length, bits } => {
+            return gen_secret::run(*rsa, *length, *bits);
+        }
+        Commands::Healthcheck { path, timeout } => {
+            return healthcheck::run(&config_file, path.as_deref(), Duration::from_secs(*timeout))
+                .await;
+        }
+        _ => {}
+    }
+
+    // Initialize logging
+    let logger_config = load_logger_config(&config_file)?;
+    logger_config.validate()?;
+    let log_guard = logger_config.init()?;
+    log_effective_config(&config_file)?;
 
     // Load database configuration
-    let store_config = load_store_config()?;
+    let store_config = load_store_config(&config_file)?;
     info!("🛢️ Database configuration loaded successfully");
 
     match &cli.command {
         Commands::Migrate => migrate::run(store_config).await?,
-        Commands::Serve => serve::run().await?,
+        Commands::CleanupExpired => {
+            let jwt_config = load_jwt_config(&config_file)?;
+            cleanup_expired::run(
+                store_config,
+                Duration::from_secs(jwt_config.refresh_token_ttl_secs),
+            )
+            .await?
+        }
+        Commands::CreateUser {
+            email,
+            password,
+            admin,
+            tenant,
+        } => {
+            create_user::run(
+                &config_file,
+                store_config,
+                create_user::NewAccount {
+                    tenant,
+                    email,
+                    password,
+                    admin: *admin,
+                },
+            )
+            .await?
+        }
+        Commands::ImportUsers { file, tenant } => {
+            import_users::run(&config_file, store_config, file, tenant).await?
+        }
+        Commands::Serve {
+            api_only,
+            management_only,
+            keep_alive,
+            ..
+        } => {
+            serve::run(
+                &config_file,
+                serve::Servers::from_flags(*api_only, *management_only),
+                serve::FailurePolicy::from_flags(*keep_alive),
+                log_guard.filter(),
+            )
+            .await?
+        }
+        Commands::CheckConfig
+        | Commands::Completions { .. }
+        | Commands::GenConfig { .. }
+        | Commands::GenSecret { .. }
+        | Commands::Healthcheck { .. } => {
+            unreachable!("handled before logging is initialized")
+        }
     }
 
     Ok(())
@@ -8,8 +8,10 @@ pub async fn run(config: Config) -> Result<()> {
     // Connect to the database
     let store = rcauth_store::store::new(config).await?;
 
-    // Run migrations
-    store.run_migrations().await?;
+    // Run migrations, closing the connections whether or not they succeed
+    let migrated = store.run_migrations().await;
+    store.close().await;
+    migrated?;
 
     info!("✅ Database migration completed successfully");
     Ok(())
@@ -1,89 +1,549 @@
+use rcauth_core::bootstrap::{self, Config as BootstrapConfig};
+use rcauth_core::email::EmailNormalizer;
 use rcauth_core::error::{Error, ErrorCode};
-use tokio::task::JoinSet;
-use tracing::{error, info};
+use rcauth_core::jwt::Jwt;
+use rcauth_core::logger::LogFilter;
+use rcauth_core::password::Hasher;
+use rcauth_server::{
+    AppState, Audit, Config as ServerConfig, Draining, EffectiveConfig, HealthChecks, Idempotency,
+    Migrations, Passwords, RateLimit, ServerMetrics, Settings, TokenBinding, TokenCookies, Users,
+};
+use rcauth_store::store::PgStore;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::{Id, JoinSet};
+use tracing::{error, info, warn};
 
-use crate::config;
+use crate::config::{self, ConfigFile};
+
+/// Selects which of the two servers `serve` runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Servers {
+    Both,
+    ApiOnly,
+    ManagementOnly,
+}
+
+impl Servers {
+    /// Maps the `--api-only` and `--management-only` flags to a selection, running both when neither is set.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// assert_eq!(Servers::from_flags(false, false), Servers::Both);
+    /// assert_eq!(Servers::from_flags(true, false), Servers::ApiOnly);
+    /// ```
+    pub fn from_flags(api_only: bool, management_only: bool) -> Self {
+        match (api_only, management_only) {
+            (true, false) => Servers::ApiOnly,
+            (false, true) => Servers::ManagementOnly,
+            _ => Servers::Both,
+        }
+    }
+
+    fn runs_api(self) -> bool {
+        self != Servers::ManagementOnly
+    }
+
+    fn runs_management(self) -> bool {
+        self != Servers::ApiOnly
+    }
+}
+
+/// Decides what happens to the remaining servers when one of them exits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Stop every server as soon as one exits.
+    FailFast,
+    /// Keep the surviving servers running after one exits.
+    KeepAlive,
+}
+
+impl FailurePolicy {
+    /// Maps the `--keep-alive` flag to a policy, failing fast by default.
+    pub fn from_flags(keep_alive: bool) -> Self {
+        if keep_alive {
+            FailurePolicy::KeepAlive
+        } else {
+            FailurePolicy::FailFast
+        }
+    }
+}
+
+/// Server tasks labeled by server name, so exits and panics can be attributed to the right server.
+struct ServerTasks {
+    tasks: JoinSet<Result<(), Error>>,
+    labels: Vec<(Id, &'static str)>,
+}
+
+impl ServerTasks {
+    fn new() -> Self {
+        ServerTasks {
+            tasks: JoinSet::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    /// Spawns a server future under `label`, converting its error into a labeled `ServerError`.
+    fn spawn<F>(&mut self, label: &'static str, server: F)
+    where
+        F: Future<Output = Result<(), Box<dyn std::error::Error>>> + Send + 'static,
+    {
+        let handle = self.tasks.spawn(async move {
+            server.await.map_err(|e| {
+                Error::new_simple(
+                    ErrorCode::ServerError,
+                    format!("{} server failed: {}", label, e),
+                )
+            })
+        });
+        self.labels.push((handle.id(), label));
+    }
+
+    /// Returns the labels of the spawned servers in spawn order.
+    fn labels(&self) -> Vec<&'static str> {
+        self.labels.iter().map(|(_, label)| *label).collect()
+    }
+
+    fn label(&self, id: Id) -> &'static str {
+        self.labels
+            .iter()
+            .find(|(task_id, _)| *task_id == id)
+            .map(|(_, label)| *label)
+            .unwrap_or("unknown")
+    }
+
+    /// Waits for the servers to exit, applying `policy` when one of them stops.
+    ///
+    /// Under [`FailurePolicy::FailFast`] the first exit stops the remaining servers and is returned. Under [`FailurePolicy::KeepAlive`] the surviving servers keep running, and the first failure is returned once every server has exited.
+    async fn supervise(mut self, policy: FailurePolicy) -> Result<(), Error> {
+        let mut first_error = None;
+
+        while let Some(result) = self.tasks.join_next_with_id().await {
+            let (label, outcome) = match result {
+                Ok((id, outcome)) => (self.label(id), outcome),
+                Err(e) => (
+                    self.label(e.id()),
+                    Err(Error::new_simple(
+                        ErrorCode::ServerError,
+                        format!("{} server task panicked: {}", self.label(e.id()), e),
+                    )),
+                ),
+            };
+
+            let err = match outcome {
+                Ok(()) => {
+                    warn!(server = label, "{} server exited", label);
+                    Error::new_simple(ErrorCode::ServerError, format!("{} server exited", label))
+                }
+                Err(e) => {
+                    error!(server = label, error = %e, "{} server stopped", label);
+                    e
+                }
+            };
+
+            if policy == FailurePolicy::FailFast {
+                self.tasks.abort_all();
+                return Err(err);
+            }
+
+            if !self.tasks.is_empty() {
+                warn!(
+                    remaining = self.tasks.len(),
+                    "Keeping remaining servers running after {} server stopped", label
+                );
+            }
+            first_error.get_or_insert(err);
+        }
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Spawns the selected servers onto `tasks`, each sharing `state`.
+fn spawn_servers(
+    tasks: &mut ServerTasks,
+    server_config: ServerConfig,
+    servers: Servers,
+    state: AppState,
+) {
+    // Start API server
+    if servers.runs_api() {
+        let api_config = server_config.clone();
+        let api_state = state.clone();
+        tasks.spawn("API", async move {
+            rcauth_server::run_api_server(&api_config, api_state).await
+        });
+    }
+
+    // Start management server
+    if servers.runs_management() {
+        let mgmt_config = server_config;
+        tasks.spawn("management", async move {
+            rcauth_server::run_management_server(&mgmt_config, state).await
+        });
+    }
+}
+
+/// Creates the admin configured through `RCAUTH_BOOTSTRAP_*` when the database has no users yet, logging whether it did.
+///
+/// # Errors
+///
+/// Returns an error if the bootstrap configuration is invalid, or the admin cannot be created, e.g. because the database is unreachable or not migrated.
+async fn bootstrap_admin(
+    store: &PgStore,
+    hasher: &Hasher,
+    emails: &EmailNormalizer,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bootstrap_config = BootstrapConfig::new()?;
+    bootstrap_config.validate()?;
+    if !bootstrap_config.is_enabled() {
+        return Ok(());
+    }
+
+    match bootstrap::bootstrap_admin(store, hasher, emails, &bootstrap_config).await? {
+        Some(admin) => info!(
+            email = admin.email,
+            tenant = bootstrap_config.tenant,
+            "👤 Created the bootstrap admin"
+        ),
+        None => info!("👤 Users already exist, skipping the bootstrap admin"),
+    }
+    Ok(())
+}
 
 /// Starts and manages the authentication API server and management server concurrently.
 ///
-/// Loads the server configuration, then launches both the API and management servers as asynchronous tasks.
-/// The function waits for either server to exit or panic, returning an error if this occurs. Both servers are expected to run indefinitely; reaching the end of this function is considered abnormal.
+/// Loads the server configuration from `config_file`, registers the database health check, loads the JWT keyring to publish its public keys, hands `log_filter` to the management server's `PUT /log-level` and the effective configuration to its `GET /config`, then launches the servers chosen by `servers` (both by default) as asynchronous tasks.
+/// The servers are expected to run indefinitely. When one exits or panics, the failure is logged with the server's name and `policy` decides whether the others are stopped or kept running. On Ctrl+C or SIGTERM `/ready` starts failing and the servers are stopped once `shutdown_drain_secs` have passed. Either way, the database connections are closed before returning.
 ///
-/// # Returns
+/// # Errors
 ///
-/// Returns `Ok(())` if both servers run indefinitely (unexpected), or an error if a server exits or panics.
+/// Returns the failure of the first server to stop: immediately under [`FailurePolicy::FailFast`], or once every server has stopped under [`FailurePolicy::KeepAlive`].
 ///
 /// # Examples
 ///
 /// ```no_run
+/// # use rcauth_cli::{config::ConfigFile, serve::{FailurePolicy, Servers}};
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-///     rcauth_cli::serve::run().await?;
+///     let log_guard = rcauth_core::logger::Config::default().init()?;
+///     let config_file = ConfigFile::new("rcauth.toml");
+///     rcauth_cli::serve::run(
+///         &config_file,
+///         Servers::Both,
+///         FailurePolicy::FailFast,
+///         log_guard.filter(),
+///     )
+///     .await?;
 ///     Ok(())
 /// }
 /// ```
-pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run(
+    config_file: &ConfigFile,
+    servers: Servers,
+    policy: FailurePolicy,
+    log_filter: LogFilter,
+) -> Result<(), Box<dyn std::error::Error>> {
     info!("Starting authentication server");
 
     // Load server configuration
-    let server_config = config::load_server_config()?;
+    let server_config = config::load_server_config(config_file)?;
     info!("🔧 Server configuration loaded successfully");
 
-    // Create a JoinSet to run both servers concurrently
-    let mut tasks = JoinSet::new();
+    // Connect lazily so the servers start, and report the database as unhealthy, while it is down
+    let store_config = config::load_store_config(config_file)?;
+    let warm_target = store_config
+        .warm_pool
+        .then_some(store_config.min_connections);
+    let store = rcauth_store::store::new_lazy(store_config)?;
+    if let Some(target) = warm_target
+        && let Err(err) = store.warm(target).await
+    {
+        // Keep starting up; the health checks report the database until it is reachable
+        warn!("Failed to warm the database connection pool: {}", err);
+    }
+    let password_config = config::load_password_config(config_file)?;
+    password_config.validate()?;
+    let hasher = Hasher::new(&password_config)?;
+    bootstrap_admin(&store, &hasher, &server_config.email_normalizer()).await?;
+    let checks = HealthChecks::default().register(store.clone());
+    let pool_monitor = store.spawn_pool_monitor();
 
-    // Clone config and store for each server
-    let api_config = server_config.clone();
-    let mgmt_config = server_config;
+    let jwt_config = config::load_jwt_config(config_file)?;
+    jwt_config.validate()?;
+    let jwt = Jwt::new(&jwt_config)?;
+    info!(
+        kid = jwt.keyring().current().kid(),
+        "🔑 JWT keyring loaded successfully"
+    );
+    let cleanup = store.spawn_cleanup(Duration::from_secs(jwt_config.refresh_token_ttl_secs));
 
-    // Start API server
-    tasks.spawn(async move {
-        match rcauth_server::run_api_server(&api_config).await {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                error!("API server error: {}", e);
-                Err(Error::new_simple(
-                    ErrorCode::ServerError,
-                    format!("API server failed: {}", e),
-                ))
-            }
+    let state = AppState {
+        checks,
+        jwks: jwt.keyring().jwks().into(),
+        jwt: Some(jwt),
+        token_binding: TokenBinding::from_config(&server_config)?,
+        token_cookies: TokenCookies::from_config(&server_config),
+        users: Users::new(store.clone()),
+        audit: Audit::new(store.clone()),
+        metrics: ServerMetrics::default().with_pool(store.pool_metrics()),
+        passwords: Passwords::new(hasher),
+        idempotency: Some(Idempotency::new(Arc::new(store.clone()))),
+        password_change_limit: Some(RateLimit::password_change(&server_config)?),
+        migrations: Migrations::new(store.clone()),
+        settings: Settings::new(store.clone()),
+        log_filter: Some(log_filter),
+        config: EffectiveConfig::new(config::effective_configs(config_file)?),
+        ..AppState::default()
+    };
+
+    // Switches flipped at runtime win over the configuration, and follow flips made through other instances
+    if let Err(err) = state.settings.load_switches(&state.maintenance).await {
+        // Keep starting up with the configured switches; the sync retries
+        warn!("Failed to load the persisted switches: {}", err);
+    }
+    let settings_sync = state.settings.spawn_sync(
+        state.maintenance.clone(),
+        Duration::from_secs(server_config.settings_sync_secs),
+    );
+
+    // Run the selected servers concurrently
+    let draining = state.draining.clone();
+    let drain_period = Duration::from_secs(server_config.shutdown_drain_secs);
+    let mut tasks = ServerTasks::new();
+    spawn_servers(&mut tasks, server_config, servers, state);
+    info!("Started servers: {}", tasks.labels().join(", "));
+
+    let outcome =
+        supervise_until_shutdown(tasks, policy, shutdown_signal(), draining, drain_period).await;
+
+    // Stop the background tasks first, so the monitor cannot rebuild the pool once it is closed
+    for task in [pool_monitor, cleanup, settings_sync].into_iter().flatten() {
+        task.abort();
+    }
+    // Close pooled connections instead of leaving Postgres to time them out
+    store.close().await;
+    outcome?;
+
+    info!("All server tasks have completed");
+    Ok(())
+}
+
+/// Supervises the servers under `policy` until `shutdown` resolves, then drains them for `drain_period` before stopping them.
+///
+/// The servers keep serving while they drain, and a server stopping meanwhile is still handled by `policy`.
+///
+/// # Errors
+///
+/// Returns the failure reported by [`ServerTasks::supervise`], if one comes before the drain ends.
+async fn supervise_until_shutdown(
+    tasks: ServerTasks,
+    policy: FailurePolicy,
+    shutdown: impl Future<Output = ()>,
+    draining: Draining,
+    drain_period: Duration,
+) -> Result<(), Error> {
+    // The supervisor owns the servers, so it must outlive the drain; dropping it aborts them
+    let supervisor = tasks.supervise(policy);
+    tokio::pin!(supervisor);
+
+    tokio::select! {
+        outcome = &mut supervisor => return outcome,
+        () = shutdown => {}
+    }
+    tokio::select! {
+        outcome = &mut supervisor => outcome,
+        () = drain(&draining, drain_period) => Ok(()),
+    }
+}
+
+/// Flags the servers as draining, so `/ready` fails and load balancers stop sending traffic, then keeps serving in-flight and straggling requests for `period`.
+async fn drain(draining: &Draining, period: Duration) {
+    draining.start();
+    if period.is_zero() {
+        info!("Shutdown signal received, stopping servers");
+        return;
+    }
+
+    info!(
+        drain_secs = period.as_secs(),
+        "Shutdown signal received, draining before stopping servers"
+    );
+    tokio::time::sleep(period).await;
+}
+
+/// Resolves when the process receives Ctrl+C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", err);
+            std::future::pending::<()>().await;
         }
-    });
+    };
 
-    // Start management server
-    tasks.spawn(async move {
-        match rcauth_server::run_management_server(&mgmt_config).await {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                error!("Management server error: {}", e);
-                Err(Error::new_simple(
-                    ErrorCode::ServerError,
-                    format!("Management server failed: {}", e),
-                ))
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                error!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
             }
         }
-    });
-
-    // Wait for any server to exit (both should run indefinitely)
-    if let Some(result) = tasks.join_next().await {
-        match result {
-            Ok(server_result) => {
-                if let Err(e) = server_result {
-                    error!("Server exited with error: {:?}", e);
-                    return Err(Box::new(e) as Box<dyn std::error::Error>);
-                }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn test_config() -> ServerConfig {
+        ServerConfig {
+            api_server_host: "127.0.0.1".to_string(),
+            api_server_port: 0,
+            management_server_host: "127.0.0.1".to_string(),
+            management_server_port: 0,
+            ..ServerConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn spawns_only_selected_servers() {
+        for (servers, expected) in [
+            (Servers::Both, vec!["API", "management"]),
+            (Servers::ApiOnly, vec!["API"]),
+            (Servers::ManagementOnly, vec!["management"]),
+        ] {
+            let mut tasks = ServerTasks::new();
+            spawn_servers(&mut tasks, test_config(), servers, AppState::default());
+            assert_eq!(tasks.labels(), expected);
+            assert_eq!(tasks.tasks.len(), expected.len());
+            tasks.tasks.abort_all();
+        }
+    }
+
+    /// Returns a config whose API port is already taken and whose management port is free.
+    fn config_with_taken_api_port() -> (ServerConfig, std::net::TcpListener) {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let free = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = ServerConfig {
+            api_server_port: taken.local_addr().unwrap().port(),
+            management_server_port: free.local_addr().unwrap().port(),
+            enable_swagger: false,
+            ..test_config()
+        };
+        (config, taken)
+    }
+
+    #[tokio::test]
+    async fn keep_alive_keeps_surviving_server_running() {
+        let (config, _taken) = config_with_taken_api_port();
+        let mgmt_addr = config.management_addr();
+
+        let mut tasks = ServerTasks::new();
+        spawn_servers(&mut tasks, config, Servers::Both, AppState::default());
+        let supervisor = tokio::spawn(tasks.supervise(FailurePolicy::KeepAlive));
+
+        let mut healthy = false;
+        for _ in 0..50 {
+            let probe = crate::healthcheck::check(
+                &mgmt_addr,
+                "/management/v1/health",
+                Duration::from_secs(1),
+            );
+            if probe.await.is_ok() {
+                healthy = true;
+                break;
             }
-            Err(e) => {
-                error!("Server task panicked: {}", e);
-                return Err(Box::new(Error::new_simple(
-                    ErrorCode::ServerError,
-                    format!("Server task panicked: {}", e),
-                )) as Box<dyn std::error::Error>);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        assert!(healthy, "management server should keep serving");
+        assert!(!supervisor.is_finished());
+        supervisor.abort();
+    }
+
+    #[tokio::test]
+    async fn fail_fast_reports_which_server_failed() {
+        let (config, _taken) = config_with_taken_api_port();
+
+        let mut tasks = ServerTasks::new();
+        spawn_servers(&mut tasks, config, Servers::Both, AppState::default());
+        let err = tasks.supervise(FailurePolicy::FailFast).await.unwrap_err();
+
+        assert_eq!(err.code, ErrorCode::ServerError);
+        assert!(err.message.starts_with("API server failed"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn servers_keep_serving_while_they_drain() {
+        let free = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = ServerConfig {
+            management_server_port: free.local_addr().unwrap().port(),
+            enable_swagger: false,
+            ..test_config()
+        };
+        drop(free);
+        let mgmt_addr = config.management_addr();
+        let ready = || {
+            crate::healthcheck::check(&mgmt_addr, "/management/v1/ready", Duration::from_secs(1))
+        };
+        let state = AppState::default();
+        let draining = state.draining.clone();
+
+        let mut tasks = ServerTasks::new();
+        spawn_servers(&mut tasks, config, Servers::ManagementOnly, state);
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let supervisor = tokio::spawn(supervise_until_shutdown(
+            tasks,
+            FailurePolicy::FailFast,
+            async {
+                let _ = stopped.await;
+            },
+            draining,
+            Duration::from_secs(2),
+        ));
+
+        let mut serving = false;
+        for _ in 0..50 {
+            if ready().await.is_ok() {
+                serving = true;
+                break;
             }
+            tokio::time::sleep(Duration::from_millis(100)).await;
         }
+        assert!(serving, "management server should start");
+
+        stop.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        // Still listening, but telling load balancers to move away
+        let err = ready().await.unwrap_err();
+        assert!(err.message.ends_with("returned 503"), "{}", err);
+        assert!(!supervisor.is_finished());
+
+        supervisor.await.unwrap().unwrap();
+        let err = ready().await.unwrap_err();
+        assert!(err.message.contains("failed"), "{}", err);
     }
 
-    // We shouldn't reach here as the servers should run indefinitely
-    info!("All server tasks have completed");
-    Ok(())
+    #[test]
+    fn from_flags_defaults_to_both() {
+        assert_eq!(Servers::from_flags(false, false), Servers::Both);
+        assert_eq!(Servers::from_flags(true, false), Servers::ApiOnly);
+        assert_eq!(Servers::from_flags(false, true), Servers::ManagementOnly);
+    }
 }
@@ -0,0 +1,23 @@
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// Action recorded when an admin impersonates a user.
+pub const IMPERSONATE_USER: &str = "user.impersonate";
+
+/// A privileged action to keep a record of: who did what to whom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// Id of the user who acted
+    pub actor: String,
+    /// What they did, e.g. [`IMPERSONATE_USER`]
+    pub action: String,
+    /// Id of what they acted on, e.g. the impersonated user
+    pub target: String,
+}
+
+/// Append-only storage of audit entries, each stamped with the time it was recorded.
+#[async_trait]
+pub trait AuditLog: Send + Sync {
+    /// Records `entry`.
+    async fn record(&self, entry: AuditEntry) -> Result<()>;
+}
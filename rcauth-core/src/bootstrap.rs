@@ -0,0 +1,342 @@
+use crate::email::EmailNormalizer;
+use crate::error::{Result, ValidationErrors};
+use crate::password::Hasher;
+use crate::user::UserProfile;
+use async_trait::async_trait;
+use figment::{Figment, providers::Env};
+use serde::Deserialize;
+use std::fmt;
+
+/// Role given to the bootstrapped admin.
+pub const ADMIN_ROLE: &str = "admin";
+
+/// First-boot admin, created when `RCAUTH_BOOTSTRAP_ADMIN_EMAIL` and `RCAUTH_BOOTSTRAP_ADMIN_PASSWORD` are set and the database has no users yet.
+///
+/// It is read from the environment only, so the password never lands in a configuration file.
+#[derive(Clone, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub admin_email: Option<String>,
+    #[serde(default)]
+    pub admin_password: Option<String>,
+    /// Slug of the tenant the admin is created in, which is created too if it does not exist
+    #[serde(default = "default_tenant")]
+    pub tenant: String,
+}
+
+/// Returns the slug of the tenant the admin is created in by default.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_tenant(), "default");
+/// ```
+fn default_tenant() -> String {
+    "default".to_string()
+}
+
+impl Default for Config {
+    /// Returns a `Config` that bootstraps no admin.
+    fn default() -> Self {
+        Self {
+            admin_email: None,
+            admin_password: None,
+            tenant: default_tenant(),
+        }
+    }
+}
+
+impl fmt::Debug for Config {
+    /// Formats the configuration with the password masked, so it is safe to log.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("admin_email", &self.admin_email)
+            .field(
+                "admin_password",
+                &self.admin_password.as_ref().map(|_| "***"),
+            )
+            .field("tenant", &self.tenant)
+            .finish()
+    }
+}
+
+impl Config {
+    /// Loads the bootstrap admin from environment variables prefixed with `RCAUTH_BOOTSTRAP_`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::bootstrap::Config;
+    /// let config = Config::new().expect("Failed to load bootstrap config");
+    /// ```
+    #[allow(clippy::result_large_err)] // figment::Error, as returned by figment itself
+    pub fn new() -> std::result::Result<Self, figment::Error> {
+        Figment::new()
+            .merge(Env::prefixed("RCAUTH_BOOTSTRAP_"))
+            .extract()
+    }
+
+    /// Whether an admin is configured to be bootstrapped.
+    pub fn is_enabled(&self) -> bool {
+        self.admin_email.is_some() || self.admin_password.is_some()
+    }
+
+    /// Validates that the email and password are set together and not empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::bootstrap::Config;
+    /// assert!(Config::default().validate().is_ok());
+    ///
+    /// let config = Config {
+    ///     admin_email: Some("admin@example.com".to_string()),
+    ///     ..Config::default()
+    /// };
+    /// assert!(config.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.validation_errors().into_result()?;
+        Ok(())
+    }
+
+    /// Collects every validation failure, keyed by the name of the offending field.
+    pub fn validation_errors(&self) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        if !self.is_enabled() {
+            return errors;
+        }
+
+        for (field, value) in [
+            ("admin_email", &self.admin_email),
+            ("admin_password", &self.admin_password),
+        ] {
+            match value.as_deref() {
+                None => errors.add(field, "must be set when bootstrapping an admin"),
+                Some("") => errors.add(field, "cannot be empty"),
+                Some(_) => {}
+            }
+        }
+        if self.tenant.is_empty() {
+            errors.add("tenant", "cannot be empty");
+        }
+        errors
+    }
+}
+
+/// Storage side of the first-boot admin bootstrap.
+#[async_trait]
+pub trait AdminBootstrap: Send + Sync {
+    /// Whether any user exists, checked without locking so that it is cheap on every startup.
+    async fn has_users(&self) -> Result<bool>;
+
+    /// Creates a verified [`ADMIN_ROLE`] user in the tenant with `tenant_slug`, creating the tenant if needed, unless any user exists.
+    ///
+    /// Returns the admin, or `None` when there were users already. Concurrent calls create at most one admin.
+    async fn create_first_admin(
+        &self,
+        tenant_slug: &str,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<Option<UserProfile>>;
+}
+
+/// Creates the configured admin if the database has no users, for provisioning a fresh deployment without manual steps.
+///
+/// The admin's email is stored as normalized by `emails`. Running it again once any user exists does nothing, not even hashing the password, so it is safe and cheap on every startup and never overwrites an existing admin. Returns the admin when it was created, and `None` when it was not or no admin is configured.
+///
+/// # Errors
+///
+/// Returns a `ValidationError` if the email is not an email address, an `Internal` error if the password cannot be hashed, or the store's error if the admin cannot be created.
+///
+/// # Examples
+///
+/// ```ignore
+/// if let Some(admin) = bootstrap::bootstrap_admin(&store, &hasher, &emails, &config).await? {
+///     info!(email = admin.email, "Created the bootstrap admin");
+/// }
+/// ```
+pub async fn bootstrap_admin(
+    store: &dyn AdminBootstrap,
+    hasher: &Hasher,
+    emails: &EmailNormalizer,
+    config: &Config,
+) -> Result<Option<UserProfile>> {
+    let (Some(email), Some(password)) = (&config.admin_email, &config.admin_password) else {
+        return Ok(None);
+    };
+    if store.has_users().await? {
+        return Ok(None);
+    }
+    let email = emails.normalize(email)?;
+    let password_hash = hasher.hash(password)?;
+    store
+        .create_first_admin(&config.tenant, &email, &password_hash)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::password::{self, Verification};
+    use std::sync::Mutex;
+
+    /// Users as (tenant, email, password hash).
+    #[derive(Default)]
+    struct Users(Mutex<Vec<(String, String, String)>>);
+
+    #[async_trait]
+    impl AdminBootstrap for Users {
+        async fn has_users(&self) -> Result<bool> {
+            Ok(!self.0.lock().unwrap().is_empty())
+        }
+
+        async fn create_first_admin(
+            &self,
+            tenant_slug: &str,
+            email: &str,
+            password_hash: &str,
+        ) -> Result<Option<UserProfile>> {
+            let mut users = self.0.lock().unwrap();
+            if !users.is_empty() {
+                return Ok(None);
+            }
+            users.push((
+                tenant_slug.to_string(),
+                email.to_string(),
+                password_hash.to_string(),
+            ));
+            Ok(Some(UserProfile {
+                id: "user-1".to_string(),
+                email: email.to_string(),
+                email_verified: true,
+                name: None,
+                must_reset_password: false,
+                version: 1,
+            }))
+        }
+    }
+
+    /// Cheap parameters, so the tests stay fast.
+    fn hasher() -> Hasher {
+        Hasher::new(&password::Config {
+            password_hash_memory_kib: 8,
+            password_hash_iterations: 1,
+            password_hash_parallelism: 1,
+            ..password::Config::default()
+        })
+        .unwrap()
+    }
+
+    fn config() -> Config {
+        Config {
+            admin_email: Some("admin@example.com".to_string()),
+            admin_password: Some("correct horse battery staple".to_string()),
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_is_created_only_while_there_are_no_users() {
+        let users = Users::default();
+        let hasher = hasher();
+
+        let emails = EmailNormalizer::default();
+        let first = Config {
+            admin_email: Some(" Admin@Example.com".to_string()),
+            ..config()
+        };
+        let admin = bootstrap_admin(&users, &hasher, &emails, &first)
+            .await
+            .unwrap();
+        assert_eq!(admin.unwrap().email, "admin@example.com");
+        let (tenant, _, hash) = users.0.lock().unwrap()[0].clone();
+        assert_eq!(tenant, "default");
+        assert_eq!(
+            hasher
+                .verify("correct horse battery staple", &hash)
+                .unwrap(),
+            Verification::Valid
+        );
+
+        // Rerunning, even with another password, leaves the admin alone
+        let rerun = Config {
+            admin_password: Some("another password".to_string()),
+            ..config()
+        };
+        assert_eq!(
+            bootstrap_admin(&users, &hasher, &emails, &rerun)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(users.0.lock().unwrap().len(), 1);
+        assert_eq!(users.0.lock().unwrap()[0].2, hash);
+    }
+
+    /// A database with users, which must not be asked to create an admin.
+    struct Populated;
+
+    #[async_trait]
+    impl AdminBootstrap for Populated {
+        async fn has_users(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn create_first_admin(
+            &self,
+            _: &str,
+            _: &str,
+            _: &str,
+        ) -> Result<Option<UserProfile>> {
+            panic!("the admin was hashed and created although users exist")
+        }
+    }
+
+    #[tokio::test]
+    async fn password_is_not_hashed_once_users_exist() {
+        let admin = bootstrap_admin(
+            &Populated,
+            &hasher(),
+            &EmailNormalizer::default(),
+            &config(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(admin, None);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_created_when_not_configured() {
+        let users = Users::default();
+        let admin = bootstrap_admin(
+            &users,
+            &hasher(),
+            &EmailNormalizer::default(),
+            &Config::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(admin, None);
+        assert!(users.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn email_and_password_are_required_together() {
+        let errors = Config {
+            admin_email: Some("admin@example.com".to_string()),
+            ..Config::default()
+        }
+        .validation_errors();
+        assert!(errors.get("admin_email").is_none());
+        assert!(errors.get("admin_password").is_some());
+
+        let errors = Config {
+            admin_email: Some(String::new()),
+            ..config()
+        }
+        .validation_errors();
+        assert_eq!(errors.get("admin_email").unwrap(), ["cannot be empty"]);
+        assert!(config().validation_errors().is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::error::{Error, Result, ValidationErrors};
+
+/// Domains whose mailboxes ignore dots in the local part.
+const GMAIL_DOMAINS: [&str; 2] = ["gmail.com", "googlemail.com"];
+
+/// Brings email addresses to the single form they are stored and looked up in, so a user who signed up as `User@Example.com` is found as `user@example.com`.
+///
+/// Addresses are always trimmed and lowercased. The aggressive normalizations are off by default, as they fold addresses that only some mail providers deliver to the same mailbox; turning them on only affects addresses normalized from then on.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::email::EmailNormalizer;
+/// let emails = EmailNormalizer::default();
+/// assert_eq!(emails.normalize(" Ada@Example.COM ").unwrap(), "ada@example.com");
+///
+/// let aggressive = EmailNormalizer { strip_plus_tags: true, strip_gmail_dots: true };
+/// assert_eq!(aggressive.normalize("A.da+news@gmail.com").unwrap(), "ada@gmail.com");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EmailNormalizer {
+    /// Drops a `+tag` suffix from the local part, e.g. `ada+news@example.com` becomes `ada@example.com`
+    pub strip_plus_tags: bool,
+    /// Drops the dots Gmail ignores from the local part of Gmail addresses, e.g. `a.da@gmail.com` becomes `ada@gmail.com`
+    pub strip_gmail_dots: bool,
+}
+
+impl EmailNormalizer {
+    /// Returns the normalized form of `email`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` on the `email` field if `email` lacks a local part or a domain.
+    pub fn normalize(&self, email: &str) -> Result<String> {
+        let email = email.trim().to_lowercase();
+        let Some((local, domain)) = email
+            .rsplit_once('@')
+            .filter(|(local, domain)| !local.is_empty() && !domain.is_empty())
+        else {
+            let mut errors = ValidationErrors::new();
+            errors.add("email", "must be an email address");
+            return Err(Error::validation(errors));
+        };
+
+        let mut local = local.to_string();
+        if self.strip_plus_tags
+            && let Some((base, _)) = local.split_once('+')
+            && !base.is_empty()
+        {
+            local.truncate(base.len());
+        }
+        if self.strip_gmail_dots && GMAIL_DOMAINS.contains(&domain) && local.contains(|c| c != '.')
+        {
+            local.retain(|c| c != '.');
+        }
+        Ok(format!("{}@{}", local, domain))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    const AGGRESSIVE: EmailNormalizer = EmailNormalizer {
+        strip_plus_tags: true,
+        strip_gmail_dots: true,
+    };
+
+    #[test]
+    fn case_and_surrounding_whitespace_are_ignored() {
+        let emails = EmailNormalizer::default();
+        for email in [
+            "User@Example.com",
+            "user@example.com",
+            "\tUSER@EXAMPLE.COM \n",
+        ] {
+            assert_eq!(emails.normalize(email).unwrap(), "user@example.com");
+        }
+        // The aggressive normalizations are opt-in
+        assert_eq!(
+            emails.normalize("a.da+news@gmail.com").unwrap(),
+            "a.da+news@gmail.com"
+        );
+    }
+
+    #[test]
+    fn aggressive_normalizations_fold_aliases_of_one_mailbox() {
+        assert_eq!(
+            AGGRESSIVE.normalize("Ada+Newsletter@Example.com").unwrap(),
+            "ada@example.com"
+        );
+        assert_eq!(
+            AGGRESSIVE.normalize("a.d.a@googlemail.com").unwrap(),
+            "ada@googlemail.com"
+        );
+        // Other providers may treat dots as significant
+        assert_eq!(
+            AGGRESSIVE.normalize("a.da@example.com").unwrap(),
+            "a.da@example.com"
+        );
+        // Nothing is left to strip a tag from
+        assert_eq!(
+            AGGRESSIVE.normalize("+ada@example.com").unwrap(),
+            "+ada@example.com"
+        );
+    }
+
+    #[test]
+    fn addresses_without_local_part_or_domain_are_rejected() {
+        for email in ["", "   ", "ada", "@example.com", "ada@"] {
+            let err = EmailNormalizer::default().normalize(email).unwrap_err();
+            assert_eq!(err.code, ErrorCode::ValidationError, "{:?}", email);
+            assert!(err.data.unwrap()["fields"]["email"].is_array());
+        }
+    }
+}
@@ -1,7 +1,9 @@
+use chrono::{DateTime, SecondsFormat, Utc};
 use http::StatusCode;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::str::FromStr;
 use thiserror::Error;
 
 /// AppError is the primary error type for the application.
@@ -19,12 +21,13 @@ pub struct Error {
     pub source: Option<Box<dyn std::error::Error + Send + Sync>>,
     pub internal: Option<String>,
     pub op: Option<String>,
-    pub data: Option<HashMap<String, serde_json::Value>>,
+    /// Boxed so that results carrying an `Error` stay small
+    pub data: Option<Box<HashMap<String, serde_json::Value>>>,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ErrorCode {
     Conflict,
@@ -34,9 +37,12 @@ pub enum ErrorCode {
     ServerError,
     Unauthorized,
     Forbidden,
+    /// The account is locked for a while, e.g. after too many failed logins; unlike `Forbidden`, retrying later can succeed
+    AccountLocked,
     Timeout,
     Unavailable,
     UnprocessableEntity,
+    TooManyRequests,
     DatabaseError,
     ValidationError,
     ConfigurationError,
@@ -52,6 +58,29 @@ impl Display for ErrorCode {
     }
 }
 
+impl FromStr for ErrorCode {
+    type Err = Error;
+
+    /// Parses the snake_case name produced by `Display` and `Serialize`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Invalid` error for unknown codes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rcauth_core::error::ErrorCode;
+    ///
+    /// assert_eq!("not_found".parse::<ErrorCode>().unwrap(), ErrorCode::NotFound);
+    /// assert!("teapot".parse::<ErrorCode>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        serde_json::from_value(serde_json::Value::String(s.to_string()))
+            .map_err(|e| Error::new(ErrorCode::Invalid, format!("Unknown error code '{}'", s), e))
+    }
+}
+
 impl ErrorCode {
     /// Returns the corresponding HTTP status code for the error code.
     ///
@@ -76,9 +105,11 @@ impl ErrorCode {
             ErrorCode::NotFound => StatusCode::NOT_FOUND,
             ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
             ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::AccountLocked => StatusCode::LOCKED,
             ErrorCode::Timeout => StatusCode::REQUEST_TIMEOUT,
             ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
             ErrorCode::UnprocessableEntity => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
             ErrorCode::DatabaseError => StatusCode::INTERNAL_SERVER_ERROR,
             ErrorCode::ValidationError => StatusCode::UNPROCESSABLE_ENTITY,
             ErrorCode::ConfigurationError => StatusCode::INTERNAL_SERVER_ERROR,
@@ -141,16 +172,142 @@ impl Error {
 
     pub fn with_data(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
         self.data
-            .get_or_insert_with(HashMap::new)
+            .get_or_insert_with(Box::default)
             .insert(key.into(), value);
         self
     }
+
+    /// Creates a `ValidationError` carrying per-field messages under the `fields` detail.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::error::{Error, ErrorCode, ValidationErrors};
+    /// let mut errors = ValidationErrors::new();
+    /// errors.add("email", "must not be empty");
+    /// let error = Error::validation(errors);
+    /// assert_eq!(error.code, ErrorCode::ValidationError);
+    /// assert!(error.data.unwrap().contains_key("fields"));
+    /// ```
+    pub fn validation(errors: ValidationErrors) -> Self {
+        let fields = serde_json::to_value(&errors.0).unwrap_or_default();
+        Error::new_simple(
+            ErrorCode::ValidationError,
+            format!("Validation failed: {}", errors),
+        )
+        .with_data("fields", fields)
+    }
+
+    /// Creates an `AccountLocked` error carrying the time the lock lifts under the `locked_until` detail, so clients can tell the user when to try again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::error::{Error, ErrorCode};
+    /// let error = Error::account_locked(chrono::Utc::now() + chrono::Duration::minutes(15));
+    /// assert_eq!(error.code, ErrorCode::AccountLocked);
+    /// assert!(error.data.unwrap().contains_key("locked_until"));
+    /// ```
+    pub fn account_locked(locked_until: DateTime<Utc>) -> Self {
+        Error::new_simple(ErrorCode::AccountLocked, "Account is temporarily locked").with_data(
+            "locked_until",
+            serde_json::json!(locked_until.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        )
+    }
+}
+
+/// Validation messages keyed by the name of the field they apply to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ValidationErrors(HashMap<String, Vec<String>>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `message` against `field`, keeping any messages already recorded for it.
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.entry(field.into()).or_default().push(message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the messages recorded for `field`, if any.
+    pub fn get(&self, field: &str) -> Option<&[String]> {
+        self.0.get(field).map(Vec::as_slice)
+    }
+
+    pub fn fields(&self) -> &HashMap<String, Vec<String>> {
+        &self.0
+    }
+
+    /// Returns `Ok(())` when nothing was recorded, and a [`Error::validation`] error otherwise.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::validation(self))
+        }
+    }
+}
+
+/// Input that can check its own field values, e.g. a request body after deserialization.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::error::{Validate, ValidationErrors};
+/// struct Signup {
+///     email: String,
+/// }
+///
+/// impl Validate for Signup {
+///     fn validate(&self) -> Result<(), ValidationErrors> {
+///         let mut errors = ValidationErrors::new();
+///         if !self.email.contains('@') {
+///             errors.add("email", "must be an email address");
+///         }
+///         if errors.is_empty() { Ok(()) } else { Err(errors) }
+///     }
+/// }
+///
+/// let signup = Signup { email: "nobody".to_string() };
+/// assert!(signup.validate().unwrap_err().get("email").is_some());
+/// ```
+pub trait Validate {
+    /// Returns every invalid field, keyed by field name. Accepts everything by default.
+    fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        Ok(())
+    }
+}
+
+impl Display for ValidationErrors {
+    /// Lists the messages sorted by field, e.g. `email: must not be empty; name: is too long`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields: Vec<_> = self.0.iter().collect();
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut first = true;
+        for (field, messages) in fields {
+            for message in messages {
+                if !first {
+                    f.write_str("; ")?;
+                }
+                write!(f, "{}: {}", field, message)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Represents the final JSON response sent to the client.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
-    /// The HTTP status code of the response. This is not serialized.
+    /// The HTTP status code of the response. This is not serialized, and defaults to `200 OK` when deserialized; use [`ErrorResponse::to_error`] to recover it from the code.
     #[serde(skip)]
     pub status: StatusCode,
     /// The machine-readable error code.
@@ -158,7 +315,7 @@ pub struct ErrorResponse {
     /// The human-readable error message.
     pub message: String,
     /// Additional details about the error. Omitted if empty.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub details: Option<HashMap<String, serde_json::Value>>,
 }
 
@@ -166,7 +323,7 @@ impl ErrorResponse {
     pub fn from_error(err: &(dyn std::error::Error + 'static)) -> Self {
         // Try to downcast the error to our specific AppError type.
         if let Some(app_err) = err.downcast_ref::<Error>() {
-            let mut details = app_err.data.clone();
+            let mut details = app_err.data.as_deref().cloned();
             if let Some(op) = &app_err.op {
                 let d = details.get_or_insert_with(HashMap::new);
                 d.insert("operation".to_string(), serde_json::json!(op));
@@ -188,4 +345,146 @@ impl ErrorResponse {
             }
         }
     }
+
+    /// Reconstructs an [`Error`] from a parsed response body.
+    ///
+    /// The string `code` is mapped back to an [`ErrorCode`], falling back to [`ErrorCode::Internal`] for unknown codes, and the status is derived from it. An `operation` entry in `details` becomes the error's `op`; the remaining details become its `data`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::error::{ErrorCode, ErrorResponse};
+    /// let body = r#"{"code":"not_found","message":"User not found"}"#;
+    /// let response: ErrorResponse = serde_json::from_str(body).unwrap();
+    /// let error = response.to_error();
+    /// assert_eq!(error.code, ErrorCode::NotFound);
+    /// assert_eq!(error.message, "User not found");
+    /// ```
+    pub fn to_error(&self) -> Error {
+        let code = self.code.parse().unwrap_or(ErrorCode::Internal);
+        let mut error = Error::new_simple(code, self.message.clone());
+
+        if let Some(details) = &self.details {
+            let mut data = details.clone();
+            if let Some(serde_json::Value::String(op)) = data.remove("operation") {
+                error = error.with_op(op);
+            }
+            if !data.is_empty() {
+                error.data = Some(Box::new(data));
+            }
+        }
+
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_code_round_trips_through_string() {
+        let codes = [
+            ErrorCode::Conflict,
+            ErrorCode::Internal,
+            ErrorCode::Invalid,
+            ErrorCode::NotFound,
+            ErrorCode::ServerError,
+            ErrorCode::Unauthorized,
+            ErrorCode::Forbidden,
+            ErrorCode::AccountLocked,
+            ErrorCode::Timeout,
+            ErrorCode::Unavailable,
+            ErrorCode::UnprocessableEntity,
+            ErrorCode::TooManyRequests,
+            ErrorCode::DatabaseError,
+            ErrorCode::ValidationError,
+            ErrorCode::ConfigurationError,
+        ];
+
+        for code in codes {
+            assert_eq!(ErrorCode::from_str(&code.to_string()).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn unknown_error_code_fails_to_parse() {
+        let err = ErrorCode::from_str("NotFound").unwrap_err();
+        assert_eq!(err.code, ErrorCode::Invalid);
+        assert!(ErrorCode::from_str("").is_err());
+    }
+
+    #[test]
+    fn error_response_round_trips() {
+        let error = Error::new_simple(ErrorCode::Conflict, "Email already registered")
+            .with_op("users.create")
+            .with_data("field", serde_json::json!("email"));
+        let response = ErrorResponse::from_error(&error);
+
+        let body = serde_json::to_string(&response).unwrap();
+        let parsed: ErrorResponse = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed.code, response.code);
+        assert_eq!(parsed.message, response.message);
+        assert_eq!(parsed.details, response.details);
+
+        let rebuilt = parsed.to_error();
+        assert_eq!(rebuilt.code, ErrorCode::Conflict);
+        assert_eq!(rebuilt.status, StatusCode::CONFLICT);
+        assert_eq!(rebuilt.message, "Email already registered");
+        assert_eq!(rebuilt.op.as_deref(), Some("users.create"));
+        assert_eq!(rebuilt.data, error.data);
+    }
+
+    #[test]
+    fn validation_response_lists_messages_per_field() {
+        let mut errors = ValidationErrors::new();
+        errors.add("email", "must not be empty");
+        errors.add("password", "is too short");
+        errors.add("password", "must contain a digit");
+
+        let error = errors.into_result().unwrap_err();
+        assert_eq!(error.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = serde_json::to_value(ErrorResponse::from_error(&error)).unwrap();
+        assert_eq!(body["code"], "validation_error");
+        assert_eq!(
+            body["details"]["fields"]["email"],
+            serde_json::json!(["must not be empty"])
+        );
+        assert_eq!(
+            body["details"]["fields"]["password"],
+            serde_json::json!(["is too short", "must contain a digit"])
+        );
+    }
+
+    #[test]
+    fn account_locked_is_distinct_from_forbidden() {
+        let locked_until = DateTime::parse_from_rfc3339("2030-01-01T12:15:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let error = Error::account_locked(locked_until);
+        assert_eq!(error.status, StatusCode::LOCKED);
+
+        let body = serde_json::to_value(ErrorResponse::from_error(&error)).unwrap();
+        assert_eq!(body["code"], "account_locked");
+        assert_eq!(body["details"]["locked_until"], "2030-01-01T12:15:00Z");
+        assert_eq!(
+            ErrorResponse::from_error(&error).to_error().code,
+            ErrorCode::AccountLocked
+        );
+    }
+
+    #[test]
+    fn empty_validation_errors_are_ok() {
+        assert!(ValidationErrors::new().into_result().is_ok());
+    }
+
+    #[test]
+    fn unknown_code_maps_to_internal() {
+        let parsed: ErrorResponse =
+            serde_json::from_str(r#"{"code":"teapot","message":"I'm a teapot"}"#).unwrap();
+        let error = parsed.to_error();
+        assert_eq!(error.code, ErrorCode::Internal);
+        assert_eq!(error.status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
 }
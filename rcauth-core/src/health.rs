@@ -0,0 +1,44 @@
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// A single subsystem probe reported by the aggregated health endpoint.
+///
+/// Implementations should be cheap and side-effect free, e.g. a `SELECT 1` against the database; the caller bounds each check with a timeout.
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// Name the check is reported under, e.g. `database`.
+    fn name(&self) -> &str;
+
+    /// Whether a failure makes the service unhealthy rather than degraded.
+    fn required(&self) -> bool {
+        true
+    }
+
+    /// Probes the subsystem, returning an error describing why it is unhealthy.
+    async fn check(&self) -> Result<()>;
+}
+
+/// Schema version of the database, compared to the migrations shipped with the server.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Version of the latest applied migration, or `None` on an empty database
+    pub version: Option<i64>,
+    /// Description of the latest applied migration
+    pub description: Option<String>,
+    /// Migrations in the migrations directory that have not been applied
+    pub pending: u64,
+}
+
+impl MigrationStatus {
+    /// Whether every shipped migration has been applied.
+    pub fn is_up_to_date(&self) -> bool {
+        self.pending == 0
+    }
+}
+
+/// Reports which migrations a database has applied, so operators can confirm the deployed schema.
+#[async_trait]
+pub trait MigrationCheck: Send + Sync {
+    /// Reads the applied migrations and counts the shipped ones still pending.
+    async fn migration_status(&self) -> Result<MigrationStatus>;
+}
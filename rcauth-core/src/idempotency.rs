@@ -0,0 +1,40 @@
+use crate::error::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// A response recorded for an idempotency key, replayed when the key is seen again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// The outcome of claiming an idempotency key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Claim {
+    /// The key was unused or expired and now belongs to the caller, which should run the request.
+    Claimed,
+    /// Another request holding the key has not finished yet.
+    InProgress { request_hash: String },
+    /// A request with the key already finished with `response`.
+    Completed {
+        request_hash: String,
+        response: CachedResponse,
+    },
+}
+
+/// Storage for `Idempotency-Key` records, which expire after a short TTL.
+///
+/// Claiming must be atomic, so that concurrent retries of the same request cannot both run it.
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// Claims `key` for a request whose contents hash to `request_hash`, or returns the record already holding it.
+    async fn claim(&self, key: &str, request_hash: &str, ttl: Duration) -> Result<Claim>;
+
+    /// Records the response of the request that claimed `key`.
+    async fn complete(&self, key: &str, response: &CachedResponse) -> Result<()>;
+
+    /// Releases a claimed `key` that has no response, so a retry runs the request again.
+    async fn release(&self, key: &str) -> Result<()>;
+}
@@ -0,0 +1,265 @@
+use crate::email::EmailNormalizer;
+use crate::error::{Error, ErrorCode, Result};
+use crate::user::{NewUser, UserProfile, UserRepository};
+use async_trait::async_trait;
+
+/// `reason` detail of a sign-in refused because the provider did not verify the email.
+pub const EMAIL_NOT_VERIFIED: &str = "email_not_verified";
+
+/// An account at an external identity provider, as asserted by the provider's OAuth callback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderIdentity {
+    /// Provider the account belongs to, e.g. `google`
+    pub provider: String,
+    /// The provider's stable id for the account, e.g. its `sub` claim
+    pub subject: String,
+    pub email: String,
+    /// Whether the provider vouched for `email`
+    pub email_verified: bool,
+}
+
+/// Storage of the provider identities linked to users. An identity belongs to at most one user, while a user may link one from every provider.
+#[async_trait]
+pub trait IdentityRepository: Send + Sync {
+    /// Returns the id of the user in `tenant_id` that `subject` at `provider` is linked to, if any.
+    async fn find_user(
+        &self,
+        tenant_id: &str,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<String>>;
+
+    /// Links `subject` at `provider` to the user with `user_id`. Linking an identity again to the same user does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Conflict` error if the identity is linked to another user.
+    async fn link(&self, user_id: &str, provider: &str, subject: &str) -> Result<()>;
+}
+
+/// Resolves the user signing in through a provider, for the OAuth callback.
+///
+/// No callback calls it yet, as the server has no OAuth routes; they must resolve users through it once they exist.
+///
+/// An identity seen before signs in as the user it is linked to. A new identity is linked to the user with its email as normalized by `emails`, who is created if there is none, so signing in through several providers with the same email reaches one account. Returns the user and whether it was created.
+///
+/// # Errors
+///
+/// Returns a `Forbidden` error with the [`EMAIL_NOT_VERIFIED`] reason for a new identity whose email the provider did not verify, as linking it would hand the account to whoever typed the address. Returns a `ValidationError` if the email is not an email address, and a `Conflict` error if a concurrent sign-in linked the identity to another user.
+///
+/// # Examples
+///
+/// ```ignore
+/// let (user, created) =
+///     identity::find_or_link(&store, &store, &emails, &tenant_id, "user", &identity).await?;
+/// ```
+pub async fn find_or_link(
+    users: &dyn UserRepository,
+    identities: &dyn IdentityRepository,
+    emails: &EmailNormalizer,
+    tenant_id: &str,
+    role: &str,
+    identity: &ProviderIdentity,
+) -> Result<(UserProfile, bool)> {
+    if let Some(user_id) = identities
+        .find_user(tenant_id, &identity.provider, &identity.subject)
+        .await?
+    {
+        // A deleted user keeps their identities until purged, so this signs nobody in as them
+        let user = users.profile(&user_id).await?.ok_or_else(|| {
+            Error::new_simple(ErrorCode::NotFound, "Linked user no longer exists")
+        })?;
+        return Ok((user, false));
+    }
+
+    if !identity.email_verified {
+        return Err(Error::new_simple(
+            ErrorCode::Forbidden,
+            "The identity provider has not verified this email address",
+        )
+        .with_data("reason", serde_json::json!(EMAIL_NOT_VERIFIED)));
+    }
+
+    let (user, created) = users
+        .upsert_by_email(NewUser {
+            tenant_id: tenant_id.to_string(),
+            email: emails.normalize(&identity.email)?,
+            password_hash: None,
+            role: role.to_string(),
+            email_verified: true,
+        })
+        .await?;
+    identities
+        .link(&user.id, &identity.provider, &identity.subject)
+        .await?;
+    Ok((user, created))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::InMemoryUsers;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Users, and identities keyed by provider and subject.
+    #[derive(Default)]
+    struct Accounts {
+        users: InMemoryUsers,
+        identities: Mutex<HashMap<(String, String), String>>,
+    }
+
+    #[async_trait]
+    impl IdentityRepository for Accounts {
+        async fn find_user(
+            &self,
+            _: &str,
+            provider: &str,
+            subject: &str,
+        ) -> Result<Option<String>> {
+            let identities = self.identities.lock().unwrap();
+            Ok(identities
+                .get(&(provider.to_string(), subject.to_string()))
+                .cloned())
+        }
+
+        async fn link(&self, user_id: &str, provider: &str, subject: &str) -> Result<()> {
+            let mut identities = self.identities.lock().unwrap();
+            let owner = identities
+                .entry((provider.to_string(), subject.to_string()))
+                .or_insert_with(|| user_id.to_string());
+            if owner != user_id {
+                return Err(Error::new_simple(
+                    ErrorCode::Conflict,
+                    "Identity is linked to another user",
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    fn identity(provider: &str, subject: &str, email: &str) -> ProviderIdentity {
+        ProviderIdentity {
+            provider: provider.to_string(),
+            subject: subject.to_string(),
+            email: email.to_string(),
+            email_verified: true,
+        }
+    }
+
+    async fn sign_in(
+        accounts: &Accounts,
+        identity: &ProviderIdentity,
+    ) -> Result<(UserProfile, bool)> {
+        find_or_link(
+            &accounts.users,
+            accounts,
+            &EmailNormalizer::default(),
+            "tenant-1",
+            "user",
+            identity,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn second_provider_links_to_the_user_with_the_same_email() {
+        let accounts = Accounts::default();
+
+        let (user, created) = sign_in(&accounts, &identity("google", "g-1", "ada@example.com"))
+            .await
+            .unwrap();
+        assert!(created);
+        let (linked, created) = sign_in(&accounts, &identity("github", "42", "Ada@example.com"))
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(linked.id, user.id);
+
+        // The linked identity signs in as the user, whatever email it reports next time
+        let (again, _) = sign_in(&accounts, &identity("github", "42", "ada@other.example"))
+            .await
+            .unwrap();
+        assert_eq!(again.id, user.id);
+        assert_eq!(accounts.identities.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn identity_owned_by_another_user_is_not_relinked() {
+        let accounts = Accounts::default();
+        let (ada, _) = sign_in(&accounts, &identity("google", "g-1", "ada@example.com"))
+            .await
+            .unwrap();
+        let (grace, _) = sign_in(&accounts, &identity("google", "g-2", "grace@example.com"))
+            .await
+            .unwrap();
+
+        let err = accounts.link(&grace.id, "google", "g-1").await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        let (user, _) = sign_in(&accounts, &identity("google", "g-1", "grace@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(user.id, ada.id);
+    }
+
+    #[tokio::test]
+    async fn new_users_are_stored_with_the_normalized_email() {
+        let accounts = Accounts::default();
+        let (user, _) = sign_in(&accounts, &identity("google", "g-1", " Ada@Example.COM "))
+            .await
+            .unwrap();
+        assert_eq!(user.email, "ada@example.com");
+        let found = accounts
+            .users
+            .find_by_email("tenant-1", "ada@example.com")
+            .await
+            .unwrap();
+        assert_eq!(found.map(|user| user.id), Some(user.id.clone()));
+
+        // With the aggressive normalizations, aliases of the mailbox reach the same account
+        let emails = EmailNormalizer {
+            strip_plus_tags: true,
+            strip_gmail_dots: true,
+        };
+        let (grace, _) = find_or_link(
+            &accounts.users,
+            &accounts,
+            &emails,
+            "tenant-1",
+            "user",
+            &identity("google", "g-2", "grace.hopper@gmail.com"),
+        )
+        .await
+        .unwrap();
+        let (alias, created) = find_or_link(
+            &accounts.users,
+            &accounts,
+            &emails,
+            "tenant-1",
+            "user",
+            &identity("github", "42", "GraceHopper+github@gmail.com"),
+        )
+        .await
+        .unwrap();
+        assert!(!created);
+        assert_eq!(alias.id, grace.id);
+        assert_eq!(grace.email, "gracehopper@gmail.com");
+    }
+
+    #[tokio::test]
+    async fn unverified_email_is_not_linked() {
+        let accounts = Accounts::default();
+        sign_in(&accounts, &identity("google", "g-1", "ada@example.com"))
+            .await
+            .unwrap();
+
+        let unverified = ProviderIdentity {
+            email_verified: false,
+            ..identity("github", "42", "ada@example.com")
+        };
+        let err = sign_in(&accounts, &unverified).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::Forbidden);
+        assert_eq!(err.data.unwrap()["reason"], EMAIL_NOT_VERIFIED);
+        assert_eq!(accounts.identities.lock().unwrap().len(), 1);
+    }
+}
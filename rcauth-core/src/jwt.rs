@@ -0,0 +1,1282 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use figment::{Figment, providers::Env};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, Jwk, JwkSet, KeyAlgorithm, PublicKeyUse,
+    RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rsa::{RsaPrivateKey, pkcs8::DecodePrivateKey, traits::PublicKeyParts};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+use crate::error::{Error, ErrorCode, Result, ValidationErrors};
+use crate::user::UserProfile;
+
+/// Shortest accepted HS256 secret, in bytes, matching the hash output size.
+const MIN_SECRET_BYTES: usize = 32;
+
+/// `kid` given to `jwt_secret` when no `jwt_keys` are configured.
+pub const DEFAULT_KID: &str = "default";
+
+/// `reason` detail of a rejected token that has expired but is otherwise valid: the client should refresh it.
+pub const TOKEN_EXPIRED: &str = "token_expired";
+
+/// `reason` detail of a rejected token that is malformed, forged, or not meant for this service: the client should re-authenticate.
+pub const TOKEN_INVALID: &str = "token_invalid";
+
+/// `reason` detail of a rejected token that is unbound while binding is enabled, or was issued to another client: the client should re-authenticate.
+pub const TOKEN_BINDING_MISMATCH: &str = "token_binding_mismatch";
+
+/// Claim names that custom claims may not use: the RFC 7519 registered claims and the ones rcauth issues.
+pub const RESERVED_CLAIMS: &[&str] = &[
+    "iss", "sub", "aud", "exp", "nbf", "iat", "jti", "scope", "fgp", "sid", "act",
+];
+
+/// One entry of the signing keyring: an HS256 `secret` or an RS256 PKCS#8 PEM at `private_key_path`.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct KeyConfig {
+    /// Key id stamped into the `kid` header of tokens signed with this key
+    pub kid: String,
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Path to a private key generated with `rcauth gen-secret --rsa`
+    #[serde(default)]
+    pub private_key_path: Option<String>,
+}
+
+impl fmt::Debug for KeyConfig {
+    /// Formats the entry with the secret masked, so it is safe to log.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyConfig")
+            .field("kid", &self.kid)
+            .field("secret", &self.secret.as_ref().map(|_| "***"))
+            .field("private_key_path", &self.private_key_path)
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Config {
+    /// HS256 signing secret; generate one with `rcauth gen-secret`. Ignored when `jwt_keys` is set.
+    #[serde(default)]
+    pub jwt_secret: String,
+    /// Keyring for rotation: tokens are signed with `jwt_current_kid` and verified against any key listed here.
+    #[serde(default)]
+    pub jwt_keys: Vec<KeyConfig>,
+    #[serde(default)]
+    pub jwt_current_kid: Option<String>,
+    #[serde(default = "default_jwt_issuer")]
+    pub jwt_issuer: String,
+    #[serde(default = "default_jwt_audience")]
+    pub jwt_audience: Vec<String>,
+    #[serde(default = "default_access_token_ttl_secs")]
+    pub access_token_ttl_secs: u64,
+    #[serde(default = "default_refresh_token_ttl_secs")]
+    pub refresh_token_ttl_secs: u64,
+    /// Seconds of clock skew tolerated when checking `exp`, `nbf` and `iat`, for servers whose clocks drift apart
+    #[serde(default = "default_jwt_leeway_secs")]
+    pub jwt_leeway_secs: u64,
+}
+
+/// Returns the default `iss` claim for issued tokens.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_jwt_issuer(), "rcauth");
+/// ```
+fn default_jwt_issuer() -> String {
+    "rcauth".to_string()
+}
+
+/// Returns the default accepted audiences.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_jwt_audience(), vec!["rcauth"]);
+/// ```
+fn default_jwt_audience() -> Vec<String> {
+    vec!["rcauth".to_string()]
+}
+
+/// Returns the default access token lifetime of 15 minutes.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_access_token_ttl_secs(), 900);
+/// ```
+fn default_access_token_ttl_secs() -> u64 {
+    15 * 60
+}
+
+/// Returns the default refresh token lifetime of 30 days.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_refresh_token_ttl_secs(), 2_592_000);
+/// ```
+fn default_refresh_token_ttl_secs() -> u64 {
+    30 * 24 * 60 * 60
+}
+
+/// Returns the default clock skew tolerance of 30 seconds.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_jwt_leeway_secs(), 30);
+/// ```
+fn default_jwt_leeway_secs() -> u64 {
+    30
+}
+
+impl Default for Config {
+    /// Returns a `Config` with the default issuer and audience and no secret, which fails validation until one is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::jwt::Config;
+    /// let config = Config::default();
+    /// assert_eq!(config.jwt_issuer, "rcauth");
+    /// assert!(config.validate().is_err());
+    /// ```
+    fn default() -> Self {
+        Self {
+            jwt_secret: String::new(),
+            jwt_keys: Vec::new(),
+            jwt_current_kid: None,
+            jwt_issuer: default_jwt_issuer(),
+            jwt_audience: default_jwt_audience(),
+            access_token_ttl_secs: default_access_token_ttl_secs(),
+            refresh_token_ttl_secs: default_refresh_token_ttl_secs(),
+            jwt_leeway_secs: default_jwt_leeway_secs(),
+        }
+    }
+}
+
+impl fmt::Debug for Config {
+    /// Formats the configuration with the secret masked, so it is safe to log.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("jwt_secret", &"***")
+            .field("jwt_keys", &self.jwt_keys)
+            .field("jwt_current_kid", &self.jwt_current_kid)
+            .field("jwt_issuer", &self.jwt_issuer)
+            .field("jwt_audience", &self.jwt_audience)
+            .field("access_token_ttl_secs", &self.access_token_ttl_secs)
+            .field("refresh_token_ttl_secs", &self.refresh_token_ttl_secs)
+            .field("jwt_leeway_secs", &self.jwt_leeway_secs)
+            .finish()
+    }
+}
+
+impl Config {
+    /// Loads JWT configuration from environment variables prefixed with `RCAUTH_JWT_`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::jwt::Config;
+    /// let config = Config::new().expect("Failed to load JWT config");
+    /// ```
+    #[allow(clippy::result_large_err)] // figment::Error, as returned by figment itself
+    pub fn new() -> std::result::Result<Self, figment::Error> {
+        Figment::new().merge(Env::prefixed("RCAUTH_JWT_")).extract()
+    }
+
+    /// Validates that the signing keys are usable, that an issuer and at least one audience are set, and that access tokens expire before refresh tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::jwt::Config;
+    /// let config = Config {
+    ///     jwt_secret: "0123456789abcdef0123456789abcdef".to_string(),
+    ///     ..Config::default()
+    /// };
+    /// assert!(config.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.validate_keys()?;
+        if self.jwt_issuer.is_empty() {
+            return Err("jwt_issuer cannot be empty".into());
+        }
+        if self.jwt_audience.iter().all(|aud| aud.is_empty()) {
+            return Err("jwt_audience must list at least one audience".into());
+        }
+        if self.access_token_ttl_secs == 0 {
+            return Err("access_token_ttl_secs must be greater than zero".into());
+        }
+        if self.access_token_ttl_secs >= self.refresh_token_ttl_secs {
+            return Err(format!(
+                "access_token_ttl_secs ({}) must be shorter than refresh_token_ttl_secs ({})",
+                self.access_token_ttl_secs, self.refresh_token_ttl_secs
+            )
+            .into());
+        }
+        // A leeway this long would let every access token outlive its lifetime twice over
+        if self.jwt_leeway_secs >= self.access_token_ttl_secs {
+            return Err(format!(
+                "jwt_leeway_secs ({}) must be shorter than access_token_ttl_secs ({})",
+                self.jwt_leeway_secs, self.access_token_ttl_secs
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Checks `jwt_keys`, or `jwt_secret` when no keyring is configured. Private keys are only read by [`Jwt::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let config = Config { jwt_secret: "too-short".to_string(), ..Config::default() };
+    /// assert!(config.validate_keys().is_err());
+    /// ```
+    fn validate_keys(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if self.jwt_keys.is_empty() {
+            if self.jwt_secret.len() < MIN_SECRET_BYTES {
+                return Err(
+                    format!("jwt_secret must be at least {} bytes", MIN_SECRET_BYTES).into(),
+                );
+            }
+            return Ok(());
+        }
+
+        let mut kids = HashSet::new();
+        for key in &self.jwt_keys {
+            if key.kid.is_empty() {
+                return Err("jwt_keys entries must have a kid".into());
+            }
+            if !kids.insert(key.kid.as_str()) {
+                return Err(format!("jwt_keys lists kid '{}' more than once", key.kid).into());
+            }
+            match (&key.secret, &key.private_key_path) {
+                (Some(secret), None) if secret.len() < MIN_SECRET_BYTES => {
+                    return Err(format!(
+                        "secret for kid '{}' must be at least {} bytes",
+                        key.kid, MIN_SECRET_BYTES
+                    )
+                    .into());
+                }
+                (Some(_), None) | (None, Some(_)) => {}
+                _ => {
+                    return Err(format!(
+                        "kid '{}' must set exactly one of secret or private_key_path",
+                        key.kid
+                    )
+                    .into());
+                }
+            }
+        }
+
+        match &self.jwt_current_kid {
+            Some(kid) if kids.contains(kid.as_str()) => Ok(()),
+            Some(kid) => Err(format!("jwt_current_kid '{}' is not in jwt_keys", kid).into()),
+            None => Err("jwt_current_kid is required when jwt_keys is set".into()),
+        }
+    }
+}
+
+/// A named key that tokens can be signed and verified with.
+#[derive(Clone)]
+pub struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    /// Public half, for asymmetric keys only
+    jwk: Option<Jwk>,
+}
+
+impl SigningKey {
+    /// Creates an HS256 key. Shared secrets are never published in the JWKS.
+    pub fn hs256(kid: impl Into<String>, secret: &[u8]) -> Self {
+        Self {
+            kid: kid.into(),
+            algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            jwk: None,
+        }
+    }
+
+    /// Creates an RS256 key from a PKCS#8 PEM private key, as printed by `rcauth gen-secret --rsa`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if `pem` is not an RSA private key.
+    pub fn rs256_pem(kid: impl Into<String>, pem: &str) -> Result<Self> {
+        let kid = kid.into();
+        let invalid = |e: &dyn fmt::Display| {
+            Error::new_simple(
+                ErrorCode::ConfigurationError,
+                format!("Invalid RSA private key for kid '{}'", kid),
+            )
+            .with_internal(e.to_string())
+        };
+
+        let private_key = RsaPrivateKey::from_pkcs8_pem(pem).map_err(|e| invalid(&e))?;
+        let n = URL_SAFE_NO_PAD.encode(private_key.n().to_bytes_be());
+        let e = URL_SAFE_NO_PAD.encode(private_key.e().to_bytes_be());
+
+        let encoding_key = EncodingKey::from_rsa_pem(pem.as_bytes()).map_err(|e| invalid(&e))?;
+        let decoding_key = DecodingKey::from_rsa_components(&n, &e).map_err(|e| invalid(&e))?;
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(KeyAlgorithm::RS256),
+                key_id: Some(kid.clone()),
+                ..CommonParameters::default()
+            },
+            algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n,
+                e,
+            }),
+        };
+
+        Ok(Self {
+            kid,
+            algorithm: Algorithm::RS256,
+            encoding_key,
+            decoding_key,
+            jwk: Some(jwk),
+        })
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+}
+
+/// The set of keys tokens are verified against, one of which signs new tokens.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::jwt::{Keyring, SigningKey};
+/// let keyring = Keyring::new(
+///     vec![
+///         SigningKey::hs256("2024-01", b"0123456789abcdef0123456789abcdef"),
+///         SigningKey::hs256("2024-06", b"fedcba9876543210fedcba9876543210"),
+///     ],
+///     "2024-06",
+/// )
+/// .unwrap();
+/// assert_eq!(keyring.current().kid(), "2024-06");
+/// assert!(keyring.get("2024-01").is_some());
+/// ```
+#[derive(Clone)]
+pub struct Keyring {
+    keys: Vec<SigningKey>,
+    current: usize,
+}
+
+impl Keyring {
+    /// Creates a keyring that signs with `current_kid`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if `current_kid` is not among `keys`.
+    pub fn new(keys: Vec<SigningKey>, current_kid: &str) -> Result<Self> {
+        let current = keys
+            .iter()
+            .position(|key| key.kid == current_kid)
+            .ok_or_else(|| {
+                Error::new_simple(
+                    ErrorCode::ConfigurationError,
+                    format!("Signing key '{}' is not in the keyring", current_kid),
+                )
+            })?;
+        Ok(Self { keys, current })
+    }
+
+    /// Builds the keyring from `jwt_keys`, reading any private keys from disk, or from `jwt_secret` under [`DEFAULT_KID`] when no keys are listed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if a private key cannot be read or parsed, or the current kid is missing.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        if config.jwt_keys.is_empty() {
+            let key = SigningKey::hs256(DEFAULT_KID, config.jwt_secret.as_bytes());
+            return Self::new(vec![key], DEFAULT_KID);
+        }
+
+        let keys = config
+            .jwt_keys
+            .iter()
+            .map(|key| match (&key.secret, &key.private_key_path) {
+                (Some(secret), _) => Ok(SigningKey::hs256(&key.kid, secret.as_bytes())),
+                (None, Some(path)) => {
+                    let pem = std::fs::read_to_string(path).map_err(|e| {
+                        Error::new(
+                            ErrorCode::ConfigurationError,
+                            format!("Failed to read private key for kid '{}'", key.kid),
+                            e,
+                        )
+                    })?;
+                    SigningKey::rs256_pem(&key.kid, &pem)
+                }
+                (None, None) => Err(Error::new_simple(
+                    ErrorCode::ConfigurationError,
+                    format!("kid '{}' has no secret or private_key_path", key.kid),
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(keys, config.jwt_current_kid.as_deref().unwrap_or_default())
+    }
+
+    /// Returns the key new tokens are signed with.
+    pub fn current(&self) -> &SigningKey {
+        &self.keys[self.current]
+    }
+
+    pub fn get(&self, kid: &str) -> Option<&SigningKey> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+
+    /// Returns the public keys of every asymmetric key in the ring, for publishing at the JWKS endpoint.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.keys.iter().filter_map(|key| key.jwk.clone()).collect(),
+        }
+    }
+}
+
+/// Party acting on behalf of a token's subject, as in the `act` claim of RFC 8693.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Actor {
+    /// Id of the acting user, e.g. the admin impersonating the subject
+    pub sub: String,
+}
+
+/// Registered claims carried by every token, and any custom claims issued alongside them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    /// Subject, usually the user id
+    pub sub: String,
+    /// Issuer
+    pub iss: String,
+    /// Audiences the token is intended for
+    pub aud: Vec<String>,
+    /// Issued-at time, in seconds since the Unix epoch
+    pub iat: u64,
+    /// Expiry time, in seconds since the Unix epoch
+    pub exp: u64,
+    /// Space-delimited scopes granted to the bearer, as in RFC 8693; absent when none were granted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Fingerprint of the client the token is bound to; absent on unbound tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fgp: Option<String>,
+    /// Login session the token was issued for, as in OpenID Connect; absent on tokens outside a session, e.g. a machine client's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// Who is really acting when the token impersonates its subject; absent on the subject's own tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+    /// Claims beyond the ones above, e.g. a plan or feature flags for downstream services
+    #[serde(flatten)]
+    pub custom: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Claims {
+    /// Scopes granted to the bearer, in the order they were issued.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::jwt::Claims;
+    /// let claims = Claims {
+    ///     sub: "client-1".to_string(),
+    ///     iss: "rcauth".to_string(),
+    ///     aud: vec!["rcauth".to_string()],
+    ///     iat: 0,
+    ///     exp: 60,
+    ///     scope: Some("users:read users:write".to_string()),
+    ///     fgp: None,
+    ///     sid: None,
+    ///     act: None,
+    ///     custom: Default::default(),
+    /// };
+    /// assert_eq!(claims.scopes().collect::<Vec<_>>(), ["users:read", "users:write"]);
+    /// ```
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.as_deref().unwrap_or_default().split_whitespace()
+    }
+
+    /// Whether `scope` was granted, compared exactly: `users` does not grant `users:read`.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().any(|granted| granted == scope)
+    }
+
+    /// Whether someone other than the subject is acting with the token; such tokens belong to no session and are never refreshed.
+    pub fn is_impersonation(&self) -> bool {
+        self.act.is_some()
+    }
+}
+
+/// What a token carries beyond the registered claims, handed to [`Jwt::issue`]; every option combines with the others.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::jwt::TokenOptions;
+/// let options = TokenOptions::default()
+///     .session("session-1")
+///     .scopes(&["openid", "email"])
+///     .claim("plan", "pro");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TokenOptions {
+    ttl: Option<Duration>,
+    scopes: Vec<String>,
+    fingerprint: Option<String>,
+    session_id: Option<String>,
+    actor: Option<String>,
+    custom: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TokenOptions {
+    /// Expires the token after `ttl`, or the configured `access_token_ttl_secs` if that is shorter.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Grants `scopes`, e.g. to a machine client. Repeated scopes are granted once.
+    pub fn scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes
+            .extend(scopes.iter().map(|scope| scope.to_string()));
+        self
+    }
+
+    /// Binds the token to the client `fingerprint`, so it is refused when presented by another client.
+    pub fn bound_to(mut self, fingerprint: &str) -> Self {
+        self.fingerprint = Some(fingerprint.to_string());
+        self
+    }
+
+    /// Issues the token in the login session `session_id`, so requests made with it can be told apart from the user's other sessions.
+    pub fn session(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
+    /// Names `actor` in the `act` claim, for a token someone else uses on the subject's behalf, e.g. a support engineer reproducing their issue.
+    pub fn actor(mut self, actor: &str) -> Self {
+        self.actor = Some(actor.to_string());
+        self
+    }
+
+    /// Adds the custom claim `name`, e.g. a plan or feature flags for downstream services. It must not be one of the [`RESERVED_CLAIMS`].
+    pub fn claim(mut self, name: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.custom.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Adds the claims of the user's record: `email`, `email_verified` and, if they gave one, `name`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::jwt::TokenOptions;
+    /// # use rcauth_core::user::UserProfile;
+    /// let user = UserProfile {
+    ///     id: "user-1".to_string(),
+    ///     email: "ada@example.com".to_string(),
+    ///     email_verified: true,
+    ///     name: None,
+    ///     must_reset_password: false,
+    ///     version: 1,
+    /// };
+    /// let options = TokenOptions::default().user_claims(&user);
+    /// ```
+    pub fn user_claims(self, user: &UserProfile) -> Self {
+        let options = self
+            .claim("email", user.email.as_str())
+            .claim("email_verified", user.email_verified);
+        match &user.name {
+            Some(name) => options.claim("name", name.as_str()),
+            None => options,
+        }
+    }
+}
+
+/// Signs tokens with the keyring's current key and verifies them against the key named by their `kid`, checking the configured issuer and audiences.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::jwt::{Config, Jwt, TokenOptions};
+/// let jwt = Jwt::new(&Config {
+///     jwt_secret: "0123456789abcdef0123456789abcdef".to_string(),
+///     ..Config::default()
+/// })
+/// .unwrap();
+/// let token = jwt.issue("user-1", TokenOptions::default()).unwrap();
+/// assert_eq!(jwt.decode(&token).unwrap().sub, "user-1");
+/// ```
+#[derive(Clone)]
+pub struct Jwt {
+    keyring: Keyring,
+    issuer: String,
+    audience: Vec<String>,
+    access_token_ttl: Duration,
+    refresh_token_ttl: Duration,
+    leeway: Duration,
+}
+
+impl Jwt {
+    /// Creates a signer from `config`, loading its keyring.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if the keyring cannot be built; see [`Keyring::from_config`].
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Self::with_keyring(Keyring::from_config(config)?, config))
+    }
+
+    /// Creates a signer using `keyring` in place of the keys in `config`.
+    pub fn with_keyring(keyring: Keyring, config: &Config) -> Self {
+        Self {
+            keyring,
+            issuer: config.jwt_issuer.clone(),
+            audience: config.jwt_audience.clone(),
+            access_token_ttl: Duration::from_secs(config.access_token_ttl_secs),
+            refresh_token_ttl: Duration::from_secs(config.refresh_token_ttl_secs),
+            leeway: Duration::from_secs(config.jwt_leeway_secs),
+        }
+    }
+
+    pub fn keyring(&self) -> &Keyring {
+        &self.keyring
+    }
+
+    /// Issues a token for `subject` carrying `options`, stamped with the configured issuer and audiences, that expires after the configured `access_token_ttl_secs` unless `options` shortens it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` on `scope` if a scope is empty or has characters RFC 6749 does not allow, such as spaces or quotes, and on `claims` if a custom claim uses one of the [`RESERVED_CLAIMS`]. Returns an `Internal` error if the token cannot be signed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::jwt::{Config, Jwt, TokenOptions};
+    /// let jwt = Jwt::new(&Config {
+    ///     jwt_secret: "0123456789abcdef0123456789abcdef".to_string(),
+    ///     ..Config::default()
+    /// })
+    /// .unwrap();
+    /// let options = TokenOptions::default().session("session-1").claim("plan", "pro");
+    /// let claims = jwt.decode(&jwt.issue("user-1", options).unwrap()).unwrap();
+    /// assert_eq!(claims.sid.as_deref(), Some("session-1"));
+    /// assert_eq!(claims.custom["plan"], "pro");
+    /// ```
+    pub fn issue(&self, subject: &str, options: TokenOptions) -> Result<String> {
+        self.encode(&self.claims(subject, options)?)
+    }
+
+    /// Returns when a refresh token issued now expires, in seconds since the Unix epoch, for storing alongside it.
+    pub fn refresh_token_expires_at(&self) -> u64 {
+        jsonwebtoken::get_current_timestamp() + self.refresh_token_ttl.as_secs()
+    }
+
+    /// Claims of a token for `subject` carrying `options`, after checking its scopes and custom claims.
+    fn claims(&self, subject: &str, options: TokenOptions) -> Result<Claims> {
+        let mut errors = ValidationErrors::new();
+        let scopes: Vec<&str> = options.scopes.iter().map(String::as_str).collect();
+        let scope = scope_claim(&scopes, &mut errors);
+        for name in options
+            .custom
+            .keys()
+            .filter(|name| RESERVED_CLAIMS.contains(&name.as_str()))
+        {
+            errors.add("claims", format!("'{}' is a reserved claim", name));
+        }
+        errors.into_result()?;
+
+        let ttl = options
+            .ttl
+            .map_or(self.access_token_ttl, |ttl| ttl.min(self.access_token_ttl));
+        let now = jsonwebtoken::get_current_timestamp();
+        Ok(Claims {
+            sub: subject.to_string(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            iat: now,
+            exp: now + ttl.as_secs(),
+            scope,
+            fgp: options.fingerprint,
+            sid: options.session_id,
+            act: options.actor.map(|sub| Actor { sub }),
+            custom: options.custom,
+        })
+    }
+
+    /// Signs `claims` as-is with the current key, naming it in the `kid` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Internal` error if the token cannot be signed.
+    pub fn encode(&self, claims: &Claims) -> Result<String> {
+        let key = self.keyring.current();
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+        jsonwebtoken::encode(&header, claims, &key.encoding_key)
+            .map_err(|e| Error::new(ErrorCode::Internal, "Failed to sign token", e))
+    }
+
+    /// Verifies the signature and expiry of `token` against the key named by its `kid`, and that its `iss` matches the configured issuer and its `aud` includes one of the configured audiences.
+    ///
+    /// Tokens without a `kid` are checked against the current key. Timestamps are checked with the configured `jwt_leeway_secs` of tolerance: a token stays valid that long past its `exp`, and is accepted that long before its `nbf` or `iat`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Unauthorized` error if the token is malformed, expired, not yet valid, signed with a key not in the ring, or has a missing or mismatched `iss` or `aud`. Its `reason` detail is [`TOKEN_EXPIRED`] for a token whose signature verified but whose `exp` has passed, and [`TOKEN_INVALID`] otherwise.
+    pub fn decode(&self, token: &str) -> Result<Claims> {
+        let rejected = |reason: String| {
+            Error::new_simple(ErrorCode::Unauthorized, "Invalid token")
+                .with_data("reason", serde_json::json!(TOKEN_INVALID))
+                .with_internal(format!("Token rejected: {}", reason))
+        };
+
+        let header = jsonwebtoken::decode_header(token).map_err(|e| rejected(e.to_string()))?;
+        let key = match &header.kid {
+            Some(kid) => self
+                .keyring
+                .get(kid)
+                .ok_or_else(|| rejected(format!("unknown kid '{}'", kid)))?,
+            None => self.keyring.current(),
+        };
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&self.audience);
+        validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+        validation.leeway = self.leeway.as_secs();
+        validation.validate_nbf = true;
+
+        let claims = jsonwebtoken::decode::<Claims>(token, &key.decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                // The signature is checked before the expiry, so the token is genuine
+                ErrorKind::ExpiredSignature => {
+                    Error::new_simple(ErrorCode::Unauthorized, "Token has expired")
+                        .with_data("reason", serde_json::json!(TOKEN_EXPIRED))
+                }
+                _ => rejected(e.to_string()),
+            })?;
+
+        // Only a clock running ahead of ours issues tokens from the future
+        if claims.iat > jsonwebtoken::get_current_timestamp() + self.leeway.as_secs() {
+            return Err(rejected(format!("iat {} is in the future", claims.iat)));
+        }
+        Ok(claims)
+    }
+}
+
+/// Joins `scopes` into a `scope` claim, dropping repeats, or records the first malformed one in `errors`.
+///
+/// ```ignore
+/// assert_eq!(scope_claim(&["a", "b", "a"], &mut errors), Some("a b".to_string()));
+/// assert_eq!(scope_claim(&[], &mut errors), None);
+/// ```
+fn scope_claim(scopes: &[&str], errors: &mut ValidationErrors) -> Option<String> {
+    // scope-token = 1*( %x21 / %x23-5B / %x5D-7E ), RFC 6749 section 3.3
+    let valid = |scope: &&str| {
+        !scope.is_empty()
+            && scope
+                .bytes()
+                .all(|b| matches!(b, 0x21 | 0x23..=0x5B | 0x5D..=0x7E))
+    };
+    if let Some(scope) = scopes.iter().find(|scope| !valid(scope)) {
+        errors.add("scope", format!("'{}' is not a valid scope", scope));
+        return None;
+    }
+
+    let mut seen = HashSet::new();
+    let unique: Vec<&str> = scopes.iter().copied().filter(|s| seen.insert(*s)).collect();
+    (!unique.is_empty()).then(|| unique.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn jwt(issuer: &str, audience: &[&str]) -> Jwt {
+        Jwt::new(&Config {
+            jwt_secret: SECRET.to_string(),
+            jwt_issuer: issuer.to_string(),
+            jwt_audience: audience.iter().map(|a| a.to_string()).collect(),
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    fn keyring_jwt(keys: &[(&str, &str)], current_kid: &str) -> Jwt {
+        Jwt::new(&Config {
+            jwt_keys: keys
+                .iter()
+                .map(|(kid, secret)| KeyConfig {
+                    kid: kid.to_string(),
+                    secret: Some(secret.to_string()),
+                    private_key_path: None,
+                })
+                .collect(),
+            jwt_current_kid: Some(current_kid.to_string()),
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    const OLD_SECRET: &str = "old-secret-0123456789abcdef012345";
+    const NEW_SECRET: &str = "new-secret-0123456789abcdef012345";
+
+    #[test]
+    fn matching_issuer_and_audience_are_accepted() {
+        let token = jwt("rcauth", &["api"])
+            .issue("user-1", TokenOptions::default())
+            .unwrap();
+
+        // Any one of several acceptable audiences is enough
+        let claims = jwt("rcauth", &["admin", "api"]).decode(&token).unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.iss, "rcauth");
+        assert_eq!(claims.aud, vec!["api"]);
+    }
+
+    #[test]
+    fn expired_token_is_told_apart_from_invalid_one() {
+        let jwt = jwt("rcauth", &["api"]);
+        let now = jsonwebtoken::get_current_timestamp();
+        let expired = jwt
+            .encode(&Claims {
+                sub: "user-1".to_string(),
+                iss: "rcauth".to_string(),
+                aud: vec!["api".to_string()],
+                iat: now - 3600,
+                exp: now - 600,
+                scope: None,
+                fgp: None,
+                sid: None,
+                act: None,
+                custom: serde_json::Map::new(),
+            })
+            .unwrap();
+
+        let err = jwt.decode(&expired).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+        assert_eq!(err.data.unwrap()["reason"], TOKEN_EXPIRED);
+
+        let err = jwt.decode("not.a.token").unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+        assert_eq!(err.data.unwrap()["reason"], TOKEN_INVALID);
+    }
+
+    #[test]
+    fn clock_skew_within_leeway_is_tolerated() {
+        let jwt = jwt("rcauth", &["api"]);
+        let now = jsonwebtoken::get_current_timestamp();
+        let token = |iat: u64, exp: u64| {
+            jwt.encode(&Claims {
+                sub: "user-1".to_string(),
+                iss: "rcauth".to_string(),
+                aud: vec!["api".to_string()],
+                iat,
+                exp,
+                scope: None,
+                fgp: None,
+                sid: None,
+                act: None,
+                custom: serde_json::Map::new(),
+            })
+            .unwrap()
+        };
+
+        // Barely expired, or issued by a server whose clock runs slightly ahead
+        assert!(jwt.decode(&token(now - 900, now - 10)).is_ok());
+        assert!(jwt.decode(&token(now + 10, now + 900)).is_ok());
+
+        let err = jwt.decode(&token(now - 900, now - 120)).unwrap_err();
+        assert_eq!(err.data.unwrap()["reason"], TOKEN_EXPIRED);
+        let err = jwt.decode(&token(now + 120, now + 900)).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+        assert_eq!(err.data.unwrap()["reason"], TOKEN_INVALID);
+    }
+
+    #[test]
+    fn nbf_is_checked_with_leeway() {
+        let now = jsonwebtoken::get_current_timestamp();
+        let token = |nbf: u64| {
+            let claims = serde_json::json!({
+                "sub": "user-1",
+                "iss": "rcauth",
+                "aud": ["api"],
+                "iat": now,
+                "exp": now + 900,
+                "nbf": nbf,
+            });
+            jsonwebtoken::encode(
+                &Header::default(),
+                &claims,
+                &EncodingKey::from_secret(SECRET.as_bytes()),
+            )
+            .unwrap()
+        };
+
+        let jwt = jwt("rcauth", &["api"]);
+        assert!(jwt.decode(&token(now + 10)).is_ok());
+        let err = jwt.decode(&token(now + 120)).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+    }
+
+    #[test]
+    fn custom_claims_survive_a_round_trip() {
+        let jwt = jwt("rcauth", &["api"]);
+        let options = TokenOptions::default()
+            .claim("tenant_id", "tenant-1")
+            .claim("plan", "pro")
+            .claim("features", serde_json::json!({ "sso": true, "seats": 25 }));
+
+        let token = jwt.issue("user-1", options).unwrap();
+        let claims = jwt.decode(&token).unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(
+            serde_json::Value::Object(claims.custom),
+            serde_json::json!({
+                "tenant_id": "tenant-1",
+                "plan": "pro",
+                "features": { "sso": true, "seats": 25 },
+            })
+        );
+
+        // Tokens without custom claims decode with none
+        let plain = jwt
+            .decode(&jwt.issue("user-1", TokenOptions::default()).unwrap())
+            .unwrap();
+        assert!(plain.custom.is_empty());
+    }
+
+    #[test]
+    fn reserved_claim_names_are_rejected() {
+        let jwt = jwt("rcauth", &["api"]);
+        for reserved in RESERVED_CLAIMS {
+            let options = TokenOptions::default()
+                .claim(reserved, "admin")
+                .claim("plan", "pro");
+
+            let err = jwt.issue("user-1", options).unwrap_err();
+            assert_eq!(err.code, ErrorCode::ValidationError);
+            assert_eq!(
+                err.data.unwrap()["fields"]["claims"],
+                serde_json::json!([format!("'{}' is a reserved claim", reserved)])
+            );
+        }
+    }
+
+    #[test]
+    fn options_combine_with_claims_from_the_user_record() {
+        let jwt = jwt("rcauth", &["api"]);
+        let user = UserProfile {
+            id: "user-1".to_string(),
+            email: "ada@example.com".to_string(),
+            email_verified: true,
+            name: Some("Ada".to_string()),
+            must_reset_password: false,
+            version: 1,
+        };
+        let options = TokenOptions::default()
+            .scopes(&["openid"])
+            .bound_to("3f9a")
+            .session("session-1")
+            .user_claims(&user)
+            .claim("plan", "pro");
+
+        let claims = jwt.decode(&jwt.issue(&user.id, options).unwrap()).unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.scope.as_deref(), Some("openid"));
+        assert_eq!(claims.fgp.as_deref(), Some("3f9a"));
+        assert_eq!(claims.sid.as_deref(), Some("session-1"));
+        assert_eq!(
+            serde_json::Value::Object(claims.custom),
+            serde_json::json!({
+                "email": "ada@example.com",
+                "email_verified": true,
+                "name": "Ada",
+                "plan": "pro",
+            })
+        );
+    }
+
+    #[test]
+    fn scopes_are_issued_and_checked_exactly() {
+        let jwt = jwt("rcauth", &["api"]);
+        let token = jwt
+            .issue(
+                "client-1",
+                TokenOptions::default().scopes(&["users:read", "audit:read", "users:read"]),
+            )
+            .unwrap();
+        let claims = jwt.decode(&token).unwrap();
+
+        assert_eq!(claims.scope.as_deref(), Some("users:read audit:read"));
+        assert!(claims.has_scope("users:read"));
+        assert!(!claims.has_scope("users:write"));
+        assert!(!claims.has_scope("users"));
+
+        let unscoped = jwt
+            .decode(&jwt.issue("user-1", TokenOptions::default()).unwrap())
+            .unwrap();
+        assert_eq!(unscoped.scope, None);
+        assert!(!unscoped.has_scope("users:read"));
+
+        for bad in ["", "users read", "users\"read"] {
+            let err = jwt
+                .issue("client-1", TokenOptions::default().scopes(&[bad]))
+                .unwrap_err();
+            assert_eq!(err.code, ErrorCode::ValidationError);
+        }
+    }
+
+    #[test]
+    fn mismatched_issuer_or_audience_is_rejected() {
+        let token = jwt("rcauth", &["api"])
+            .issue("user-1", TokenOptions::default())
+            .unwrap();
+
+        let err = jwt("someone-else", &["api"]).decode(&token).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+        assert!(jwt("rcauth", &["billing"]).decode(&token).is_err());
+    }
+
+    #[test]
+    fn token_missing_claims_is_rejected() {
+        #[derive(Serialize)]
+        struct Bare {
+            sub: String,
+            exp: u64,
+        }
+
+        let bare = Bare {
+            sub: "user-1".to_string(),
+            exp: jsonwebtoken::get_current_timestamp() + 60,
+        };
+        let token = jsonwebtoken::encode(
+            &Header::default(),
+            &bare,
+            &EncodingKey::from_secret(SECRET.as_bytes()),
+        )
+        .unwrap();
+
+        let err = jwt("rcauth", &["api"]).decode(&token).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+    }
+
+    #[test]
+    fn issued_tokens_carry_configured_expiry() {
+        let jwt = Jwt::new(&Config {
+            jwt_secret: SECRET.to_string(),
+            access_token_ttl_secs: 120,
+            refresh_token_ttl_secs: 3600,
+            ..Config::default()
+        })
+        .unwrap();
+
+        let before = jsonwebtoken::get_current_timestamp();
+        let claims = jwt
+            .decode(&jwt.issue("user-1", TokenOptions::default()).unwrap())
+            .unwrap();
+        assert_eq!(claims.exp - claims.iat, 120);
+        assert!(claims.iat >= before);
+
+        let refresh_expiry = jwt.refresh_token_expires_at();
+        assert!((before + 3600..=before + 3601).contains(&refresh_expiry));
+    }
+
+    #[test]
+    fn access_ttl_must_be_shorter_than_refresh_ttl() {
+        let config = Config {
+            jwt_secret: SECRET.to_string(),
+            access_token_ttl_secs: 7200,
+            refresh_token_ttl_secs: 3600,
+            ..Config::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("access_token_ttl_secs"));
+    }
+
+    #[test]
+    fn leeway_must_be_shorter_than_access_ttl() {
+        let config = Config {
+            jwt_secret: SECRET.to_string(),
+            access_token_ttl_secs: 60,
+            jwt_leeway_secs: 60,
+            ..Config::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("jwt_leeway_secs"));
+    }
+
+    #[test]
+    fn short_secret_fails_validation() {
+        let config = Config {
+            jwt_secret: "too-short".to_string(),
+            ..Config::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn rotated_keyring_signs_with_new_key_and_verifies_old_tokens() {
+        let before = keyring_jwt(&[("old", OLD_SECRET)], "old");
+        let old_token = before.issue("user-1", TokenOptions::default()).unwrap();
+
+        let rotated = keyring_jwt(&[("old", OLD_SECRET), ("new", NEW_SECRET)], "new");
+        let new_token = rotated.issue("user-2", TokenOptions::default()).unwrap();
+        let header = jsonwebtoken::decode_header(&new_token).unwrap();
+        assert_eq!(header.kid.as_deref(), Some("new"));
+
+        // In-flight tokens signed before the rotation still verify
+        assert_eq!(rotated.decode(&old_token).unwrap().sub, "user-1");
+        assert_eq!(rotated.decode(&new_token).unwrap().sub, "user-2");
+
+        // Once the old key is retired, its tokens are rejected
+        let retired = keyring_jwt(&[("new", NEW_SECRET)], "new");
+        let err = retired.decode(&old_token).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Unauthorized);
+        assert!(retired.decode(&new_token).is_ok());
+    }
+
+    #[test]
+    fn impersonation_token_names_the_actor_and_belongs_to_no_session() {
+        let jwt = jwt("rcauth", &["rcauth"]);
+        let token = jwt
+            .issue(
+                "user-1",
+                TokenOptions::default()
+                    .actor("admin-1")
+                    .ttl(Duration::from_secs(86_400)),
+            )
+            .unwrap();
+
+        let claims = jwt.decode(&token).unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(
+            claims.act,
+            Some(Actor {
+                sub: "admin-1".to_string()
+            })
+        );
+        assert!(claims.is_impersonation());
+        assert_eq!(claims.sid, None);
+        // Never outlives an ordinary access token
+        assert_eq!(claims.exp - claims.iat, 900);
+
+        let own = jwt
+            .decode(&jwt.issue("user-1", TokenOptions::default()).unwrap())
+            .unwrap();
+        assert!(!own.is_impersonation());
+
+        let forged = TokenOptions::default().claim("act", serde_json::json!({ "sub": "admin-1" }));
+        assert!(jwt.issue("user-1", forged).is_err());
+    }
+
+    #[test]
+    fn kid_selects_the_verifying_key() {
+        // A token claiming the old kid but signed with the new secret must not verify
+        let forged = keyring_jwt(&[("old", NEW_SECRET)], "old")
+            .issue("user-1", TokenOptions::default())
+            .unwrap();
+        let rotated = keyring_jwt(&[("old", OLD_SECRET), ("new", NEW_SECRET)], "new");
+        assert!(rotated.decode(&forged).is_err());
+    }
+
+    #[test]
+    fn jwks_publishes_rsa_public_keys_only() {
+        use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+
+        let private_key = RsaPrivateKey::new(&mut rand::rngs::OsRng, 2048).unwrap();
+        let pem = private_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rs-2024.pem");
+        std::fs::write(&path, pem.as_bytes()).unwrap();
+
+        let jwt = Jwt::new(&Config {
+            jwt_keys: vec![
+                KeyConfig {
+                    kid: "hs-old".to_string(),
+                    secret: Some(OLD_SECRET.to_string()),
+                    private_key_path: None,
+                },
+                KeyConfig {
+                    kid: "rs-2024".to_string(),
+                    secret: None,
+                    private_key_path: Some(path.to_string_lossy().into_owned()),
+                },
+            ],
+            jwt_current_kid: Some("rs-2024".to_string()),
+            ..Config::default()
+        })
+        .unwrap();
+
+        let jwks = jwt.keyring().jwks();
+        assert_eq!(jwks.keys.len(), 1);
+        assert_eq!(jwks.keys[0].common.key_id.as_deref(), Some("rs-2024"));
+
+        // The published key alone is enough to verify tokens from the current signer
+        let token = jwt.issue("user-1", TokenOptions::default()).unwrap();
+        let public = DecodingKey::from_jwk(&jwks.keys[0]).unwrap();
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["rcauth"]);
+        let claims = jsonwebtoken::decode::<Claims>(&token, &public, &validation).unwrap();
+        assert_eq!(claims.claims.sub, "user-1");
+    }
+
+    #[test]
+    fn keyring_config_is_validated() {
+        let key = |kid: &str| KeyConfig {
+            kid: kid.to_string(),
+            secret: Some(NEW_SECRET.to_string()),
+            private_key_path: None,
+        };
+
+        let missing_current = Config {
+            jwt_keys: vec![key("a")],
+            ..Config::default()
+        };
+        assert!(missing_current.validate().is_err());
+
+        let unknown_current = Config {
+            jwt_keys: vec![key("a")],
+            jwt_current_kid: Some("b".to_string()),
+            ..Config::default()
+        };
+        assert!(unknown_current.validate().is_err());
+
+        let duplicate = Config {
+            jwt_keys: vec![key("a"), key("a")],
+            jwt_current_kid: Some("a".to_string()),
+            ..Config::default()
+        };
+        assert!(duplicate.validate().is_err());
+
+        // The keyring replaces jwt_secret, so an empty secret is fine here
+        let valid = Config {
+            jwt_keys: vec![key("a"), key("b")],
+            jwt_current_kid: Some("b".to_string()),
+            ..Config::default()
+        };
+        assert!(valid.validate().is_ok());
+    }
+}
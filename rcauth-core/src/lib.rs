@@ -1,4 +1,17 @@
 #![allow(dead_code)]
+pub mod audit;
+pub mod bootstrap;
+pub mod email;
 pub mod error;
+pub mod health;
+pub mod idempotency;
+pub mod identity;
+pub mod jwt;
 pub mod logger;
+pub mod metrics;
+pub mod password;
+pub mod settings;
 pub mod store;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod user;
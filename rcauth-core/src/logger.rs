@@ -2,20 +2,117 @@ use figment::{
     providers::Env,
     Figment,
 };
-use serde::Deserialize;
-use tracing::Level;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use tracing::{
+    Event, Level, Subscriber,
+    field::{DisplayValue, Field, Value, display},
+};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry,
+    field::{MakeVisitor, Visit, VisitFmt, VisitOutput},
+    fmt::{
+        FmtContext, FormatEvent, FormatFields,
+        format::{PrettyFields, Writer},
+        writer::{BoxMakeWriter, MakeWriter},
+    },
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    reload,
+};
+#[cfg(feature = "otel")]
+use {
+    opentelemetry::trace::TracerProvider as _,
+    opentelemetry_otlp::{SpanExporter, WithExportConfig},
+    opentelemetry_sdk::{Resource, trace::SdkTracerProvider},
+};
+
+use crate::error::{Error, ErrorCode, Result};
+
+/// Output formats supported by the tracing subscriber.
+const LOG_FORMATS: [&str; 3] = ["pretty", "compact", "json"];
+
+/// Levels accepted by [`LogFilter::set_level`].
+pub const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
 
-#[derive(Debug, Deserialize, Clone)]
+/// Rotation policies supported for file logging.
+const LOG_ROTATIONS: [&str; 3] = ["daily", "hourly", "never"];
+
+/// Field names masked in log output unless overridden by `log_redact_fields`.
+pub const DEFAULT_REDACTED_FIELDS: [&str; 6] = [
+    "password",
+    "token",
+    "secret",
+    "pepper",
+    "authorization",
+    "cookie",
+];
+
+/// Replacement written in place of a redacted value.
+const REDACTED: &str = "***";
+
+/// Redactor installed by [`Config::init`], used by [`redact_uri`] and [`redact_body`].
+static GLOBAL_REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
     #[serde(default = "default_log_level")]
     pub log_level: String,
+    #[serde(default = "default_log_format")]
+    pub log_format: String,
+    #[serde(default)]
+    pub log_directives: Option<String>,
+    #[serde(default)]
+    pub log_file: Option<String>,
+    #[serde(default = "default_log_rotation")]
+    pub log_rotation: String,
+    #[serde(default)]
+    pub otel_endpoint: Option<String>,
+    #[serde(default = "default_otel_service_name")]
+    pub otel_service_name: String,
+    #[serde(default = "default_log_redact_fields")]
+    pub log_redact_fields: Vec<String>,
+}
+
+/// Keeps background log writers and trace exporters alive, and gives access to the [`LogFilter`].
+///
+/// Dropping the guard flushes any buffered log lines and spans and shuts the exporters down, so it should be held for the lifetime of the process.
+#[must_use = "dropping the guard stops background log writers and trace exporters"]
+pub struct LogGuard {
+    worker: Option<WorkerGuard>,
+    filter: LogFilter,
+    #[cfg(feature = "otel")]
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl LogGuard {
+    /// Returns the handle for changing the subscriber's filter at runtime.
+    pub fn filter(&self) -> LogFilter {
+        self.filter.clone()
+    }
+}
+
+impl Drop for LogGuard {
+    /// Flushes and shuts down the OpenTelemetry tracer provider, if one was configured.
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.tracer_provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
 }
 
 /// Returns the default log level as a string ("info").
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// let level = default_log_level();
 /// assert_eq!(level, "info");
 /// ```
@@ -23,6 +120,57 @@ fn default_log_level() -> String {
     "info".to_string()
 }
 
+/// Returns the default log output format as a string ("pretty").
+///
+/// # Examples
+///
+/// ```ignore
+/// let format = default_log_format();
+/// assert_eq!(format, "pretty");
+/// ```
+fn default_log_format() -> String {
+    "pretty".to_string()
+}
+
+/// Returns the default log file rotation policy as a string ("daily").
+///
+/// # Examples
+///
+/// ```ignore
+/// let rotation = default_log_rotation();
+/// assert_eq!(rotation, "daily");
+/// ```
+fn default_log_rotation() -> String {
+    "daily".to_string()
+}
+
+/// Returns the default service name reported to the OpenTelemetry collector ("rcauth").
+///
+/// # Examples
+///
+/// ```ignore
+/// let name = default_otel_service_name();
+/// assert_eq!(name, "rcauth");
+/// ```
+fn default_otel_service_name() -> String {
+    "rcauth".to_string()
+}
+
+/// Returns the default list of field names redacted from log output.
+///
+/// # Examples
+///
+/// ```ignore
+/// let fields = default_log_redact_fields();
+/// assert!(fields.contains(&"password".to_string()));
+/// ```
+fn default_log_redact_fields() -> Vec<String> {
+    DEFAULT_REDACTED_FIELDS
+        .iter()
+        .map(|f| f.to_string())
+        .collect()
+}
+
 impl Config {
     /// Loads logger configuration from environment variables.
     ///
@@ -35,9 +183,11 @@ impl Config {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_core::logger::Config;
     /// let config = Config::new().expect("Failed to load logger config");
     /// ```
-    pub fn new() -> Result<Self, figment::Error> {
+    #[allow(clippy::result_large_err)] // figment::Error, as returned by figment itself
+    pub fn new() -> std::result::Result<Self, figment::Error> {
         Figment::new()
             .merge(Env::prefixed("RCAUTH_LOGGER_"))
             .extract()
@@ -50,7 +200,8 @@ impl Config {
     /// # Examples
     ///
     /// ```
-    /// let config = Config { log_level: "debug".to_string() };
+    /// # use rcauth_core::logger::Config;
+    /// let config = Config { log_level: "debug".to_string(), ..Config::default() };
     /// assert_eq!(config.level(), tracing::Level::DEBUG);
     /// ```
     pub fn level(&self) -> Level {
@@ -64,40 +215,1287 @@ impl Config {
         }
     }
 
-    /// Initializes the global tracing subscriber with the configured log level.
+    /// Builds the `EnvFilter` that decides which spans and events are recorded.
+    ///
+    /// When `log_directives` is set (e.g. `"info,rcauth_store=debug,sqlx=warn"`) it takes precedence and is parsed as `EnvFilter` directives. Otherwise the filter falls back to the single global `log_level`.
+    ///
+    /// # Errors
     ///
-    /// Sets up a formatted tracing subscriber using the log level specified in this configuration.
-    /// Panics if the global subscriber cannot be set.
+    /// Returns a `ConfigurationError` if the directive string cannot be parsed.
     ///
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_core::logger::Config;
+    /// let config = Config {
+    ///     log_directives: Some("info,sqlx=warn".to_string()),
+    ///     ..Config::default()
+    /// };
+    /// assert!(config.env_filter().is_ok());
+    /// ```
+    pub fn env_filter(&self) -> Result<EnvFilter> {
+        match self.log_directives.as_deref().map(str::trim) {
+            Some(directives) if !directives.is_empty() => {
+                EnvFilter::builder().parse(directives).map_err(|e| {
+                    Error::new(ErrorCode::ConfigurationError, "Invalid log directives", e)
+                })
+            }
+            _ => Ok(EnvFilter::default().add_directive(self.level().into())),
+        }
+    }
+
+    /// Returns the `tracing_appender` rotation policy for the configured `log_rotation` string.
+    ///
+    /// Unrecognized values default to `Rotation::DAILY`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::Config;
+    /// let config = Config { log_rotation: "hourly".to_string(), ..Config::default() };
+    /// assert_eq!(config.rotation(), tracing_appender::rolling::Rotation::HOURLY);
+    /// ```
+    pub fn rotation(&self) -> Rotation {
+        match self.log_rotation.to_lowercase().as_str() {
+            "hourly" => Rotation::HOURLY,
+            "never" => Rotation::NEVER,
+            _ => Rotation::DAILY,
+        }
+    }
+
+    /// Validates the logger configuration for correctness.
+    ///
+    /// Checks that `log_format` is one of `pretty`, `compact`, or `json` and `log_rotation` is one of `daily`, `hourly`, or `never` (both case-insensitive), that `log_file`, if set, names a file, that `log_directives`, if set, parses, and that OTLP export is only configured when the `otel` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the invalid value if validation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::Config;
     /// let config = Config::default();
-    /// config.init();
+    /// assert!(config.validate().is_ok());
+    ///
+    /// let invalid = Config { log_format: "xml".to_string(), ..Config::default() };
+    /// assert!(invalid.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if !LOG_FORMATS.contains(&self.log_format.to_lowercase().as_str()) {
+            return Err(format!(
+                "Invalid log format '{}', expected one of: {}",
+                self.log_format,
+                LOG_FORMATS.join(", ")
+            )
+            .into());
+        }
+
+        if !LOG_ROTATIONS.contains(&self.log_rotation.to_lowercase().as_str()) {
+            return Err(format!(
+                "Invalid log rotation '{}', expected one of: {}",
+                self.log_rotation,
+                LOG_ROTATIONS.join(", ")
+            )
+            .into());
+        }
+
+        if let Some(log_file) = &self.log_file
+            && Path::new(log_file).file_name().is_none()
+        {
+            return Err(format!("Log file path '{}' does not name a file", log_file).into());
+        }
+
+        if self.log_redact_fields.iter().any(|f| f.trim().is_empty()) {
+            return Err("Redacted log field names cannot be empty".into());
+        }
+
+        if self.otel_endpoint.is_some() {
+            if !cfg!(feature = "otel") {
+                return Err(
+                    "otel_endpoint is set but rcauth was built without the `otel` feature".into(),
+                );
+            }
+            if self.otel_service_name.is_empty() {
+                return Err("OpenTelemetry service name cannot be empty".into());
+            }
+        }
+
+        self.env_filter()?;
+
+        Ok(())
+    }
+
+    /// Builds the writer log lines are sent to.
+    ///
+    /// When `log_file` is set, lines go through a non-blocking writer backed by a rolling file appender using the configured rotation policy. The returned `WorkerGuard` flushes pending lines when dropped and must be kept alive for as long as logging is needed. Otherwise lines are written to stdout and no guard is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if the log file or its directory cannot be created.
+    fn writer(&self) -> Result<(BoxMakeWriter, Option<WorkerGuard>)> {
+        let Some(log_file) = &self.log_file else {
+            return Ok((BoxMakeWriter::new(std::io::stdout), None));
+        };
+
+        let path = Path::new(log_file);
+        let file_name = path.file_name().ok_or_else(|| {
+            Error::new_simple(
+                ErrorCode::ConfigurationError,
+                format!("Log file path '{}' does not name a file", log_file),
+            )
+        })?;
+        let directory = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        let appender = RollingFileAppender::builder()
+            .rotation(self.rotation())
+            .filename_prefix(file_name.to_string_lossy())
+            .build(directory)
+            .map_err(|e| Error::new(ErrorCode::ConfigurationError, "Failed to open log file", e))?;
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+
+        Ok((BoxMakeWriter::new(writer), Some(guard)))
+    }
+
+    /// Builds the OpenTelemetry tracer provider exporting spans over OTLP/HTTP to `otel_endpoint`.
+    ///
+    /// The endpoint is the collector's full traces URL (e.g. `http://localhost:4318/v1/traces`). Returns `None` when no endpoint is configured, leaving tracing local only.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if the exporter cannot be built.
+    #[cfg(feature = "otel")]
+    fn tracer_provider(&self) -> Result<Option<SdkTracerProvider>> {
+        let Some(endpoint) = &self.otel_endpoint else {
+            return Ok(None);
+        };
+
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| {
+                Error::new(
+                    ErrorCode::ConfigurationError,
+                    "Failed to build OTLP exporter",
+                    e,
+                )
+            })?;
+
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(self.otel_service_name.clone())
+                    .build(),
+            )
+            .build();
+
+        Ok(Some(provider))
+    }
+
+    /// Wraps [`Config::env_filter`] in a layer whose filter can be swapped through the returned [`LogFilter`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if `log_directives` cannot be parsed.
+    fn reloadable_filter(&self) -> Result<(reload::Layer<EnvFilter, Registry>, LogFilter)> {
+        let (layer, handle) = reload::Layer::new(self.env_filter()?);
+        Ok((layer, LogFilter(handle)))
+    }
+
+    /// Builds the formatting layer for the configured output format, writing to `writer`.
+    ///
+    /// Values of fields matching `log_redact_fields` are replaced with `***` in both span fields and event fields, for every format.
+    fn fmt_layer<S, W>(&self, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+    {
+        let redactor = Redactor::new(&self.log_redact_fields);
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(self.log_file.is_none())
+            .with_target(true);
+
+        match self.log_format.to_lowercase().as_str() {
+            "json" => layer
+                .json()
+                .map_event_format(|format| RedactedJson::new(format, redactor))
+                .boxed(),
+            "compact" => layer
+                .compact()
+                .map_fmt_fields(|fields| RedactedFields::new(fields, redactor))
+                .boxed(),
+            _ => layer
+                .pretty()
+                .fmt_fields(RedactedFields::new(PrettyFields::new(), redactor.clone()))
+                .map_event_format(|format| RedactedEvent::new(format, redactor))
+                .boxed(),
+        }
+    }
+
+    /// Builds a tracing subscriber for the configured filter, output format, and destination.
+    ///
+    /// Filtering is delegated to [`Config::env_filter`]. The `log_format` field selects between the `pretty`, `compact`, and `json` formatters. Unrecognized values fall back to `pretty`; use [`Config::validate`] to reject them up front. Output goes to `log_file` when set (without ANSI colors) and to stdout otherwise. Fields named in `log_redact_fields` are masked in the output.
+    ///
+    /// With the `otel` feature enabled and `otel_endpoint` set, an OpenTelemetry layer is added so spans (including those from the HTTP request middleware) are exported to the collector.
+    ///
+    /// The returned [`LogGuard`] must be held until logging is no longer needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if `log_directives` cannot be parsed, the log file cannot be opened, or the OTLP exporter cannot be built.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::Config;
+    /// let config = Config { log_format: "json".to_string(), ..Config::default() };
+    /// let (subscriber, _guard) = config.subscriber().unwrap();
+    /// tracing::subscriber::with_default(subscriber, || tracing::info!("hello"));
+    /// ```
+    pub fn subscriber(&self) -> Result<(Box<dyn Subscriber + Send + Sync>, LogGuard)> {
+        let (writer, worker) = self.writer()?;
+        let (filter_layer, filter) = self.reloadable_filter()?;
+        let subscriber = Registry::default()
+            .with(filter_layer)
+            .with(self.fmt_layer(writer));
+
+        #[cfg(feature = "otel")]
+        {
+            let tracer_provider = self.tracer_provider()?;
+            let otel_layer = tracer_provider.as_ref().map(|provider| {
+                tracing_opentelemetry::layer().with_tracer(provider.tracer("rcauth"))
+            });
+
+            Ok((
+                Box::new(subscriber.with(otel_layer)),
+                LogGuard {
+                    worker,
+                    filter,
+                    tracer_provider,
+                },
+            ))
+        }
+
+        #[cfg(not(feature = "otel"))]
+        Ok((Box::new(subscriber), LogGuard { worker, filter }))
+    }
+
+    /// Initializes the global tracing subscriber with the configured log level, format, and destination.
+    ///
+    /// Sets up the subscriber returned by [`Config::subscriber`] as the global default and returns its [`LogGuard`]. Dropping the guard stops file logging and trace export, so callers should hold it for the lifetime of the process. The configured `log_redact_fields` are also registered for [`redact_uri`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the logger configuration is invalid or a global subscriber has already been set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::Config;
+    /// let config = Config::default();
+    /// let _guard = config.init().unwrap();
     /// // Logging is now initialized at the default "info" level.
+    /// assert!(config.init().is_err());
     /// ```
-    pub fn init(&self) {
-        let subscriber = tracing_subscriber::FmtSubscriber::builder()
-            .with_max_level(self.level())
-            .with_target(true)
-            .finish();
+    pub fn init(&self) -> std::result::Result<LogGuard, Box<dyn std::error::Error>> {
+        let (subscriber, guard) = self.subscriber()?;
+        tracing::subscriber::set_global_default(subscriber)?;
+        let _ = GLOBAL_REDACTOR.set(Redactor::new(&self.log_redact_fields));
+        Ok(guard)
+    }
 
-        tracing::subscriber::set_global_default(subscriber).expect("Failed to set subscriber");
+    /// Initializes the global tracing subscriber, leaving an existing one in place.
+    ///
+    /// Behaves like [`Config::init`], except that a global subscriber set earlier (for example by another test in the same harness) is not treated as an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the logger configuration is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::Config;
+    /// let config = Config::default();
+    /// let _guard = config.try_init().unwrap();
+    /// let _guard = config.try_init().unwrap();
+    /// ```
+    pub fn try_init(&self) -> std::result::Result<LogGuard, Box<dyn std::error::Error>> {
+        let (subscriber, guard) = self.subscriber()?;
+        if tracing::subscriber::set_global_default(subscriber).is_ok() {
+            let _ = GLOBAL_REDACTOR.set(Redactor::new(&self.log_redact_fields));
+        }
+        Ok(guard)
     }
 }
 
 impl Default for Config {
-    /// Returns a `Config` instance with the default log level set to `"info"`.
+    /// Returns a `Config` instance with the default log level set to `"info"`, format set to `"pretty"`, and logging to stdout.
     ///
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_core::logger::Config;
     /// let config = Config::default();
     /// assert_eq!(config.log_level, "info");
+    /// assert_eq!(config.log_format, "pretty");
     /// ```
     fn default() -> Self {
         Self {
             log_level: default_log_level(),
+            log_format: default_log_format(),
+            log_directives: None,
+            log_file: None,
+            log_rotation: default_log_rotation(),
+            otel_endpoint: None,
+            otel_service_name: default_otel_service_name(),
+            log_redact_fields: default_log_redact_fields(),
         }
     }
 }
+
+#[derive(Default)]
+pub struct ConfigBuilder {
+    log_level: Option<String>,
+    log_format: Option<String>,
+    log_directives: Option<String>,
+    log_file: Option<String>,
+    log_rotation: Option<String>,
+    otel_endpoint: Option<String>,
+    otel_service_name: Option<String>,
+    log_redact_fields: Option<Vec<String>>,
+}
+
+impl ConfigBuilder {
+    /// Sets the log level for the configuration builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let builder = ConfigBuilder::default().log_level("debug");
+    /// ```
+    pub fn log_level<T: Into<String>>(mut self, level: T) -> Self {
+        self.log_level = Some(level.into());
+        self
+    }
+
+    /// Sets the log output format (`pretty`, `compact`, or `json`) for the configuration builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let config = ConfigBuilder::default().log_format("json").build().unwrap();
+    /// assert_eq!(config.log_format, "json");
+    /// ```
+    pub fn log_format<T: Into<String>>(mut self, format: T) -> Self {
+        self.log_format = Some(format.into());
+        self
+    }
+
+    /// Sets per-module filter directives, overriding the global log level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let builder = ConfigBuilder::default().log_directives("info,rcauth_store=debug,sqlx=warn");
+    /// ```
+    pub fn log_directives<T: Into<String>>(mut self, directives: T) -> Self {
+        self.log_directives = Some(directives.into());
+        self
+    }
+
+    /// Sets the file that logs are written to instead of stdout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let builder = ConfigBuilder::default().log_file("/var/log/rcauth/rcauth.log");
+    /// ```
+    pub fn log_file<T: Into<String>>(mut self, path: T) -> Self {
+        self.log_file = Some(path.into());
+        self
+    }
+
+    /// Sets the log file rotation policy (`daily`, `hourly`, or `never`) for the configuration builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let config = ConfigBuilder::default().log_rotation("hourly").build().unwrap();
+    /// assert_eq!(config.log_rotation, "hourly");
+    /// ```
+    pub fn log_rotation<T: Into<String>>(mut self, rotation: T) -> Self {
+        self.log_rotation = Some(rotation.into());
+        self
+    }
+
+    /// Sets the OTLP/HTTP traces endpoint spans are exported to. Requires the `otel` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let builder = ConfigBuilder::default().otel_endpoint("http://localhost:4318/v1/traces");
+    /// ```
+    pub fn otel_endpoint<T: Into<String>>(mut self, endpoint: T) -> Self {
+        self.otel_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Sets the service name reported with exported spans.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let config = ConfigBuilder::default().otel_service_name("rcauth-eu").build().unwrap();
+    /// assert_eq!(config.otel_service_name, "rcauth-eu");
+    /// ```
+    pub fn otel_service_name<T: Into<String>>(mut self, name: T) -> Self {
+        self.otel_service_name = Some(name.into());
+        self
+    }
+
+    /// Sets the field names whose values are masked in log output, replacing the defaults.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let builder = ConfigBuilder::default().log_redact_fields(vec!["password", "api_key"]);
+    /// ```
+    pub fn log_redact_fields<T: Into<String>>(mut self, fields: Vec<T>) -> Self {
+        self.log_redact_fields = Some(fields.into_iter().map(|f| f.into()).collect());
+        self
+    }
+
+    /// Builds a `Config` instance from the provided builder values, applying defaults where necessary and validating the result.
+    ///
+    /// Returns a validated `Config` if successful, or an error if validation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::logger::ConfigBuilder;
+    /// let config = ConfigBuilder::default()
+    ///     .log_level("warn")
+    ///     .log_format("compact")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.log_level, "warn");
+    /// assert_eq!(config.log_format, "compact");
+    /// ```
+    pub fn build(self) -> std::result::Result<Config, Box<dyn std::error::Error>> {
+        let default_config = Config::default();
+
+        let config = Config {
+            log_level: self.log_level.unwrap_or(default_config.log_level),
+            log_format: self.log_format.unwrap_or(default_config.log_format),
+            log_directives: self.log_directives.or(default_config.log_directives),
+            log_file: self.log_file.or(default_config.log_file),
+            log_rotation: self.log_rotation.unwrap_or(default_config.log_rotation),
+            otel_endpoint: self.otel_endpoint.or(default_config.otel_endpoint),
+            otel_service_name: self
+                .otel_service_name
+                .unwrap_or(default_config.otel_service_name),
+            log_redact_fields: self
+                .log_redact_fields
+                .unwrap_or(default_config.log_redact_fields),
+        };
+
+        // Validate the configuration
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+/// Handle to the filter of a subscriber built by [`Config::subscriber`], for changing what is logged without a restart.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::logger::Config;
+/// // The handle only works while the subscriber is alive
+/// let (_subscriber, guard) = Config::default().subscriber().unwrap();
+/// guard.filter().set_level("debug").unwrap();
+/// assert_eq!(guard.filter().current().unwrap(), "debug");
+/// assert!(guard.filter().set_level("loud").is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct LogFilter(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilter {
+    /// Replaces the active filter, including any `log_directives`, with a single global `level`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Invalid` error if `level` is not one of [`LOG_LEVELS`], or an `Internal` error if the subscriber has been dropped.
+    pub fn set_level(&self, level: &str) -> Result<()> {
+        let level = level.trim().to_lowercase();
+        if !LOG_LEVELS.contains(&level.as_str()) {
+            return Err(Error::new_simple(
+                ErrorCode::Invalid,
+                format!(
+                    "Invalid log level '{}', expected one of: {}",
+                    level,
+                    LOG_LEVELS.join(", ")
+                ),
+            ));
+        }
+
+        self.0
+            .reload(EnvFilter::new(level))
+            .map_err(|e| Error::new(ErrorCode::Internal, "Failed to reload log filter", e))
+    }
+
+    /// Returns the active filter as directives, e.g. `info,sqlx=warn`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Internal` error if the subscriber has been dropped.
+    pub fn current(&self) -> Result<String> {
+        self.0
+            .with_current(|filter| filter.to_string())
+            .map_err(|e| Error::new(ErrorCode::Internal, "Failed to read log filter", e))
+    }
+}
+
+/// Decides which field names are sensitive.
+///
+/// A field is sensitive when its name is one of the configured names or ends with one as its last `_`, `-` or `.` separated segment, compared case-insensitively. So `password` also covers `new_password` and `token` covers `refresh_token`, while `password_min_length` and `access_token_ttl_secs` are left alone.
+#[derive(Clone, Debug)]
+struct Redactor {
+    fields: Arc<[String]>,
+}
+
+impl Redactor {
+    fn new(fields: &[String]) -> Self {
+        Self {
+            fields: fields.iter().map(|f| f.trim().to_lowercase()).collect(),
+        }
+    }
+
+    fn is_sensitive(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        let last_segment = name.rsplit(['_', '-', '.']).next().unwrap_or(&name);
+        self.fields
+            .iter()
+            .any(|f| name == *f || (last_segment == f && name.len() > f.len()))
+    }
+
+    /// Masks the values of sensitive keys in a `key=value&...` string.
+    fn redact_query(&self, query: &str) -> String {
+        query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, _)) if self.is_sensitive(key) => format!("{}={}", key, REDACTED),
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Recursively masks the string and number values of sensitive keys in a JSON document.
+    ///
+    /// Objects under a sensitive key are recursed into rather than masked whole, and booleans are kept, so a switch such as `auth_methods.password` stays readable.
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        self.redact_value(value, false);
+    }
+
+    fn redact_value(&self, value: &mut serde_json::Value, sensitive: bool) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    self.redact_value(value, self.is_sensitive(key));
+                }
+            }
+            serde_json::Value::Array(items) => items
+                .iter_mut()
+                .for_each(|item| self.redact_value(item, sensitive)),
+            serde_json::Value::String(_) | serde_json::Value::Number(_) if sensitive => {
+                *value = serde_json::Value::from(REDACTED);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(&default_log_redact_fields())
+    }
+}
+
+/// Masks sensitive query parameters in a request URI for logging.
+///
+/// Uses the field names registered by [`Config::init`], or [`DEFAULT_REDACTED_FIELDS`] if logging has not been initialized.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::logger::redact_uri;
+/// let uri: http::Uri = "/api/v1/verify?token=abc&type=signup".parse().unwrap();
+/// assert_eq!(redact_uri(&uri), "/api/v1/verify?token=***&type=signup");
+/// ```
+pub fn redact_uri(uri: &http::Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.to_string();
+    };
+
+    let redactor = GLOBAL_REDACTOR.get_or_init(Redactor::default);
+    format!("{}?{}", uri.path(), redactor.redact_query(query))
+}
+
+/// Masks sensitive fields in a request or response body for logging.
+///
+/// JSON bodies have the values of sensitive keys masked at any depth, other text is treated as `application/x-www-form-urlencoded`, and binary bodies are replaced by their size. Uses the same field names as [`redact_uri`].
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::logger::redact_body;
+/// assert_eq!(
+///     redact_body(br#"{"email":"a@example.com","password":"hunter2"}"#),
+///     r#"{"email":"a@example.com","password":"***"}"#
+/// );
+/// assert_eq!(redact_body(b"grant_type=password&password=hunter2"), "grant_type=password&password=***");
+/// ```
+pub fn redact_body(body: &[u8]) -> String {
+    let redactor = GLOBAL_REDACTOR.get_or_init(Redactor::default);
+
+    if let Ok(mut document) = serde_json::from_slice::<serde_json::Value>(body) {
+        redactor.redact_json(&mut document);
+        return document.to_string();
+    }
+
+    match std::str::from_utf8(body) {
+        Ok(text) => redactor.redact_query(text),
+        Err(_) => format!("<{} bytes of binary data>", body.len()),
+    }
+}
+
+/// Masks the values of sensitive keys at any depth of a JSON document, e.g. a serialized configuration. Uses the same field names as [`redact_uri`].
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::logger::redact_json;
+/// let mut config = serde_json::json!({"user": "admin", "password": "hunter2"});
+/// redact_json(&mut config);
+/// assert_eq!(config, serde_json::json!({"user": "admin", "password": "***"}));
+/// ```
+pub fn redact_json(document: &mut serde_json::Value) {
+    GLOBAL_REDACTOR
+        .get_or_init(Redactor::default)
+        .redact_json(document);
+}
+
+/// Masks `value` as the value of a field called `name`, e.g. one setting of a configuration document. Uses the same field names as [`redact_uri`].
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::logger::redact_field;
+/// let mut password = serde_json::json!("hunter2");
+/// redact_field("password", &mut password);
+/// assert_eq!(password, "***");
+///
+/// let mut ttl = serde_json::json!(900);
+/// redact_field("access_token_ttl_secs", &mut ttl);
+/// assert_eq!(ttl, 900);
+/// ```
+pub fn redact_field(name: &str, value: &mut serde_json::Value) {
+    let redactor = GLOBAL_REDACTOR.get_or_init(Redactor::default);
+    redactor.redact_value(value, redactor.is_sensitive(name));
+}
+
+/// Field formatter that masks sensitive fields before delegating to the wrapped formatter.
+struct RedactedFields<F> {
+    inner: F,
+    redactor: Redactor,
+}
+
+impl<F> RedactedFields<F> {
+    fn new(inner: F, redactor: Redactor) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+impl<T, F: MakeVisitor<T>> MakeVisitor<T> for RedactedFields<F> {
+    type Visitor = RedactedVisitor<F::Visitor>;
+
+    fn make_visitor(&self, target: T) -> Self::Visitor {
+        RedactedVisitor {
+            inner: self.inner.make_visitor(target),
+            redactor: self.redactor.clone(),
+        }
+    }
+}
+
+/// Visitor that records `***` in place of sensitive field values.
+struct RedactedVisitor<V> {
+    inner: V,
+    redactor: Redactor,
+}
+
+impl<V: Visit> Visit for RedactedVisitor<V> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if self.redactor.is_sensitive(field.name()) {
+            self.inner
+                .record_debug(field, &format_args!("{}", REDACTED));
+        } else {
+            self.inner.record_debug(field, value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if self.redactor.is_sensitive(field.name()) {
+            self.record_debug(field, &value);
+        } else {
+            self.inner.record_str(field, value);
+        }
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        if self.redactor.is_sensitive(field.name()) {
+            self.record_debug(field, &value);
+        } else {
+            self.inner.record_error(field, value);
+        }
+    }
+}
+
+impl<V: VisitOutput<fmt::Result>> VisitOutput<fmt::Result> for RedactedVisitor<V> {
+    fn finish(self) -> fmt::Result {
+        self.inner.finish()
+    }
+}
+
+impl<V: VisitFmt> VisitFmt for RedactedVisitor<V> {
+    fn writer(&mut self) -> &mut dyn fmt::Write {
+        self.inner.writer()
+    }
+}
+
+/// Event formatter that re-emits events carrying sensitive fields with their values masked.
+///
+/// Needed for formatters such as `Pretty` that record event fields with their own visitor rather than through the field formatter.
+struct RedactedEvent<F> {
+    inner: F,
+    redactor: Redactor,
+}
+
+impl<F> RedactedEvent<F> {
+    fn new(inner: F, redactor: Redactor) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+/// A field value captured from an event so it can be re-recorded.
+enum CapturedValue {
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    Display(DisplayValue<String>),
+    Redacted(DisplayValue<&'static str>),
+}
+
+impl CapturedValue {
+    fn as_value(&self) -> &dyn Value {
+        match self {
+            CapturedValue::I64(v) => v,
+            CapturedValue::U64(v) => v,
+            CapturedValue::I128(v) => v,
+            CapturedValue::U128(v) => v,
+            CapturedValue::F64(v) => v,
+            CapturedValue::Bool(v) => v,
+            CapturedValue::Str(v) => v,
+            CapturedValue::Display(v) => v,
+            CapturedValue::Redacted(v) => v,
+        }
+    }
+}
+
+/// Visitor collecting an event's field values, masking sensitive ones.
+struct CapturedFields<'a> {
+    redactor: &'a Redactor,
+    values: Vec<(Field, CapturedValue)>,
+}
+
+impl CapturedFields<'_> {
+    fn capture(&mut self, field: &Field, value: CapturedValue) {
+        let value = if self.redactor.is_sensitive(field.name()) {
+            CapturedValue::Redacted(display(REDACTED))
+        } else {
+            value
+        };
+        self.values.push((field.clone(), value));
+    }
+}
+
+impl Visit for CapturedFields<'_> {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.capture(field, CapturedValue::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.capture(field, CapturedValue::U64(value));
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.capture(field, CapturedValue::I128(value));
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.capture(field, CapturedValue::U128(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.capture(field, CapturedValue::F64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.capture(field, CapturedValue::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.capture(field, CapturedValue::Str(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.capture(
+            field,
+            CapturedValue::Display(display(format!("{:?}", value))),
+        );
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for RedactedEvent<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        if !metadata
+            .fields()
+            .iter()
+            .any(|field| self.redactor.is_sensitive(field.name()))
+        {
+            return self.inner.format_event(ctx, writer, event);
+        }
+
+        let mut captured = CapturedFields {
+            redactor: &self.redactor,
+            values: Vec::new(),
+        };
+        event.record(&mut captured);
+        let values = captured
+            .values
+            .iter()
+            .map(|(field, value)| (field, Some(value.as_value())))
+            .collect::<Vec<_>>();
+
+        // `ValueSet`s can only be built from fixed-size arrays; events carry at most 32 fields
+        macro_rules! format_redacted {
+            ($($len:literal)*) => {
+                match values.len() {
+                    $($len => {
+                        let values: [_; $len] = values.try_into().map_err(|_| fmt::Error)?;
+                        let value_set = metadata.fields().value_set(&values);
+                        let redacted = if event.is_contextual() {
+                            Event::new(metadata, &value_set)
+                        } else {
+                            Event::new_child_of(event.parent().cloned(), metadata, &value_set)
+                        };
+                        self.inner.format_event(ctx, writer, &redacted)
+                    })*
+                    _ => Err(fmt::Error),
+                }
+            };
+        }
+
+        format_redacted!(
+            0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16
+            17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32
+        )
+    }
+}
+
+/// JSON event formatter that masks sensitive keys in the formatted line.
+///
+/// The JSON formatter serializes event fields directly rather than through a field formatter, so redaction is applied to the rendered document instead, covering event fields as well as the current span and span list.
+struct RedactedJson<F> {
+    inner: F,
+    redactor: Redactor,
+}
+
+impl<F> RedactedJson<F> {
+    fn new(inner: F, redactor: Redactor) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+impl<S, N, F> FormatEvent<S, N> for RedactedJson<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut line = String::new();
+        self.inner
+            .format_event(ctx, Writer::new(&mut line), event)?;
+
+        match serde_json::from_str::<serde_json::Value>(&line) {
+            Ok(mut document) => {
+                self.redactor.redact_json(&mut document);
+                writeln!(writer, "{}", document)
+            }
+            Err(_) => writer.write_str(&line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::CapturedLogs;
+
+    #[test]
+    fn validate_accepts_known_formats() {
+        for format in ["pretty", "compact", "json", "JSON"] {
+            let config = ConfigBuilder::default().log_format(format).build();
+            assert!(config.is_ok(), "format {format} should be valid");
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unknown_format() {
+        let err = ConfigBuilder::default()
+            .log_format("xml")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("xml"));
+    }
+
+    #[test]
+    fn subscriber_constructs_for_each_format() {
+        for format in LOG_FORMATS {
+            let config = ConfigBuilder::default().log_format(format).build().unwrap();
+            let (subscriber, _guard) = config.subscriber().unwrap();
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::info!(format, "subscriber constructed");
+            });
+        }
+    }
+
+    #[test]
+    fn init_twice_returns_error() {
+        let config = Config::default();
+        let _guard = config.init();
+        assert!(config.init().is_err());
+    }
+
+    #[test]
+    fn try_init_ignores_existing_subscriber() {
+        let config = Config::default();
+        assert!(config.try_init().is_ok());
+        assert!(config.try_init().is_ok());
+    }
+
+    #[test]
+    fn env_filter_parses_directives() {
+        let config = ConfigBuilder::default()
+            .log_directives("info,rcauth_store=debug,sqlx=warn")
+            .build()
+            .unwrap();
+        let filter = config.env_filter().unwrap();
+        assert_eq!(filter.max_level_hint(), Some(Level::DEBUG.into()));
+    }
+
+    #[test]
+    fn env_filter_rejects_invalid_directives() {
+        let config = Config {
+            log_directives: Some("rcauth_store=verbose".to_string()),
+            ..Config::default()
+        };
+        let err = config.env_filter().unwrap_err();
+        assert_eq!(err.code, ErrorCode::ConfigurationError);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn env_filter_prefers_directives_over_log_level() {
+        let config = ConfigBuilder::default()
+            .log_level("trace")
+            .log_directives("warn")
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.env_filter().unwrap().max_level_hint(),
+            Some(Level::WARN.into())
+        );
+    }
+
+    #[test]
+    fn env_filter_falls_back_to_log_level() {
+        for directives in [None, Some("  ".to_string())] {
+            let config = Config {
+                log_level: "debug".to_string(),
+                log_directives: directives,
+                ..Config::default()
+            };
+            assert_eq!(
+                config.env_filter().unwrap().max_level_hint(),
+                Some(Level::DEBUG.into())
+            );
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unknown_rotation() {
+        let err = ConfigBuilder::default()
+            .log_rotation("weekly")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("weekly"));
+    }
+
+    #[test]
+    fn file_logging_writes_to_configured_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("rcauth.log");
+        let config = ConfigBuilder::default()
+            .log_file(path.to_string_lossy())
+            .log_rotation("never")
+            .build()
+            .unwrap();
+
+        let (subscriber, guard) = config.subscriber().unwrap();
+        assert!(guard.worker.is_some());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("written to the log file");
+        });
+        // Dropping the guard flushes the non-blocking writer
+        drop(guard);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("written to the log file"));
+    }
+
+    #[cfg(not(feature = "otel"))]
+    #[test]
+    fn validate_rejects_otel_endpoint_without_feature() {
+        let err = ConfigBuilder::default()
+            .otel_endpoint("http://localhost:4318/v1/traces")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("otel"));
+    }
+
+    #[cfg(feature = "otel")]
+    #[test]
+    fn otel_exports_spans_to_collector() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        // Minimal OTLP/HTTP collector that records the first export request
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let collector = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            (request_line, body)
+        });
+
+        let config = ConfigBuilder::default()
+            .otel_endpoint(format!("http://{}/v1/traces", addr))
+            .otel_service_name("rcauth-test")
+            .build()
+            .unwrap();
+        let (subscriber, guard) = config.subscriber().unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("exported_span").in_scope(|| tracing::info!("inside span"));
+        });
+        // Dropping the guard flushes and shuts down the exporter
+        drop(guard);
+
+        let (request_line, body) = collector.join().unwrap();
+        assert!(request_line.starts_with("POST /v1/traces"));
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("exported_span"));
+        assert!(body.contains("rcauth-test"));
+    }
+
+    #[test]
+    fn redacts_sensitive_fields_for_each_format() {
+        for format in LOG_FORMATS {
+            let config = ConfigBuilder::default().log_format(format).build().unwrap();
+            let output = CapturedLogs::default();
+            let writer = output.clone();
+            let subscriber = Registry::default().with(config.fmt_layer(move || writer.clone()));
+
+            tracing::subscriber::with_default(subscriber, || {
+                let span =
+                    tracing::info_span!("signup", email = "user@example.com", password = "hunter2");
+                span.in_scope(|| tracing::info!(refresh_token = "abc123", "issued tokens"));
+            });
+
+            let contents = output.contents();
+            assert!(!contents.contains("hunter2"), "{format}: {contents}");
+            assert!(!contents.contains("abc123"), "{format}: {contents}");
+            assert!(contents.contains("***"), "{format}: {contents}");
+            assert!(contents.contains("issued tokens"), "{format}: {contents}");
+            assert!(
+                contents.contains("user@example.com"),
+                "{format}: {contents}"
+            );
+        }
+    }
+
+    #[test]
+    fn reloading_the_filter_changes_what_is_logged() {
+        let config = ConfigBuilder::default()
+            .log_level("info")
+            .log_format("compact")
+            .build()
+            .unwrap();
+        let output = CapturedLogs::default();
+        let writer = output.clone();
+        let (filter_layer, filter) = config.reloadable_filter().unwrap();
+        let subscriber = Registry::default()
+            .with(filter_layer)
+            .with(config.fmt_layer(move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("before reload");
+            filter.set_level("DEBUG").unwrap();
+            tracing::debug!("after reload");
+
+            let err = filter.set_level("verbose").unwrap_err();
+            assert_eq!(err.code, ErrorCode::Invalid);
+            tracing::debug!("after rejected reload");
+            assert_eq!(filter.current().unwrap(), "debug");
+        });
+
+        let contents = output.contents();
+        assert!(!contents.contains("before reload"), "{contents}");
+        assert!(contents.contains("after reload"), "{contents}");
+        assert!(contents.contains("after rejected reload"), "{contents}");
+    }
+
+    #[test]
+    fn redact_uri_masks_sensitive_query_parameters() {
+        let uri: http::Uri = "/verify?type=signup&token=abc&redirect_to=/home"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            redact_uri(&uri),
+            "/verify?type=signup&token=***&redirect_to=/home"
+        );
+
+        let uri: http::Uri = "/health".parse().unwrap();
+        assert_eq!(redact_uri(&uri), "/health");
+    }
+
+    #[test]
+    fn redact_body_masks_nested_json_and_skips_binary() {
+        let body = br#"{"user":{"email":"a@example.com","new_password":"hunter2"}}"#;
+        assert_eq!(
+            redact_body(body),
+            r#"{"user":{"email":"a@example.com","new_password":"***"}}"#
+        );
+        assert_eq!(redact_body(&[0xff, 0xfe, 0x00]), "<3 bytes of binary data>");
+    }
+
+    #[test]
+    fn redaction_keeps_settings_that_only_mention_a_sensitive_word() {
+        let redactor = Redactor::default();
+        let mut config = serde_json::json!({
+            "access_token_ttl_secs": 900,
+            "refresh_token_ttl_secs": 2592000,
+            "confirmation_token_ttl_secs": 86400,
+            "recovery_token_ttl_secs": 3600,
+            "token_binding": true,
+            "password_min_length": 8,
+            "auth_methods": { "password": true, "oauth": false },
+            "token_cookies": { "enabled": true, "csrf_protection": true },
+            "password": "redcardinal",
+            "jwt_secret": "development-only-secret",
+            "password_pepper": "pepper",
+            "jwt_keys": [{ "kid": "2024-01", "secret": "key" }],
+        });
+        redactor.redact_json(&mut config);
+
+        assert_eq!(
+            config,
+            serde_json::json!({
+                "access_token_ttl_secs": 900,
+                "refresh_token_ttl_secs": 2592000,
+                "confirmation_token_ttl_secs": 86400,
+                "recovery_token_ttl_secs": 3600,
+                "token_binding": true,
+                "password_min_length": 8,
+                "auth_methods": { "password": true, "oauth": false },
+                "token_cookies": { "enabled": true, "csrf_protection": true },
+                "password": "***",
+                "jwt_secret": "***",
+                "password_pepper": "***",
+                "jwt_keys": [{ "kid": "2024-01", "secret": "***" }],
+            })
+        );
+        assert!(redactor.is_sensitive("X-Refresh-Token"));
+        assert!(!redactor.is_sensitive("access_token_ttl_secs"));
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the `db_pool_acquire_wait_seconds` buckets.
+pub const ACQUIRE_WAIT_BUCKETS: [f64; 11] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// Contention of the store's connection pool: how long queries waited for a connection, and how often they gave up.
+///
+/// Clones share the counters, so the store records into the same ones the servers render at `/metrics`.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::metrics::PoolMetrics;
+/// # use std::time::Duration;
+/// let metrics = PoolMetrics::default();
+/// metrics.record_acquire(Duration::from_millis(20));
+/// assert!(metrics.render().contains("db_pool_acquire_wait_seconds_bucket{le=\"0.025\"} 1"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PoolMetrics(Arc<Counters>);
+
+#[derive(Debug, Default)]
+struct Counters {
+    /// Observations at or below each bound of [`ACQUIRE_WAIT_BUCKETS`], not cumulative
+    buckets: [AtomicU64; ACQUIRE_WAIT_BUCKETS.len()],
+    /// Observations above the last bound
+    overflow: AtomicU64,
+    /// Sum of the observed waits, in microseconds
+    wait_micros: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+impl PoolMetrics {
+    /// Records a connection handed out after waiting `wait`.
+    pub fn record_acquire(&self, wait: Duration) {
+        let seconds = wait.as_secs_f64();
+        match ACQUIRE_WAIT_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+        {
+            Some(bucket) => self.0.buckets[bucket].fetch_add(1, Ordering::Relaxed),
+            None => self.0.overflow.fetch_add(1, Ordering::Relaxed),
+        };
+        let micros = u64::try_from(wait.as_micros()).unwrap_or(u64::MAX);
+        self.0.wait_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    /// Records a query that gave up waiting for a connection.
+    pub fn record_acquire_timeout(&self) {
+        self.0.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Connections handed out so far.
+    pub fn acquires(&self) -> u64 {
+        self.0
+            .buckets
+            .iter()
+            .chain([&self.0.overflow])
+            .map(|count| count.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Total time spent waiting for the connections handed out so far.
+    pub fn acquire_wait(&self) -> Duration {
+        Duration::from_micros(self.0.wait_micros.load(Ordering::Relaxed))
+    }
+
+    pub fn acquire_timeouts(&self) -> u64 {
+        self.0.timeouts.load(Ordering::Relaxed)
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format, version 0.0.4.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str(
+            "# HELP db_pool_acquire_wait_seconds Time queries waited for a pooled connection.\n",
+        );
+        out.push_str("# TYPE db_pool_acquire_wait_seconds histogram\n");
+        let mut cumulative = 0;
+        for (bound, count) in ACQUIRE_WAIT_BUCKETS.iter().zip(&self.0.buckets) {
+            cumulative += count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "db_pool_acquire_wait_seconds_bucket{{le=\"{}\"}} {}",
+                bound, cumulative
+            );
+        }
+        cumulative += self.0.overflow.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "db_pool_acquire_wait_seconds_bucket{{le=\"+Inf\"}} {}",
+            cumulative
+        );
+        let _ = writeln!(
+            out,
+            "db_pool_acquire_wait_seconds_sum {}",
+            self.acquire_wait().as_secs_f64()
+        );
+        let _ = writeln!(out, "db_pool_acquire_wait_seconds_count {}", cumulative);
+        out.push_str(
+            "# HELP db_pool_acquire_timeouts_total Queries that gave up waiting for a pooled connection.\n",
+        );
+        out.push_str("# TYPE db_pool_acquire_timeouts_total counter\n");
+        let _ = writeln!(
+            out,
+            "db_pool_acquire_timeouts_total {}",
+            self.acquire_timeouts()
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let metrics = PoolMetrics::default();
+        metrics.record_acquire(Duration::ZERO);
+        metrics.record_acquire(Duration::from_millis(30));
+        metrics.record_acquire(Duration::from_secs(10));
+        metrics.record_acquire_timeout();
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("db_pool_acquire_wait_seconds_bucket{le=\"0.001\"} 1\n"));
+        assert!(rendered.contains("db_pool_acquire_wait_seconds_bucket{le=\"0.025\"} 1\n"));
+        assert!(rendered.contains("db_pool_acquire_wait_seconds_bucket{le=\"0.05\"} 2\n"));
+        assert!(rendered.contains("db_pool_acquire_wait_seconds_bucket{le=\"5\"} 2\n"));
+        assert!(rendered.contains("db_pool_acquire_wait_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(rendered.contains("db_pool_acquire_wait_seconds_sum 10.03\n"));
+        assert!(rendered.contains("db_pool_acquire_wait_seconds_count 3\n"));
+        assert!(rendered.contains("db_pool_acquire_timeouts_total 1\n"));
+        assert_eq!(metrics.acquires(), 3);
+    }
+}
@@ -0,0 +1,558 @@
+use crate::error::{Error, ErrorCode, Result, ValidationErrors};
+use argon2::password_hash::{
+    PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use figment::{Figment, providers::Env};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::sync::Arc;
+
+/// Shortest accepted pepper, matching the minimum JWT secret length.
+const MIN_PEPPER_BYTES: usize = 32;
+
+/// Marks a stored hash of the password's HMAC with the pepper, rather than of the password itself.
+///
+/// Hashes without it were made before a pepper was configured; they are upgraded as their users log in.
+const PEPPERED_PREFIX: &str = "$pepper-v1";
+
+/// Argon2id cost parameters for new password hashes.
+///
+/// The defaults follow the OWASP recommendation of 19 MiB of memory, 2 iterations and 1 degree of parallelism. Raising any of them upgrades existing hashes as their users log in, see [`Hasher::verify`].
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Config {
+    /// Memory cost in KiB
+    #[serde(default = "default_password_hash_memory_kib")]
+    pub password_hash_memory_kib: u32,
+    /// Number of passes over the memory
+    #[serde(default = "default_password_hash_iterations")]
+    pub password_hash_iterations: u32,
+    /// Number of lanes hashed in parallel
+    #[serde(default = "default_password_hash_parallelism")]
+    pub password_hash_parallelism: u32,
+    /// Server-side secret mixed into every password with HMAC-SHA256 before hashing, so stolen hashes cannot be cracked without it
+    #[serde(default)]
+    pub password_pepper: Option<String>,
+    /// Fewest characters a password chosen by a user may have
+    #[serde(default = "default_password_min_length")]
+    pub password_min_length: usize,
+}
+
+/// Returns the default Argon2 memory cost of 19 MiB.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_password_hash_memory_kib(), 19_456);
+/// ```
+fn default_password_hash_memory_kib() -> u32 {
+    19 * 1024
+}
+
+/// Returns the default Argon2 iteration count.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_password_hash_iterations(), 2);
+/// ```
+fn default_password_hash_iterations() -> u32 {
+    2
+}
+
+/// Returns the default Argon2 parallelism.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_password_hash_parallelism(), 1);
+/// ```
+fn default_password_hash_parallelism() -> u32 {
+    1
+}
+
+/// Returns the default minimum password length, the 8 characters NIST SP 800-63B asks of user-chosen passwords.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_password_min_length(), 8);
+/// ```
+fn default_password_min_length() -> usize {
+    8
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            password_hash_memory_kib: default_password_hash_memory_kib(),
+            password_hash_iterations: default_password_hash_iterations(),
+            password_hash_parallelism: default_password_hash_parallelism(),
+            password_pepper: None,
+            password_min_length: default_password_min_length(),
+        }
+    }
+}
+
+impl fmt::Debug for Config {
+    /// Formats the configuration with the pepper masked, so it is safe to log.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("password_hash_memory_kib", &self.password_hash_memory_kib)
+            .field("password_hash_iterations", &self.password_hash_iterations)
+            .field("password_hash_parallelism", &self.password_hash_parallelism)
+            .field(
+                "password_pepper",
+                &self.password_pepper.as_ref().map(|_| "***"),
+            )
+            .field("password_min_length", &self.password_min_length)
+            .finish()
+    }
+}
+
+impl Config {
+    /// Loads password hashing configuration from environment variables prefixed with `RCAUTH_PASSWORD_`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::password::Config;
+    /// let config = Config::new().expect("Failed to load password config");
+    /// ```
+    #[allow(clippy::result_large_err)] // figment::Error, as returned by figment itself
+    pub fn new() -> std::result::Result<Self, figment::Error> {
+        Figment::new()
+            .merge(Env::prefixed("RCAUTH_PASSWORD_"))
+            .extract()
+    }
+
+    /// Validates that the parameters are accepted by Argon2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::password::Config;
+    /// assert!(Config::default().validate().is_ok());
+    ///
+    /// let config = Config { password_hash_iterations: 0, ..Config::default() };
+    /// assert!(config.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.validation_errors().into_result()?;
+        Ok(())
+    }
+
+    /// Collects every validation failure, keyed by the name of the offending field.
+    pub fn validation_errors(&self) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+
+        if self.password_hash_parallelism == 0 {
+            errors.add("password_hash_parallelism", "must be greater than zero");
+        }
+        if self.password_hash_iterations == 0 {
+            errors.add("password_hash_iterations", "must be greater than zero");
+        }
+        // Argon2 needs at least 8 KiB per lane
+        let min_memory_kib = 8 * self.password_hash_parallelism.max(1);
+        if self.password_hash_memory_kib < min_memory_kib {
+            errors.add(
+                "password_hash_memory_kib",
+                format!(
+                    "must be at least {} (8 per degree of parallelism)",
+                    min_memory_kib
+                ),
+            );
+        }
+        if errors.is_empty() && self.params().is_err() {
+            errors.add(
+                "password_hash_memory_kib",
+                "parameters are not accepted by Argon2",
+            );
+        }
+        if let Some(pepper) = &self.password_pepper
+            && pepper.len() < MIN_PEPPER_BYTES
+        {
+            errors.add(
+                "password_pepper",
+                format!("must be at least {} bytes", MIN_PEPPER_BYTES),
+            );
+        }
+        if self.password_min_length == 0 {
+            errors.add("password_min_length", "must be greater than zero");
+        }
+
+        errors
+    }
+
+    fn params(&self) -> std::result::Result<Params, argon2::Error> {
+        Params::new(
+            self.password_hash_memory_kib,
+            self.password_hash_iterations,
+            self.password_hash_parallelism,
+            None,
+        )
+    }
+}
+
+/// Outcome of checking a password against a stored hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// The password does not match.
+    Invalid,
+    /// The password matches a hash made with the current parameters.
+    Valid,
+    /// The password matches a hash made with weaker parameters; store this hash, made with the current ones, in its place.
+    Rehashed(String),
+}
+
+/// Hashes and verifies passwords with Argon2id and the configured cost.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::password::{Config, Hasher, Verification};
+/// let hasher = Hasher::new(&Config::default()).unwrap();
+/// let hash = hasher.hash("correct horse battery staple").unwrap();
+/// assert_eq!(hasher.verify("correct horse battery staple", &hash).unwrap(), Verification::Valid);
+/// ```
+#[derive(Clone)]
+pub struct Hasher {
+    argon2: Argon2<'static>,
+    params: Params,
+    pepper: Option<Vec<u8>>,
+    min_length: usize,
+    /// Hash of a random password with the current parameters, verified in place of a missing user's hash
+    dummy_hash: Arc<str>,
+}
+
+impl Hasher {
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if Argon2 rejects the parameters.
+    pub fn new(config: &Config) -> Result<Self> {
+        let params = config.params().map_err(|e| {
+            Error::new(
+                ErrorCode::ConfigurationError,
+                "Invalid password hashing parameters",
+                e,
+            )
+        })?;
+        let mut hasher = Self {
+            argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone()),
+            params,
+            pepper: config
+                .password_pepper
+                .as_ref()
+                .map(|pepper| pepper.as_bytes().to_vec()),
+            min_length: config.password_min_length,
+            dummy_hash: Arc::from(""),
+        };
+        // Nobody knows the password, so the dummy hash never verifies
+        let unguessable = SaltString::generate(&mut OsRng);
+        hasher.dummy_hash = hasher.hash(unguessable.as_str())?.into();
+        Ok(hasher)
+    }
+
+    /// Hashes `password` with a fresh salt, as a PHC string such as `$argon2id$v=19$m=19456,t=2,p=1$...`.
+    ///
+    /// With a pepper configured, the password's HMAC with the pepper is hashed instead, and the PHC string is prefixed with `$pepper-v1`.
+    pub fn hash(&self, password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        let (prefix, input) = match &self.pepper {
+            Some(pepper) => (PEPPERED_PREFIX, peppered(pepper, password)),
+            None => ("", password.as_bytes().to_vec()),
+        };
+        self.argon2
+            .hash_password(&input, &salt)
+            .map(|hash| format!("{}{}", prefix, hash))
+            .map_err(|e| Error::new(ErrorCode::Internal, "Failed to hash password", e))
+    }
+
+    /// Checks `password` against the stored `hash`, rehashing it when the hash was made with weaker parameters than the current ones, or without the configured pepper.
+    ///
+    /// The parameters recorded in the hash are used to verify it, so raising the cost does not lock anyone out; the upgraded hash should be stored once the login succeeds. Peppered hashes only verify with the pepper they were made with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Internal` error if `hash` is not a PHC string, e.g. a corrupted database value, or a `ConfigurationError` if it is peppered and no pepper is configured.
+    pub fn verify(&self, password: &str, hash: &str) -> Result<Verification> {
+        let (input, phc, peppered_hash) = match hash.strip_prefix(PEPPERED_PREFIX) {
+            Some(phc) => {
+                let Some(pepper) = &self.pepper else {
+                    return Err(Error::new_simple(
+                        ErrorCode::ConfigurationError,
+                        "Stored password hash is peppered but no password_pepper is configured",
+                    ));
+                };
+                (peppered(pepper, password), phc, true)
+            }
+            None => (password.as_bytes().to_vec(), hash, false),
+        };
+        let parsed = PasswordHash::new(phc)
+            .map_err(|e| Error::new(ErrorCode::Internal, "Stored password hash is malformed", e))?;
+
+        // A hash from before the pepper was configured is upgraded like a weaker one
+        let unpeppered = self.pepper.is_some() && !peppered_hash;
+        match self.argon2.verify_password(&input, &parsed) {
+            Ok(()) if unpeppered || self.is_outdated(&parsed) => {
+                Ok(Verification::Rehashed(self.hash(password)?))
+            }
+            Ok(()) => Ok(Verification::Valid),
+            Err(argon2::password_hash::Error::Password) => Ok(Verification::Invalid),
+            Err(e) => Err(Error::new(
+                ErrorCode::Internal,
+                "Failed to verify password",
+                e,
+            )),
+        }
+    }
+
+    /// Checks `password` against the user's stored `hash`, or, for an unknown user or one without a password, against a dummy hash that never matches.
+    ///
+    /// Both branches run a full Argon2 verification with the current parameters, so the response time does not reveal whether an account or password exists. `POST /password/change` checks the current password with it; there is no login handler yet, which must use it too.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Hasher::verify`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::password::{Config, Hasher, Verification};
+    /// let hasher = Hasher::new(&Config::default()).unwrap();
+    /// assert_eq!(hasher.verify_login("hunter22", None).unwrap(), Verification::Invalid);
+    /// ```
+    pub fn verify_login(&self, password: &str, hash: Option<&str>) -> Result<Verification> {
+        match hash {
+            Some(hash) => self.verify(password, hash),
+            None => {
+                self.verify(password, &self.dummy_hash)?;
+                Ok(Verification::Invalid)
+            }
+        }
+    }
+
+    /// Checks a password a user is choosing against the policy, recording each failure against `field`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::error::ValidationErrors;
+    /// # use rcauth_core::password::{Config, Hasher};
+    /// let hasher = Hasher::new(&Config::default()).unwrap();
+    /// let mut errors = ValidationErrors::new();
+    /// hasher.check_policy("password", "hunter2", &mut errors);
+    /// assert!(errors.get("password").is_some());
+    /// ```
+    pub fn check_policy(&self, field: &str, password: &str, errors: &mut ValidationErrors) {
+        if password.chars().count() < self.min_length {
+            errors.add(
+                field,
+                format!("must be at least {} characters long", self.min_length),
+            );
+        }
+        if password.trim().is_empty() {
+            errors.add(field, "cannot be blank");
+        }
+    }
+
+    /// Returns true if `hash` is not Argon2id or any of its costs is below the current parameters.
+    fn is_outdated(&self, hash: &PasswordHash<'_>) -> bool {
+        if hash.algorithm != Algorithm::Argon2id.ident() {
+            return true;
+        }
+        match Params::try_from(hash) {
+            Ok(params) => {
+                params.m_cost() < self.params.m_cost()
+                    || params.t_cost() < self.params.t_cost()
+                    || params.p_cost() < self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+/// Mixes `pepper` into `password` with HMAC-SHA256.
+fn peppered(pepper: &[u8], password: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(pepper).expect("HMAC accepts keys of any length");
+    mac.update(password.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEPPER: &str = "0123456789abcdef0123456789abcdef";
+
+    /// Cheap parameters, so the tests stay fast.
+    fn config(memory_kib: u32, iterations: u32) -> Config {
+        Config {
+            password_hash_memory_kib: memory_kib,
+            password_hash_iterations: iterations,
+            password_hash_parallelism: 1,
+            ..Config::default()
+        }
+    }
+
+    fn hasher(memory_kib: u32, iterations: u32) -> Hasher {
+        Hasher::new(&config(memory_kib, iterations)).unwrap()
+    }
+
+    fn peppered_hasher(pepper: &str) -> Hasher {
+        Hasher::new(&Config {
+            password_pepper: Some(pepper.to_string()),
+            ..config(8, 1)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn login_with_old_parameter_hash_upgrades_it() {
+        let mut stored = hasher(8, 1).hash("hunter22").unwrap();
+        let current = hasher(64, 2);
+
+        match current.verify("hunter22", &stored).unwrap() {
+            Verification::Rehashed(upgraded) => stored = upgraded,
+            other => panic!("expected an upgraded hash, got {:?}", other),
+        }
+
+        assert!(stored.contains("m=64,t=2,p=1"));
+        assert_eq!(
+            current.verify("hunter22", &stored).unwrap(),
+            Verification::Valid
+        );
+    }
+
+    #[test]
+    fn wrong_password_is_invalid_and_not_rehashed() {
+        let stored = hasher(8, 1).hash("hunter22").unwrap();
+
+        assert_eq!(
+            hasher(64, 2).verify("hunter23", &stored).unwrap(),
+            Verification::Invalid
+        );
+    }
+
+    #[test]
+    fn stronger_stored_hash_is_kept() {
+        let stored = hasher(64, 3).hash("hunter22").unwrap();
+
+        assert_eq!(
+            hasher(64, 2).verify("hunter22", &stored).unwrap(),
+            Verification::Valid
+        );
+    }
+
+    #[test]
+    fn malformed_hash_is_an_error() {
+        let err = hasher(8, 1).verify("hunter22", "plaintext").unwrap_err();
+        assert_eq!(err.code, ErrorCode::Internal);
+    }
+
+    #[test]
+    fn peppered_hash_verifies_with_the_pepper() {
+        let peppered = peppered_hasher(PEPPER);
+        let stored = peppered.hash("hunter22").unwrap();
+
+        assert!(stored.starts_with("$pepper-v1$argon2id$"));
+        assert_eq!(
+            peppered.verify("hunter22", &stored).unwrap(),
+            Verification::Valid
+        );
+        assert_eq!(
+            peppered.verify("hunter23", &stored).unwrap(),
+            Verification::Invalid
+        );
+        // Without the pepper, the hash is useless
+        assert!(hasher(8, 1).verify("hunter22", &stored).is_err());
+    }
+
+    #[test]
+    fn changing_the_pepper_invalidates_peppered_hashes() {
+        let stored = peppered_hasher(PEPPER).hash("hunter22").unwrap();
+
+        assert_eq!(
+            peppered_hasher("fedcba9876543210fedcba9876543210")
+                .verify("hunter22", &stored)
+                .unwrap(),
+            Verification::Invalid
+        );
+    }
+
+    #[test]
+    fn unpeppered_hash_is_upgraded_once_a_pepper_is_configured() {
+        let legacy = hasher(8, 1).hash("hunter22").unwrap();
+        let hasher = peppered_hasher(PEPPER);
+
+        let Verification::Rehashed(upgraded) = hasher.verify("hunter22", &legacy).unwrap() else {
+            panic!("expected the legacy hash to be upgraded");
+        };
+        assert!(upgraded.starts_with("$pepper-v1$"));
+        assert_eq!(
+            hasher.verify("hunter22", &upgraded).unwrap(),
+            Verification::Valid
+        );
+    }
+
+    #[test]
+    fn unknown_user_login_still_compares_a_hash() {
+        let mut hasher = peppered_hasher(PEPPER);
+        let dummy =
+            PasswordHash::new(hasher.dummy_hash.strip_prefix(PEPPERED_PREFIX).unwrap()).unwrap();
+        // The dummy costs as much to verify as a current user's hash
+        assert!(!hasher.is_outdated(&dummy));
+
+        assert_eq!(
+            hasher.verify_login("hunter22", None).unwrap(),
+            Verification::Invalid
+        );
+        let stored = hasher.hash("hunter22").unwrap();
+        assert_eq!(
+            hasher.verify_login("hunter22", Some(&stored)).unwrap(),
+            Verification::Valid
+        );
+
+        // A comparison that cannot run fails the login, so the unknown-user branch must be running one
+        hasher.dummy_hash = Arc::from("plaintext");
+        assert!(hasher.verify_login("hunter22", None).is_err());
+    }
+
+    #[test]
+    fn policy_enforces_minimum_length_in_characters() {
+        let hasher = Hasher::new(&Config {
+            password_min_length: 10,
+            ..config(8, 1)
+        })
+        .unwrap();
+        let failures = |password: &str| {
+            let mut errors = ValidationErrors::new();
+            hasher.check_policy("new_password", password, &mut errors);
+            errors.get("new_password").map(<[String]>::to_vec)
+        };
+
+        assert_eq!(
+            failures("short"),
+            Some(vec!["must be at least 10 characters long".to_string()])
+        );
+        assert_eq!(failures("long enough"), None);
+        // Characters, not bytes
+        assert!(failures("pässwörtçh").is_none());
+        assert!(
+            failures("          ")
+                .unwrap()
+                .contains(&"cannot be blank".to_string())
+        );
+    }
+
+    #[test]
+    fn debug_hides_pepper() {
+        let config = Config {
+            password_pepper: Some(PEPPER.to_string()),
+            ..Config::default()
+        };
+        assert!(!format!("{:?}", config).contains(PEPPER));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::error::{Error, ErrorCode, Result};
+use async_trait::async_trait;
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Key of the persisted maintenance-mode switch, a `bool`.
+pub const MAINTENANCE: &str = "maintenance";
+
+/// Storage for small global settings, e.g. runtime switches, persisted across restarts and shared by every instance.
+///
+/// Values are stored as JSON text; use the typed [`get`](SettingsRepository::get) and [`set`](SettingsRepository::set) helpers rather than the raw methods.
+#[async_trait]
+pub trait SettingsRepository: Send + Sync {
+    /// Returns the JSON text stored under `key`, or `None` if it was never set.
+    async fn get_raw(&self, key: &str) -> Result<Option<String>>;
+
+    /// Stores the JSON text `value` under `key`, replacing any previous value.
+    async fn set_raw(&self, key: &str, value: &str) -> Result<()>;
+}
+
+impl dyn SettingsRepository {
+    /// Returns the value stored under `key` as a `T`, or `None` if it was never set.
+    ///
+    /// # Errors
+    ///
+    /// Returns the store's error, or an `Internal` error if the stored value is not a valid `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let maintenance: Option<bool> = settings.get(settings::MAINTENANCE).await?;
+    /// ```
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let Some(value) = self.get_raw(key).await? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&value).map(Some).map_err(|e| {
+            Error::new_simple(ErrorCode::Internal, "Failed to read a setting").with_internal(
+                format!(
+                    "Setting '{}' is not a valid {}: {}",
+                    key,
+                    std::any::type_name::<T>(),
+                    e
+                ),
+            )
+        })
+    }
+
+    /// Stores `value` under `key`, replacing any previous value.
+    ///
+    /// # Errors
+    ///
+    /// Returns the store's error, or an `Internal` error if `value` cannot be serialized to JSON.
+    pub async fn set<T: Serialize + ?Sized + Sync>(&self, key: &str, value: &T) -> Result<()> {
+        let value = serde_json::to_string(value).map_err(|e| {
+            Error::new_simple(ErrorCode::Internal, "Failed to write a setting")
+                .with_internal(format!("Setting '{}' cannot be serialized: {}", key, e))
+        })?;
+        self.set_raw(key, &value).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Settings(Mutex<HashMap<String, String>>);
+
+    #[async_trait]
+    impl SettingsRepository for Settings {
+        async fn get_raw(&self, key: &str) -> Result<Option<String>> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        async fn set_raw(&self, key: &str, value: &str) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Banner {
+        message: String,
+        until: u64,
+    }
+
+    #[tokio::test]
+    async fn typed_values_round_trip() {
+        let settings: &dyn SettingsRepository = &Settings::default();
+        assert_eq!(settings.get::<bool>(MAINTENANCE).await.unwrap(), None);
+
+        settings.set(MAINTENANCE, &true).await.unwrap();
+        assert_eq!(settings.get(MAINTENANCE).await.unwrap(), Some(true));
+
+        let banner = Banner {
+            message: "Down at noon".to_string(),
+            until: 1_700_000_000,
+        };
+        settings.set("banner", &banner).await.unwrap();
+        assert_eq!(settings.get("banner").await.unwrap(), Some(banner));
+    }
+
+    #[tokio::test]
+    async fn values_of_another_type_are_an_internal_error() {
+        let settings: &dyn SettingsRepository = &Settings::default();
+        settings.set(MAINTENANCE, "yes").await.unwrap();
+
+        let err = settings.get::<bool>(MAINTENANCE).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::Internal);
+        assert!(
+            err.internal
+                .unwrap()
+                .contains("'maintenance' is not a valid bool")
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! In-memory stand-ins for the storage traits, for tests of the code built on them.
+//!
+//! Compiled for this crate's tests and, through the `test-util` feature, as a dev-dependency of the others.
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::idempotency::{CachedResponse, Claim, IdempotencyStore};
+use crate::jwt::{Config as JwtConfig, Jwt};
+use crate::user::{
+    BulkCreateReport, NewUser, UserChanges, UserFilter, UserProfile, UserRepository,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Token signer with a fixed HS256 secret and default settings; every instance accepts the others' tokens.
+pub fn jwt() -> Jwt {
+    Jwt::new(&JwtConfig {
+        jwt_secret: "0123456789abcdef0123456789abcdef".to_string(),
+        ..JwtConfig::default()
+    })
+    .unwrap()
+}
+
+/// Log output captured in memory, to be handed to a test subscriber as its writer; clones share it.
+///
+/// # Examples
+///
+/// ```ignore
+/// let logs = CapturedLogs::default();
+/// let writer = logs.clone();
+/// let subscriber = tracing_subscriber::fmt().with_writer(move || writer.clone()).finish();
+/// tracing::subscriber::with_default(subscriber, || tracing::info!("hello"));
+/// assert!(logs.contents().contains("hello"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    /// Everything written so far.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An [`IdempotencyStore`] keeping its records in memory, ignoring the TTL; clones share them.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryIdempotency(Arc<Mutex<IdempotencyRecords>>);
+
+/// Request hash of each key, and its response once recorded.
+type IdempotencyRecords = HashMap<String, (String, Option<CachedResponse>)>;
+
+#[async_trait]
+impl IdempotencyStore for InMemoryIdempotency {
+    async fn claim(&self, key: &str, request_hash: &str, _ttl: Duration) -> Result<Claim> {
+        let mut records = self.0.lock().unwrap();
+        Ok(match records.get(key) {
+            None => {
+                records.insert(key.to_string(), (request_hash.to_string(), None));
+                Claim::Claimed
+            }
+            Some((request_hash, None)) => Claim::InProgress {
+                request_hash: request_hash.clone(),
+            },
+            Some((request_hash, Some(response))) => Claim::Completed {
+                request_hash: request_hash.clone(),
+                response: response.clone(),
+            },
+        })
+    }
+
+    async fn complete(&self, key: &str, response: &CachedResponse) -> Result<()> {
+        if let Some(record) = self.0.lock().unwrap().get_mut(key) {
+            record.1 = Some(response.clone());
+        }
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) -> Result<()> {
+        let mut records = self.0.lock().unwrap();
+        if records
+            .get(key)
+            .is_some_and(|(_, response)| response.is_none())
+        {
+            records.remove(key);
+        }
+        Ok(())
+    }
+}
+
+/// A user as kept by [`InMemoryUsers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredUser {
+    pub tenant_id: String,
+    pub profile: UserProfile,
+    pub role: String,
+    pub password_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Ids of the user's live sessions
+    pub sessions: Vec<String>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl StoredUser {
+    /// A verified user with `id` and `email` in the tenant `tenant-1`, without a password or sessions.
+    pub fn new(id: &str, email: &str) -> Self {
+        Self {
+            tenant_id: "tenant-1".to_string(),
+            profile: UserProfile {
+                id: id.to_string(),
+                email: email.to_string(),
+                email_verified: true,
+                name: None,
+                must_reset_password: false,
+                version: 1,
+            },
+            role: "user".to_string(),
+            password_hash: None,
+            created_at: Utc::now(),
+            sessions: Vec::new(),
+            deleted_at: None,
+        }
+    }
+
+    pub fn with_password_hash(mut self, password_hash: impl Into<String>) -> Self {
+        self.password_hash = Some(password_hash.into());
+        self
+    }
+
+    pub fn with_sessions(mut self, sessions: &[&str]) -> Self {
+        self.sessions = sessions.iter().map(|s| s.to_string()).collect();
+        self
+    }
+}
+
+/// A [`UserRepository`] keeping its users in memory; clones share them.
+///
+/// Emails are matched ignoring case within a tenant, as in the database, and new users get the ids `user-1`, `user-2` and so on. Deleted users are kept until purged, as in the database.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryUsers(Arc<Mutex<Vec<StoredUser>>>);
+
+impl InMemoryUsers {
+    pub fn insert(&self, user: StoredUser) {
+        self.0.lock().unwrap().push(user);
+    }
+
+    pub fn get(&self, id: &str) -> Option<StoredUser> {
+        self.with_user(id, |user| user.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn with_user<T>(&self, id: &str, f: impl FnOnce(&mut StoredUser) -> T) -> Option<T> {
+        let mut users = self.0.lock().unwrap();
+        users.iter_mut().find(|user| user.profile.id == id).map(f)
+    }
+
+    /// Like `with_user`, but for a user that is not deleted.
+    fn with_live_user<T>(&self, id: &str, f: impl FnOnce(&mut StoredUser) -> T) -> Option<T> {
+        let mut users = self.0.lock().unwrap();
+        users
+            .iter_mut()
+            .find(|user| user.profile.id == id && user.deleted_at.is_none())
+            .map(f)
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUsers {
+    async fn profile(&self, id: &str) -> Result<Option<UserProfile>> {
+        Ok(self.with_live_user(id, |user| user.profile.clone()))
+    }
+
+    async fn profile_including_deleted(
+        &self,
+        id: &str,
+    ) -> Result<Option<(UserProfile, Option<DateTime<Utc>>)>> {
+        Ok(self.with_user(id, |user| (user.profile.clone(), user.deleted_at)))
+    }
+
+    async fn delete(&self, id: &str) -> Result<Option<u64>> {
+        Ok(self.with_live_user(id, |user| {
+            user.deleted_at = Some(Utc::now());
+            std::mem::take(&mut user.sessions).len() as u64
+        }))
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        expected_version: u64,
+        changes: &UserChanges,
+    ) -> Result<Option<UserProfile>> {
+        let mut users = self.0.lock().unwrap();
+        let Some(index) = users
+            .iter()
+            .position(|user| user.profile.id == id && user.deleted_at.is_none())
+        else {
+            return Ok(None);
+        };
+        let user = &users[index];
+        if user.profile.version != expected_version {
+            return Err(Error::new_simple(
+                ErrorCode::Conflict,
+                "The user was changed since it was read",
+            ));
+        }
+        if let Some(email) = &changes.email {
+            let taken = users.iter().enumerate().any(|(other, stored)| {
+                other != index
+                    && stored.tenant_id == user.tenant_id
+                    && stored.profile.email.eq_ignore_ascii_case(email)
+            });
+            if taken {
+                return Err(Error::new_simple(
+                    ErrorCode::Conflict,
+                    "Conflict: Record already exists",
+                ));
+            }
+        }
+
+        let user = &mut users[index];
+        if let Some(email) = &changes.email {
+            user.profile.email = email.clone();
+        }
+        if let Some(email_verified) = changes.email_verified {
+            user.profile.email_verified = email_verified;
+        }
+        if let Some(role) = &changes.role {
+            user.role = role.clone();
+        }
+        user.profile.version += 1;
+        Ok(Some(user.profile.clone()))
+    }
+
+    async fn purge(&self, deleted_before: DateTime<Utc>) -> Result<u64> {
+        let mut users = self.0.lock().unwrap();
+        let before = users.len();
+        users.retain(|user| {
+            user.deleted_at
+                .is_none_or(|deleted| deleted >= deleted_before)
+        });
+        Ok((before - users.len()) as u64)
+    }
+
+    async fn force_password_reset(&self, id: &str) -> Result<Option<u64>> {
+        Ok(self.with_live_user(id, |user| {
+            user.profile.must_reset_password = true;
+            std::mem::take(&mut user.sessions).len() as u64
+        }))
+    }
+
+    async fn count(&self, filter: &UserFilter) -> Result<u64> {
+        let users = self.0.lock().unwrap();
+        let matching = users.iter().filter(|user| {
+            (filter.include_deleted || user.deleted_at.is_none())
+                && filter
+                    .verified
+                    .is_none_or(|verified| user.profile.email_verified == verified)
+                && filter
+                    .must_reset_password
+                    .is_none_or(|reset| user.profile.must_reset_password == reset)
+                && filter
+                    .created_after
+                    .is_none_or(|after| user.created_at >= after)
+                && filter
+                    .created_before
+                    .is_none_or(|before| user.created_at < before)
+        });
+        Ok(matching.count() as u64)
+    }
+
+    async fn upsert_by_email(&self, user: NewUser) -> Result<(UserProfile, bool)> {
+        let mut users = self.0.lock().unwrap();
+        let existing = users.iter().find(|stored| {
+            stored.tenant_id == user.tenant_id
+                && stored.profile.email.eq_ignore_ascii_case(&user.email)
+        });
+        if let Some(existing) = existing {
+            if existing.deleted_at.is_some() {
+                return Err(Error::new_simple(
+                    ErrorCode::Conflict,
+                    "The email belongs to a deleted user",
+                ));
+            }
+            return Ok((existing.profile.clone(), false));
+        }
+
+        let mut stored = StoredUser::new(&format!("user-{}", users.len() + 1), &user.email);
+        stored.tenant_id = user.tenant_id;
+        stored.profile.email_verified = user.email_verified;
+        stored.role = user.role;
+        stored.password_hash = user.password_hash;
+        let profile = stored.profile.clone();
+        users.push(stored);
+        Ok((profile, true))
+    }
+
+    async fn bulk_create(&self, users: &[NewUser]) -> Result<BulkCreateReport> {
+        let mut report = BulkCreateReport::default();
+        for user in users {
+            match self.upsert_by_email(user.clone()).await {
+                Ok((_, true)) => report.inserted += 1,
+                Ok((_, false)) => report.skipped += 1,
+                Err(e) if e.code == ErrorCode::Conflict => report.skipped += 1,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(report)
+    }
+
+    async fn find_by_email(&self, tenant_id: &str, email: &str) -> Result<Option<UserProfile>> {
+        let users = self.0.lock().unwrap();
+        Ok(users
+            .iter()
+            .find(|user| {
+                user.tenant_id == tenant_id
+                    && user.profile.email.eq_ignore_ascii_case(email)
+                    && user.deleted_at.is_none()
+            })
+            .map(|user| user.profile.clone()))
+    }
+
+    async fn password_hash(&self, id: &str) -> Result<Option<String>> {
+        Ok(self
+            .with_live_user(id, |user| user.password_hash.clone())
+            .flatten())
+    }
+
+    async fn change_password(
+        &self,
+        id: &str,
+        password_hash: &str,
+        keep_session: Option<&str>,
+    ) -> Result<Option<u64>> {
+        Ok(self.with_live_user(id, |user| {
+            user.password_hash = Some(password_hash.to_string());
+            user.profile.must_reset_password = false;
+            let before = user.sessions.len();
+            user.sessions
+                .retain(|session| Some(session.as_str()) == keep_session);
+            (before - user.sessions.len()) as u64
+        }))
+    }
+}
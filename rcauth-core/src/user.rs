@@ -0,0 +1,316 @@
+use crate::email::EmailNormalizer;
+use crate::error::{Error, ErrorCode, Result, ValidationErrors};
+use crate::password::Hasher;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// Role of users who are not administrators.
+pub const USER_ROLE: &str = "user";
+
+/// `reason` detail of a login refused until the user resets their password.
+pub const PASSWORD_RESET_REQUIRED: &str = "password_reset_required";
+
+/// What the service knows about a user, as released to clients through OIDC claims.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserProfile {
+    /// User id, the `sub` of the user's tokens
+    pub id: String,
+    pub email: String,
+    /// Whether the user confirmed they own `email`
+    pub email_verified: bool,
+    /// Display name, if the user gave one
+    pub name: Option<String>,
+    /// Set by an administrator; the user cannot log in until they reset their password
+    pub must_reset_password: bool,
+    /// Revision of the user, to hand back to [`UserRepository::update`] so it detects a concurrent edit
+    pub version: u64,
+}
+
+impl UserProfile {
+    /// Refuses a login the account cannot make yet, even with the right password.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Forbidden` error with the [`PASSWORD_RESET_REQUIRED`] reason if an administrator forced a password reset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_core::user::UserProfile;
+    /// let user = UserProfile {
+    ///     id: "user-1".to_string(),
+    ///     email: "ada@example.com".to_string(),
+    ///     email_verified: true,
+    ///     name: None,
+    ///     must_reset_password: false,
+    ///     version: 1,
+    /// };
+    /// assert!(user.ensure_can_log_in().is_ok());
+    /// ```
+    pub fn ensure_can_log_in(&self) -> Result<()> {
+        if self.must_reset_password {
+            return Err(Error::new_simple(
+                ErrorCode::Forbidden,
+                "Password must be reset before logging in",
+            )
+            .with_data("reason", serde_json::json!(PASSWORD_RESET_REQUIRED)));
+        }
+        Ok(())
+    }
+}
+
+/// Conditions a counted user must meet; unset fields match every user.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::user::UserFilter;
+/// // Unverified users who signed up in the last week
+/// let filter = UserFilter {
+///     verified: Some(false),
+///     created_after: Some(chrono::Utc::now() - chrono::Duration::days(7)),
+///     ..UserFilter::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserFilter {
+    /// Whether the user confirmed their email
+    pub verified: Option<bool>,
+    /// Whether the user is blocked from logging in until they reset their password
+    pub must_reset_password: Option<bool>,
+    /// Created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Created before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Whether to count deleted users that are not purged yet, for admins
+    pub include_deleted: bool,
+}
+
+/// Account created on a user's first sign-in when none exists for their email, e.g. through an OAuth provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewUser {
+    /// Tenant the account belongs to; emails are unique within a tenant, ignoring case
+    pub tenant_id: String,
+    /// Email in the form of [`EmailNormalizer::normalize`](crate::email::EmailNormalizer::normalize), which is how it is stored
+    pub email: String,
+    /// Password hash, or `None` for users who only sign in through a provider
+    pub password_hash: Option<String>,
+    pub role: String,
+    /// Whether the provider vouched for `email`
+    pub email_verified: bool,
+}
+
+/// Edit of a user by an admin, through [`UserRepository::update`]; fields left `None` are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserChanges {
+    /// Email in the form of [`EmailNormalizer::normalize`](crate::email::EmailNormalizer::normalize)
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub role: Option<String>,
+}
+
+/// Outcome of [`UserRepository::bulk_create`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BulkCreateReport {
+    /// Users created
+    pub inserted: u64,
+    /// Users left out because their email was already taken in their tenant, including by a deleted user
+    pub skipped: u64,
+}
+
+/// Storage of user accounts.
+///
+/// Deleted users are kept, hidden from every method but [`profile_including_deleted`](Self::profile_including_deleted), until they are purged. Their email stays taken in their tenant until then.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Returns the profile of the user with `id`, or `None` if there is no such user.
+    async fn profile(&self, id: &str) -> Result<Option<UserProfile>>;
+
+    /// Returns the profile of the user with `id` and when they were deleted, if they were, or `None` if there is no such user or they were purged; for admins.
+    async fn profile_including_deleted(
+        &self,
+        id: &str,
+    ) -> Result<Option<(UserProfile, Option<DateTime<Utc>>)>>;
+
+    /// Deletes the user with `id`, keeping their record until it is purged, and revokes all their sessions.
+    ///
+    /// Returns the number of sessions revoked, or `None` if there is no such user. Both changes are made atomically.
+    async fn delete(&self, id: &str) -> Result<Option<u64>>;
+
+    /// Applies `changes` to the user with `id` if they are still at `expected_version`, i.e. unchanged since they were read, returning the user at their next version.
+    ///
+    /// Returns `None` if there is no such user.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Conflict` error if the user was changed since `expected_version`, or if the new email is already taken in their tenant.
+    async fn update(
+        &self,
+        id: &str,
+        expected_version: u64,
+        changes: &UserChanges,
+    ) -> Result<Option<UserProfile>>;
+
+    /// Permanently removes the users deleted before `deleted_before`, with their sessions and identities, returning how many were removed.
+    async fn purge(&self, deleted_before: DateTime<Utc>) -> Result<u64>;
+
+    /// Blocks the user with `id` from logging in until they reset their password, and revokes all their sessions.
+    ///
+    /// Returns the number of sessions revoked, or `None` if there is no such user. Both changes are made atomically.
+    async fn force_password_reset(&self, id: &str) -> Result<Option<u64>>;
+
+    /// Counts the users matching `filter`, without loading them.
+    async fn count(&self, filter: &UserFilter) -> Result<u64>;
+
+    /// Returns the user with `user`'s email in its tenant, creating it from `user` if there is none, and whether it was created.
+    ///
+    /// Concurrent calls for the same email create a single user. An existing user is returned unchanged, so signing in through a provider never replaces the password of a password user.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Conflict` error if the email belongs to a deleted user that is not purged yet.
+    async fn upsert_by_email(&self, user: NewUser) -> Result<(UserProfile, bool)>;
+
+    /// Creates `users` in one transaction, e.g. when importing them from another system, skipping those whose email is already taken in their tenant, including by an earlier entry of `users`.
+    ///
+    /// Existing users are left unchanged.
+    async fn bulk_create(&self, users: &[NewUser]) -> Result<BulkCreateReport>;
+
+    /// Returns the user with `email` in the tenant with `tenant_id`, or `None` if there is none.
+    ///
+    /// `email` should be normalized with [`EmailNormalizer`](crate::email::EmailNormalizer) as it was on sign-up; it is also matched ignoring case.
+    async fn find_by_email(&self, tenant_id: &str, email: &str) -> Result<Option<UserProfile>>;
+
+    /// Returns the password hash of the user with `id`, or `None` if there is no such user or they only sign in through a provider.
+    async fn password_hash(&self, id: &str) -> Result<Option<String>>;
+
+    /// Replaces the password hash of the user with `id`, lifting any forced password reset, and revokes all their sessions but `keep_session`.
+    ///
+    /// Returns the number of sessions revoked, or `None` if there is no such user. Both changes are made atomically.
+    async fn change_password(
+        &self,
+        id: &str,
+        password_hash: &str,
+        keep_session: Option<&str>,
+    ) -> Result<Option<u64>>;
+}
+
+/// Creates a verified password user with `email` and `role` in the tenant with `tenant_id`, e.g. an operator's admin account.
+///
+/// The email is stored as normalized by `emails`, and the password must meet the `hasher`'s policy.
+///
+/// # Errors
+///
+/// Returns a `ValidationError` if the email is not an email address or the password is too weak, a `Conflict` error if the tenant already has a user with the email, or the store's error if the user cannot be created.
+///
+/// # Examples
+///
+/// ```ignore
+/// let admin = user::create_user(&store, &hasher, &emails, &tenant_id, "ada@example.com", password, ADMIN_ROLE).await?;
+/// ```
+pub async fn create_user(
+    users: &dyn UserRepository,
+    hasher: &Hasher,
+    emails: &EmailNormalizer,
+    tenant_id: &str,
+    email: &str,
+    password: &str,
+    role: &str,
+) -> Result<UserProfile> {
+    let email = emails.normalize(email)?;
+    let mut errors = ValidationErrors::new();
+    hasher.check_policy("password", password, &mut errors);
+    errors.into_result()?;
+
+    let (user, created) = users
+        .upsert_by_email(NewUser {
+            tenant_id: tenant_id.to_string(),
+            email: email.clone(),
+            password_hash: Some(hasher.hash(password)?),
+            role: role.to_string(),
+            email_verified: true,
+        })
+        .await?;
+    if !created {
+        return Err(Error::new_simple(
+            ErrorCode::Conflict,
+            format!("A user with the email {} already exists", email),
+        )
+        .with_data("id", serde_json::json!(user.id)));
+    }
+    Ok(user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::password::{self, Verification};
+    use crate::testing::InMemoryUsers;
+
+    #[test]
+    fn forced_reset_blocks_login() {
+        let mut user = UserProfile {
+            id: "user-1".to_string(),
+            email: "ada@example.com".to_string(),
+            email_verified: true,
+            name: None,
+            must_reset_password: false,
+            version: 1,
+        };
+        assert!(user.ensure_can_log_in().is_ok());
+
+        user.must_reset_password = true;
+        let err = user.ensure_can_log_in().unwrap_err();
+        assert_eq!(err.code, ErrorCode::Forbidden);
+        assert_eq!(err.data.unwrap()["reason"], PASSWORD_RESET_REQUIRED);
+    }
+
+    /// Cheap parameters, so the tests stay fast.
+    fn hasher() -> Hasher {
+        Hasher::new(&password::Config {
+            password_hash_memory_kib: 8,
+            password_hash_iterations: 1,
+            password_hash_parallelism: 1,
+            ..password::Config::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn created_user_can_log_in_and_duplicates_conflict() {
+        let users = InMemoryUsers::default();
+        let hasher = hasher();
+        let emails = EmailNormalizer::default();
+        let create = |email: &'static str, password: &'static str| {
+            create_user(
+                &users, &hasher, &emails, "tenant-1", email, password, "admin",
+            )
+        };
+
+        let admin = create(" Ada@Example.com", "correct horse battery staple")
+            .await
+            .unwrap();
+        assert_eq!(admin.email, "ada@example.com");
+        assert!(admin.email_verified);
+        let stored = users.get(&admin.id).unwrap();
+        assert_eq!(stored.role, "admin");
+        let hash = users.password_hash(&admin.id).await.unwrap();
+        assert_eq!(
+            hasher
+                .verify_login("correct horse battery staple", hash.as_deref())
+                .unwrap(),
+            Verification::Valid
+        );
+
+        let err = create("ADA@example.com", "another passphrase")
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        // The existing user keeps their password
+        assert_eq!(users.password_hash(&admin.id).await.unwrap(), hash);
+
+        let err = create("grace@example.com", "short").await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::ValidationError);
+        assert_eq!(users.len(), 1);
+    }
+}
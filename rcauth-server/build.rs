@@ -0,0 +1,20 @@
+use std::process::Command;
+
+/// Embeds the git commit hash and build timestamp for the `/health/info` endpoint.
+fn main() {
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let build_timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+    println!("cargo:rustc-env=RCAUTH_GIT_COMMIT={}", git_commit);
+    println!("cargo:rustc-env=RCAUTH_BUILD_TIMESTAMP={}", build_timestamp);
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+}
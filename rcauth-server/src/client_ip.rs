@@ -0,0 +1,147 @@
+use http::HeaderMap;
+use ipnet::IpNet;
+use rcauth_core::error::Result;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::Config;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Resolves the real client IP of a request that may have passed through reverse proxies.
+///
+/// `X-Forwarded-For` is only consulted when the socket peer is a trusted proxy, and is walked right-to-left: each trusted hop is skipped and the first untrusted address is the client. Entries to the left of it were supplied by the client and may be spoofed, so they are ignored.
+///
+/// The servers expose the socket peer to handlers as `ConnectInfo<SocketAddr>`.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::{ConfigBuilder, TrustedProxies};
+/// # use http::HeaderMap;
+/// let config = ConfigBuilder::default().trusted_proxies(vec!["10.0.0.0/8"]).build().unwrap();
+/// let proxies = TrustedProxies::from_config(&config).unwrap();
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert("x-forwarded-for", "203.0.113.7".parse().unwrap());
+/// let ip = proxies.client_ip(&headers, "10.1.2.3".parse().unwrap());
+/// assert_eq!(ip.to_string(), "203.0.113.7");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies {
+    networks: Arc<[IpNet]>,
+}
+
+impl TrustedProxies {
+    pub fn new(networks: Vec<IpNet>) -> Self {
+        Self {
+            networks: networks.into(),
+        }
+    }
+
+    /// Builds the list from `trusted_proxies` in `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if an entry is not an IP address or CIDR range.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self::new(config.trusted_proxy_networks()?))
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+        self.networks.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Returns the client IP for a request received from `peer`, falling back to `peer` when it is not a trusted proxy or sent no usable `X-Forwarded-For`.
+    pub fn client_ip(&self, headers: &HeaderMap, peer: IpAddr) -> IpAddr {
+        let mut client = canonical(peer);
+        if !self.is_trusted(client) {
+            return client;
+        }
+
+        // Proxies may append to an existing header or send several, so treat them as one list
+        let hops = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect::<Vec<_>>();
+
+        for hop in hops.into_iter().rev() {
+            let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+                // An unparseable entry cannot be attributed, so stop at the last trusted hop
+                break;
+            };
+            client = canonical(ip);
+            if !self.is_trusted(client) {
+                break;
+            }
+        }
+
+        client
+    }
+}
+
+/// Maps IPv4-mapped IPv6 addresses such as `::ffff:10.0.0.1` to plain IPv4, so they match IPv4 ranges.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxies(networks: &[&str]) -> TrustedProxies {
+        TrustedProxies::new(networks.iter().map(|n| n.parse().unwrap()).collect())
+    }
+
+    fn forwarded_for(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(X_FORWARDED_FOR, value.parse().unwrap());
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn direct_connection_uses_peer_and_ignores_header() {
+        let proxies = proxies(&["10.0.0.0/8"]);
+        let client = proxies.client_ip(&forwarded_for("1.2.3.4"), ip("198.51.100.9"));
+        assert_eq!(client, ip("198.51.100.9"));
+    }
+
+    #[test]
+    fn single_trusted_proxy_forwards_client() {
+        let proxies = proxies(&["10.0.0.0/8"]);
+        let client = proxies.client_ip(&forwarded_for("203.0.113.7"), ip("10.0.0.2"));
+        assert_eq!(client, ip("203.0.113.7"));
+
+        // Without a header the proxy itself is all we know
+        assert_eq!(
+            proxies.client_ip(&HeaderMap::new(), ip("10.0.0.2")),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn spoofed_hops_left_of_the_client_are_ignored() {
+        let proxies = proxies(&["10.0.0.0/8"]);
+        // The client prepended a fake address; the first untrusted hop from the right is the real one
+        let headers = forwarded_for("1.1.1.1, 203.0.113.7, 10.0.0.5");
+        let client = proxies.client_ip(&headers, ip("10.0.0.2"));
+        assert_eq!(client, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn ipv4_mapped_peer_matches_ipv4_range() {
+        let proxies = proxies(&["10.0.0.0/8"]);
+        let client = proxies.client_ip(&forwarded_for("203.0.113.7"), ip("::ffff:10.0.0.2"));
+        assert_eq!(client, ip("203.0.113.7"));
+    }
+}
@@ -1,6 +1,156 @@
-use serde::Deserialize;
+use http::{HeaderValue, Uri};
+use ipnet::IpNet;
+use rcauth_core::email::EmailNormalizer;
+use rcauth_core::error::{Error, ErrorCode, Result, ValidationErrors};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 
-#[derive(Clone, Debug, Deserialize)]
+use crate::{ErrorEnvelope, TimestampFormat};
+
+/// Origins accepted by the CORS layer, parsed from `cors_allowed_origins`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CorsOrigins {
+    /// The `*` wildcard: any origin is allowed.
+    Any,
+    /// An explicit list of origins.
+    List(Vec<HeaderValue>),
+}
+
+/// Authentication methods a deployment offers, e.g. password-only or OAuth-only.
+///
+/// The routes of a disabled method are not registered at all, so they answer `404 Not Found` and are left out of the OpenAPI document. Every method is enabled by default.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AuthMethods {
+    /// Password logins and password changes
+    pub password: bool,
+    /// Sign-in through external OAuth providers
+    pub oauth: bool,
+    /// Passwordless sign-in through emailed links
+    pub magic_link: bool,
+    /// Passkeys and security keys
+    pub webauthn: bool,
+    /// Time-based one-time passwords
+    pub totp: bool,
+}
+
+impl Default for AuthMethods {
+    /// Returns `AuthMethods` with every method enabled.
+    fn default() -> Self {
+        Self {
+            password: true,
+            oauth: true,
+            magic_link: true,
+            webauthn: true,
+            totp: true,
+        }
+    }
+}
+
+/// Token cookies for browser clients, which keep the tokens out of reach of scripts.
+///
+/// When enabled, authenticated routes accept the access token from its cookie when no `Authorization` header is sent. Mutating requests authenticated by cookie must then also pass the double-submit CSRF check, unless `csrf_protection` is turned off. Disabled by default.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TokenCookieSettings {
+    /// Accepts the access token cookie
+    pub enabled: bool,
+    /// Requires the CSRF token header on mutating requests authenticated by cookie
+    pub csrf_protection: bool,
+}
+
+impl Default for TokenCookieSettings {
+    /// Returns disabled `TokenCookieSettings`, which would still require the CSRF check once enabled.
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            csrf_protection: true,
+        }
+    }
+}
+
+/// CORS settings of one server: its `api_cors_*` or `management_cors_*` settings, falling back to the shared `cors_*` ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CorsSettings {
+    pub allowed_origins: Vec<String>,
+    pub max_age_secs: Option<u64>,
+    pub allow_credentials: bool,
+}
+
+impl CorsSettings {
+    /// Parses `allowed_origins` into the origins accepted by the CORS layer.
+    ///
+    /// A `*` entry allows any origin. Every other entry must be a well-formed `scheme://host[:port]` origin; a trailing `/` is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` naming the first malformed origin.
+    pub fn origins(&self) -> Result<CorsOrigins> {
+        if self.allowed_origins.iter().any(|origin| origin == "*") {
+            return Ok(CorsOrigins::Any);
+        }
+
+        self.allowed_origins
+            .iter()
+            .map(|origin| parse_cors_origin(origin))
+            .collect::<Result<Vec<_>>>()
+            .map(CorsOrigins::List)
+    }
+
+    /// Adds the problems of these settings to `errors`, under the names of the settings prefixed with `prefix`.
+    fn validate(&self, errors: &mut ValidationErrors, prefix: &str) {
+        if self.allowed_origins.is_empty() {
+            errors.add(
+                format!("{}cors_allowed_origins", prefix),
+                "CORS is enabled but no allowed origins are specified",
+            );
+            return;
+        }
+
+        // Reject malformed origins up front instead of when the server starts
+        match self.origins() {
+            // Browsers reject credentialed responses that allow any origin
+            Ok(CorsOrigins::Any) if self.allow_credentials => errors.add(
+                format!("{}cors_allow_credentials", prefix),
+                "CORS credentials cannot be allowed with the '*' wildcard origin",
+            ),
+            Ok(_) => {}
+            Err(e) => errors.add(format!("{}cors_allowed_origins", prefix), e.message),
+        }
+    }
+}
+
+/// Parses a single CORS origin, which must be a `scheme://host[:port]` URL without a path.
+fn parse_cors_origin(origin: &str) -> Result<HeaderValue> {
+    let uri: Uri = origin.parse().map_err(|e| {
+        Error::new(
+            ErrorCode::ConfigurationError,
+            format!("Invalid CORS origin '{}'", origin),
+            e,
+        )
+    })?;
+
+    let has_path = !matches!(uri.path_and_query().map(|p| p.as_str()), None | Some("/"));
+    if uri.scheme().is_none() || uri.authority().is_none() || has_path {
+        return Err(Error::new_simple(
+            ErrorCode::ConfigurationError,
+            format!(
+                "Invalid CORS origin '{}': expected scheme://host[:port]",
+                origin
+            ),
+        ));
+    }
+
+    HeaderValue::from_str(origin.trim_end_matches('/')).map_err(|e| {
+        Error::new(
+            ErrorCode::ConfigurationError,
+            format!("Invalid CORS origin '{}'", origin),
+            e,
+        )
+    })
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
     #[serde(default = "default_api_server_host")]
     pub api_server_host: String,
@@ -16,13 +166,135 @@ pub struct Config {
     pub enable_cors: bool,
     #[serde(default = "default_cors_allowed_origins")]
     pub cors_allowed_origins: Vec<String>,
+    #[serde(default)]
+    pub cors_max_age_secs: Option<u64>,
+    #[serde(default)]
+    pub cors_allow_credentials: bool,
+    /// Origins the API server allows, in place of `cors_allowed_origins`
+    #[serde(default)]
+    pub api_cors_allowed_origins: Option<Vec<String>>,
+    /// Preflight cache lifetime of the API server, in place of `cors_max_age_secs`
+    #[serde(default)]
+    pub api_cors_max_age_secs: Option<u64>,
+    /// Whether the API server allows credentials, in place of `cors_allow_credentials`
+    #[serde(default)]
+    pub api_cors_allow_credentials: Option<bool>,
+    /// Origins the management server allows, in place of `cors_allowed_origins`
+    #[serde(default)]
+    pub management_cors_allowed_origins: Option<Vec<String>>,
+    /// Preflight cache lifetime of the management server, in place of `cors_max_age_secs`
+    #[serde(default)]
+    pub management_cors_max_age_secs: Option<u64>,
+    /// Whether the management server allows credentials, in place of `cors_allow_credentials`
+    #[serde(default)]
+    pub management_cors_allow_credentials: Option<bool>,
+    #[serde(default = "default_api_base_path")]
+    pub api_base_path: String,
+    #[serde(default = "default_management_base_path")]
+    pub management_base_path: String,
+    #[serde(default)]
+    pub openapi_title: Option<String>,
+    #[serde(default)]
+    pub openapi_version: Option<String>,
+    #[serde(default)]
+    pub openapi_description: Option<String>,
+    #[serde(default)]
+    pub openapi_contact_name: Option<String>,
+    #[serde(default)]
+    pub openapi_contact_email: Option<String>,
+    #[serde(default)]
+    pub openapi_contact_url: Option<String>,
+    #[serde(default = "default_http_client_connect_timeout_secs")]
+    pub http_client_connect_timeout_secs: u64,
+    #[serde(default = "default_http_client_timeout_secs")]
+    pub http_client_timeout_secs: u64,
+    #[serde(default = "default_http_client_max_retries")]
+    pub http_client_max_retries: u32,
+    #[serde(default = "default_http_client_user_agent")]
+    pub http_client_user_agent: String,
+    #[serde(default)]
+    pub debug_log_bodies: bool,
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    /// Sets `TCP_NODELAY` on the listeners, so small responses are sent without waiting to coalesce
+    #[serde(default)]
+    pub tcp_nodelay: bool,
+    /// Enables TCP keep-alive on the listeners, probing idle connections after, and then every, this many seconds
+    #[serde(default)]
+    pub tcp_keepalive_secs: Option<u64>,
+    /// Drops `+tag` suffixes when normalizing emails, so `ada+news@example.com` is the account of `ada@example.com`
+    #[serde(default)]
+    pub email_strip_plus_tags: bool,
+    /// Drops the dots of Gmail addresses when normalizing emails, so `a.da@gmail.com` is the account of `ada@gmail.com`
+    #[serde(default)]
+    pub email_strip_gmail_dots: bool,
+    /// `POST /password/change` requests a client IP may make in a burst, which bounds guesses of the current password
+    #[serde(default = "default_password_change_limit")]
+    pub password_change_limit: u32,
+    /// Seconds over which a client IP's password change requests are refilled
+    #[serde(default = "default_password_change_period_secs")]
+    pub password_change_period_secs: u64,
+    /// Serializes timestamps in responses as RFC 3339 strings or Unix epoch seconds
+    #[serde(default)]
+    pub timestamp_format: TimestampFormat,
+    /// Returns error bodies as they are or nested under an `error` key
+    #[serde(default)]
+    pub error_envelope: ErrorEnvelope,
+    /// Binds access tokens to a fingerprint of the client's user agent and network, refusing them from other clients and refusing unbound tokens
+    #[serde(default)]
+    pub token_binding: bool,
+    /// Authentication methods whose routes are served
+    #[serde(default)]
+    pub auth_methods: AuthMethods,
+    /// Sets the tokens as cookies for browser clients, and accepts the access token from its cookie
+    #[serde(default)]
+    pub token_cookies: TokenCookieSettings,
+    /// Seconds the servers keep serving after a shutdown signal, with `/ready` failing so load balancers drain them first
+    #[serde(default)]
+    pub shutdown_drain_secs: u64,
+    /// Seconds between reloads of the persisted maintenance switch, which picks up changes made through other instances; 0 disables reloading
+    #[serde(default = "default_settings_sync_secs")]
+    pub settings_sync_secs: u64,
 }
 
-/// Returns the default API server host address.
+/// Returns the default burst of five password change requests per client IP.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_password_change_limit(), 5);
+/// ```
+fn default_password_change_limit() -> u32 {
+    5
+}
+
+/// Returns the default password change refill period of five minutes.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_password_change_period_secs(), 300);
+/// ```
+fn default_password_change_period_secs() -> u64 {
+    300
+}
+
+/// Returns the default interval of ten seconds between reloads of the persisted switches.
 ///
 /// # Examples
 ///
+/// ```ignore
+/// assert_eq!(default_settings_sync_secs(), 10);
 /// ```
+fn default_settings_sync_secs() -> u64 {
+    10
+}
+
+/// Returns the default API server host address.
+///
+/// # Examples
+///
+/// ```ignore
 /// let host = default_api_server_host();
 /// assert_eq!(host, "0.0.0.0");
 /// ```
@@ -34,7 +306,7 @@ fn default_api_server_host() -> String {
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// let port = default_api_server_port();
 /// assert_eq!(port, 8000);
 /// ```
@@ -46,7 +318,7 @@ fn default_api_server_port() -> u16 {
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// let host = default_management_server_host();
 /// assert_eq!(host, "0.0.0.0");
 /// ```
@@ -58,7 +330,7 @@ fn default_management_server_host() -> String {
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// let port = default_management_server_port();
 /// assert_eq!(port, 8001);
 /// ```
@@ -70,7 +342,7 @@ fn default_management_server_port() -> u16 {
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// assert_eq!(default_enable_swagger(), true);
 /// ```
 fn default_enable_swagger() -> bool {
@@ -83,7 +355,7 @@ fn default_enable_swagger() -> bool {
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// let cors_enabled = default_enable_cors();
 /// assert!(cors_enabled);
 /// ```
@@ -95,7 +367,7 @@ fn default_enable_cors() -> bool {
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// let origins = default_cors_allowed_origins();
 /// assert_eq!(origins, vec!["*"]);
 /// ```
@@ -103,12 +375,92 @@ fn default_cors_allowed_origins() -> Vec<String> {
     vec!["*".to_string()]
 }
 
+/// Returns the default path prefix for API server routes.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_api_base_path(), "/api/v1");
+/// ```
+fn default_api_base_path() -> String {
+    "/api/v1".to_string()
+}
+
+/// Returns the default path prefix for management server routes.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_management_base_path(), "/management/v1");
+/// ```
+fn default_management_base_path() -> String {
+    "/management/v1".to_string()
+}
+
+/// Returns the default time allowed to establish an outbound connection.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_http_client_connect_timeout_secs(), 5);
+/// ```
+fn default_http_client_connect_timeout_secs() -> u64 {
+    5
+}
+
+/// Returns the default time allowed for a whole outbound request, including reading the response.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_http_client_timeout_secs(), 10);
+/// ```
+fn default_http_client_timeout_secs() -> u64 {
+    10
+}
+
+/// Returns the default number of times an idempotent outbound request is retried.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_eq!(default_http_client_max_retries(), 2);
+/// ```
+fn default_http_client_max_retries() -> u32 {
+    2
+}
+
+/// Returns the default `User-Agent` sent on outbound requests, e.g. `rcauth/0.1.0`.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert!(default_http_client_user_agent().starts_with("rcauth/"));
+/// ```
+fn default_http_client_user_agent() -> String {
+    format!("rcauth/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Records an error against `name` unless the route prefix starts with `/` and is not the root or slash-terminated, as required for nesting routes.
+fn validate_base_path(errors: &mut ValidationErrors, name: &str, path: &str) {
+    if !path.starts_with('/') || path.ends_with('/') {
+        errors.add(
+            name,
+            format!(
+                "must start with '/' and must not end with '/' (got '{}')",
+                path
+            ),
+        );
+    }
+}
+
 impl Default for Config {
     /// Creates a `Config` instance with default server and feature settings.
     ///
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let config = Config::default();
     /// assert_eq!(config.api_server_host, "0.0.0.0");
     /// assert_eq!(config.api_server_port, 8000);
@@ -123,6 +475,41 @@ impl Default for Config {
             enable_swagger: default_enable_swagger(),
             enable_cors: default_enable_cors(),
             cors_allowed_origins: default_cors_allowed_origins(),
+            cors_max_age_secs: None,
+            cors_allow_credentials: false,
+            api_cors_allowed_origins: None,
+            api_cors_max_age_secs: None,
+            api_cors_allow_credentials: None,
+            management_cors_allowed_origins: None,
+            management_cors_max_age_secs: None,
+            management_cors_allow_credentials: None,
+            api_base_path: default_api_base_path(),
+            management_base_path: default_management_base_path(),
+            openapi_title: None,
+            openapi_version: None,
+            openapi_description: None,
+            openapi_contact_name: None,
+            openapi_contact_email: None,
+            openapi_contact_url: None,
+            http_client_connect_timeout_secs: default_http_client_connect_timeout_secs(),
+            http_client_timeout_secs: default_http_client_timeout_secs(),
+            http_client_max_retries: default_http_client_max_retries(),
+            http_client_user_agent: default_http_client_user_agent(),
+            debug_log_bodies: false,
+            trusted_proxies: Vec::new(),
+            tcp_nodelay: false,
+            tcp_keepalive_secs: None,
+            email_strip_plus_tags: false,
+            email_strip_gmail_dots: false,
+            password_change_limit: default_password_change_limit(),
+            password_change_period_secs: default_password_change_period_secs(),
+            timestamp_format: TimestampFormat::default(),
+            error_envelope: ErrorEnvelope::default(),
+            token_binding: false,
+            auth_methods: AuthMethods::default(),
+            token_cookies: TokenCookieSettings::default(),
+            shutdown_drain_secs: 0,
+            settings_sync_secs: default_settings_sync_secs(),
         }
     }
 }
@@ -133,6 +520,7 @@ impl Config {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let config = Config::default();
     /// let addr = config.api_addr();
     /// assert_eq!(addr, "0.0.0.0:8000");
@@ -146,6 +534,7 @@ impl Config {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let config = Config::default();
     /// let addr = config.management_addr();
     /// assert_eq!(addr, "0.0.0.0:8001");
@@ -157,38 +546,237 @@ impl Config {
         )
     }
 
+    /// Parses the shared `cors_allowed_origins` into the origins accepted by the CORS layer; see [`CorsSettings::origins`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` naming the first malformed origin.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{ConfigBuilder, CorsOrigins};
+    /// let config = ConfigBuilder::default()
+    ///     .cors_allowed_origins(vec!["https://example.com"])
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(matches!(config.cors_origins().unwrap(), CorsOrigins::List(origins) if origins.len() == 1));
+    /// ```
+    pub fn cors_origins(&self) -> Result<CorsOrigins> {
+        self.shared_cors().origins()
+    }
+
+    /// Returns the CORS settings of the API server, taking each unset `api_cors_*` setting from the shared `cors_*` one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default()
+    ///     .cors_allowed_origins(vec!["https://app.example.com"])
+    ///     .cors_max_age_secs(600)
+    ///     .management_cors_allowed_origins(vec!["https://admin.example.com"])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.api_cors().allowed_origins, ["https://app.example.com"]);
+    /// assert_eq!(config.management_cors().allowed_origins, ["https://admin.example.com"]);
+    /// assert_eq!(config.management_cors().max_age_secs, Some(600));
+    /// ```
+    pub fn api_cors(&self) -> CorsSettings {
+        self.server_cors(
+            &self.api_cors_allowed_origins,
+            self.api_cors_max_age_secs,
+            self.api_cors_allow_credentials,
+        )
+    }
+
+    /// Returns the CORS settings of the management server, taking each unset `management_cors_*` setting from the shared `cors_*` one.
+    pub fn management_cors(&self) -> CorsSettings {
+        self.server_cors(
+            &self.management_cors_allowed_origins,
+            self.management_cors_max_age_secs,
+            self.management_cors_allow_credentials,
+        )
+    }
+
+    fn shared_cors(&self) -> CorsSettings {
+        CorsSettings {
+            allowed_origins: self.cors_allowed_origins.clone(),
+            max_age_secs: self.cors_max_age_secs,
+            allow_credentials: self.cors_allow_credentials,
+        }
+    }
+
+    fn server_cors(
+        &self,
+        allowed_origins: &Option<Vec<String>>,
+        max_age_secs: Option<u64>,
+        allow_credentials: Option<bool>,
+    ) -> CorsSettings {
+        CorsSettings {
+            allowed_origins: allowed_origins
+                .clone()
+                .unwrap_or_else(|| self.cors_allowed_origins.clone()),
+            max_age_secs: max_age_secs.or(self.cors_max_age_secs),
+            allow_credentials: allow_credentials.unwrap_or(self.cors_allow_credentials),
+        }
+    }
+
+    /// Parses `trusted_proxies` into networks; a bare IP address is treated as a single-host network.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` naming the first entry that is neither an IP address nor a CIDR range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default()
+    ///     .trusted_proxies(vec!["10.0.0.0/8", "192.168.1.10"])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.trusted_proxy_networks().unwrap().len(), 2);
+    /// ```
+    pub fn trusted_proxy_networks(&self) -> Result<Vec<IpNet>> {
+        self.trusted_proxies
+            .iter()
+            .map(|proxy| {
+                let proxy = proxy.trim();
+                proxy
+                    .parse::<IpNet>()
+                    .or_else(|_| proxy.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|e| {
+                        Error::new(
+                            ErrorCode::ConfigurationError,
+                            format!("Invalid trusted proxy '{}'", proxy),
+                            e,
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the normalizer emails are stored and looked up with, as configured by `email_strip_plus_tags` and `email_strip_gmail_dots`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default().email_strip_plus_tags(true).build().unwrap();
+    /// let emails = config.email_normalizer();
+    /// assert_eq!(emails.normalize("Ada+news@Example.com").unwrap(), "ada@example.com");
+    /// ```
+    pub fn email_normalizer(&self) -> EmailNormalizer {
+        EmailNormalizer {
+            strip_plus_tags: self.email_strip_plus_tags,
+            strip_gmail_dots: self.email_strip_gmail_dots,
+        }
+    }
+
     /// Validates the server configuration for correctness.
     ///
-    /// Checks that server hosts are valid IP addresses or "localhost", ports are non-zero, API and management servers do not share the same host and port, and if CORS is enabled, that allowed origins are specified.
+    /// Checks that server hosts are valid IP addresses or "localhost", ports are non-zero, API and management servers do not share the same host and port, and if CORS is enabled, that allowed origins are specified and well-formed and that credentials are not combined with the `*` wildcard. Base paths must start with `/`, the outbound HTTP timeouts, TCP keep-alive and password change limit must be non-zero, and trusted proxies must be IP addresses or CIDR ranges.
     ///
     /// # Errors
     ///
-    /// Returns an error if any validation fails, describing the specific issue.
+    /// Returns a `ValidationError` listing every failure, with the messages keyed by field under its `fields` detail.
     ///
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let config = Config::default();
     /// assert!(config.validate().is_ok());
     /// ```
-    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn validate(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.validation_errors().into_result()?;
+        Ok(())
+    }
+
+    /// Collects every validation failure, keyed by the name of the offending field.
+    ///
+    /// [`Config::validate`] reports the same failures as a single `ValidationError` whose `fields` detail holds this map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::Config;
+    /// let config = Config {
+    ///     api_base_path: "api".to_string(),
+    ///     ..Config::default()
+    /// };
+    /// let errors = config.validation_errors();
+    /// assert!(errors.get("api_base_path").is_some());
+    /// ```
+    pub fn validation_errors(&self) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+
         // Validate that API and management servers don't use the same port if on the same host
         if self.api_server_host == self.management_server_host
             && self.api_server_port == self.management_server_port
         {
-            return Err(format!(
-                "API and management servers cannot share the same host:port combination ({}:{})",
-                self.api_server_host, self.api_server_port
-            )
-            .into());
+            errors.add(
+                "management_server_port",
+                format!(
+                    "API and management servers cannot share the same host:port combination ({}:{})",
+                    self.api_server_host, self.api_server_port
+                ),
+            );
         }
 
-        // If CORS is enabled, validate that we have allowed origins
-        if self.enable_cors && self.cors_allowed_origins.is_empty() {
-            return Err("CORS is enabled but no allowed origins are specified".into());
+        for (name, secs) in [
+            (
+                "http_client_connect_timeout_secs",
+                self.http_client_connect_timeout_secs,
+            ),
+            ("http_client_timeout_secs", self.http_client_timeout_secs),
+        ] {
+            if secs == 0 {
+                errors.add(name, "must be greater than zero");
+            }
         }
 
-        Ok(())
+        if let Err(e) = self.trusted_proxy_networks() {
+            errors.add("trusted_proxies", e.message);
+        }
+
+        if self.tcp_keepalive_secs == Some(0) {
+            errors.add("tcp_keepalive_secs", "must be greater than zero");
+        }
+
+        if self.password_change_limit == 0 {
+            errors.add("password_change_limit", "must be greater than zero");
+        }
+        if self.password_change_period_secs == 0 {
+            errors.add("password_change_period_secs", "must be greater than zero");
+        }
+
+        validate_base_path(&mut errors, "api_base_path", &self.api_base_path);
+        validate_base_path(
+            &mut errors,
+            "management_base_path",
+            &self.management_base_path,
+        );
+
+        if self.enable_cors {
+            self.shared_cors().validate(&mut errors, "");
+            // Each server's settings are checked once it overrides any of them, as the overrides may not fit the shared ones
+            if self.api_cors_allowed_origins.is_some()
+                || self.api_cors_max_age_secs.is_some()
+                || self.api_cors_allow_credentials.is_some()
+            {
+                self.api_cors().validate(&mut errors, "api_");
+            }
+            if self.management_cors_allowed_origins.is_some()
+                || self.management_cors_max_age_secs.is_some()
+                || self.management_cors_allow_credentials.is_some()
+            {
+                self.management_cors().validate(&mut errors, "management_");
+            }
+        }
+
+        errors
     }
 }
 
@@ -202,6 +790,41 @@ pub struct ConfigBuilder {
     enable_swagger: Option<bool>,
     enable_cors: Option<bool>,
     cors_allowed_origins: Option<Vec<String>>,
+    cors_max_age_secs: Option<u64>,
+    cors_allow_credentials: Option<bool>,
+    api_cors_allowed_origins: Option<Vec<String>>,
+    api_cors_max_age_secs: Option<u64>,
+    api_cors_allow_credentials: Option<bool>,
+    management_cors_allowed_origins: Option<Vec<String>>,
+    management_cors_max_age_secs: Option<u64>,
+    management_cors_allow_credentials: Option<bool>,
+    api_base_path: Option<String>,
+    management_base_path: Option<String>,
+    openapi_title: Option<String>,
+    openapi_version: Option<String>,
+    openapi_description: Option<String>,
+    openapi_contact_name: Option<String>,
+    openapi_contact_email: Option<String>,
+    openapi_contact_url: Option<String>,
+    http_client_connect_timeout_secs: Option<u64>,
+    http_client_timeout_secs: Option<u64>,
+    http_client_max_retries: Option<u32>,
+    http_client_user_agent: Option<String>,
+    debug_log_bodies: Option<bool>,
+    trusted_proxies: Option<Vec<String>>,
+    tcp_nodelay: Option<bool>,
+    tcp_keepalive_secs: Option<u64>,
+    email_strip_plus_tags: Option<bool>,
+    email_strip_gmail_dots: Option<bool>,
+    password_change_limit: Option<u32>,
+    password_change_period_secs: Option<u64>,
+    timestamp_format: Option<TimestampFormat>,
+    error_envelope: Option<ErrorEnvelope>,
+    token_binding: Option<bool>,
+    auth_methods: Option<AuthMethods>,
+    token_cookies: Option<TokenCookieSettings>,
+    shutdown_drain_secs: Option<u64>,
+    settings_sync_secs: Option<u64>,
 }
 
 impl ConfigBuilder {
@@ -210,6 +833,7 @@ impl ConfigBuilder {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let builder = ConfigBuilder::default().api_server_host("127.0.0.1");
     /// ```
     pub fn api_server_host<T: Into<String>>(mut self, host: T) -> Self {
@@ -222,6 +846,7 @@ impl ConfigBuilder {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let builder = ConfigBuilder::default().api_server_port(8080);
     /// ```
     pub fn api_server_port(mut self, port: u16) -> Self {
@@ -234,6 +859,7 @@ impl ConfigBuilder {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let builder = ConfigBuilder::default().management_server_host("127.0.0.1");
     /// ```
     pub fn management_server_host<T: Into<String>>(mut self, host: T) -> Self {
@@ -246,6 +872,7 @@ impl ConfigBuilder {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let builder = ConfigBuilder::default().management_server_port(9001);
     /// ```
     pub fn management_server_port(mut self, port: u16) -> Self {
@@ -258,6 +885,7 @@ impl ConfigBuilder {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let builder = ConfigBuilder::default().enable_swagger(false);
     /// ```
     pub fn enable_swagger(mut self, enable: bool) -> Self {
@@ -270,6 +898,7 @@ impl ConfigBuilder {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let builder = ConfigBuilder::default().enable_cors(false);
     /// let config = builder.build().unwrap();
     /// assert!(!config.enable_cors);
@@ -286,6 +915,7 @@ impl ConfigBuilder {
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let builder = ConfigBuilder::default()
     ///     .cors_allowed_origins(vec!["https://example.com", "https://another.com"]);
     /// ```
@@ -294,47 +924,805 @@ impl ConfigBuilder {
         self
     }
 
-    /// Builds a `Config` instance from the provided builder values, applying defaults where necessary and validating the result.
+    /// Sets how long, in seconds, browsers may cache CORS preflight responses.
     ///
-    /// Returns a validated `Config` if successful, or an error if validation fails.
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().cors_max_age_secs(600).build().unwrap();
+    /// assert_eq!(config.cors_max_age_secs, Some(600));
+    /// ```
+    pub fn cors_max_age_secs(mut self, secs: u64) -> Self {
+        self.cors_max_age_secs = Some(secs);
+        self
+    }
+
+    /// Sets whether CORS responses allow credentials (cookies and authorization headers).
+    ///
+    /// Credentials require an explicit origin list; combining them with `*` fails validation.
     ///
     /// # Examples
     ///
     /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
     /// let config = ConfigBuilder::default()
-    ///     .api_server_host("127.0.0.1")
-    ///     .api_server_port(8080)
+    ///     .cors_allowed_origins(vec!["https://example.com"])
+    ///     .cors_allow_credentials(true)
     ///     .build()
     ///     .unwrap();
-    /// assert_eq!(config.api_server_host, "127.0.0.1");
-    /// assert_eq!(config.api_server_port, 8080);
+    /// assert!(config.cors_allow_credentials);
     /// ```
-    pub fn build(self) -> Result<Config, Box<dyn std::error::Error>> {
-        let default_config = Config::default();
+    pub fn cors_allow_credentials(mut self, allow: bool) -> Self {
+        self.cors_allow_credentials = Some(allow);
+        self
+    }
 
-        let config = Config {
-            api_server_host: self
-                .api_server_host
-                .unwrap_or(default_config.api_server_host),
-            api_server_port: self
-                .api_server_port
-                .unwrap_or(default_config.api_server_port),
-            management_server_host: self
-                .management_server_host
-                .unwrap_or(default_config.management_server_host),
-            management_server_port: self
-                .management_server_port
-                .unwrap_or(default_config.management_server_port),
-            enable_swagger: self.enable_swagger.unwrap_or(default_config.enable_swagger),
-            enable_cors: self.enable_cors.unwrap_or(default_config.enable_cors),
-            cors_allowed_origins: self
-                .cors_allowed_origins
-                .unwrap_or(default_config.cors_allowed_origins),
-        };
+    /// Sets the origins the API server allows, overriding `cors_allowed_origins` for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default()
+    ///     .api_cors_allowed_origins(vec!["https://app.example.com"])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.api_cors().allowed_origins, ["https://app.example.com"]);
+    /// ```
+    pub fn api_cors_allowed_origins<T: Into<String>>(mut self, origins: Vec<T>) -> Self {
+        self.api_cors_allowed_origins = Some(origins.into_iter().map(|o| o.into()).collect());
+        self
+    }
 
-        // Validate the configuration
-        config.validate()?;
+    /// Sets how long browsers may cache the API server's preflight responses, overriding `cors_max_age_secs` for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default().api_cors_max_age_secs(600).build().unwrap();
+    /// assert_eq!(config.api_cors().max_age_secs, Some(600));
+    /// ```
+    pub fn api_cors_max_age_secs(mut self, secs: u64) -> Self {
+        self.api_cors_max_age_secs = Some(secs);
+        self
+    }
 
-        Ok(config)
+    /// Sets whether the API server allows credentialed requests, overriding `cors_allow_credentials` for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default()
+    ///     .api_cors_allowed_origins(vec!["https://app.example.com"])
+    ///     .api_cors_allow_credentials(true)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.api_cors().allow_credentials);
+    /// ```
+    pub fn api_cors_allow_credentials(mut self, allow: bool) -> Self {
+        self.api_cors_allow_credentials = Some(allow);
+        self
+    }
+
+    /// Sets the origins the management server allows, overriding `cors_allowed_origins` for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default()
+    ///     .management_cors_allowed_origins(vec!["https://admin.example.com"])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.management_cors().allowed_origins, ["https://admin.example.com"]);
+    /// ```
+    pub fn management_cors_allowed_origins<T: Into<String>>(mut self, origins: Vec<T>) -> Self {
+        self.management_cors_allowed_origins =
+            Some(origins.into_iter().map(|o| o.into()).collect());
+        self
+    }
+
+    /// Sets how long browsers may cache the management server's preflight responses, overriding `cors_max_age_secs` for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default()
+    ///     .management_cors_max_age_secs(60)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.management_cors().max_age_secs, Some(60));
+    /// ```
+    pub fn management_cors_max_age_secs(mut self, secs: u64) -> Self {
+        self.management_cors_max_age_secs = Some(secs);
+        self
+    }
+
+    /// Sets whether the management server allows credentialed requests, overriding `cors_allow_credentials` for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ConfigBuilder;
+    /// let config = ConfigBuilder::default()
+    ///     .management_cors_allowed_origins(vec!["https://admin.example.com"])
+    ///     .management_cors_allow_credentials(true)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.management_cors().allow_credentials);
+    /// ```
+    pub fn management_cors_allow_credentials(mut self, allow: bool) -> Self {
+        self.management_cors_allow_credentials = Some(allow);
+        self
+    }
+
+    /// Sets the path prefix under which API server routes are served.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().api_base_path("/auth/v1").build().unwrap();
+    /// assert_eq!(config.api_base_path, "/auth/v1");
+    /// ```
+    pub fn api_base_path<T: Into<String>>(mut self, path: T) -> Self {
+        self.api_base_path = Some(path.into());
+        self
+    }
+
+    /// Sets the path prefix under which management server routes are served.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let builder = ConfigBuilder::default().management_base_path("/auth/management/v1");
+    /// ```
+    pub fn management_base_path<T: Into<String>>(mut self, path: T) -> Self {
+        self.management_base_path = Some(path.into());
+        self
+    }
+
+    /// Sets the title shown in the OpenAPI documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().openapi_title("Acme Auth").build().unwrap();
+    /// assert_eq!(config.openapi_title.as_deref(), Some("Acme Auth"));
+    /// ```
+    pub fn openapi_title<T: Into<String>>(mut self, value: T) -> Self {
+        self.openapi_title = Some(value.into());
+        self
+    }
+
+    /// Sets the version shown in the OpenAPI documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().openapi_version("2.1.0").build().unwrap();
+    /// assert_eq!(config.openapi_version.as_deref(), Some("2.1.0"));
+    /// ```
+    pub fn openapi_version<T: Into<String>>(mut self, value: T) -> Self {
+        self.openapi_version = Some(value.into());
+        self
+    }
+
+    /// Sets the description shown in the OpenAPI documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().openapi_description("Acme authentication service").build().unwrap();
+    /// assert_eq!(config.openapi_description.as_deref(), Some("Acme authentication service"));
+    /// ```
+    pub fn openapi_description<T: Into<String>>(mut self, value: T) -> Self {
+        self.openapi_description = Some(value.into());
+        self
+    }
+
+    /// Sets the contact name shown in the OpenAPI documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().openapi_contact_name("Platform Team").build().unwrap();
+    /// assert_eq!(config.openapi_contact_name.as_deref(), Some("Platform Team"));
+    /// ```
+    pub fn openapi_contact_name<T: Into<String>>(mut self, value: T) -> Self {
+        self.openapi_contact_name = Some(value.into());
+        self
+    }
+
+    /// Sets the contact email shown in the OpenAPI documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().openapi_contact_email("platform@example.com").build().unwrap();
+    /// assert_eq!(config.openapi_contact_email.as_deref(), Some("platform@example.com"));
+    /// ```
+    pub fn openapi_contact_email<T: Into<String>>(mut self, value: T) -> Self {
+        self.openapi_contact_email = Some(value.into());
+        self
+    }
+
+    /// Sets the contact URL shown in the OpenAPI documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().openapi_contact_url("https://example.com/support").build().unwrap();
+    /// assert_eq!(config.openapi_contact_url.as_deref(), Some("https://example.com/support"));
+    /// ```
+    pub fn openapi_contact_url<T: Into<String>>(mut self, value: T) -> Self {
+        self.openapi_contact_url = Some(value.into());
+        self
+    }
+
+    /// Sets the time allowed to establish an outbound connection, in seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().http_client_connect_timeout_secs(2).build().unwrap();
+    /// assert_eq!(config.http_client_connect_timeout_secs, 2);
+    /// ```
+    pub fn http_client_connect_timeout_secs(mut self, secs: u64) -> Self {
+        self.http_client_connect_timeout_secs = Some(secs);
+        self
+    }
+
+    /// Sets the time allowed for a whole outbound request, in seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().http_client_timeout_secs(30).build().unwrap();
+    /// assert_eq!(config.http_client_timeout_secs, 30);
+    /// ```
+    pub fn http_client_timeout_secs(mut self, secs: u64) -> Self {
+        self.http_client_timeout_secs = Some(secs);
+        self
+    }
+
+    /// Sets how many times an idempotent outbound request is retried after a transient failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().http_client_max_retries(0).build().unwrap();
+    /// assert_eq!(config.http_client_max_retries, 0);
+    /// ```
+    pub fn http_client_max_retries(mut self, retries: u32) -> Self {
+        self.http_client_max_retries = Some(retries);
+        self
+    }
+
+    /// Sets the `User-Agent` sent on outbound requests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().http_client_user_agent("acme-auth/1.0").build().unwrap();
+    /// assert_eq!(config.http_client_user_agent, "acme-auth/1.0");
+    /// ```
+    pub fn http_client_user_agent<T: Into<String>>(mut self, value: T) -> Self {
+        self.http_client_user_agent = Some(value.into());
+        self
+    }
+
+    /// Enables logging of redacted request and response bodies at `DEBUG`. Meant for temporary debugging only.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().debug_log_bodies(true).build().unwrap();
+    /// assert!(config.debug_log_bodies);
+    /// ```
+    pub fn debug_log_bodies(mut self, enabled: bool) -> Self {
+        self.debug_log_bodies = Some(enabled);
+        self
+    }
+
+    /// Sets the proxies, as IP addresses or CIDR ranges, whose `X-Forwarded-For` entries are trusted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().trusted_proxies(vec!["10.0.0.0/8"]).build().unwrap();
+    /// assert_eq!(config.trusted_proxies, vec!["10.0.0.0/8"]);
+    /// ```
+    pub fn trusted_proxies<T: Into<String>>(mut self, proxies: Vec<T>) -> Self {
+        self.trusted_proxies = Some(proxies.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets whether the listeners disable Nagle's algorithm with `TCP_NODELAY`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().tcp_nodelay(true).build().unwrap();
+    /// assert!(config.tcp_nodelay);
+    /// ```
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = Some(enabled);
+        self
+    }
+
+    /// Enables TCP keep-alive on the listeners with the given idle time and probe interval.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().tcp_keepalive_secs(60).build().unwrap();
+    /// assert_eq!(config.tcp_keepalive_secs, Some(60));
+    /// ```
+    pub fn tcp_keepalive_secs(mut self, secs: u64) -> Self {
+        self.tcp_keepalive_secs = Some(secs);
+        self
+    }
+
+    /// Sets whether `+tag` suffixes are dropped when normalizing emails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().email_strip_plus_tags(true).build().unwrap();
+    /// assert!(config.email_strip_plus_tags);
+    /// ```
+    pub fn email_strip_plus_tags(mut self, strip: bool) -> Self {
+        self.email_strip_plus_tags = Some(strip);
+        self
+    }
+
+    /// Sets whether the dots of Gmail addresses are dropped when normalizing emails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().email_strip_gmail_dots(true).build().unwrap();
+    /// assert!(config.email_strip_gmail_dots);
+    /// ```
+    pub fn email_strip_gmail_dots(mut self, strip: bool) -> Self {
+        self.email_strip_gmail_dots = Some(strip);
+        self
+    }
+
+    /// Sets how many `POST /password/change` requests a client IP may make in a burst.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().password_change_limit(3).build().unwrap();
+    /// assert_eq!(config.password_change_limit, 3);
+    /// ```
+    pub fn password_change_limit(mut self, limit: u32) -> Self {
+        self.password_change_limit = Some(limit);
+        self
+    }
+
+    /// Sets the seconds over which a client IP's password change requests are refilled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().password_change_period_secs(600).build().unwrap();
+    /// assert_eq!(config.password_change_period_secs, 600);
+    /// ```
+    pub fn password_change_period_secs(mut self, secs: u64) -> Self {
+        self.password_change_period_secs = Some(secs);
+        self
+    }
+
+    /// Sets how timestamps in responses are serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{ConfigBuilder, TimestampFormat};
+    /// let config = ConfigBuilder::default().timestamp_format(TimestampFormat::Epoch).build().unwrap();
+    /// assert_eq!(config.timestamp_format, TimestampFormat::Epoch);
+    /// ```
+    pub fn timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamp_format = Some(format);
+        self
+    }
+
+    /// Sets whether error bodies are returned as they are or nested under an `error` key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{ConfigBuilder, ErrorEnvelope};
+    /// let config = ConfigBuilder::default().error_envelope(ErrorEnvelope::Wrapped).build().unwrap();
+    /// assert_eq!(config.error_envelope, ErrorEnvelope::Wrapped);
+    /// ```
+    pub fn error_envelope(mut self, envelope: ErrorEnvelope) -> Self {
+        self.error_envelope = Some(envelope);
+        self
+    }
+
+    /// Sets whether access tokens are bound to a fingerprint of the client they were issued to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().token_binding(true).build().unwrap();
+    /// assert!(config.token_binding);
+    /// ```
+    pub fn token_binding(mut self, enable: bool) -> Self {
+        self.token_binding = Some(enable);
+        self
+    }
+
+    /// Sets the authentication methods whose routes are served.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{AuthMethods, ConfigBuilder};
+    /// let methods = AuthMethods { password: false, ..AuthMethods::default() };
+    /// let config = ConfigBuilder::default().auth_methods(methods).build().unwrap();
+    /// assert!(!config.auth_methods.password);
+    /// ```
+    pub fn auth_methods(mut self, methods: AuthMethods) -> Self {
+        self.auth_methods = Some(methods);
+        self
+    }
+
+    /// Sets the token cookies accepted from browser clients.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{ConfigBuilder, TokenCookieSettings};
+    /// let cookies = TokenCookieSettings {
+    ///     enabled: true,
+    ///     ..TokenCookieSettings::default()
+    /// };
+    /// let config = ConfigBuilder::default().token_cookies(cookies).build().unwrap();
+    /// assert!(config.token_cookies.enabled);
+    /// ```
+    pub fn token_cookies(mut self, cookies: TokenCookieSettings) -> Self {
+        self.token_cookies = Some(cookies);
+        self
+    }
+
+    /// Sets how long, in seconds, the servers keep draining after a shutdown signal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().shutdown_drain_secs(15).build().unwrap();
+    /// assert_eq!(config.shutdown_drain_secs, 15);
+    /// ```
+    pub fn shutdown_drain_secs(mut self, secs: u64) -> Self {
+        self.shutdown_drain_secs = Some(secs);
+        self
+    }
+
+    /// Sets how often, in seconds, the persisted switches are reloaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default().settings_sync_secs(30).build().unwrap();
+    /// assert_eq!(config.settings_sync_secs, 30);
+    /// ```
+    pub fn settings_sync_secs(mut self, secs: u64) -> Self {
+        self.settings_sync_secs = Some(secs);
+        self
+    }
+
+    /// Builds a `Config` instance from the provided builder values, applying defaults where necessary and validating the result.
+    ///
+    /// Returns a validated `Config` if successful, or an error if validation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{Config, ConfigBuilder};
+    /// let config = ConfigBuilder::default()
+    ///     .api_server_host("127.0.0.1")
+    ///     .api_server_port(8080)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.api_server_host, "127.0.0.1");
+    /// assert_eq!(config.api_server_port, 8080);
+    /// ```
+    pub fn build(self) -> std::result::Result<Config, Box<dyn std::error::Error>> {
+        let default_config = Config::default();
+
+        let config = Config {
+            api_server_host: self
+                .api_server_host
+                .unwrap_or(default_config.api_server_host),
+            api_server_port: self
+                .api_server_port
+                .unwrap_or(default_config.api_server_port),
+            management_server_host: self
+                .management_server_host
+                .unwrap_or(default_config.management_server_host),
+            management_server_port: self
+                .management_server_port
+                .unwrap_or(default_config.management_server_port),
+            enable_swagger: self.enable_swagger.unwrap_or(default_config.enable_swagger),
+            enable_cors: self.enable_cors.unwrap_or(default_config.enable_cors),
+            cors_allowed_origins: self
+                .cors_allowed_origins
+                .unwrap_or(default_config.cors_allowed_origins),
+            cors_max_age_secs: self.cors_max_age_secs.or(default_config.cors_max_age_secs),
+            cors_allow_credentials: self
+                .cors_allow_credentials
+                .unwrap_or(default_config.cors_allow_credentials),
+            api_cors_allowed_origins: self
+                .api_cors_allowed_origins
+                .or(default_config.api_cors_allowed_origins),
+            api_cors_max_age_secs: self
+                .api_cors_max_age_secs
+                .or(default_config.api_cors_max_age_secs),
+            api_cors_allow_credentials: self
+                .api_cors_allow_credentials
+                .or(default_config.api_cors_allow_credentials),
+            management_cors_allowed_origins: self
+                .management_cors_allowed_origins
+                .or(default_config.management_cors_allowed_origins),
+            management_cors_max_age_secs: self
+                .management_cors_max_age_secs
+                .or(default_config.management_cors_max_age_secs),
+            management_cors_allow_credentials: self
+                .management_cors_allow_credentials
+                .or(default_config.management_cors_allow_credentials),
+            api_base_path: self.api_base_path.unwrap_or(default_config.api_base_path),
+            management_base_path: self
+                .management_base_path
+                .unwrap_or(default_config.management_base_path),
+            openapi_title: self.openapi_title.or(default_config.openapi_title),
+            openapi_version: self.openapi_version.or(default_config.openapi_version),
+            openapi_description: self
+                .openapi_description
+                .or(default_config.openapi_description),
+            openapi_contact_name: self
+                .openapi_contact_name
+                .or(default_config.openapi_contact_name),
+            openapi_contact_email: self
+                .openapi_contact_email
+                .or(default_config.openapi_contact_email),
+            openapi_contact_url: self
+                .openapi_contact_url
+                .or(default_config.openapi_contact_url),
+            http_client_connect_timeout_secs: self
+                .http_client_connect_timeout_secs
+                .unwrap_or(default_config.http_client_connect_timeout_secs),
+            http_client_timeout_secs: self
+                .http_client_timeout_secs
+                .unwrap_or(default_config.http_client_timeout_secs),
+            http_client_max_retries: self
+                .http_client_max_retries
+                .unwrap_or(default_config.http_client_max_retries),
+            http_client_user_agent: self
+                .http_client_user_agent
+                .unwrap_or(default_config.http_client_user_agent),
+            debug_log_bodies: self
+                .debug_log_bodies
+                .unwrap_or(default_config.debug_log_bodies),
+            trusted_proxies: self
+                .trusted_proxies
+                .unwrap_or(default_config.trusted_proxies),
+            tcp_nodelay: self.tcp_nodelay.unwrap_or(default_config.tcp_nodelay),
+            tcp_keepalive_secs: self
+                .tcp_keepalive_secs
+                .or(default_config.tcp_keepalive_secs),
+            email_strip_plus_tags: self
+                .email_strip_plus_tags
+                .unwrap_or(default_config.email_strip_plus_tags),
+            email_strip_gmail_dots: self
+                .email_strip_gmail_dots
+                .unwrap_or(default_config.email_strip_gmail_dots),
+            password_change_limit: self
+                .password_change_limit
+                .unwrap_or(default_config.password_change_limit),
+            password_change_period_secs: self
+                .password_change_period_secs
+                .unwrap_or(default_config.password_change_period_secs),
+            timestamp_format: self
+                .timestamp_format
+                .unwrap_or(default_config.timestamp_format),
+            error_envelope: self.error_envelope.unwrap_or(default_config.error_envelope),
+            token_binding: self.token_binding.unwrap_or(default_config.token_binding),
+            auth_methods: self.auth_methods.unwrap_or(default_config.auth_methods),
+            token_cookies: self.token_cookies.unwrap_or(default_config.token_cookies),
+            shutdown_drain_secs: self
+                .shutdown_drain_secs
+                .unwrap_or(default_config.shutdown_drain_secs),
+            settings_sync_secs: self
+                .settings_sync_secs
+                .unwrap_or(default_config.settings_sync_secs),
+        };
+
+        // Validate the configuration
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_cors_origins_are_parsed() {
+        let config = ConfigBuilder::default()
+            .cors_allowed_origins(vec!["https://example.com", "http://localhost:3000/"])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            config.cors_origins().unwrap(),
+            CorsOrigins::List(vec![
+                HeaderValue::from_static("https://example.com"),
+                HeaderValue::from_static("http://localhost:3000"),
+            ])
+        );
+    }
+
+    #[test]
+    fn malformed_cors_origin_is_rejected_at_validate() {
+        for origin in ["not a url", "example.com", "https://example.com/app"] {
+            let config = Config {
+                cors_allowed_origins: vec![origin.to_string()],
+                ..Config::default()
+            };
+
+            let err = config.cors_origins().unwrap_err();
+            assert_eq!(err.code, ErrorCode::ConfigurationError);
+            assert!(config.validate().is_err(), "{} should be rejected", origin);
+        }
+    }
+
+    #[test]
+    fn credentials_conflict_with_wildcard_origin() {
+        let result = ConfigBuilder::default()
+            .cors_allow_credentials(true)
+            .build();
+        assert!(result.is_err());
+
+        let result = ConfigBuilder::default()
+            .cors_allowed_origins(vec!["https://example.com"])
+            .cors_allow_credentials(true)
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn server_cors_overrides_fall_back_to_shared_settings() {
+        let config = ConfigBuilder::default()
+            .cors_allowed_origins(vec!["https://app.example.com"])
+            .cors_max_age_secs(600)
+            .management_cors_allowed_origins(vec!["https://admin.example.com"])
+            .management_cors_allow_credentials(true)
+            .build()
+            .unwrap();
+
+        let api = config.api_cors();
+        assert_eq!(api.allowed_origins, vec!["https://app.example.com"]);
+        assert_eq!(api.max_age_secs, Some(600));
+        assert!(!api.allow_credentials);
+
+        let management = config.management_cors();
+        assert_eq!(
+            management.allowed_origins,
+            vec!["https://admin.example.com"]
+        );
+        assert_eq!(management.max_age_secs, Some(600));
+        assert!(management.allow_credentials);
+    }
+
+    #[test]
+    fn server_cors_errors_are_keyed_by_server() {
+        let config = Config {
+            api_cors_allowed_origins: Some(vec!["example.com".to_string()]),
+            management_cors_allow_credentials: Some(true),
+            ..Config::default()
+        };
+
+        let errors = config.validation_errors();
+        assert_eq!(errors.fields().len(), 2);
+        assert!(errors.get("api_cors_allowed_origins").unwrap()[0].contains("example.com"));
+        assert!(errors.get("management_cors_allow_credentials").is_some());
+    }
+
+    #[test]
+    fn validation_errors_are_keyed_by_field() {
+        let config = Config {
+            api_base_path: "api".to_string(),
+            management_base_path: "/management/".to_string(),
+            cors_allowed_origins: vec!["example.com".to_string()],
+            ..Config::default()
+        };
+
+        let errors = config.validation_errors();
+        assert_eq!(errors.fields().len(), 3);
+        assert!(errors.get("api_base_path").is_some());
+        assert!(errors.get("management_base_path").is_some());
+        assert!(errors.get("cors_allowed_origins").unwrap()[0].contains("example.com"));
+
+        let err = config.validate().unwrap_err();
+        let err = err.downcast_ref::<Error>().unwrap();
+        assert_eq!(err.code, ErrorCode::ValidationError);
+        assert!(err.data.as_ref().unwrap().contains_key("fields"));
+    }
+
+    #[test]
+    fn invalid_trusted_proxy_fails_validation() {
+        let result = ConfigBuilder::default()
+            .trusted_proxies(vec!["10.0.0.0/8", "10.0.0.0/33"])
+            .build();
+        assert!(result.is_err());
+
+        let config = Config {
+            trusted_proxies: vec!["proxy.internal".to_string()],
+            ..Config::default()
+        };
+        assert!(config.validation_errors().get("trusted_proxies").is_some());
+    }
+
+    #[test]
+    fn base_paths_must_start_with_slash() {
+        assert!(
+            ConfigBuilder::default()
+                .api_base_path("auth")
+                .build()
+                .is_err()
+        );
+        assert!(ConfigBuilder::default().api_base_path("/").build().is_err());
+        assert!(
+            ConfigBuilder::default()
+                .management_base_path("/auth/")
+                .build()
+                .is_err()
+        );
+        assert!(
+            ConfigBuilder::default()
+                .api_base_path("/auth")
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn wildcard_cors_origin_allows_any() {
+        let config = Config::default();
+        assert_eq!(config.cors_origins().unwrap(), CorsOrigins::Any);
+        assert!(config.validate().is_ok());
     }
 }
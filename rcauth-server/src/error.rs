@@ -0,0 +1,161 @@
+use axum::{
+    Json,
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rcauth_core::error::{Error, ErrorResponse};
+use serde::{Deserialize, Serialize};
+
+/// Shape of error response bodies, set with `error_envelope`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorEnvelope {
+    /// The [`ErrorResponse`] itself, e.g. `{"code": "not_found", "message": "User not found"}`.
+    #[default]
+    Flat,
+    /// The [`ErrorResponse`] under an `error` key, e.g. `{"error": {"code": "not_found", "message": "User not found"}}`.
+    Wrapped,
+}
+
+tokio::task_local! {
+    /// Envelope of the response being built, installed per request by [`error_envelope`].
+    static ENVELOPE: ErrorEnvelope;
+}
+
+impl ErrorEnvelope {
+    /// Envelope in effect for the current request, or the default outside of one.
+    pub fn current() -> Self {
+        ENVELOPE.try_with(|envelope| *envelope).unwrap_or_default()
+    }
+
+    /// Runs `f` with error responses built in this envelope, e.g. to build one outside a request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::ErrorEnvelope;
+    /// let envelope = ErrorEnvelope::Wrapped.scope(ErrorEnvelope::current);
+    /// assert_eq!(envelope, ErrorEnvelope::Wrapped);
+    /// ```
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        ENVELOPE.sync_scope(self, f)
+    }
+}
+
+/// An [`ErrorResponse`] nested under an `error` key, for [`ErrorEnvelope::Wrapped`].
+#[derive(Serialize)]
+struct Wrapped<'a> {
+    error: &'a ErrorResponse,
+}
+
+/// Wraps an [`Error`] so handlers can return it as an [`ErrorResponse`] with the error's status.
+///
+/// The body is built in the request's [`ErrorEnvelope`].
+///
+/// # Examples
+///
+/// ```
+/// # use axum::response::IntoResponse;
+/// # use rcauth_core::error::{Error, ErrorCode};
+/// # use rcauth_server::ApiError;
+/// let response = ApiError::from(Error::new_simple(ErrorCode::NotFound, "User not found")).into_response();
+/// assert_eq!(response.status(), 404);
+/// ```
+#[derive(Debug)]
+pub struct ApiError(pub Error);
+
+impl From<Error> for ApiError {
+    fn from(error: Error) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse::from_error(&self.0);
+        match ErrorEnvelope::current() {
+            ErrorEnvelope::Flat => (body.status, Json(&body)).into_response(),
+            ErrorEnvelope::Wrapped => (body.status, Json(Wrapped { error: &body })).into_response(),
+        }
+    }
+}
+
+/// Builds the error responses of the request in `envelope`.
+///
+/// Error bodies are serialized when the [`ApiError`] is turned into a response, so every layer inside this one picks up the envelope installed here.
+pub async fn error_envelope(
+    State(envelope): State<ErrorEnvelope>,
+    request: Request,
+    next: Next,
+) -> Response {
+    ENVELOPE.scope(envelope, next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware::from_fn_with_state, routing::get};
+    use rcauth_core::error::ErrorCode;
+    use tower::ServiceExt;
+
+    fn not_found() -> ApiError {
+        ApiError(
+            Error::new_simple(ErrorCode::NotFound, "User not found")
+                .with_data("id", serde_json::json!("user-1")),
+        )
+    }
+
+    async fn body_of(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn flat_is_the_default() {
+        let response = not_found().into_response();
+        assert_eq!(response.status(), 404);
+        assert_eq!(
+            body_of(response).await,
+            serde_json::json!({
+                "code": "not_found",
+                "message": "User not found",
+                "details": { "id": "user-1" },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn wrapped_nests_the_error() {
+        let response = ErrorEnvelope::Wrapped.scope(|| not_found().into_response());
+        assert_eq!(response.status(), 404);
+        assert_eq!(
+            body_of(response).await,
+            serde_json::json!({
+                "error": {
+                    "code": "not_found",
+                    "message": "User not found",
+                    "details": { "id": "user-1" },
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn middleware_applies_the_configured_envelope() {
+        for (envelope, pointer) in [
+            (ErrorEnvelope::Flat, "/code"),
+            (ErrorEnvelope::Wrapped, "/error/code"),
+        ] {
+            let app = Router::new()
+                .route("/user", get(|| async { not_found() }))
+                .layer(from_fn_with_state(envelope, error_envelope));
+            let request = Request::get("/user").body(Body::empty()).unwrap();
+
+            let body = body_of(app.oneshot(request).await.unwrap()).await;
+            assert_eq!(body.pointer(pointer).unwrap(), "not_found", "{}", body);
+        }
+    }
+}
@@ -0,0 +1,633 @@
+use axum::{
+    Json,
+    extract::{FromRequest, FromRequestParts, Request, rejection::JsonRejection},
+    http::{HeaderValue, StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use rcauth_core::error::{Error, ErrorCode, Validate};
+use rcauth_core::jwt::{Claims, TOKEN_BINDING_MISMATCH, TOKEN_EXPIRED};
+use serde::de::DeserializeOwned;
+
+use crate::{ApiError, AppState, token_cookies};
+
+/// JSON request body that is deserialized and then checked with [`Validate`].
+///
+/// Rejects malformed JSON with `400`, a missing JSON content type with `415`, and bodies that do not match `T` or fail validation with `422`. Validation failures list their messages per field under the `fields` detail of the [`ErrorResponse`](rcauth_core::error::ErrorResponse).
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::error::{Validate, ValidationErrors};
+/// # use rcauth_server::ValidatedJson;
+/// #[derive(serde::Deserialize)]
+/// struct Login {
+///     email: String,
+/// }
+///
+/// impl Validate for Login {}
+///
+/// async fn login(ValidatedJson(login): ValidatedJson<Login>) -> String {
+///     login.email
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(rejection_error)?;
+
+        value.validate().map_err(Error::validation)?;
+        Ok(Self(value))
+    }
+}
+
+/// Converts axum's JSON rejection into an [`Error`] that keeps the rejection's status.
+fn rejection_error(rejection: JsonRejection) -> Error {
+    let code = match rejection.status() {
+        StatusCode::UNPROCESSABLE_ENTITY => ErrorCode::UnprocessableEntity,
+        _ => ErrorCode::Invalid,
+    };
+    Error::new_simple(code, rejection.body_text()).with_status(rejection.status())
+}
+
+/// Claims of the verified `Authorization: Bearer` token of the request.
+///
+/// When `token_cookies` is enabled, a request without an `Authorization` header is authenticated by the access token cookie instead, as browser clients send it. Rejects a missing, malformed, forged or expired token with `401` and a `WWW-Authenticate` challenge. The `reason` detail tells the client what to do: [`TOKEN_EXPIRED`] means refresh the token, [`TOKEN_INVALID`](rcauth_core::jwt::TOKEN_INVALID) means log in again.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::BearerClaims;
+/// async fn me(BearerClaims(claims): BearerClaims) -> String {
+///     claims.sub
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BearerClaims(pub Claims);
+
+impl BearerClaims {
+    /// Guards a handler behind `scope`, refusing tokens that were not granted it with `403 Forbidden`.
+    ///
+    /// The `required_scope` detail names the missing scope. Scopes are compared exactly, so a token needs every scope a route asks for.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Forbidden` error if the token lacks `scope`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::{ApiError, BearerClaims};
+    /// async fn list_users(claims: BearerClaims) -> Result<&'static str, ApiError> {
+    ///     claims.require_scope("users:read")?;
+    ///     Ok("[]")
+    /// }
+    /// ```
+    pub fn require_scope(&self, scope: &str) -> Result<(), ApiError> {
+        if self.0.has_scope(scope) {
+            return Ok(());
+        }
+
+        Err(ApiError(
+            Error::new_simple(ErrorCode::Forbidden, "Token lacks the required scope")
+                .with_data("required_scope", serde_json::json!(scope)),
+        ))
+    }
+}
+
+impl FromRequestParts<AppState> for BearerClaims {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(jwt) = &state.jwt else {
+            return Err(ApiError(Error::new_simple(
+                ErrorCode::Unavailable,
+                "Token verification is not configured",
+            ))
+            .into_response());
+        };
+
+        let authorization = parts.headers.get(header::AUTHORIZATION);
+        let token = match authorization {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(str::trim)
+                .filter(|token| !token.is_empty()),
+            None if state.token_cookies.is_some() => token_cookies::access_token_of(&parts.headers),
+            None => None,
+        };
+        let Some(token) = token else {
+            return Err(challenge(
+                Error::new_simple(ErrorCode::Unauthorized, "Missing bearer token"),
+                "Bearer",
+            ));
+        };
+
+        let claims = jwt.decode(token).map_err(|e| {
+            let expired = e
+                .data
+                .as_ref()
+                .is_some_and(|data| data.get("reason") == Some(&serde_json::json!(TOKEN_EXPIRED)));
+            let header = if expired {
+                r#"Bearer error="invalid_token", error_description="The access token expired""#
+            } else {
+                r#"Bearer error="invalid_token""#
+            };
+            challenge(e, header)
+        })?;
+
+        // Unbound tokens, e.g. issued before binding was enabled, could be replayed from anywhere
+        if let Some(binding) = &state.token_binding
+            && claims.fgp.as_deref() != Some(binding.fingerprint_of(parts).as_str())
+        {
+            return Err(challenge(
+                Error::new_simple(
+                    ErrorCode::Unauthorized,
+                    "Token was not issued to this client",
+                )
+                .with_data("reason", serde_json::json!(TOKEN_BINDING_MISMATCH)),
+                r#"Bearer error="invalid_token", error_description="The access token is not bound to this client""#,
+            ));
+        }
+
+        Ok(Self(claims))
+    }
+}
+
+/// Bearer claims of the caller if they sent a valid access token, for routes that also serve anonymous callers.
+///
+/// Never rejects: a missing, malformed, expired or forged token, or a server without token verification, all yield `None`. Use [`BearerClaims`] on routes that require authentication.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::OptionalAuth;
+/// async fn greeting(OptionalAuth(caller): OptionalAuth) -> String {
+///     match caller {
+///         Some(claims) => format!("Hello, {}", claims.0.sub),
+///         None => "Hello, stranger".to_string(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OptionalAuth(pub Option<BearerClaims>);
+
+impl FromRequestParts<AppState> for OptionalAuth {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self(
+            BearerClaims::from_request_parts(parts, state).await.ok(),
+        ))
+    }
+}
+
+/// Answers with `error` and the `WWW-Authenticate` challenge of RFC 6750.
+fn challenge(error: Error, www_authenticate: &'static str) -> Response {
+    let mut response = ApiError(error).into_response();
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_static(www_authenticate),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, routing::post};
+    use rcauth_core::error::ValidationErrors;
+    use tower::ServiceExt;
+
+    #[derive(serde::Deserialize)]
+    struct Signup {
+        email: String,
+        password: String,
+    }
+
+    impl Validate for Signup {
+        fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+            let mut errors = ValidationErrors::new();
+            if !self.email.contains('@') {
+                errors.add("email", "must be an email address");
+            }
+            if self.password.len() < 8 {
+                errors.add("password", "must be at least 8 characters");
+            }
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            }
+        }
+    }
+
+    async fn send(body: &'static str) -> (StatusCode, serde_json::Value) {
+        let app = Router::new().route(
+            "/signup",
+            post(|ValidatedJson(signup): ValidatedJson<Signup>| async move { signup.email }),
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/signup")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn valid_body_reaches_handler() {
+        let (status, _) = send(r#"{"email":"a@example.com","password":"correct horse"}"#).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_bad_request() {
+        let (status, body) = send(r#"{"email":"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid");
+    }
+
+    #[tokio::test]
+    async fn validation_failure_lists_fields() {
+        let (status, body) = send(r#"{"email":"nobody","password":"short"}"#).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "validation_error");
+        assert_eq!(
+            body["details"]["fields"]["email"][0],
+            "must be an email address"
+        );
+        assert_eq!(
+            body["details"]["fields"]["password"][0],
+            "must be at least 8 characters"
+        );
+    }
+
+    mod bearer {
+        use super::*;
+        use axum::routing::get;
+        use rcauth_core::jwt::{TOKEN_INVALID, TokenOptions};
+        use rcauth_core::testing::jwt;
+
+        async fn get_me(token: &str) -> (StatusCode, Option<HeaderValue>, serde_json::Value) {
+            let app = Router::new()
+                .route(
+                    "/me",
+                    get(|BearerClaims(claims): BearerClaims| async move { claims.sub }),
+                )
+                .with_state(AppState {
+                    jwt: Some(jwt()),
+                    ..AppState::default()
+                });
+            let request = Request::builder()
+                .uri("/me")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let challenge = response.headers().get(header::WWW_AUTHENTICATE).cloned();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+            (status, challenge, body)
+        }
+
+        #[tokio::test]
+        async fn valid_token_reaches_handler() {
+            let token = jwt().issue("user-1", TokenOptions::default()).unwrap();
+            let (status, challenge, _) = get_me(&token).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(challenge.is_none());
+        }
+
+        async fn list_users(scope: Option<&[&str]>) -> (StatusCode, serde_json::Value) {
+            let app = Router::new()
+                .route(
+                    "/users",
+                    get(|claims: BearerClaims| async move {
+                        claims.require_scope("users:read")?;
+                        Ok::<_, ApiError>("[]")
+                    }),
+                )
+                .with_state(AppState {
+                    jwt: Some(jwt()),
+                    ..AppState::default()
+                });
+            let token = match scope {
+                Some(scopes) => jwt().issue("client-1", TokenOptions::default().scopes(scopes)),
+                None => jwt().issue("user-1", TokenOptions::default()),
+            };
+            let request = Request::builder()
+                .uri("/users")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token.unwrap()))
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+            (status, body)
+        }
+
+        #[tokio::test]
+        async fn token_with_the_scope_is_allowed() {
+            let (status, _) = list_users(Some(&["users:read"])).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        #[tokio::test]
+        async fn token_without_the_scope_is_forbidden() {
+            for scope in [None, Some(&[][..])] {
+                let (status, body) = list_users(scope).await;
+                assert_eq!(status, StatusCode::FORBIDDEN);
+                assert_eq!(body["code"], "forbidden");
+                assert_eq!(body["details"]["required_scope"], "users:read");
+            }
+        }
+
+        #[tokio::test]
+        async fn only_an_exact_scope_among_several_is_enough() {
+            let (status, _) = list_users(Some(&["audit:read", "users:read"])).await;
+            assert_eq!(status, StatusCode::OK);
+
+            let (status, _) = list_users(Some(&["users:write", "users", "audit:read"])).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+        }
+
+        #[tokio::test]
+        async fn expired_and_invalid_tokens_are_distinguishable() {
+            let now = jsonwebtoken::get_current_timestamp();
+            let expired = jwt()
+                .encode(&Claims {
+                    sub: "user-1".to_string(),
+                    iss: "rcauth".to_string(),
+                    aud: vec!["rcauth".to_string()],
+                    iat: now - 3600,
+                    exp: now - 600,
+                    scope: None,
+                    fgp: None,
+                    sid: None,
+                    act: None,
+                    custom: serde_json::Map::new(),
+                })
+                .unwrap();
+
+            let (status, challenge, body) = get_me(&expired).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(body["code"], "unauthorized");
+            assert_eq!(body["details"]["reason"], TOKEN_EXPIRED);
+            assert!(
+                challenge
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .contains("The access token expired")
+            );
+
+            let forged = format!(
+                "{}x",
+                jwt().issue("user-1", TokenOptions::default()).unwrap()
+            );
+            let (status, challenge, body) = get_me(&forged).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(body["details"]["reason"], TOKEN_INVALID);
+            assert_eq!(challenge.unwrap(), r#"Bearer error="invalid_token""#);
+        }
+    }
+
+    mod binding {
+        use super::*;
+        use crate::TokenBinding;
+        use axum::{extract::connect_info::MockConnectInfo, routing::get};
+        use rcauth_core::jwt::TokenOptions;
+        use rcauth_core::testing::jwt;
+        use std::net::SocketAddr;
+
+        /// Fingerprint of the mobile client the token was issued to.
+        fn issued_fingerprint() -> String {
+            let mut headers = axum::http::HeaderMap::new();
+            headers.insert(header::USER_AGENT, "rcauth-ios/1.0".parse().unwrap());
+            TokenBinding::default().fingerprint(&headers, "198.51.100.7".parse().unwrap())
+        }
+
+        async fn get_me(
+            token: &str,
+            user_agent: &str,
+            peer: [u8; 4],
+        ) -> (StatusCode, serde_json::Value) {
+            let app = Router::new()
+                .route(
+                    "/me",
+                    get(|BearerClaims(claims): BearerClaims| async move { claims.sub }),
+                )
+                .with_state(AppState {
+                    jwt: Some(jwt()),
+                    token_binding: Some(TokenBinding::default()),
+                    ..AppState::default()
+                })
+                .layer(MockConnectInfo(SocketAddr::from((peer, 4000))));
+            let request = Request::builder()
+                .uri("/me")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::USER_AGENT, user_agent)
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+            (status, body)
+        }
+
+        #[tokio::test]
+        async fn token_is_accepted_from_its_client_network() {
+            let token = jwt()
+                .issue(
+                    "user-1",
+                    TokenOptions::default().bound_to(&issued_fingerprint()),
+                )
+                .unwrap();
+
+            // Another address in the same /24, as when a phone changes cells
+            let (status, _) = get_me(&token, "rcauth-ios/1.0", [198, 51, 100, 42]).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        #[tokio::test]
+        async fn token_is_refused_from_another_client() {
+            let token = jwt()
+                .issue(
+                    "user-1",
+                    TokenOptions::default().bound_to(&issued_fingerprint()),
+                )
+                .unwrap();
+
+            for (user_agent, peer) in [
+                ("rcauth-ios/1.0", [203, 0, 113, 7]),
+                ("curl/8.0", [198, 51, 100, 7]),
+            ] {
+                let (status, body) = get_me(&token, user_agent, peer).await;
+                assert_eq!(status, StatusCode::UNAUTHORIZED);
+                assert_eq!(body["details"]["reason"], TOKEN_BINDING_MISMATCH);
+            }
+        }
+
+        #[tokio::test]
+        async fn unbound_token_is_refused() {
+            let token = jwt().issue("user-1", TokenOptions::default()).unwrap();
+            let (status, body) = get_me(&token, "rcauth-ios/1.0", [198, 51, 100, 7]).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(body["details"]["reason"], TOKEN_BINDING_MISMATCH);
+        }
+    }
+
+    mod cookie {
+        use super::*;
+        use crate::{TokenCookieSettings, TokenCookies};
+        use axum::routing::get;
+        use rcauth_core::jwt::TokenOptions;
+        use rcauth_core::testing::jwt;
+
+        /// Sends the issued access token cookie, with `authorization` if given, and returns the status.
+        async fn get_me(cookies: Option<TokenCookies>, authorization: Option<&str>) -> StatusCode {
+            let app = Router::new()
+                .route(
+                    "/me",
+                    get(|BearerClaims(claims): BearerClaims| async move { claims.sub }),
+                )
+                .with_state(AppState {
+                    jwt: Some(jwt()),
+                    token_cookies: cookies,
+                    ..AppState::default()
+                });
+            let token = jwt().issue("user-1", TokenOptions::default()).unwrap();
+            let mut request = Request::builder().uri("/me").header(
+                header::COOKIE,
+                format!("theme=dark; {}={}", crate::ACCESS_TOKEN_COOKIE, token),
+            );
+            if let Some(authorization) = authorization {
+                request = request.header(header::AUTHORIZATION, authorization);
+            }
+
+            app.oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        }
+
+        fn enabled() -> Option<TokenCookies> {
+            Some(TokenCookies::new(TokenCookieSettings {
+                enabled: true,
+                ..TokenCookieSettings::default()
+            }))
+        }
+
+        #[tokio::test]
+        async fn access_token_cookie_authenticates_when_enabled() {
+            assert_eq!(get_me(enabled(), None).await, StatusCode::OK);
+            assert_eq!(get_me(None, None).await, StatusCode::UNAUTHORIZED);
+        }
+
+        #[tokio::test]
+        async fn authorization_header_takes_precedence_over_the_cookie() {
+            let status = get_me(enabled(), Some("Bearer not-a-token")).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    mod optional {
+        use super::*;
+        use axum::routing::get;
+        use rcauth_core::jwt::TokenOptions;
+        use rcauth_core::testing::jwt;
+
+        async fn greet(authorization: Option<String>) -> (StatusCode, String) {
+            let app = Router::new()
+                .route(
+                    "/greeting",
+                    get(|OptionalAuth(caller): OptionalAuth| async move {
+                        caller.map_or("anonymous".to_string(), |BearerClaims(claims)| claims.sub)
+                    }),
+                )
+                .with_state(AppState {
+                    jwt: Some(jwt()),
+                    ..AppState::default()
+                });
+            let mut request = Request::builder().uri("/greeting");
+            if let Some(authorization) = authorization {
+                request = request.header(header::AUTHORIZATION, authorization);
+            }
+
+            let response = app
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        }
+
+        #[tokio::test]
+        async fn anonymous_caller_gets_none() {
+            assert_eq!(greet(None).await, (StatusCode::OK, "anonymous".to_string()));
+        }
+
+        #[tokio::test]
+        async fn valid_token_gets_its_claims() {
+            let token = jwt().issue("user-1", TokenOptions::default()).unwrap();
+            let response = greet(Some(format!("Bearer {}", token))).await;
+            assert_eq!(response, (StatusCode::OK, "user-1".to_string()));
+        }
+
+        #[tokio::test]
+        async fn invalid_token_is_treated_as_anonymous() {
+            let forged = format!(
+                "{}x",
+                jwt().issue("user-1", TokenOptions::default()).unwrap()
+            );
+            for authorization in [
+                format!("Bearer {}", forged),
+                "Basic dXNlcjpwYXNz".to_string(),
+            ] {
+                let response = greet(Some(authorization)).await;
+                assert_eq!(response, (StatusCode::OK, "anonymous".to_string()));
+            }
+        }
+    }
+}
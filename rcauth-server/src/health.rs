@@ -0,0 +1,256 @@
+use rcauth_core::health::HealthCheck;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// Time allowed for each check before it is reported as unhealthy.
+pub const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Outcome of a single check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Healthy,
+    Unhealthy,
+}
+
+/// Overall status of the service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// Every check passed.
+    Healthy,
+    /// Only optional checks failed.
+    Degraded,
+    /// At least one required check failed.
+    Unhealthy,
+    /// The server is shutting down and takes no new traffic, whatever its checks report.
+    Draining,
+}
+
+/// Result of one check in the aggregated report.
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct CheckReport {
+    /// Name the check was registered under
+    pub name: String,
+    pub status: CheckStatus,
+    /// Time the check took, in milliseconds
+    pub latency_ms: u64,
+    /// Whether a failure makes the service unhealthy rather than degraded
+    pub required: bool,
+    /// Why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Aggregated readiness report returned by `/health/full`.
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    /// Seconds since the server started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_secs: Option<u64>,
+    /// Check results keyed by check name; the database check's `latency_ms` is the round trip of its ping
+    pub checks: BTreeMap<String, CheckReport>,
+}
+
+/// The subsystem checks behind `/health/full`, run concurrently with a per-check timeout.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::HealthChecks;
+/// # use std::time::Duration;
+/// let checks = HealthChecks::new(Duration::from_secs(1));
+/// assert!(checks.is_empty());
+/// ```
+#[derive(Clone)]
+pub struct HealthChecks {
+    checks: Vec<Arc<dyn HealthCheck>>,
+    timeout: Duration,
+}
+
+impl Default for HealthChecks {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHECK_TIMEOUT)
+    }
+}
+
+impl HealthChecks {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            checks: Vec::new(),
+            timeout,
+        }
+    }
+
+    /// Registers a check, reported under its [`HealthCheck::name`].
+    pub fn register(mut self, check: impl HealthCheck + 'static) -> Self {
+        self.checks.push(Arc::new(check));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    /// Runs every check concurrently and aggregates the results.
+    ///
+    /// A check that fails, panics, or exceeds the timeout is reported as unhealthy. The overall status is unhealthy if any required check failed and degraded if only optional checks failed.
+    pub async fn run(&self) -> HealthReport {
+        let mut tasks = JoinSet::new();
+        for check in &self.checks {
+            let check = Arc::clone(check);
+            let timeout = self.timeout;
+            tasks.spawn(async move { run_check(check.as_ref(), timeout).await });
+        }
+
+        let mut checks = BTreeMap::new();
+        while let Some(result) = tasks.join_next().await {
+            // A panicked check has no report; it is counted as a required failure below
+            if let Ok(report) = result {
+                checks.insert(report.name.clone(), report);
+            }
+        }
+
+        let failed = |required: bool| {
+            checks
+                .values()
+                .any(|c| c.required == required && c.status == CheckStatus::Unhealthy)
+        };
+        let status = if failed(true) || checks.len() < self.checks.len() {
+            HealthStatus::Unhealthy
+        } else if failed(false) {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        };
+
+        HealthReport {
+            status,
+            uptime_secs: None,
+            checks,
+        }
+    }
+}
+
+async fn run_check(check: &dyn HealthCheck, timeout: Duration) -> CheckReport {
+    let started = Instant::now();
+    let error = match tokio::time::timeout(timeout, check.check()).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.message),
+        Err(_) => Some(format!("Timed out after {}ms", timeout.as_millis())),
+    };
+
+    CheckReport {
+        name: check.name().to_string(),
+        status: if error.is_none() {
+            CheckStatus::Healthy
+        } else {
+            CheckStatus::Unhealthy
+        },
+        latency_ms: started.elapsed().as_millis() as u64,
+        required: check.required(),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rcauth_core::error::{Error, ErrorCode, Result};
+
+    struct StubCheck {
+        name: &'static str,
+        required: bool,
+        healthy: bool,
+        delay: Duration,
+    }
+
+    impl StubCheck {
+        fn healthy(name: &'static str) -> Self {
+            Self {
+                name,
+                required: true,
+                healthy: true,
+                delay: Duration::ZERO,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl HealthCheck for StubCheck {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn required(&self) -> bool {
+            self.required
+        }
+
+        async fn check(&self) -> Result<()> {
+            tokio::time::sleep(self.delay).await;
+            if self.healthy {
+                Ok(())
+            } else {
+                Err(Error::new_simple(
+                    ErrorCode::Unavailable,
+                    "connection refused",
+                ))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn all_healthy() {
+        let report = HealthChecks::default()
+            .register(StubCheck::healthy("database"))
+            .register(StubCheck::healthy("cache"))
+            .run()
+            .await;
+
+        assert_eq!(report.status, HealthStatus::Healthy);
+        assert_eq!(report.checks.len(), 2);
+        assert_eq!(report.checks["database"].status, CheckStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn optional_failure_is_degraded() {
+        let report = HealthChecks::default()
+            .register(StubCheck::healthy("database"))
+            .register(StubCheck {
+                required: false,
+                healthy: false,
+                ..StubCheck::healthy("smtp")
+            })
+            .run()
+            .await;
+
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(report.checks["smtp"].status, CheckStatus::Unhealthy);
+        assert_eq!(
+            report.checks["smtp"].error.as_deref(),
+            Some("connection refused")
+        );
+    }
+
+    #[tokio::test]
+    async fn check_timing_out_is_unhealthy() {
+        let report = HealthChecks::new(Duration::from_millis(50))
+            .register(StubCheck {
+                delay: Duration::from_secs(10),
+                ..StubCheck::healthy("database")
+            })
+            .run()
+            .await;
+
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+        let database = &report.checks["database"];
+        assert_eq!(database.status, CheckStatus::Unhealthy);
+        assert!(database.error.as_deref().unwrap().contains("Timed out"));
+        assert!(database.latency_ms < 10_000);
+    }
+}
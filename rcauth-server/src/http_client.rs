@@ -0,0 +1,239 @@
+use rcauth_core::error::{Error, ErrorCode, Result};
+use reqwest::{Client, IntoUrl, Method, RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::Config;
+use crate::trace_context::{TRACEPARENT, TraceContext};
+
+/// Delay before the first retry; doubled after every further attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Shared client for outbound calls to third-party services, such as OAuth providers and breached-password lookups.
+///
+/// Every request is bounded by the configured connect and overall timeouts and carries the configured `User-Agent`. Requests made while handling an inbound request also carry its `traceparent`, so the trace continues downstream. [`HttpClient::get`] also retries transient failures; other methods go through [`HttpClient::request`] and are sent once, since they may not be safe to repeat.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::{Config, HttpClient};
+/// let client = HttpClient::new(&Config::default()).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    client: Client,
+    max_retries: u32,
+}
+
+impl HttpClient {
+    /// Builds the client from the `http_client_*` settings in `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if the underlying client cannot be built, e.g. because the user agent is not a valid header value.
+    pub fn new(config: &Config) -> Result<Self> {
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(config.http_client_connect_timeout_secs))
+            .timeout(Duration::from_secs(config.http_client_timeout_secs))
+            .user_agent(&config.http_client_user_agent)
+            .build()
+            .map_err(|e| {
+                Error::new(
+                    ErrorCode::ConfigurationError,
+                    "Failed to build HTTP client",
+                    e,
+                )
+            })?;
+
+        Ok(Self {
+            client,
+            max_retries: config.http_client_max_retries,
+        })
+    }
+
+    /// Returns the underlying client. Its requests carry no trace context; prefer [`HttpClient::request`].
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Starts a request that is sent once, such as a token exchange, carrying the current trace context.
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        traced(self.client.request(method, url))
+    }
+
+    /// Sends a GET request, retrying connection failures, timeouts, and `429`/`502`/`503`/`504` responses with exponential backoff.
+    ///
+    /// Any other response, including client errors, is returned as-is for the caller to interpret.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Timeout` error if the last attempt timed out and an `Unavailable` error for any other transport failure.
+    pub async fn get(&self, url: impl IntoUrl) -> Result<Response> {
+        let url = url
+            .into_url()
+            .map_err(|e| Error::new(ErrorCode::Invalid, "Invalid outbound request URL", e))?;
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+
+        loop {
+            let result = traced(self.client.get(url.clone())).send().await;
+
+            let retryable = match &result {
+                Ok(response) => is_retryable_status(response.status()),
+                Err(e) => e.is_timeout() || e.is_connect(),
+            };
+            if !retryable || attempt >= self.max_retries {
+                return result.map_err(|e| {
+                    let code = if e.is_timeout() {
+                        ErrorCode::Timeout
+                    } else {
+                        ErrorCode::Unavailable
+                    };
+                    Error::new(code, format!("GET {} failed", url), e)
+                });
+            }
+
+            attempt += 1;
+            warn!(url = %url, attempt, "Retrying outbound GET after transient failure");
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+/// Adds the `traceparent` of the request being handled, if any.
+fn traced(request: RequestBuilder) -> RequestBuilder {
+    match TraceContext::current() {
+        Some(context) => request.header(TRACEPARENT, context.traceparent()),
+        None => request,
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Starts a server that answers the nth request with `statuses[n]`, repeating the last one, and counts requests.
+    async fn stub_server(statuses: &'static [&'static str]) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&hits);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0; 1024];
+                let _ = socket.read(&mut buf).await;
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let status = statuses[n.min(statuses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        (url, hits)
+    }
+
+    fn client(max_retries: u32) -> HttpClient {
+        let config = Config {
+            http_client_timeout_secs: 1,
+            http_client_max_retries: max_retries,
+            ..Config::default()
+        };
+        HttpClient::new(&config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn get_retries_transient_failures() {
+        let (url, hits) = stub_server(&["503 Service Unavailable", "200 OK"]).await;
+
+        let response = client(2).get(&url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn get_gives_up_after_max_retries() {
+        let (url, hits) = stub_server(&["503 Service Unavailable"]).await;
+
+        let response = client(1).get(&url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn get_does_not_retry_client_errors() {
+        let (url, hits) = stub_server(&["404 Not Found", "200 OK"]).await;
+
+        let response = client(2).get(&url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn get_times_out_on_unresponsive_server() {
+        // Accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let err = client(0).get(&url).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::Timeout);
+    }
+    /// Starts a server that answers `200 OK` and hands over the head of the first request.
+    async fn recording_server() -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let _ = tx.send(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+            let _ = socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await;
+        });
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn outbound_requests_continue_the_current_trace() {
+        let (url, head) = recording_server().await;
+        let context = TraceContext::default();
+        context.clone().scope(client(0).get(&url)).await.unwrap();
+        let head = head.await.unwrap();
+        assert!(
+            head.contains(&format!("traceparent: {}\r\n", context.traceparent())),
+            "{}",
+            head
+        );
+
+        // Outside of a request there is no trace to continue
+        let (url, head) = recording_server().await;
+        client(0).request(Method::POST, &url).send().await.unwrap();
+        assert!(!head.await.unwrap().contains("traceparent"));
+    }
+}
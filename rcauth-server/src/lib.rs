@@ -1,7 +1,38 @@
 #![allow(dead_code)]
+mod client_ip;
 mod config;
+mod error;
+mod extract;
+mod health;
+mod http_client;
+mod metrics;
 mod routes;
 mod server;
+mod state;
+mod timestamp;
+mod token_binding;
+mod token_cookies;
+mod trace_context;
 
-pub use config::Config;
+pub use client_ip::TrustedProxies;
+pub use config::{
+    AuthMethods, Config, ConfigBuilder, CorsOrigins, CorsSettings, TokenCookieSettings,
+};
+pub use error::{ApiError, ErrorEnvelope};
+pub use extract::{BearerClaims, OptionalAuth, ValidatedJson};
+pub use health::{
+    CheckReport, CheckStatus, DEFAULT_CHECK_TIMEOUT, HealthChecks, HealthReport, HealthStatus,
+};
+pub use http_client::HttpClient;
+pub use metrics::ServerMetrics;
+pub use routes::idempotency::Idempotency;
+pub use routes::rate_limit::RateLimit;
 pub use server::*;
+pub use state::{
+    AppState, Audit, Draining, EffectiveConfig, Jwks, Maintenance, Migrations, Passwords, Settings,
+    Uptime, Users,
+};
+pub use timestamp::TimestampFormat;
+pub use token_binding::TokenBinding;
+pub use token_cookies::{ACCESS_TOKEN_COOKIE, CSRF_COOKIE, CSRF_HEADER, TokenCookies};
+pub use trace_context::{TRACEPARENT, TraceContext};
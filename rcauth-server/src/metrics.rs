@@ -0,0 +1,52 @@
+use rcauth_core::metrics::PoolMetrics;
+
+/// Metrics of the store's connection pool once attached, served in the Prometheus text format at `/metrics`.
+///
+/// Clones share the pool's metrics, so both servers serve the same ones.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_core::metrics::PoolMetrics;
+/// # use rcauth_server::ServerMetrics;
+/// let metrics = ServerMetrics::default().with_pool(PoolMetrics::default());
+/// assert!(metrics.render().contains("db_pool_acquire_timeouts_total 0"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ServerMetrics {
+    pool: Option<PoolMetrics>,
+}
+
+impl ServerMetrics {
+    /// Serves `pool`'s acquire wait times and timeouts.
+    pub fn with_pool(mut self, pool: PoolMetrics) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format, version 0.0.4.
+    pub fn render(&self) -> String {
+        self.pool
+            .as_ref()
+            .map(PoolMetrics::render)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attached_pool_metrics_are_rendered() {
+        assert_eq!(ServerMetrics::default().render(), "");
+
+        let pool = PoolMetrics::default();
+        let metrics = ServerMetrics::default().with_pool(pool.clone());
+        pool.record_acquire_timeout();
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("# TYPE db_pool_acquire_wait_seconds histogram\n"));
+        assert!(rendered.contains("db_pool_acquire_timeouts_total 1\n"));
+    }
+}
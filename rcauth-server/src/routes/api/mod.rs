@@ -1,2 +1,3 @@
 mod v1;
 
+pub use v1::{PasswordDoc, UserInfoDoc, change_password, userinfo};
@@ -0,0 +1,187 @@
+mod password;
+
+pub use password::{PasswordDoc, change_password};
+
+use axum::{Json, extract::State};
+use rcauth_core::error::{Error, ErrorCode};
+use rcauth_core::jwt::{Claims, TOKEN_INVALID};
+use rcauth_core::user::UserProfile;
+
+use crate::{ApiError, BearerClaims, Users};
+
+/// Scope a token needs to read `/userinfo` at all.
+const OPENID_SCOPE: &str = "openid";
+
+/// Scope releasing the `email` and `email_verified` claims.
+const EMAIL_SCOPE: &str = "email";
+
+/// Scope releasing the `name` claim.
+const PROFILE_SCOPE: &str = "profile";
+
+/// Standard OIDC claims about the token's subject, limited to what its scopes release.
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct UserInfo {
+    /// User id
+    pub sub: String,
+    /// Released with the `email` scope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Released with the `email` scope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    /// Released with the `profile` scope, if the user gave a name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl UserInfo {
+    /// Releases the claims of `profile` that the scopes in `claims` grant.
+    fn new(profile: UserProfile, claims: &Claims) -> Self {
+        let email = claims.has_scope(EMAIL_SCOPE);
+        Self {
+            sub: profile.id,
+            email: email.then_some(profile.email),
+            email_verified: email.then_some(profile.email_verified),
+            name: profile.name.filter(|_| claims.has_scope(PROFILE_SCOPE)),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/userinfo",
+    responses(
+        (status = 200, description = "Claims about the token's subject that its scopes release", body = UserInfo),
+        (status = 401, description = "The bearer token is missing, invalid or expired, or its subject no longer exists"),
+        (status = 403, description = "The token lacks the openid scope")
+    ),
+    tag = "OpenID Connect"
+)]
+pub async fn userinfo(
+    State(users): State<Users>,
+    claims: BearerClaims,
+) -> Result<Json<UserInfo>, ApiError> {
+    claims.require_scope(OPENID_SCOPE)?;
+
+    let BearerClaims(claims) = claims;
+    let profile = users
+        .repository()?
+        .profile(&claims.sub)
+        .await?
+        .ok_or_else(|| {
+            // A deleted user's tokens stay valid until they expire, but no longer name anyone
+            Error::new_simple(ErrorCode::Unauthorized, "Invalid token")
+                .with_data("reason", serde_json::json!(TOKEN_INVALID))
+                .with_internal(format!("Token subject '{}' does not exist", claims.sub))
+        })?;
+
+    Ok(Json(UserInfo::new(profile, &claims)))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(userinfo),
+    components(schemas(UserInfo)),
+    tags(
+        (name = "OpenID Connect", description = "Claims about the authenticated user")
+    )
+)]
+pub struct UserInfoDoc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::api_routes;
+    use crate::{AppState, AuthMethods};
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode, header},
+    };
+    use rcauth_core::jwt::TokenOptions;
+    use rcauth_core::testing::{InMemoryUsers, StoredUser, jwt};
+    use tower::ServiceExt;
+
+    /// Users knowing only `user-1`, Ada.
+    fn one_user() -> InMemoryUsers {
+        let mut ada = StoredUser::new("user-1", "ada@example.com");
+        ada.profile.name = Some("Ada".to_string());
+        let users = InMemoryUsers::default();
+        users.insert(ada);
+        users
+    }
+
+    async fn get_userinfo(token: &str) -> (StatusCode, serde_json::Value) {
+        let state = AppState {
+            jwt: Some(jwt()),
+            users: Users::new(one_user()),
+            ..AppState::default()
+        };
+        let request = Request::get("/userinfo")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = api_routes(state, &AuthMethods::default())
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn full_scope_token_gets_every_claim() {
+        let token = jwt()
+            .issue(
+                "user-1",
+                TokenOptions::default().scopes(&["openid", "email", "profile"]),
+            )
+            .unwrap();
+
+        let (status, body) = get_userinfo(&token).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "sub": "user-1",
+                "email": "ada@example.com",
+                "email_verified": true,
+                "name": "Ada",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn minimal_scope_token_gets_only_the_subject() {
+        let token = jwt()
+            .issue("user-1", TokenOptions::default().scopes(&["openid"]))
+            .unwrap();
+
+        let (status, body) = get_userinfo(&token).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "sub": "user-1" }));
+
+        // Without openid the token was not meant for identity claims at all
+        let token = jwt()
+            .issue("user-1", TokenOptions::default().scopes(&["email"]))
+            .unwrap();
+        assert_eq!(get_userinfo(&token).await.0, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn invalid_token_or_unknown_subject_is_unauthorized() {
+        let (status, body) = get_userinfo("not.a.token").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["details"]["reason"], TOKEN_INVALID);
+
+        let token = jwt()
+            .issue("deleted-user", TokenOptions::default().scopes(&["openid"]))
+            .unwrap();
+        let (status, body) = get_userinfo(&token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["details"]["reason"], TOKEN_INVALID);
+    }
+}
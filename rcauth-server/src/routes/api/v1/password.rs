@@ -0,0 +1,333 @@
+use axum::{Json, extract::State};
+use rcauth_core::error::{Error, ErrorCode, Validate, ValidationErrors};
+use rcauth_core::jwt::TOKEN_INVALID;
+use rcauth_core::password::Verification;
+use tracing::info;
+
+use crate::{ApiError, BearerClaims, Passwords, Users, ValidatedJson};
+
+/// A logged-in user's request to replace their password.
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct ChangePasswordRequest {
+    /// The password the user logs in with today
+    pub current_password: String,
+    /// The password to log in with from now on; it must meet the password policy
+    pub new_password: String,
+}
+
+impl Validate for ChangePasswordRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.current_password.is_empty() {
+            errors.add("current_password", "is required");
+        }
+        if self.new_password.is_empty() {
+            errors.add("new_password", "is required");
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Outcome of a password change.
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct PasswordChangeStatus {
+    /// The user's other sessions, revoked by the change
+    pub revoked_sessions: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/password/change",
+    request_body = ChangePasswordRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-chosen key of the request; a retry repeating it gets the first response back instead of changing the password again")
+    ),
+    responses(
+        (status = 200, description = "The new password is in effect and every other session of the user is revoked", body = PasswordChangeStatus),
+        (status = 401, description = "The bearer token is missing, invalid or expired, or the current password is wrong"),
+        (status = 409, description = "A request with the same Idempotency-Key is still being processed"),
+        (status = 422, description = "A password is missing, the new one does not meet the password policy, or the Idempotency-Key was used with a different body"),
+        (status = 429, description = "The client made too many password change requests; see Retry-After"),
+        (status = 503, description = "No store backs the user accounts")
+    ),
+    tag = "Account"
+)]
+pub async fn change_password(
+    State(users): State<Users>,
+    State(passwords): State<Passwords>,
+    BearerClaims(claims): BearerClaims,
+    ValidatedJson(request): ValidatedJson<ChangePasswordRequest>,
+) -> Result<Json<PasswordChangeStatus>, ApiError> {
+    let repository = users.repository()?;
+    let hasher = passwords.hasher()?;
+
+    // Users without a password, e.g. provider-only ones, are refused like a wrong guess
+    let hash = repository.password_hash(&claims.sub).await?;
+    if hasher.verify_login(&request.current_password, hash.as_deref())? == Verification::Invalid {
+        return Err(ApiError(Error::new_simple(
+            ErrorCode::Unauthorized,
+            "Current password is incorrect",
+        )));
+    }
+
+    let mut errors = ValidationErrors::new();
+    hasher.check_policy("new_password", &request.new_password, &mut errors);
+    if request.new_password == request.current_password {
+        errors.add("new_password", "must differ from the current password");
+    }
+    errors.into_result()?;
+
+    let new_hash = hasher.hash(&request.new_password)?;
+    let revoked_sessions = repository
+        .change_password(&claims.sub, &new_hash, claims.sid.as_deref())
+        .await?
+        .ok_or_else(|| {
+            Error::new_simple(ErrorCode::Unauthorized, "Invalid token")
+                .with_data("reason", serde_json::json!(TOKEN_INVALID))
+                .with_internal(format!("Token subject '{}' does not exist", claims.sub))
+        })?;
+
+    info!(user_id = %claims.sub, revoked_sessions, "Password changed");
+    Ok(Json(PasswordChangeStatus { revoked_sessions }))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(change_password),
+    components(schemas(ChangePasswordRequest, PasswordChangeStatus)),
+    tags(
+        (name = "Account", description = "Actions of the authenticated user on their own account")
+    )
+)]
+pub struct PasswordDoc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::api_routes;
+    use crate::routes::idempotency::{IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED};
+    use crate::routes::rate_limit::{X_RATELIMIT_LIMIT, X_RATELIMIT_REMAINING};
+    use crate::{AppState, AuthMethods, Idempotency, RateLimit, TrustedProxies};
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode, header},
+    };
+    use rcauth_core::jwt::TokenOptions;
+    use rcauth_core::password::{self, Hasher};
+    use rcauth_core::testing::{InMemoryIdempotency, InMemoryUsers, StoredUser, jwt};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const PASSWORD: &str = "correct horse battery staple";
+
+    /// Cheap parameters, so the tests stay fast.
+    fn hasher() -> Hasher {
+        Hasher::new(&password::Config {
+            password_hash_memory_kib: 8,
+            password_hash_iterations: 1,
+            password_hash_parallelism: 1,
+            ..password::Config::default()
+        })
+        .unwrap()
+    }
+
+    /// `user-1`, with the password [`PASSWORD`] and three sessions.
+    fn account() -> InMemoryUsers {
+        let users = InMemoryUsers::default();
+        users.insert(
+            StoredUser::new("user-1", "ada@example.com")
+                .with_password_hash(hasher().hash(PASSWORD).unwrap())
+                .with_sessions(&["session-1", "session-2", "session-3"]),
+        );
+        users
+    }
+
+    fn sessions(account: &InMemoryUsers) -> Vec<String> {
+        account.get("user-1").unwrap().sessions
+    }
+
+    async fn change(
+        account: &InMemoryUsers,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let state = AppState {
+            jwt: Some(jwt()),
+            users: Users::new(account.clone()),
+            passwords: Passwords::new(hasher()),
+            ..AppState::default()
+        };
+        let token = jwt()
+            .issue("user-1", TokenOptions::default().session("session-1"))
+            .unwrap();
+        let request = Request::post("/password/change")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+
+        let response = api_routes(state, &AuthMethods::default())
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn password_is_replaced_and_other_sessions_revoked() {
+        let account = account();
+        let (status, body) = change(
+            &account,
+            serde_json::json!({
+                "current_password": PASSWORD,
+                "new_password": "a brand new passphrase",
+            }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "revoked_sessions": 2 }));
+        let hash = account.get("user-1").unwrap().password_hash.unwrap();
+        assert_eq!(
+            hasher().verify("a brand new passphrase", &hash).unwrap(),
+            Verification::Valid
+        );
+        // The session making the request stays logged in
+        assert_eq!(sessions(&account), ["session-1"]);
+    }
+
+    #[tokio::test]
+    async fn retry_with_the_same_idempotency_key_is_replayed() {
+        let account = account();
+        let state = AppState {
+            jwt: Some(jwt()),
+            users: Users::new(account.clone()),
+            passwords: Passwords::new(hasher()),
+            idempotency: Some(Idempotency::new(Arc::new(InMemoryIdempotency::default()))),
+            ..AppState::default()
+        };
+        let app = api_routes(state, &AuthMethods::default());
+        let token = jwt()
+            .issue("user-1", TokenOptions::default().session("session-1"))
+            .unwrap();
+        let body = serde_json::json!({
+            "current_password": PASSWORD,
+            "new_password": "a brand new passphrase",
+        });
+        let request = || {
+            Request::post("/password/change")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(IDEMPOTENCY_KEY, "change-1")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let first = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        let hash = account.get("user-1").unwrap().password_hash;
+
+        // Run again, the retry would be refused, as the current password changed
+        let retry = app.oneshot(request()).await.unwrap();
+        assert_eq!(retry.status(), StatusCode::OK);
+        assert_eq!(retry.headers()[IDEMPOTENT_REPLAYED], "true");
+        let body = axum::body::to_bytes(retry.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({ "revoked_sessions": 2 })
+        );
+        assert_eq!(account.get("user-1").unwrap().password_hash, hash);
+    }
+
+    #[tokio::test]
+    async fn guesses_of_the_current_password_are_rate_limited() {
+        let state = AppState {
+            jwt: Some(jwt()),
+            users: Users::new(account()),
+            passwords: Passwords::new(hasher()),
+            password_change_limit: Some(RateLimit::new(
+                2,
+                Duration::from_secs(60),
+                TrustedProxies::default(),
+            )),
+            ..AppState::default()
+        };
+        let app = api_routes(state, &AuthMethods::default());
+        let token = jwt()
+            .issue("user-1", TokenOptions::default().session("session-1"))
+            .unwrap();
+        let guess = || {
+            let body = serde_json::json!({
+                "current_password": "not my password",
+                "new_password": "a brand new passphrase",
+            });
+            Request::post("/password/change")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        for remaining in ["1", "0"] {
+            let response = app.clone().oneshot(guess()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.headers()[X_RATELIMIT_LIMIT], "2");
+            assert_eq!(response.headers()[X_RATELIMIT_REMAINING], remaining);
+        }
+        let refused = app.oneshot(guess()).await.unwrap();
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(refused.headers().contains_key(header::RETRY_AFTER));
+    }
+
+    #[tokio::test]
+    async fn wrong_current_password_is_unauthorized() {
+        let account = account();
+        let (status, body) = change(
+            &account,
+            serde_json::json!({
+                "current_password": "not my password",
+                "new_password": "a brand new passphrase",
+            }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "unauthorized");
+        assert_eq!(sessions(&account).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn new_password_must_meet_the_policy() {
+        let account = account();
+        for (new_password, message) in [
+            ("short", "must be at least 8 characters long"),
+            (PASSWORD, "must differ from the current password"),
+        ] {
+            let (status, body) = change(
+                &account,
+                serde_json::json!({
+                    "current_password": PASSWORD,
+                    "new_password": new_password,
+                }),
+            )
+            .await;
+
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(
+                body["details"]["fields"]["new_password"],
+                serde_json::json!([message])
+            );
+        }
+        assert_eq!(sessions(&account).len(), 3);
+    }
+}
@@ -0,0 +1,721 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{header, request::Parts},
+    response::IntoResponse,
+    routing::{get, post, put},
+};
+use rcauth_core::audit::{self, AuditEntry};
+use rcauth_core::error::{Error, ErrorCode, Validate, ValidationErrors};
+use rcauth_core::jwt::TokenOptions;
+use rcauth_core::logger::LOG_LEVELS;
+use rcauth_core::settings;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::ServerMetrics;
+use crate::state::{AppState, EffectiveConfig, Maintenance, Settings, Users};
+use crate::{ApiError, BearerClaims, ValidatedJson};
+
+/// Whether maintenance mode is on after the request.
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/maintenance/on",
+    responses(
+        (status = 200, description = "API routes other than health checks now return 503, on every instance", body = MaintenanceStatus),
+        (status = 500, description = "The switch could not be persisted and is unchanged")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn maintenance_on(
+    State(maintenance): State<Maintenance>,
+    State(settings): State<Settings>,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    settings.save(settings::MAINTENANCE, &true).await?;
+    maintenance.enable();
+    warn!("Maintenance mode enabled; API routes return 503 until it is turned off");
+    Ok(Json(MaintenanceStatus { enabled: true }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/maintenance/off",
+    responses(
+        (status = 200, description = "API routes are served again, on every instance", body = MaintenanceStatus),
+        (status = 500, description = "The switch could not be persisted and is unchanged")
+    ),
+    tag = "Maintenance"
+)]
+pub async fn maintenance_off(
+    State(maintenance): State<Maintenance>,
+    State(settings): State<Settings>,
+) -> Result<Json<MaintenanceStatus>, ApiError> {
+    settings.save(settings::MAINTENANCE, &false).await?;
+    maintenance.disable();
+    warn!("Maintenance mode disabled");
+    Ok(Json(MaintenanceStatus { enabled: false }))
+}
+
+/// Scope an admin's token needs to force password resets.
+pub const USERS_WRITE_SCOPE: &str = "users:write";
+
+/// Outcome of forcing a user to reset their password.
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct PasswordResetStatus {
+    /// Whether login is blocked until the password is reset
+    pub must_reset_password: bool,
+    /// Sessions revoked by the request
+    pub revoked_sessions: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/force-password-reset",
+    params(
+        ("id" = String, Path, description = "User id")
+    ),
+    responses(
+        (status = 200, description = "The user cannot log in until they reset their password, and all their sessions are revoked", body = PasswordResetStatus),
+        (status = 401, description = "The admin's bearer token is missing or invalid"),
+        (status = 403, description = "The admin's token lacks the `users:write` scope"),
+        (status = 404, description = "No user has this id"),
+        (status = 503, description = "No store backs the user accounts")
+    ),
+    tag = "Users"
+)]
+pub async fn force_password_reset(
+    State(users): State<Users>,
+    claims: BearerClaims,
+    Path(id): Path<String>,
+) -> Result<Json<PasswordResetStatus>, ApiError> {
+    claims.require_scope(USERS_WRITE_SCOPE)?;
+    let Some(revoked_sessions) = users.repository()?.force_password_reset(&id).await? else {
+        return Err(ApiError(Error::new_simple(
+            ErrorCode::NotFound,
+            "User not found",
+        )));
+    };
+
+    warn!(user_id = %id, revoked_sessions, "Password reset forced");
+    Ok(Json(PasswordResetStatus {
+        must_reset_password: true,
+        revoked_sessions,
+    }))
+}
+
+/// Scope an admin's token needs to impersonate users.
+pub const IMPERSONATE_SCOPE: &str = "users:impersonate";
+
+/// Lifetime of impersonation tokens, further capped by `access_token_ttl_secs`.
+const IMPERSONATION_TOKEN_TTL: Duration = Duration::from_secs(300);
+
+/// Access token to act as a user with, which comes without a refresh token.
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct ImpersonationToken {
+    /// Access token for the user, naming the admin in its `act` claim
+    pub access_token: String,
+    /// Always `Bearer`
+    pub token_type: String,
+    /// Seconds until the token expires
+    pub expires_in: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/impersonate",
+    params(
+        ("id" = String, Path, description = "User id")
+    ),
+    responses(
+        (status = 200, description = "A short-lived access token for the user, recorded in the audit log and bound to the admin's client when `token_binding` is enabled", body = ImpersonationToken),
+        (status = 401, description = "The admin's bearer token is missing or invalid"),
+        (status = 403, description = "The admin's token lacks the `users:impersonate` scope, or is itself an impersonation token"),
+        (status = 404, description = "No user has this id"),
+        (status = 503, description = "No store backs the user accounts or the audit log")
+    ),
+    tag = "Users"
+)]
+pub async fn impersonate_user(
+    State(state): State<AppState>,
+    claims: BearerClaims,
+    Path(id): Path<String>,
+    parts: Parts,
+) -> Result<Json<ImpersonationToken>, ApiError> {
+    claims.require_scope(IMPERSONATE_SCOPE)?;
+    if claims.0.is_impersonation() {
+        return Err(ApiError(Error::new_simple(
+            ErrorCode::Forbidden,
+            "Impersonation tokens cannot impersonate other users",
+        )));
+    }
+    if state.users.repository()?.profile(&id).await?.is_none() {
+        return Err(ApiError(Error::new_simple(
+            ErrorCode::NotFound,
+            "User not found",
+        )));
+    }
+    let jwt = state.jwt.as_ref().ok_or_else(|| {
+        Error::new_simple(ErrorCode::Unavailable, "Token signing is not configured")
+    })?;
+
+    let admin = claims.0.sub;
+    // Recorded first, so no token is ever handed out without a trace
+    state
+        .audit
+        .log()?
+        .record(AuditEntry {
+            actor: admin.clone(),
+            action: audit::IMPERSONATE_USER.to_string(),
+            target: id.clone(),
+        })
+        .await?;
+    let mut options = TokenOptions::default()
+        .actor(&admin)
+        .ttl(IMPERSONATION_TOKEN_TTL);
+    // The admin's client presents the token, so it is bound like the admin's own
+    if let Some(binding) = &state.token_binding {
+        options = options.bound_to(&binding.fingerprint_of(&parts));
+    }
+    let access_token = jwt.issue(&id, options)?;
+    let claims = jwt.decode(&access_token)?;
+
+    warn!(admin = %admin, user_id = %id, "User impersonated");
+    Ok(Json(ImpersonationToken {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: claims.exp.saturating_sub(claims.iat),
+    }))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(force_password_reset, impersonate_user),
+    components(schemas(PasswordResetStatus, ImpersonationToken)),
+    tags(
+        (name = "Users", description = "Account actions for support staff")
+    )
+)]
+pub struct UsersDoc;
+
+/// Level to log at from now on.
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+pub struct LogLevelRequest {
+    /// One of `trace`, `debug`, `info`, `warn` or `error`
+    pub level: String,
+}
+
+impl Validate for LogLevelRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if !LOG_LEVELS.contains(&self.level.trim().to_lowercase().as_str()) {
+            errors.add(
+                "level",
+                format!("must be one of: {}", LOG_LEVELS.join(", ")),
+            );
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Scope an admin's token needs to change the log level.
+pub const LOGGING_WRITE_SCOPE: &str = "logging:write";
+
+/// The filter in effect after the request.
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct LogLevelStatus {
+    /// Active filter directives, e.g. `debug`
+    pub filter: String,
+}
+
+#[utoipa::path(
+    put,
+    path = "/log-level",
+    request_body = LogLevelRequest,
+    responses(
+        (status = 200, description = "The new level is in effect, replacing any configured log directives", body = LogLevelStatus),
+        (status = 401, description = "The admin's bearer token is missing or invalid"),
+        (status = 403, description = "The admin's token lacks the `logging:write` scope"),
+        (status = 422, description = "The level is not one of the allowed levels"),
+        (status = 503, description = "This process has no reloadable log filter")
+    ),
+    tag = "Logging"
+)]
+pub async fn set_log_level(
+    State(state): State<AppState>,
+    claims: BearerClaims,
+    ValidatedJson(request): ValidatedJson<LogLevelRequest>,
+) -> Result<Json<LogLevelStatus>, ApiError> {
+    claims.require_scope(LOGGING_WRITE_SCOPE)?;
+    let Some(filter) = &state.log_filter else {
+        return Err(ApiError(Error::new_simple(
+            ErrorCode::Unavailable,
+            "The log level cannot be changed because logging was not set up by this process",
+        )));
+    };
+
+    filter.set_level(&request.level)?;
+    // Logged at WARN so the change is recorded at any level
+    warn!(level = %request.level, "Log level changed at runtime");
+    Ok(Json(LogLevelStatus {
+        filter: filter.current()?,
+    }))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(set_log_level),
+    components(schemas(LogLevelRequest, LogLevelStatus)),
+    tags(
+        (name = "Logging", description = "Runtime logging controls")
+    )
+)]
+pub struct LoggingDoc;
+
+/// Scope an admin's token needs to read the effective configuration.
+pub const CONFIG_READ_SCOPE: &str = "config:read";
+
+#[utoipa::path(
+    get,
+    path = "/config",
+    responses(
+        (status = 200, description = "Effective configuration by section, each setting as `{\"value\", \"source\"}` with secrets masked"),
+        (status = 401, description = "The admin's bearer token is missing or invalid"),
+        (status = 403, description = "The admin's token lacks the `config:read` scope")
+    ),
+    tag = "Config"
+)]
+pub async fn config(
+    State(config): State<EffectiveConfig>,
+    claims: BearerClaims,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    claims.require_scope(CONFIG_READ_SCOPE)?;
+    Ok(Json(config.document().clone()))
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(config),
+    tags(
+        (name = "Config", description = "Runtime configuration introspection")
+    )
+)]
+pub struct ConfigDoc;
+
+/// Content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Connection pool metrics in the Prometheus text format", content_type = "text/plain", body = String)
+    ),
+    tag = "Metrics"
+)]
+pub async fn metrics(State(metrics): State<ServerMetrics>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        metrics.render(),
+    )
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(metrics),
+    tags(
+        (name = "Metrics", description = "Metrics for scraping by Prometheus")
+    )
+)]
+pub struct MetricsDoc;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(maintenance_on, maintenance_off),
+    components(schemas(MaintenanceStatus)),
+    tags(
+        (name = "Maintenance", description = "Maintenance mode for the API server")
+    )
+)]
+pub struct MaintenanceDoc;
+
+/// Builds the management-only routes.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/maintenance/on", post(maintenance_on))
+        .route("/maintenance/off", post(maintenance_off))
+        .route("/log-level", put(set_log_level))
+        .route("/config", get(config))
+        .route("/metrics", get(metrics))
+        .route(
+            "/users/{id}/force-password-reset",
+            post(force_password_reset),
+        )
+        .route("/users/{id}/impersonate", post(impersonate_user))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Audit;
+    use crate::routes::management_routes;
+    use async_trait::async_trait;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use rcauth_core::audit::AuditLog;
+    use rcauth_core::settings::SettingsRepository;
+    use rcauth_core::testing::{InMemoryUsers, StoredUser, jwt};
+    use rcauth_core::user::{PASSWORD_RESET_REQUIRED, UserRepository};
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    /// Forces a reset of `id`'s password, as the holder of `token` if given.
+    async fn force_reset(
+        app: &Router,
+        id: &str,
+        token: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::post(format!("/users/{}/force-password-reset", id));
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = request.body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn forced_reset_blocks_login_and_revokes_sessions() {
+        let user = InMemoryUsers::default();
+        user.insert(
+            StoredUser::new("user-1", "ada@example.com").with_sessions(&[
+                "session-1",
+                "session-2",
+                "session-3",
+            ]),
+        );
+        let app = management_routes(AppState {
+            jwt: Some(jwt()),
+            users: Users::new(user.clone()),
+            ..AppState::default()
+        });
+        let admin = jwt()
+            .issue(
+                "admin-1",
+                TokenOptions::default().scopes(&[USERS_WRITE_SCOPE]),
+            )
+            .unwrap();
+
+        let (status, body) = force_reset(&app, "user-1", Some(&admin)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["revoked_sessions"], 3);
+        assert_eq!(body["must_reset_password"], true);
+
+        let profile = user.profile("user-1").await.unwrap().unwrap();
+        let err = profile.ensure_can_log_in().unwrap_err();
+        assert_eq!(err.data.unwrap()["reason"], PASSWORD_RESET_REQUIRED);
+
+        let (_, body) = force_reset(&app, "user-1", Some(&admin)).await;
+        assert_eq!(body["revoked_sessions"], 0);
+
+        let (status, body) = force_reset(&app, "user-2", Some(&admin)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn forced_reset_is_refused_without_the_scope() {
+        let user = InMemoryUsers::default();
+        user.insert(StoredUser::new("user-1", "ada@example.com").with_sessions(&["session-1"]));
+        let app = management_routes(AppState {
+            jwt: Some(jwt()),
+            users: Users::new(user.clone()),
+            ..AppState::default()
+        });
+
+        let (status, _) = force_reset(&app, "user-1", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let plain = jwt().issue("admin-1", TokenOptions::default()).unwrap();
+        let (status, body) = force_reset(&app, "user-1", Some(&plain)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["details"]["required_scope"], USERS_WRITE_SCOPE);
+
+        let profile = user.profile("user-1").await.unwrap().unwrap();
+        assert!(profile.ensure_can_log_in().is_ok());
+    }
+
+    /// Audit entries, in the order they were recorded.
+    #[derive(Clone, Default)]
+    struct Recorded(Arc<Mutex<Vec<AuditEntry>>>);
+
+    #[async_trait]
+    impl AuditLog for Recorded {
+        async fn record(&self, entry: AuditEntry) -> rcauth_core::error::Result<()> {
+            self.0.lock().unwrap().push(entry);
+            Ok(())
+        }
+    }
+
+    /// Management routes knowing one user, `user-1`, and recording to `audit`.
+    fn impersonation_routes(audit: &Recorded) -> Router {
+        let user = InMemoryUsers::default();
+        user.insert(StoredUser::new("user-1", "ada@example.com"));
+        management_routes(AppState {
+            jwt: Some(jwt()),
+            users: Users::new(user),
+            audit: Audit::new(audit.clone()),
+            ..AppState::default()
+        })
+    }
+
+    async fn impersonate(app: &Router, id: &str, token: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::post(format!("/users/{}/impersonate", id))
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn impersonation_token_names_the_admin_and_is_audited() {
+        let audit = Recorded::default();
+        let app = impersonation_routes(&audit);
+        let admin = jwt()
+            .issue(
+                "admin-1",
+                TokenOptions::default().scopes(&[IMPERSONATE_SCOPE]),
+            )
+            .unwrap();
+
+        let (status, body) = impersonate(&app, "user-1", &admin).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["token_type"], "Bearer");
+        assert_eq!(body["expires_in"], 300);
+        // Nothing to refresh it with: no refresh token, and no session to rotate
+        assert!(body.get("refresh_token").is_none());
+        let token = body["access_token"].as_str().unwrap();
+        let claims = jwt().decode(token).unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.act.unwrap().sub, "admin-1");
+        assert_eq!(claims.sid, None);
+
+        assert_eq!(
+            *audit.0.lock().unwrap(),
+            [AuditEntry {
+                actor: "admin-1".to_string(),
+                action: audit::IMPERSONATE_USER.to_string(),
+                target: "user-1".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn impersonation_is_refused_without_the_scope_or_the_user() {
+        let audit = Recorded::default();
+        let app = impersonation_routes(&audit);
+
+        let plain = jwt().issue("admin-1", TokenOptions::default()).unwrap();
+        let (status, body) = impersonate(&app, "user-1", &plain).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["details"]["required_scope"], IMPERSONATE_SCOPE);
+
+        let admin = jwt()
+            .issue(
+                "admin-1",
+                TokenOptions::default().scopes(&[IMPERSONATE_SCOPE]),
+            )
+            .unwrap();
+        let (status, _) = impersonate(&app, "user-2", &admin).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // An impersonation token cannot be used to chain into another user
+        let (_, body) = impersonate(&app, "user-1", &admin).await;
+        let token = body["access_token"].as_str().unwrap().to_string();
+        audit.0.lock().unwrap().clear();
+        let (status, _) = impersonate(&app, "user-1", &token).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        assert!(audit.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn impersonation_token_is_bound_to_the_admin_client() {
+        use crate::TokenBinding;
+        use axum::extract::connect_info::MockConnectInfo;
+        use std::net::SocketAddr;
+
+        let user = InMemoryUsers::default();
+        user.insert(StoredUser::new("user-1", "ada@example.com"));
+        let app = management_routes(AppState {
+            jwt: Some(jwt()),
+            users: Users::new(user),
+            audit: Audit::new(Recorded::default()),
+            token_binding: Some(TokenBinding::default()),
+            ..AppState::default()
+        })
+        .layer(MockConnectInfo(SocketAddr::from(([198, 51, 100, 7], 4000))));
+        let post = |uri: &str, token: &str| {
+            Request::post(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::USER_AGENT, "rcauth-admin/1.0")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(header::USER_AGENT, "rcauth-admin/1.0".parse().unwrap());
+        let fingerprint =
+            TokenBinding::default().fingerprint(&headers, "198.51.100.7".parse().unwrap());
+        let admin = jwt()
+            .issue(
+                "admin-1",
+                TokenOptions::default()
+                    .scopes(&[IMPERSONATE_SCOPE])
+                    .bound_to(&fingerprint),
+            )
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(post("/users/user-1/impersonate", &admin))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let token = body["access_token"].as_str().unwrap();
+        assert_eq!(jwt().decode(token).unwrap().fgp, Some(fingerprint));
+
+        // Accepted from the admin's client: refused for its scope, not its binding
+        let response = app
+            .oneshot(post("/users/user-1/force-password-reset", token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn metrics_expose_the_pool() {
+        let pool = rcauth_core::metrics::PoolMetrics::default();
+        let state = AppState {
+            metrics: ServerMetrics::default().with_pool(pool.clone()),
+            ..AppState::default()
+        };
+        let app = management_routes(state);
+        pool.record_acquire_timeout();
+
+        let request = Request::get("/metrics").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            PROMETHEUS_CONTENT_TYPE
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("db_pool_acquire_timeouts_total 1\n"));
+    }
+
+    /// Settings shared by every instance, as JSON text; writes fail while `broken`.
+    #[derive(Clone, Default)]
+    struct SharedSettings {
+        values: Arc<Mutex<std::collections::HashMap<String, String>>>,
+        broken: bool,
+    }
+
+    #[async_trait]
+    impl SettingsRepository for SharedSettings {
+        async fn get_raw(&self, key: &str) -> rcauth_core::error::Result<Option<String>> {
+            Ok(self.values.lock().unwrap().get(key).cloned())
+        }
+
+        async fn set_raw(&self, key: &str, value: &str) -> rcauth_core::error::Result<()> {
+            if self.broken {
+                return Err(Error::new_simple(ErrorCode::Internal, "Database is down"));
+            }
+            self.values
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+    }
+
+    async fn post(app: &Router, uri: &str) -> StatusCode {
+        let request = Request::post(uri).body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn switch_is_persisted_for_other_instances_and_restarts() {
+        let shared = SharedSettings::default();
+        let instance = AppState {
+            settings: Settings::new(shared.clone()),
+            ..AppState::default()
+        };
+        let app = management_routes(instance.clone());
+        assert_eq!(post(&app, "/maintenance/on").await, StatusCode::OK);
+
+        // Another instance, or this one restarted, picks the switch up
+        let other = AppState {
+            settings: Settings::new(shared.clone()),
+            ..AppState::default()
+        };
+        other
+            .settings
+            .load_switches(&other.maintenance)
+            .await
+            .unwrap();
+        assert!(other.maintenance.is_enabled());
+
+        assert_eq!(post(&app, "/maintenance/off").await, StatusCode::OK);
+        other
+            .settings
+            .load_switches(&other.maintenance)
+            .await
+            .unwrap();
+        assert!(!other.maintenance.is_enabled());
+    }
+
+    #[tokio::test]
+    async fn switch_is_unchanged_when_it_cannot_be_persisted() {
+        let state = AppState {
+            settings: Settings::new(SharedSettings {
+                broken: true,
+                ..SharedSettings::default()
+            }),
+            ..AppState::default()
+        };
+        let app = management_routes(state.clone());
+
+        assert_eq!(
+            post(&app, "/maintenance/on").await,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert!(!state.maintenance.is_enabled());
+    }
+}
@@ -0,0 +1,148 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::Request,
+    middleware::Next,
+    response::Response,
+};
+use rcauth_core::logger::redact_body;
+use tracing::debug;
+
+/// Largest body, in bytes, that is buffered for logging; larger or unsized bodies are streamed through untouched.
+pub const MAX_BUFFERED_BODY_BYTES: u64 = 64 * 1024;
+
+/// Number of bytes of a redacted body written to the log.
+pub const MAX_LOGGED_BODY_BYTES: usize = 4 * 1024;
+
+/// Logs redacted request and response bodies at `DEBUG`, for temporarily debugging integrations.
+///
+/// Only installed when `debug_log_bodies` is set. Bodies are buffered only when their size is known and at most [`MAX_BUFFERED_BODY_BYTES`], and the logged text is capped at [`MAX_LOGGED_BODY_BYTES`].
+pub async fn log_bodies(request: Request, next: Next) -> Response {
+    let (parts, body) = request.into_parts();
+    let body = log_body("request", body).await;
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    let (parts, body) = response.into_parts();
+    let body = log_body("response", body).await;
+    Response::from_parts(parts, body)
+}
+
+/// Buffers and logs `body` if it is small enough, returning a body with the same contents.
+async fn log_body(direction: &'static str, body: Body) -> Body {
+    let size = body.size_hint().upper();
+    if size.is_none_or(|size| size > MAX_BUFFERED_BODY_BYTES) {
+        debug!(
+            direction,
+            size, "{} body not logged: too large or streamed", direction
+        );
+        return body;
+    }
+
+    match axum::body::to_bytes(body, MAX_BUFFERED_BODY_BYTES as usize).await {
+        Ok(bytes) => {
+            debug!(
+                direction,
+                size = bytes.len(),
+                body = %truncate(&redact_body(&bytes)),
+                "{} body",
+                direction
+            );
+            Body::from(bytes)
+        }
+        Err(e) => {
+            debug!(direction, error = %e, "Failed to read {} body for logging", direction);
+            Body::from(Bytes::new())
+        }
+    }
+}
+
+/// Caps `text` at [`MAX_LOGGED_BODY_BYTES`] on a character boundary, marking truncated output.
+fn truncate(text: &str) -> String {
+    if text.len() <= MAX_LOGGED_BODY_BYTES {
+        return text.to_string();
+    }
+
+    let mut end = MAX_LOGGED_BODY_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… ({} bytes total)", &text[..end], text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::post};
+    use rcauth_core::testing::CapturedLogs;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn echo_router() -> Router {
+        Router::new().route("/echo", post(|body: String| async move { body }))
+    }
+
+    fn post_echo(body: Body) -> Request {
+        Request::builder()
+            .method("POST")
+            .uri("/echo")
+            .body(body)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn bodies_are_logged_redacted_when_enabled() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = echo_router().layer(axum::middleware::from_fn(log_bodies));
+        let payload = r#"{"email":"a@example.com","password":"hunter2"}"#;
+        let response = app.oneshot(post_echo(Body::from(payload))).await.unwrap();
+
+        // The body still reaches the handler and the client intact
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, payload);
+
+        let logs = logs.contents();
+        assert!(logs.contains("request body"), "{}", logs);
+        assert!(logs.contains("response body"), "{}", logs);
+        assert!(logs.contains("a@example.com"), "{}", logs);
+        assert!(!logs.contains("hunter2"), "{}", logs);
+    }
+
+    /// A body that never yields a frame, so any middleware that buffers it hangs.
+    struct PendingBody;
+
+    impl HttpBody for PendingBody {
+        type Data = Bytes;
+        type Error = std::convert::Infallible;
+
+        fn poll_frame(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<Result<http_body::Frame<Bytes>, Self::Error>>> {
+            std::task::Poll::Pending
+        }
+    }
+
+    #[tokio::test]
+    async fn bodies_are_not_buffered_when_disabled() {
+        let app = crate::api_router(&crate::Config::default(), Default::default()).unwrap();
+        let request = Request::builder()
+            .uri("/api/v1/health")
+            .header("content-type", "application/json")
+            .body(Body::new(PendingBody))
+            .unwrap();
+
+        let response = tokio::time::timeout(Duration::from_secs(5), app.oneshot(request))
+            .await
+            .expect("request should not wait for the body")
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+    }
+}
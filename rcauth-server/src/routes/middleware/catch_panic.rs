@@ -0,0 +1,72 @@
+use axum::{body::Body, http::Response, response::IntoResponse};
+use rcauth_core::error::{Error, ErrorCode};
+use std::any::Any;
+use tower_http::catch_panic::CatchPanicLayer;
+use tracing::error;
+
+use crate::ApiError;
+
+/// Signature of [`panic_response`], as stored in the layer.
+pub type PanicHandler = fn(Box<dyn Any + Send + 'static>) -> Response<Body>;
+
+/// Converts a handler panic into a generic `500` [`ErrorResponse`](rcauth_core::error::ErrorResponse), logging the panic message at `ERROR`.
+///
+/// The message never reaches the client, as it may describe internals. It is logged inside the request span when the request logger wraps this layer, which ties it to the method and route.
+///
+/// # Examples
+///
+/// ```ignore
+/// let response = panic_response(Box::new("index out of bounds"));
+/// assert_eq!(response.status(), 500);
+/// ```
+pub fn panic_response(payload: Box<dyn Any + Send + 'static>) -> Response<Body> {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload");
+    error!(panic = message, "Request handler panicked");
+
+    ApiError(Error::new_simple(
+        ErrorCode::Internal,
+        "An unexpected error occurred",
+    ))
+    .into_response()
+}
+
+/// Creates the layer that answers panicking requests with [`panic_response`] instead of dropping the connection.
+pub fn create_catch_panic_layer() -> CatchPanicLayer<PanicHandler> {
+    CatchPanicLayer::custom(panic_response as PanicHandler)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, http::Request, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn panicking_handler_returns_generic_json_error() {
+        let app = Router::new()
+            .route(
+                "/boom",
+                get(|| async {
+                    panic!("secret internals: db password is hunter2");
+                    #[allow(unreachable_code)]
+                    ""
+                }),
+            )
+            .layer(create_catch_panic_layer());
+
+        let request = Request::builder().uri("/boom").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), 500);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "internal");
+        assert!(!String::from_utf8_lossy(&body).contains("hunter2"));
+    }
+}
@@ -0,0 +1,156 @@
+use axum::{
+    body::HttpBody,
+    extract::{Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rcauth_core::error::{Error, ErrorCode};
+use std::sync::Arc;
+
+use crate::ApiError;
+
+/// Media types a route group accepts for request bodies.
+#[derive(Clone, Debug)]
+pub struct AcceptedContentTypes(Arc<[String]>);
+
+impl AcceptedContentTypes {
+    pub fn new<T: Into<String>>(media_types: Vec<T>) -> Self {
+        Self(
+            media_types
+                .into_iter()
+                .map(|t| t.into().to_ascii_lowercase())
+                .collect(),
+        )
+    }
+
+    /// Accepts only `application/json`, the default for API routes.
+    pub fn json() -> Self {
+        Self::new(vec!["application/json"])
+    }
+
+    /// Returns true if `content_type`, ignoring parameters such as `charset`, is one of the accepted media types.
+    fn accepts(&self, content_type: &str) -> bool {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        self.0.contains(&essence)
+    }
+}
+
+/// Rejects requests whose body is not one of the accepted media types with `415 Unsupported Media Type`.
+///
+/// Requests without a body, such as most `GET`s, pass regardless of their headers. Apply it with `route_layer` to each route group, with the media types that group accepts as state.
+///
+/// # Examples
+///
+/// ```ignore
+/// let routes = Router::new()
+///     .route("/token", post(token))
+///     .route_layer(from_fn_with_state(
+///         AcceptedContentTypes::new(vec!["application/x-www-form-urlencoded"]),
+///         require_content_type,
+///     ));
+/// ```
+pub async fn require_content_type(
+    State(accepted): State<AcceptedContentTypes>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if has_body(&request) {
+        let content_type = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+
+        if !content_type.is_some_and(|content_type| accepted.accepts(content_type)) {
+            let message = format!(
+                "Unsupported content type {}; expected {}",
+                content_type.map_or("(none)".to_string(), |t| format!("'{}'", t)),
+                accepted.0.join(" or ")
+            );
+            return ApiError(
+                Error::new_simple(ErrorCode::Invalid, message)
+                    .with_status(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            )
+            .into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Returns false only when the request is known to carry no body.
+fn has_body(request: &Request) -> bool {
+    let declared_empty = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .is_some_and(|length| length == "0");
+    !declared_empty && request.body().size_hint().exact() != Some(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        Router,
+        body::Body,
+        middleware::from_fn_with_state,
+        routing::{get, post},
+    };
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/items", post(|| async { "created" }))
+            .route("/items", get(|| async { "listed" }))
+            .route_layer(from_fn_with_state(
+                AcceptedContentTypes::json(),
+                require_content_type,
+            ))
+    }
+
+    async fn status(request: Request) -> StatusCode {
+        app().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn json_body_is_accepted() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/items")
+            .header(header::CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(Body::from("{}"))
+            .unwrap();
+        assert_eq!(status(request).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn body_without_json_content_type_is_rejected() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/items")
+            .body(Body::from("name=widget"))
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "invalid");
+    }
+
+    #[tokio::test]
+    async fn get_without_body_is_allowed() {
+        let request = Request::builder()
+            .uri("/items")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status(request).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,156 @@
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rcauth_core::error::{Error, ErrorCode};
+
+use crate::ApiError;
+use crate::token_cookies::{self, CSRF_COOKIE, CSRF_HEADER, TokenCookies};
+
+/// `reason` detail of a request refused by the CSRF check.
+pub const CSRF_TOKEN_INVALID: &str = "csrf_token_invalid";
+
+/// Refuses mutating requests authenticated by the access token cookie with `403 Forbidden` unless their [`CSRF_HEADER`] matches their [`CSRF_COOKIE`].
+///
+/// This is the double-submit defence: another site can make a browser send the cookies, but cannot read them to echo the token. Safe methods, requests with an `Authorization` header and requests without the access token cookie pass untouched, as do all requests when token cookies or `csrf_protection` are disabled.
+///
+/// # Examples
+///
+/// ```ignore
+/// let routes = Router::new()
+///     .route("/password/change", post(change_password))
+///     .route_layer(from_fn_with_state(state.token_cookies.clone(), require_csrf_token));
+/// ```
+pub async fn require_csrf_token(
+    State(cookies): State<Option<TokenCookies>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let checked = cookies.is_some_and(|cookies| cookies.csrf_protection())
+        && !request.method().is_safe()
+        && !request.headers().contains_key(header::AUTHORIZATION)
+        && token_cookies::access_token_of(request.headers()).is_some();
+    if !checked {
+        return next.run(request).await;
+    }
+
+    let headers = request.headers();
+    let expected = token_cookies::cookie_of(headers, CSRF_COOKIE);
+    let sent = headers
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if let (Some(expected), Some(sent)) = (expected, sent)
+        && constant_time_eq(expected.as_bytes(), sent.as_bytes())
+    {
+        return next.run(request).await;
+    }
+
+    ApiError(
+        Error::new_simple(ErrorCode::Forbidden, "Missing or invalid CSRF token")
+            .with_data("reason", serde_json::json!(CSRF_TOKEN_INVALID)),
+    )
+    .into_response()
+}
+
+/// Compares `a` and `b` in time independent of where they differ, so the token cannot be guessed byte by byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::api_routes;
+    use crate::{AppState, AuthMethods, TokenCookieSettings};
+    use axum::{body::Body, http::StatusCode};
+    use rcauth_core::jwt::TokenOptions;
+    use rcauth_core::testing::jwt;
+    use tower::ServiceExt;
+
+    const CSRF: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    /// Posts a password change authenticated by `cookie` or `bearer`, sending `csrf` in the header if given.
+    ///
+    /// No store backs the accounts, so a request that gets past authentication and the CSRF check answers `503`.
+    async fn change_password(
+        cookie: Option<&str>,
+        bearer: Option<&str>,
+        csrf: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        let state = AppState {
+            jwt: Some(jwt()),
+            token_cookies: Some(TokenCookies::new(TokenCookieSettings {
+                enabled: true,
+                ..TokenCookieSettings::default()
+            })),
+            ..AppState::default()
+        };
+        let mut request =
+            Request::post("/password/change").header(header::CONTENT_TYPE, "application/json");
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+        if let Some(bearer) = bearer {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", bearer));
+        }
+        if let Some(csrf) = csrf {
+            request = request.header(CSRF_HEADER, csrf);
+        }
+        let body = serde_json::json!({
+            "current_password": "correct horse battery staple",
+            "new_password": "a brand new passphrase",
+        });
+
+        let response = api_routes(state, &AuthMethods::default())
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn cookies(token: &str) -> String {
+        format!("rcauth_access_token={}; rcauth_csrf_token={}", token, CSRF)
+    }
+
+    #[tokio::test]
+    async fn matching_token_pair_passes() {
+        let token = jwt().issue("user-1", TokenOptions::default()).unwrap();
+        let (status, body) = change_password(Some(&cookies(&token)), None, Some(CSRF)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{}", body);
+    }
+
+    #[tokio::test]
+    async fn missing_or_mismatched_header_is_forbidden() {
+        let token = jwt().issue("user-1", TokenOptions::default()).unwrap();
+        let other = CSRF.replace('0', "1");
+        for csrf in [None, Some(other.as_str()), Some("")] {
+            let (status, body) = change_password(Some(&cookies(&token)), None, csrf).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert_eq!(body["details"]["reason"], CSRF_TOKEN_INVALID);
+        }
+    }
+
+    #[tokio::test]
+    async fn bearer_requests_bypass_the_check() {
+        let token = jwt().issue("user-1", TokenOptions::default()).unwrap();
+        // Browsers may attach the cookies to an explicit bearer request too
+        let (status, body) = change_password(Some(&cookies(&token)), Some(&token), None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{}", body);
+
+        let (status, body) = change_password(None, Some(&token), None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{}", body);
+    }
+
+    #[test]
+    fn tokens_are_compared_whole() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+}
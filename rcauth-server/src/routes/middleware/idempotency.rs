@@ -0,0 +1,397 @@
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rcauth_core::error::{Error, ErrorCode};
+use rcauth_core::idempotency::{CachedResponse, Claim, IdempotencyStore};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::ApiError;
+
+/// Request header carrying the client-chosen key that identifies retries of one request.
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Response header set to `true` on responses replayed from an earlier request.
+pub const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+/// How long a key is remembered by default.
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Longest accepted `Idempotency-Key`.
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Largest request or response body, in bytes, buffered for an idempotent request.
+pub const MAX_IDEMPOTENT_BODY_BYTES: usize = 64 * 1024;
+
+/// Where idempotency keys are recorded, and for how long.
+#[derive(Clone)]
+pub struct Idempotency {
+    store: Arc<dyn IdempotencyStore>,
+    ttl: Duration,
+}
+
+impl Idempotency {
+    pub fn new(store: Arc<dyn IdempotencyStore>) -> Self {
+        Self {
+            store,
+            ttl: DEFAULT_IDEMPOTENCY_TTL,
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+/// Replays the recorded response when a request repeats an `Idempotency-Key`, instead of running it again.
+///
+/// Keys are scoped to the method, the path and the caller's credentials, i.e. its `Authorization` and `Cookie` headers, so one client's key never replays another's response. They are remembered with a hash of the request body, less its password fields: reusing a key with a different body is rejected with `422`, and retrying while the first request is still running with `409`. Server errors are not recorded, so the request can be retried. Requests without the header pass through untouched.
+///
+/// # Examples
+///
+/// ```ignore
+/// let routes = Router::new()
+///     .route("/signup", post(signup))
+///     .route_layer(from_fn_with_state(Idempotency::new(Arc::new(store)), idempotent));
+/// ```
+pub async fn idempotent(
+    State(idempotency): State<Idempotency>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => key.to_string(),
+        _ => {
+            return ApiError(Error::new_simple(
+                ErrorCode::Invalid,
+                format!(
+                    "Idempotency-Key must be 1 to {} visible ASCII characters",
+                    MAX_IDEMPOTENCY_KEY_LEN
+                ),
+            ))
+            .into_response();
+        }
+    };
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, MAX_IDEMPOTENT_BODY_BYTES).await else {
+        return ApiError(
+            Error::new_simple(
+                ErrorCode::Invalid,
+                "Request body is too large to use with an Idempotency-Key",
+            )
+            .with_status(StatusCode::PAYLOAD_TOO_LARGE),
+        )
+        .into_response();
+    };
+
+    let mut caller = Sha256::new();
+    for name in [header::AUTHORIZATION, header::COOKIE] {
+        if let Some(value) = parts.headers.get(name) {
+            caller.update(value.as_bytes());
+        }
+        caller.update([0]);
+    }
+    let key = format!(
+        "{} {} {:x} {}",
+        parts.method,
+        parts.uri.path(),
+        caller.finalize(),
+        key
+    );
+    let request_hash = request_hash(&body);
+
+    match idempotency
+        .store
+        .claim(&key, &request_hash, idempotency.ttl)
+        .await
+    {
+        Ok(Claim::Claimed) => {}
+        Ok(
+            Claim::Completed {
+                request_hash: recorded,
+                ..
+            }
+            | Claim::InProgress {
+                request_hash: recorded,
+            },
+        ) if recorded != request_hash => {
+            return ApiError(Error::new_simple(
+                ErrorCode::UnprocessableEntity,
+                "Idempotency-Key was already used with a different request body",
+            ))
+            .into_response();
+        }
+        Ok(Claim::Completed { response, .. }) => return replay(response),
+        Ok(Claim::InProgress { .. }) => {
+            return ApiError(Error::new_simple(
+                ErrorCode::Conflict,
+                "A request with this Idempotency-Key is still being processed",
+            ))
+            .into_response();
+        }
+        Err(e) => return ApiError(e).into_response(),
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    record(&idempotency, &key, response).await
+}
+
+/// Hash of a request body to remember with its key, leaving out any JSON field whose name contains `password`.
+///
+/// The hash is stored for the key's lifetime; over a plaintext password it would let guesses be checked offline, bypassing the password hasher.
+fn request_hash(body: &[u8]) -> String {
+    fn redact(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                fields.retain(|name, _| !name.contains("password"));
+                fields.values_mut().for_each(redact);
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+            _ => {}
+        }
+    }
+
+    let redacted = serde_json::from_slice(body)
+        .ok()
+        .and_then(|mut value: serde_json::Value| {
+            redact(&mut value);
+            serde_json::to_vec(&value).ok()
+        });
+    format!("{:x}", Sha256::digest(redacted.as_deref().unwrap_or(body)))
+}
+
+/// Records `response` against the claimed `key`, releasing the key instead when the response should not be replayed.
+async fn record(idempotency: &Idempotency, key: &str, response: Response) -> Response {
+    let size = response.body().size_hint().upper();
+    if response.status().is_server_error()
+        || size.is_none_or(|size| size > MAX_IDEMPOTENT_BODY_BYTES as u64)
+    {
+        release(idempotency, key).await;
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_IDEMPOTENT_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            release(idempotency, key).await;
+            return ApiError(Error::new(
+                ErrorCode::Internal,
+                "Failed to read response body",
+                e,
+            ))
+            .into_response();
+        }
+    };
+
+    let cached = CachedResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        body: body.to_vec(),
+    };
+    if let Err(e) = idempotency.store.complete(key, &cached).await {
+        warn!(error = %e, "Failed to record idempotent response");
+        release(idempotency, key).await;
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Releases `key` so a retry runs the request again, logging rather than failing the request on error.
+async fn release(idempotency: &Idempotency, key: &str) {
+    if let Err(e) = idempotency.store.release(key).await {
+        warn!(error = %e, "Failed to release idempotency key");
+    }
+}
+
+/// Rebuilds a recorded response, marking it as replayed.
+fn replay(cached: CachedResponse) -> Response {
+    let status = StatusCode::from_u16(cached.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, cached.body).into_response();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(content_type) = cached
+        .content_type
+        .and_then(|value| HeaderValue::from_str(&value).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, middleware::from_fn_with_state, routing::post};
+    use rcauth_core::testing::InMemoryIdempotency;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    /// Counts handler runs and answers `201` with the run number, or `500` for the body `fail`.
+    fn app(runs: Arc<AtomicUsize>) -> Router {
+        let idempotency = Idempotency::new(Arc::new(InMemoryIdempotency::default()));
+        Router::new()
+            .route(
+                "/signup",
+                post(move |body: String| async move {
+                    let run = runs.fetch_add(1, Ordering::SeqCst) + 1;
+                    if body == "fail" {
+                        return (StatusCode::INTERNAL_SERVER_ERROR, String::new());
+                    }
+                    (StatusCode::CREATED, format!("run {}", run))
+                }),
+            )
+            .route_layer(from_fn_with_state(idempotency, idempotent))
+    }
+
+    fn signup(key: Option<&str>, body: &str) -> Request {
+        let mut request = Request::post("/signup");
+        if let Some(key) = key {
+            request = request.header(IDEMPOTENCY_KEY, key);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn text(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn repeated_key_replays_cached_response() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let app = app(runs.clone());
+
+        let first = app
+            .clone()
+            .oneshot(signup(Some("abc"), "alice"))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert!(first.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(text(first).await, "run 1");
+
+        let replayed = app.oneshot(signup(Some("abc"), "alice")).await.unwrap();
+        assert_eq!(replayed.status(), StatusCode::CREATED);
+        assert_eq!(replayed.headers()[IDEMPOTENT_REPLAYED], "true");
+        assert_eq!(
+            replayed.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(text(replayed).await, "run 1");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn keys_are_scoped_to_the_caller() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let app = app(runs.clone());
+        let as_caller = |token: &str| {
+            let mut request = signup(Some("abc"), "alice");
+            request.headers_mut().insert(
+                header::AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+            );
+            request
+        };
+
+        let first = app.clone().oneshot(as_caller("token-a")).await.unwrap();
+        assert_eq!(text(first).await, "run 1");
+        let other = app.clone().oneshot(as_caller("token-b")).await.unwrap();
+        assert!(other.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(text(other).await, "run 2");
+        let replayed = app.oneshot(as_caller("token-a")).await.unwrap();
+        assert_eq!(text(replayed).await, "run 1");
+    }
+
+    #[tokio::test]
+    async fn reused_key_with_different_body_is_rejected() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let app = app(runs.clone());
+
+        app.clone()
+            .oneshot(signup(Some("abc"), "alice"))
+            .await
+            .unwrap();
+        let response = app.oneshot(signup(Some("abc"), "mallory")).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(text(response).await.contains("unprocessable_entity"));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn passwords_are_left_out_of_the_request_hash() {
+        let change =
+            request_hash(br#"{"current_password":"hunter2","new_password":"correct horse"}"#);
+        assert_eq!(
+            change,
+            request_hash(br#"{"current_password":"guess","new_password":"guess"}"#)
+        );
+        assert_ne!(
+            change,
+            format!(
+                "{:x}",
+                Sha256::digest(br#"{"current_password":"hunter2","new_password":"correct horse"}"#)
+            )
+        );
+
+        assert_ne!(
+            request_hash(br#"{"email":"ada@example.com","password":"hunter2"}"#),
+            request_hash(br#"{"email":"bob@example.com","password":"hunter2"}"#)
+        );
+        assert_ne!(request_hash(b"alice"), request_hash(b"mallory"));
+    }
+
+    #[tokio::test]
+    async fn server_errors_and_requests_without_a_key_run_again() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let app = app(runs.clone());
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(signup(None, "alice")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        for _ in 0..2 {
+            let response = app
+                .clone()
+                .oneshot(signup(Some("abc"), "fail"))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn oversized_key_is_rejected() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let key = "k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1);
+
+        let response = app(runs.clone())
+            .oneshot(signup(Some(&key), "alice"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,13 +1,195 @@
+use axum::{
+    extract::MatchedPath,
+    http::{Request, Response},
+};
+use rcauth_core::logger::redact_uri;
+use std::time::Duration;
 use tower_http::{
     classify::{ServerErrorsAsFailures, SharedClassifier},
-    trace::{DefaultMakeSpan, DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, TraceLayer},
+    trace::{
+        DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, MakeSpan, OnResponse, TraceLayer,
+    },
 };
-use tracing::Level;
+use tracing::{Level, Span, field};
+
+use crate::TraceContext;
+
+/// Route recorded for requests that matched no route, such as 404s.
+pub const UNMATCHED_ROUTE: &str = "<unmatched>";
+
+/// Returns the route template `request` matched, e.g. `/api/v1/users/{id}`, or [`UNMATCHED_ROUTE`].
+///
+/// Use this rather than the URI path wherever requests are grouped, such as span names and metric labels, so each route yields one series instead of one per id. Templates include the prefixes of any nested routers. Only available inside the router, i.e. from layers added with `Router::layer` or `Router::route_layer`, and in handlers.
+///
+/// # Examples
+///
+/// ```ignore
+/// let request = Request::get("/api/v1/users/42").body(()).unwrap();
+/// assert_eq!(route_label(&request), UNMATCHED_ROUTE);
+/// ```
+pub fn route_label<B>(request: &Request<B>) -> &str {
+    request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, MatchedPath::as_str)
+}
+
+/// Creates request spans like `DefaultMakeSpan`, but with sensitive query parameters masked in the recorded URI, a low-cardinality `route` field holding the matched template (e.g. `/api/v1/users/{id}`), the W3C `trace_id` of the request, and an empty `latency_ms` field filled in by [`LatencyOnResponse`].
+///
+/// The span is also named `"{method} {route}"` through the `otel.name` field, which OpenTelemetry exporters use in place of the static `request` name. Aggregate on `route` rather than `uri`. The template is only known once the router has matched, so the layer must be installed with `Router::layer` rather than around the whole service. The trace id is only recorded when [`propagate_trace_context`](crate::trace_context::propagate_trace_context) runs outside this layer.
+#[derive(Clone, Debug, Default)]
+pub struct RedactingMakeSpan;
+
+impl<B> MakeSpan<B> for RedactingMakeSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        let route = route_label(request);
+        let trace_id = request
+            .extensions()
+            .get::<TraceContext>()
+            .map(TraceContext::trace_id);
+
+        tracing::info_span!(
+            "request",
+            otel.name = %format_args!("{} {}", request.method(), route),
+            method = %request.method(),
+            route = %route,
+            uri = %redact_uri(request.uri()),
+            version = ?request.version(),
+            trace_id = trace_id.as_deref().map(field::display),
+            latency_ms = field::Empty,
+        )
+    }
+}
+
+/// Records the request duration on the span as `latency_ms`, then logs the response like `DefaultOnResponse`.
+#[derive(Clone, Debug)]
+pub struct LatencyOnResponse {
+    inner: DefaultOnResponse,
+}
+
+impl Default for LatencyOnResponse {
+    fn default() -> Self {
+        Self {
+            inner: DefaultOnResponse::new().level(Level::INFO),
+        }
+    }
+}
 
-pub fn create_logger_middleware_http() -> TraceLayer<SharedClassifier<ServerErrorsAsFailures>> {
+impl<B> OnResponse<B> for LatencyOnResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        // Fractional milliseconds keep sub-millisecond requests distinguishable in percentiles
+        span.record("latency_ms", latency.as_secs_f64() * 1000.0);
+        self.inner.on_response(response, latency, span);
+    }
+}
+
+pub fn create_logger_middleware_http() -> TraceLayer<
+    SharedClassifier<ServerErrorsAsFailures>,
+    RedactingMakeSpan,
+    DefaultOnRequest,
+    LatencyOnResponse,
+> {
     TraceLayer::new_for_http()
-        .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
+        .make_span_with(RedactingMakeSpan)
         .on_request(DefaultOnRequest::new().level(Level::INFO))
-        .on_response(DefaultOnResponse::new().level(Level::INFO))
+        .on_response(LatencyOnResponse::default())
         .on_failure(DefaultOnFailure::new().level(Level::ERROR))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, routing::get};
+    use rcauth_core::testing::CapturedLogs;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn span_records_latency_and_route_template() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let users = Router::new().route("/users/{id}", get(|| async { "user" }));
+        let app = Router::new()
+            .nest("/api/v1", users)
+            .layer(create_logger_middleware_http());
+        let request = Request::builder()
+            .uri("/api/v1/users/42")
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap();
+
+        let logs = logs.contents();
+        let closed = logs
+            .lines()
+            .find(|line| line.contains("close"))
+            .expect("span should close");
+        assert!(closed.contains("route=/api/v1/users/{id}"), "{}", logs);
+        assert!(closed.contains("latency_ms="), "{}", logs);
+    }
+
+    #[tokio::test]
+    async fn deeply_nested_routes_use_the_full_template() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let users = Router::new().route(
+            "/users/{id}/sessions/{session_id}",
+            get(|| async { "session" }),
+        );
+        let app = Router::new()
+            .nest("/api", Router::new().nest("/v1", users))
+            .layer(create_logger_middleware_http());
+        let request = Request::builder()
+            .uri("/api/v1/users/42/sessions/7")
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap();
+
+        let logs = logs.contents();
+        let template = "/api/v1/users/{id}/sessions/{session_id}";
+        assert!(
+            logs.contains(&format!("otel.name=GET {} ", template)),
+            "{}",
+            logs
+        );
+        assert!(logs.contains(&format!("route={}", template)), "{}", logs);
+    }
+
+    #[tokio::test]
+    async fn unmatched_requests_share_one_route_label() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route("/health", get(|| async { "OK" }))
+            .layer(create_logger_middleware_http());
+        let request = Request::builder()
+            .uri("/no/such/path/123")
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap();
+
+        let logs = logs.contents();
+        assert!(
+            logs.contains(&format!("route={}", UNMATCHED_ROUTE)),
+            "{}",
+            logs
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rcauth_core::error::{Error, ErrorCode};
+
+use crate::{ApiError, Maintenance};
+
+/// Seconds clients are told to wait, through `Retry-After`, before retrying during maintenance.
+pub const MAINTENANCE_RETRY_AFTER_SECS: u64 = 60;
+
+/// Short-circuits requests with `503 Service Unavailable` and a `Retry-After` header while maintenance mode is on.
+///
+/// Apply it with `route_layer` to the API routes that should go dark, leaving health checks outside so orchestrators don't restart the instance.
+///
+/// # Examples
+///
+/// ```ignore
+/// let routes = Router::new()
+///     .route("/token", post(token))
+///     .route_layer(from_fn_with_state(state.maintenance.clone(), reject_during_maintenance));
+/// ```
+pub async fn reject_during_maintenance(
+    State(maintenance): State<Maintenance>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !maintenance.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let mut response = ApiError(Error::new_simple(
+        ErrorCode::Unavailable,
+        "The service is down for maintenance",
+    ))
+    .into_response();
+    response.headers_mut().insert(
+        header::RETRY_AFTER,
+        HeaderValue::from(MAINTENANCE_RETRY_AFTER_SECS),
+    );
+    response
+}
@@ -1 +1,8 @@
+pub mod body_logger;
+pub mod catch_panic;
+pub mod content_type;
+pub mod csrf;
+pub mod idempotency;
 pub mod logger;
+pub mod maintenance;
+pub mod rate_limit;
@@ -0,0 +1,251 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rcauth_core::error::{Error, ErrorCode};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::{ApiError, Config, TrustedProxies};
+
+/// Bucket capacity, i.e. the requests a client may make in a burst.
+pub const X_RATELIMIT_LIMIT: &str = "x-ratelimit-limit";
+
+/// Requests the client may still make right away.
+pub const X_RATELIMIT_REMAINING: &str = "x-ratelimit-remaining";
+
+/// Seconds until the bucket is full again.
+pub const X_RATELIMIT_RESET: &str = "x-ratelimit-reset";
+
+/// Number of clients tracked before buckets that have refilled are dropped.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Per-client token buckets: each client may make `limit` requests in a burst, refilled evenly over `period`.
+///
+/// Clients are told where they stand through `X-RateLimit-*` headers on every limited response, so they can throttle themselves. Clients are identified by IP, resolved through the trusted proxies.
+///
+/// # Examples
+///
+/// ```ignore
+/// let limit = RateLimit::new(5, Duration::from_secs(60), proxies);
+/// let routes = Router::new()
+///     .route("/token", post(token))
+///     .route_layer(from_fn_with_state(limit, rate_limit));
+/// ```
+#[derive(Clone)]
+pub struct RateLimit {
+    limit: u32,
+    period: Duration,
+    proxies: TrustedProxies,
+    buckets: Arc<Mutex<HashMap<IpAddr, Bucket>>>,
+}
+
+/// Tokens left for one client as of `updated`.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Where a client stands after a request was counted, or refused.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Decision {
+    allowed: bool,
+    remaining: u32,
+    /// Seconds until the bucket is full
+    reset: u64,
+    /// Seconds until the next request would be allowed
+    retry_after: u64,
+}
+
+impl RateLimit {
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero or `period` is empty.
+    pub fn new(limit: u32, period: Duration, proxies: TrustedProxies) -> Self {
+        assert!(limit > 0, "rate limit must allow at least one request");
+        assert!(!period.is_zero(), "rate limit period must not be empty");
+        Self {
+            limit,
+            period,
+            proxies,
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Builds the limit of `POST /password/change` from `password_change_limit` and `password_change_period_secs`, identifying clients through the configured trusted proxies.
+    ///
+    /// It bounds how fast a stolen access token can be used to guess the user's current password.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if `trusted_proxies` is malformed.
+    pub fn password_change(config: &Config) -> rcauth_core::error::Result<Self> {
+        Ok(Self::new(
+            config.password_change_limit,
+            Duration::from_secs(config.password_change_period_secs),
+            TrustedProxies::from_config(config)?,
+        ))
+    }
+
+    /// Seconds it takes to refill one token.
+    fn seconds_per_token(&self) -> f64 {
+        self.period.as_secs_f64() / f64::from(self.limit)
+    }
+
+    /// Takes a token from `client`'s bucket if one is left.
+    fn check(&self, client: IpAddr, now: Instant) -> Decision {
+        let limit = f64::from(self.limit);
+        let seconds_per_token = self.seconds_per_token();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            // A full bucket carries no state, so forgetting it changes nothing for the client
+            buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() / seconds_per_token
+                    < limit
+            });
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: limit,
+            updated: now,
+        });
+        let refilled = now.duration_since(bucket.updated).as_secs_f64() / seconds_per_token;
+        bucket.tokens = (bucket.tokens + refilled).min(limit);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        Decision {
+            allowed,
+            remaining: bucket.tokens.floor() as u32,
+            reset: ((limit - bucket.tokens) * seconds_per_token).ceil() as u64,
+            retry_after: ((1.0 - bucket.tokens).max(0.0) * seconds_per_token).ceil() as u64,
+        }
+    }
+
+    /// Identifies the client by its IP, or groups clients together when the peer address is unknown.
+    fn client(&self, request: &Request) -> IpAddr {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |ConnectInfo(peer)| {
+                self.proxies.client_ip(request.headers(), peer.ip())
+            })
+    }
+}
+
+/// Counts the request against the client's bucket, answering `429 Too Many Requests` with `Retry-After` once it is empty.
+///
+/// Every response, refused or not, carries `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`.
+pub async fn rate_limit(State(limit): State<RateLimit>, request: Request, next: Next) -> Response {
+    let decision = limit.check(limit.client(&request), Instant::now());
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        let mut response = ApiError(Error::new_simple(
+            ErrorCode::TooManyRequests,
+            "Too many requests, retry later",
+        ))
+        .into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(decision.retry_after));
+        response
+    };
+
+    set_headers(response.headers_mut(), limit.limit, &decision);
+    response
+}
+
+fn set_headers(headers: &mut HeaderMap, limit: u32, decision: &Decision) {
+    headers.insert(X_RATELIMIT_LIMIT, HeaderValue::from(limit));
+    headers.insert(X_RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(X_RATELIMIT_RESET, HeaderValue::from(decision.reset));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        Router, body::Body, extract::connect_info::MockConnectInfo, http::StatusCode,
+        middleware::from_fn_with_state, routing::get,
+    };
+    use tower::ServiceExt;
+
+    /// Allows 3 requests a minute, i.e. one token every 20 seconds.
+    fn app() -> Router {
+        let limit = RateLimit::new(3, Duration::from_secs(60), TrustedProxies::default());
+        Router::new()
+            .route("/limited", get(|| async { "ok" }))
+            .route_layer(from_fn_with_state(limit, rate_limit))
+            .layer(MockConnectInfo(SocketAddr::from(([203, 0, 113, 7], 4000))))
+    }
+
+    async fn get_limited(app: &Router) -> Response {
+        let request = Request::get("/limited").body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    fn header_value(response: &Response, name: &str) -> u64 {
+        response.headers()[name].to_str().unwrap().parse().unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn headers_count_down_until_requests_are_refused() {
+        let app = app();
+
+        for remaining in [2, 1, 0] {
+            let response = get_limited(&app).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(header_value(&response, X_RATELIMIT_LIMIT), 3);
+            assert_eq!(header_value(&response, X_RATELIMIT_REMAINING), remaining);
+            // Each spent token takes 20 seconds to come back
+            assert_eq!(
+                header_value(&response, X_RATELIMIT_RESET),
+                20 * (3 - remaining)
+            );
+        }
+
+        let refused = get_limited(&app).await;
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header_value(&refused, X_RATELIMIT_REMAINING), 0);
+        assert_eq!(header_value(&refused, X_RATELIMIT_RESET), 60);
+        assert_eq!(header_value(&refused, header::RETRY_AFTER.as_str()), 20);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tokens_refill_over_the_period() {
+        let app = app();
+        for _ in 0..3 {
+            get_limited(&app).await;
+        }
+
+        tokio::time::advance(Duration::from_secs(25)).await;
+        let response = get_limited(&app).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header_value(&response, X_RATELIMIT_REMAINING), 0);
+        // A quarter of a token came back early, so the bucket fills 5 seconds sooner
+        assert_eq!(header_value(&response, X_RATELIMIT_RESET), 55);
+    }
+
+    #[test]
+    fn clients_have_separate_buckets() {
+        let limit = RateLimit::new(1, Duration::from_secs(60), TrustedProxies::default());
+        let now = Instant::now();
+
+        assert!(limit.check("198.51.100.1".parse().unwrap(), now).allowed);
+        assert!(!limit.check("198.51.100.1".parse().unwrap(), now).allowed);
+        assert!(limit.check("198.51.100.2".parse().unwrap(), now).allowed);
+    }
+}
@@ -2,8 +2,24 @@ mod api;
 mod management;
 mod middleware;
 
+pub use api::{PasswordDoc, UserInfoDoc};
+pub use management::{ConfigDoc, LoggingDoc, MaintenanceDoc, MetricsDoc, UsersDoc};
 pub use middleware::*;
 
+use crate::ApiError;
+use crate::config::AuthMethods;
+use crate::health::{CheckReport, CheckStatus, HealthChecks, HealthReport, HealthStatus};
+use crate::state::{AppState, Draining, Jwks, Migrations, Uptime};
+use axum::{
+    Router,
+    extract::State,
+    http::{Method, StatusCode, Uri},
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use rcauth_core::error::{Error, ErrorCode};
+use std::sync::LazyLock;
+
 #[utoipa::path(
     get,
     path = "/health",
@@ -16,9 +32,159 @@ pub async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Identifies the build that is serving requests.
+#[derive(Clone, Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: &'static str,
+    /// Git commit the binary was built from, or "unknown"
+    pub git_commit: &'static str,
+    /// Time of the build, as RFC 3339 or Unix seconds depending on `timestamp_format`
+    #[serde(with = "crate::timestamp")]
+    #[schema(value_type = String)]
+    pub build_timestamp: DateTime<Utc>,
+}
+
+/// Build information embedded at compile time.
+pub static BUILD_INFO: LazyLock<BuildInfo> = LazyLock::new(|| BuildInfo {
+    version: env!("CARGO_PKG_VERSION"),
+    git_commit: env!("RCAUTH_GIT_COMMIT"),
+    build_timestamp: DateTime::parse_from_rfc3339(env!("RCAUTH_BUILD_TIMESTAMP"))
+        .expect("build script emits an RFC 3339 timestamp")
+        .to_utc(),
+});
+
+#[utoipa::path(
+    get,
+    path = "/health/info",
+    responses(
+        (status = 200, description = "Build and version information", body = BuildInfo)
+    ),
+    tag = "Health"
+)]
+pub async fn health_info() -> axum::Json<BuildInfo> {
+    axum::Json(BUILD_INFO.clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/full",
+    responses(
+        (status = 200, description = "All required checks passed", body = HealthReport),
+        (status = 503, description = "A required check failed", body = HealthReport)
+    ),
+    tag = "Health"
+)]
+pub async fn health_full(
+    State(checks): State<HealthChecks>,
+    State(uptime): State<Uptime>,
+) -> (StatusCode, axum::Json<HealthReport>) {
+    let mut report = checks.run().await;
+    report.uptime_secs = Some(uptime.seconds());
+    (report_status(&report), axum::Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/ready",
+    responses(
+        (status = 200, description = "Ready to take traffic", body = HealthReport),
+        (status = 503, description = "Draining for shutdown, or a required check failed", body = HealthReport)
+    ),
+    tag = "Health"
+)]
+pub async fn ready(
+    State(checks): State<HealthChecks>,
+    State(draining): State<Draining>,
+) -> (StatusCode, axum::Json<HealthReport>) {
+    let mut report = checks.run().await;
+    // Load balancers stop routing here, while `/health` keeps the process alive to finish in-flight requests
+    if draining.is_draining() {
+        report.status = HealthStatus::Draining;
+    }
+    (report_status(&report), axum::Json(report))
+}
+
+/// Maps a report to the status probes act on: only unhealthy and draining servers fail.
+fn report_status(report: &HealthReport) -> StatusCode {
+    match report.status {
+        HealthStatus::Unhealthy | HealthStatus::Draining => StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Healthy | HealthStatus::Degraded => StatusCode::OK,
+    }
+}
+
+/// Schema version of the database, compared to the migrations shipped with the server.
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct DatabaseSchema {
+    /// Version of the latest applied migration, absent on an empty database
+    pub version: Option<i64>,
+    /// Description of the latest applied migration
+    pub description: Option<String>,
+    /// Shipped migrations that have not been applied
+    pub pending_migrations: u64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/db",
+    responses(
+        (status = 200, description = "Every shipped migration is applied", body = DatabaseSchema),
+        (status = 503, description = "Migrations are pending, or the database is unreachable", body = DatabaseSchema)
+    ),
+    tag = "Health"
+)]
+pub async fn health_db(
+    State(migrations): State<Migrations>,
+) -> Result<(StatusCode, axum::Json<DatabaseSchema>), ApiError> {
+    let status = migrations.check()?.migration_status().await.map_err(|e| {
+        Error::new(
+            ErrorCode::Unavailable,
+            "Could not read the applied migrations",
+            e,
+        )
+    })?;
+
+    // An out-of-date schema is as good as no database for the handlers that rely on it
+    let code = if status.is_up_to_date() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((
+        code,
+        axum::Json(DatabaseSchema {
+            version: status.version,
+            description: status.description,
+            pending_migrations: status.pending,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    responses(
+        (status = 200, description = "Public keys for verifying issued tokens, as a JSON Web Key Set")
+    ),
+    tag = "Keys"
+)]
+pub async fn jwks(State(jwks): State<Jwks>) -> axum::Json<jsonwebtoken::jwk::JwkSet> {
+    axum::Json(jwks.keys().clone())
+}
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(jwks),
+    tags(
+        (name = "Keys", description = "Token verification keys")
+    )
+)]
+pub struct KeysDoc;
+
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(health_check),
+    paths(health_check, health_info, health_full, health_db, ready),
+    components(schemas(HealthReport, CheckReport, CheckStatus, HealthStatus, DatabaseSchema)),
     tags(
         (name = "Health", description = "System health and status endpoints")
     ),
@@ -30,6 +196,478 @@ pub async fn health_check() -> &'static str {
 )]
 pub struct HealthCheckDoc;
 
-pub fn routes() -> axum::Router {
-    axum::Router::new().route("/health", axum::routing::get(health_check))
+/// Answers requests for unknown paths with a `NotFound` [`ErrorResponse`](rcauth_core::error::ErrorResponse) naming the method and path.
+pub async fn not_found(method: Method, uri: Uri) -> ApiError {
+    ApiError(unmatched(
+        ErrorCode::NotFound,
+        StatusCode::NOT_FOUND,
+        "No route matches the requested path",
+        &method,
+        &uri,
+    ))
+}
+
+/// Answers requests using a method the matched path does not support with `405 Method Not Allowed`.
+pub async fn method_not_allowed(method: Method, uri: Uri) -> ApiError {
+    ApiError(unmatched(
+        ErrorCode::Invalid,
+        StatusCode::METHOD_NOT_ALLOWED,
+        "Method not allowed for the requested path",
+        &method,
+        &uri,
+    ))
+}
+
+/// Builds the error for a request no handler accepted, with its method and path as details. The query is left out, as it may hold secrets.
+fn unmatched(
+    code: ErrorCode,
+    status: StatusCode,
+    message: &str,
+    method: &Method,
+    uri: &Uri,
+) -> Error {
+    Error::new_simple(code, message)
+        .with_status(status)
+        .with_data("method", serde_json::json!(method.as_str()))
+        .with_data("path", serde_json::json!(uri.path()))
+}
+
+/// Builds the health routes served by both servers, with the aggregated `/health/full` report backed by the state's checks, the schema version at `/health/db` and the `/ready` probe, which fails while draining for shutdown.
+fn health_routes() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/ready", get(ready))
+        .route("/health/info", get(health_info))
+        .route("/health/full", get(health_full))
+        .route("/health/db", get(health_db))
+}
+
+/// Every route speaks JSON; groups accepting other bodies get their own layer.
+fn json_only(routes: Router<AppState>) -> Router<AppState> {
+    routes.route_layer(axum::middleware::from_fn_with_state(
+        content_type::AcceptedContentTypes::json(),
+        content_type::require_content_type,
+    ))
+}
+
+/// Builds the API server's routes: the health checks, the published JWKS, `/userinfo` and the password change, which go dark with the rest of the API during maintenance.
+///
+/// Routes of the authentication methods disabled in `methods` are left out, so they answer `404 Not Found`. Mutating requests authenticated by the access token cookie must pass the CSRF check.
+pub fn api_routes(state: AppState, methods: &AuthMethods) -> Router {
+    let mut api = Router::new()
+        .route("/.well-known/jwks.json", get(jwks))
+        .route("/userinfo", get(api::userinfo));
+    if methods.password {
+        let mut change_password = post(api::change_password);
+        if let Some(idempotency) = state.idempotency.clone() {
+            change_password = change_password.layer(axum::middleware::from_fn_with_state(
+                idempotency,
+                idempotency::idempotent,
+            ));
+        }
+        // Outside the replay, so retries count against the limit too
+        if let Some(limit) = state.password_change_limit.clone() {
+            change_password = change_password.layer(axum::middleware::from_fn_with_state(
+                limit,
+                rate_limit::rate_limit,
+            ));
+        }
+        api = api.route("/password/change", change_password);
+    }
+    let api = api
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.token_cookies.clone(),
+            csrf::require_csrf_token,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.maintenance.clone(),
+            maintenance::reject_during_maintenance,
+        ));
+
+    json_only(health_routes().merge(api)).with_state(state)
+}
+
+/// Builds the management server's routes: the health checks, the maintenance-mode switch, the log level, the effective configuration, the metrics and account actions.
+pub fn management_routes(state: AppState) -> Router {
+    json_only(health_routes().merge(management::routes())).with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use rcauth_core::jwt::TokenOptions;
+    use rcauth_core::testing::jwt;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn health_info_reports_version() {
+        let request = Request::builder()
+            .uri("/health/info")
+            .body(Body::empty())
+            .unwrap();
+        let response = api_routes(AppState::default(), &AuthMethods::default())
+            .oneshot(request)
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let info: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert!(info["git_commit"].as_str().is_some_and(|c| !c.is_empty()));
+        assert!(info["build_timestamp"].as_str().is_some());
+    }
+
+    /// Stands in for the database ping.
+    struct PingCheck;
+
+    #[async_trait::async_trait]
+    impl rcauth_core::health::HealthCheck for PingCheck {
+        fn name(&self) -> &str {
+            "database"
+        }
+
+        async fn check(&self) -> rcauth_core::error::Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn health_full_report(app: &Router) -> serde_json::Value {
+        let request = Request::builder()
+            .uri("/health/full")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn health_full_reports_uptime_and_database_latency() {
+        let app = api_routes(
+            AppState {
+                checks: HealthChecks::default().register(PingCheck),
+                ..AppState::default()
+            },
+            &AuthMethods::default(),
+        );
+
+        let first = health_full_report(&app).await;
+        tokio::time::advance(std::time::Duration::from_secs(5)).await;
+        let second = health_full_report(&app).await;
+
+        let first_uptime = first["uptime_secs"].as_u64().unwrap();
+        assert!(second["uptime_secs"].as_u64().unwrap() >= first_uptime + 5);
+        assert!(second["checks"]["database"]["latency_ms"].is_u64());
+    }
+
+    /// Reports a database that applied migrations up to 2 of `2 + pending`.
+    struct AppliedMigrations {
+        pending: u64,
+    }
+
+    #[async_trait::async_trait]
+    impl rcauth_core::health::MigrationCheck for AppliedMigrations {
+        async fn migration_status(
+            &self,
+        ) -> rcauth_core::error::Result<rcauth_core::health::MigrationStatus> {
+            Ok(rcauth_core::health::MigrationStatus {
+                version: Some(2),
+                description: Some("init".to_string()),
+                pending: self.pending,
+            })
+        }
+    }
+
+    async fn health_db(pending: u64) -> (StatusCode, serde_json::Value) {
+        let app = api_routes(
+            AppState {
+                migrations: Migrations::new(AppliedMigrations { pending }),
+                ..AppState::default()
+            },
+            &AuthMethods::default(),
+        );
+        let response = send(&app, "GET", "/health/db").await;
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn health_db_reports_an_up_to_date_schema() {
+        let (status, schema) = health_db(0).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(schema["version"], 2);
+        assert_eq!(schema["description"], "init");
+        assert_eq!(schema["pending_migrations"], 0);
+    }
+
+    #[tokio::test]
+    async fn health_db_is_unavailable_while_migrations_are_pending() {
+        let (status, schema) = health_db(3).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(schema["version"], 2);
+        assert_eq!(schema["pending_migrations"], 3);
+    }
+
+    #[tokio::test]
+    async fn jwks_publishes_configured_keys() {
+        let key: jsonwebtoken::jwk::Jwk = serde_json::from_value(serde_json::json!({
+            "kty": "RSA",
+            "kid": "2024-06",
+            "use": "sig",
+            "alg": "RS256",
+            "n": "sXch",
+            "e": "AQAB"
+        }))
+        .unwrap();
+        let state = AppState {
+            jwks: jsonwebtoken::jwk::JwkSet { keys: vec![key] }.into(),
+            ..AppState::default()
+        };
+
+        let request = Request::builder()
+            .uri("/.well-known/jwks.json")
+            .body(Body::empty())
+            .unwrap();
+        let response = api_routes(state, &AuthMethods::default())
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let set: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(set["keys"][0]["kid"], "2024-06");
+        assert_eq!(set["keys"].as_array().unwrap().len(), 1);
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str) -> axum::response::Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn maintenance_mode_takes_api_routes_down_until_turned_off() {
+        let state = AppState::default();
+        let api = api_routes(state.clone(), &AuthMethods::default());
+        let management = management_routes(state);
+
+        assert_eq!(
+            send(&api, "GET", "/.well-known/jwks.json").await.status(),
+            StatusCode::OK
+        );
+
+        let on = send(&management, "POST", "/maintenance/on").await;
+        assert_eq!(on.status(), StatusCode::OK);
+
+        let response = send(&api, "GET", "/.well-known/jwks.json").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            response.headers()[axum::http::header::RETRY_AFTER],
+            maintenance::MAINTENANCE_RETRY_AFTER_SECS.to_string()
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "unavailable");
+
+        // Health checks stay up so orchestrators keep the instance
+        assert_eq!(send(&api, "GET", "/health").await.status(), StatusCode::OK);
+
+        send(&management, "POST", "/maintenance/off").await;
+        assert_eq!(
+            send(&api, "GET", "/.well-known/jwks.json").await.status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn draining_fails_readiness_but_not_liveness() {
+        let state = AppState {
+            checks: HealthChecks::default().register(PingCheck),
+            ..AppState::default()
+        };
+        let app = api_routes(state.clone(), &AuthMethods::default());
+        assert_eq!(send(&app, "GET", "/ready").await.status(), StatusCode::OK);
+
+        state.draining.start();
+        let response = send(&app, "GET", "/ready").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["status"], "draining");
+        // The database is still fine; only new traffic is turned away
+        assert_eq!(report["checks"]["database"]["status"], "healthy");
+
+        assert_eq!(send(&app, "GET", "/health").await.status(), StatusCode::OK);
+    }
+
+    /// Sets the log level, as the holder of `token` if given.
+    async fn put_log_level(
+        app: &Router,
+        level: &str,
+        token: Option<&str>,
+    ) -> axum::response::Response {
+        let mut request = Request::builder()
+            .method("PUT")
+            .uri("/log-level")
+            .header(axum::http::header::CONTENT_TYPE, "application/json");
+        if let Some(token) = token {
+            request = request.header(
+                axum::http::header::AUTHORIZATION,
+                format!("Bearer {}", token),
+            );
+        }
+        let request = request
+            .body(Body::from(
+                serde_json::json!({ "level": level }).to_string(),
+            ))
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    /// Token of an admin allowed to change the log level.
+    fn logging_admin() -> String {
+        jwt()
+            .issue(
+                "admin-1",
+                TokenOptions::default().scopes(&[management::LOGGING_WRITE_SCOPE]),
+            )
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn log_level_can_be_changed_at_runtime() {
+        // The filter handle only works while its subscriber is alive
+        let (_subscriber, guard) = rcauth_core::logger::Config::default().subscriber().unwrap();
+        let management = management_routes(AppState {
+            jwt: Some(jwt()),
+            log_filter: Some(guard.filter()),
+            ..AppState::default()
+        });
+        let admin = logging_admin();
+
+        let response = put_log_level(&management, "debug", Some(&admin)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["filter"], "debug");
+        assert_eq!(guard.filter().current().unwrap(), "debug");
+
+        let response = put_log_level(&management, "loud", Some(&admin)).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(guard.filter().current().unwrap(), "debug");
+    }
+
+    #[tokio::test]
+    async fn log_level_is_refused_without_the_scope() {
+        let (_subscriber, guard) = rcauth_core::logger::Config::default().subscriber().unwrap();
+        let management = management_routes(AppState {
+            jwt: Some(jwt()),
+            log_filter: Some(guard.filter()),
+            ..AppState::default()
+        });
+        let before = guard.filter().current().unwrap();
+
+        let response = put_log_level(&management, "trace", None).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let plain = jwt().issue("admin-1", TokenOptions::default()).unwrap();
+        let response = put_log_level(&management, "trace", Some(&plain)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(guard.filter().current().unwrap(), before);
+    }
+
+    #[tokio::test]
+    async fn log_level_is_unavailable_without_a_filter() {
+        let management = management_routes(AppState {
+            jwt: Some(jwt()),
+            ..AppState::default()
+        });
+        let response = put_log_level(&management, "debug", Some(&logging_admin())).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    /// Fetches the effective configuration, as the holder of `token` if given.
+    async fn get_config(app: &Router, token: Option<&str>) -> axum::response::Response {
+        let mut request = Request::get("/config");
+        if let Some(token) = token {
+            request = request.header(
+                axum::http::header::AUTHORIZATION,
+                format!("Bearer {}", token),
+            );
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn config_is_served_with_secrets_masked() {
+        let management = management_routes(AppState {
+            jwt: Some(jwt()),
+            config: crate::EffectiveConfig::new(serde_json::json!({
+                "server": { "api_server_port": { "value": 8000, "source": "file" } },
+                "store": {
+                    "host": { "value": "db.internal", "source": "env" },
+                    "password": { "value": "hunter2", "source": "env" }
+                }
+            })),
+            ..AppState::default()
+        });
+
+        let admin = jwt()
+            .issue(
+                "admin-1",
+                TokenOptions::default().scopes(&[management::CONFIG_READ_SCOPE]),
+            )
+            .unwrap();
+        let response = get_config(&management, Some(&admin)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let config: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(config["server"]["api_server_port"]["value"], 8000);
+        assert_eq!(config["store"]["host"]["value"], "db.internal");
+        assert_eq!(config["store"]["password"]["value"], "***");
+        assert_eq!(config["store"]["password"]["source"], "env");
+        assert!(!String::from_utf8_lossy(&body).contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn config_is_refused_without_the_scope() {
+        let management = management_routes(AppState {
+            jwt: Some(jwt()),
+            ..AppState::default()
+        });
+
+        let response = get_config(&management, None).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let plain = jwt().issue("admin-1", TokenOptions::default()).unwrap();
+        let response = get_config(&management, Some(&plain)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
 }
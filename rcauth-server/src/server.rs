@@ -1,21 +1,261 @@
-use crate::routes::{logger, HealthCheckDoc};
+use crate::routes::{
+    ConfigDoc, HealthCheckDoc, KeysDoc, LoggingDoc, MaintenanceDoc, MetricsDoc, PasswordDoc,
+    UserInfoDoc, UsersDoc, body_logger, catch_panic, logger, rate_limit,
+};
 use axum::Router;
+use axum::http::{HeaderName, header};
 use std::error::Error;
 use tracing::{info, warn};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+use socket2::{Domain, Protocol, Socket, TcpKeepalive, Type};
 use std::net::SocketAddr;
 use std::str::FromStr;
-use tower_http::cors::{Any, CorsLayer};
-use utoipa::openapi::{Info, Paths};
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, Any, CorsLayer};
+use utoipa::openapi::{ContactBuilder, Info, InfoBuilder, Paths, Server};
 
-use crate::Config;
+use crate::{AppState, Config, CorsOrigins, CorsSettings};
+
+/// Builds the CORS layer for the named server from its settings.
+///
+/// # Errors
+///
+/// Returns a `ConfigurationError` if an allowed origin is malformed.
+fn cors_layer(settings: &CorsSettings, server: &str) -> Result<CorsLayer, Box<dyn Error>> {
+    let cors = match settings.origins()? {
+        CorsOrigins::Any => {
+            warn!(
+                "CORS is configured to allow any origin for {} server. This is not recommended for production.",
+                server
+            );
+            CorsLayer::new().allow_origin(Any)
+        }
+        CorsOrigins::List(origins) => CorsLayer::new().allow_origin(origins),
+    };
+
+    // Credentialed responses may not use wildcards, so mirror the preflight request instead
+    let cors = if settings.allow_credentials {
+        cors.allow_credentials(true)
+            .allow_methods(AllowMethods::mirror_request())
+            .allow_headers(AllowHeaders::mirror_request())
+            .expose_headers([
+                HeaderName::from_static(rate_limit::X_RATELIMIT_LIMIT),
+                HeaderName::from_static(rate_limit::X_RATELIMIT_REMAINING),
+                HeaderName::from_static(rate_limit::X_RATELIMIT_RESET),
+                header::RETRY_AFTER,
+            ])
+    } else {
+        cors.allow_methods(Any)
+            .allow_headers(Any)
+            .expose_headers(Any)
+    };
+
+    Ok(match settings.max_age_secs {
+        Some(secs) => cors.max_age(Duration::from_secs(secs)),
+        None => cors,
+    })
+}
+
+/// Builds the OpenAPI info block from the configured metadata, falling back to `default_title` and version `0.0.1`.
+///
+/// A configured title is used as-is for the API server and gets a " Management" suffix for the management server, so the two documents stay distinguishable.
+fn openapi_info(config: &Config, default_title: &str, title_suffix: &str) -> Info {
+    let title = match &config.openapi_title {
+        Some(title) => format!("{}{}", title, title_suffix),
+        None => default_title.to_string(),
+    };
+
+    let contact = (config.openapi_contact_name.is_some()
+        || config.openapi_contact_email.is_some()
+        || config.openapi_contact_url.is_some())
+    .then(|| {
+        ContactBuilder::new()
+            .name(config.openapi_contact_name.clone())
+            .email(config.openapi_contact_email.clone())
+            .url(config.openapi_contact_url.clone())
+            .build()
+    });
+
+    InfoBuilder::new()
+        .title(title)
+        .version(config.openapi_version.as_deref().unwrap_or("0.0.1"))
+        .description(config.openapi_description.clone())
+        .contact(contact)
+        .build()
+}
+
+/// Pending connections the kernel queues per listener, matching tokio's default.
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Binds a listener on `addr` with the configured `tcp_nodelay` and `tcp_keepalive_secs` applied.
+///
+/// The options are set on the listening socket before binding. Linux copies them to every accepted connection; other platforms may not.
+///
+/// # Errors
+///
+/// Returns an error if the socket cannot be created, configured, or bound.
+///
+/// # Examples
+///
+/// ```ignore
+/// let config = ConfigBuilder::default().tcp_nodelay(true).build()?;
+/// let listener = bind_listener(&config, "127.0.0.1:0".parse()?)?;
+/// ```
+fn bind_listener(config: &Config, addr: SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    // Like tokio's bind, so restarts don't wait for TIME_WAIT connections to expire
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_tcp_nodelay(config.tcp_nodelay)?;
+    if let Some(secs) = config.tcp_keepalive_secs {
+        let period = Duration::from_secs(secs);
+        let keepalive = TcpKeepalive::new().with_time(period);
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        let keepalive = keepalive.with_interval(period);
+        socket.set_tcp_keepalive(&keepalive)?;
+    }
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+
+    tokio::net::TcpListener::from_std(socket.into())
+}
+
+/// Builds a server's router: its `routes` nested under `base_path`, optional Swagger UI documenting them with `docs`, JSON 404 and 405 fallbacks, error bodies in the configured envelope, CORS with the server's `cors` settings, optional body logging, panic recovery, request logging, and W3C trace context propagation.
+fn build_router(
+    config: &Config,
+    server: &str,
+    info: Info,
+    base_path: &str,
+    routes: Router,
+    docs: utoipa::openapi::OpenApi,
+    cors: CorsSettings,
+) -> Result<Router, Box<dyn Error>> {
+    let mut app = Router::new().nest(base_path, routes);
+
+    // Setup OpenAPI documentation if enabled
+    if config.enable_swagger {
+        info!(
+            "Enabling Swagger UI for {} server at /swagger-ui and OpenAPI docs at /api-docs/openapi.json",
+            server
+        );
+        let mut openapi = utoipa::openapi::OpenApi::new(info, Paths::new());
+        openapi.merge(docs);
+        openapi.servers = Some(vec![Server::new(base_path)]);
+
+        app = app.merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", openapi));
+    }
+
+    // Answer unknown paths and unsupported methods with JSON errors, like every other failure
+    app = app
+        .fallback(crate::routes::not_found)
+        .method_not_allowed_fallback(crate::routes::method_not_allowed);
+
+    // Timestamp fields read the format while handlers serialize their responses
+    app = app.layer(axum::middleware::from_fn_with_state(
+        config.timestamp_format,
+        crate::timestamp::timestamp_format,
+    ));
+
+    // Apply CORS after the routes are registered so it covers all of them
+    if config.enable_cors {
+        app = app.layer(cors_layer(&cors, server)?);
+    }
+
+    // Only install body logging when asked for, so bodies are never buffered otherwise
+    if config.debug_log_bodies {
+        warn!(
+            "Request and response body logging is enabled for {} server. Disable it once debugging is done.",
+            server
+        );
+        app = app.layer(axum::middleware::from_fn(body_logger::log_bodies));
+    }
+
+    // Inside the request logger, so panics are logged within the request's span
+    app = app.layer(catch_panic::create_catch_panic_layer());
+
+    // Outside panic recovery, so every error body the server builds picks up the envelope
+    app = app.layer(axum::middleware::from_fn_with_state(
+        config.error_envelope,
+        crate::error::error_envelope,
+    ));
+
+    app = app.layer(logger::create_logger_middleware_http());
+
+    // Outside the request logger, so its span records the trace id
+    Ok(app.layer(axum::middleware::from_fn(
+        crate::trace_context::propagate_trace_context,
+    )))
+}
+
+/// Builds the API server's router with its routes nested under `api_base_path`, serving `state` to its handlers.
+///
+/// # Errors
+///
+/// Returns an error if the CORS configuration is invalid.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::{Config, AppState, api_router};
+/// let router = api_router(&Config::default(), AppState::default()).unwrap();
+/// ```
+pub fn api_router(config: &Config, state: AppState) -> Result<Router, Box<dyn Error>> {
+    let info = openapi_info(config, "RCAuth API", "");
+    let mut docs = HealthCheckDoc::openapi();
+    docs.merge(KeysDoc::openapi());
+    docs.merge(UserInfoDoc::openapi());
+    if config.auth_methods.password {
+        docs.merge(PasswordDoc::openapi());
+    }
+    build_router(
+        config,
+        "api",
+        info,
+        &config.api_base_path,
+        crate::routes::api_routes(state, &config.auth_methods),
+        docs,
+        config.api_cors(),
+    )
+}
+
+/// Builds the management server's router with its routes nested under `management_base_path`, serving `state` to its handlers.
+///
+/// # Errors
+///
+/// Returns an error if the CORS configuration is invalid.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::{Config, AppState, management_router};
+/// let router = management_router(&Config::default(), AppState::default()).unwrap();
+/// ```
+pub fn management_router(config: &Config, state: AppState) -> Result<Router, Box<dyn Error>> {
+    let info = openapi_info(config, "RCAuth Management API", " Management");
+    let mut docs = HealthCheckDoc::openapi();
+    docs.merge(MaintenanceDoc::openapi());
+    docs.merge(LoggingDoc::openapi());
+    docs.merge(ConfigDoc::openapi());
+    docs.merge(UsersDoc::openapi());
+    docs.merge(MetricsDoc::openapi());
+    build_router(
+        config,
+        "management",
+        info,
+        &config.management_base_path,
+        crate::routes::management_routes(state),
+        docs,
+        config.management_cors(),
+    )
+}
 
 /// Starts the main API HTTP server with configured routes, CORS, and optional Swagger UI documentation.
 ///
-/// Validates the provided configuration, applies CORS settings if enabled, and sets up API routes under `/api/v1`.
-/// If Swagger UI is enabled, serves OpenAPI documentation at `/swagger-ui` and `/api/v1/api-docs/openapi.json`.
+/// Validates the provided configuration, applies CORS settings if enabled, and sets up API routes under `api_base_path` (`/api/v1` by default).
+/// If Swagger UI is enabled, serves it at `/swagger-ui` with the OpenAPI document at `/api-docs/openapi.json`, pointing its server URL at the base path.
 /// Binds to the address specified in the configuration and serves requests asynchronously.
 ///
 /// # Errors
@@ -25,79 +265,37 @@ use crate::Config;
 /// # Examples
 ///
 /// ```no_run
+/// # use rcauth_server::{Config, AppState, run_api_server};
 /// let config = Config::default();
 /// tokio::runtime::Runtime::new().unwrap().block_on(async {
-///     run_api_server(&config).await.unwrap();
+///     run_api_server(&config, AppState::default()).await.unwrap();
 /// });
 /// ```
-pub async fn run_api_server(config: &Config) -> Result<(), Box<dyn Error>> {
+pub async fn run_api_server(config: &Config, state: AppState) -> Result<(), Box<dyn Error>> {
     if let Err(err) = config.validate() {
         return Err(format!("Invalid API server configuration: {}", err).into());
     }
 
-    let mut app = axum::Router::new();
-
-    if config.enable_cors {
-        let cors = if config.cors_allowed_origins.contains(&"*".to_string()) {
-            warn!(
-                "CORS is configured to allow any origin for api server. This is not recommended for production."
-            );
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any)
-                .expose_headers(Any)
-        } else {
-            let origins = config
-                .cors_allowed_origins
-                .iter()
-                .map(|origin| origin.parse().expect("Invalid CORS origin"))
-                .collect::<Vec<_>>();
-
-            CorsLayer::new()
-                .allow_origin(origins)
-                .allow_methods(Any)
-                .allow_headers(Any)
-                .expose_headers(Any)
-        };
-
-        app = app.layer(cors);
-    }
-
-    // Setup OpenAPI documentation if enabled
-    let mut app = if config.enable_swagger {
-        info!(
-            "Enabling Swagger UI at /swagger-ui and OpenAPI docs at /api/v1/api-docs/openapi.json"
-        );
-        let mut openapi =
-            utoipa::openapi::OpenApi::new(Info::new("RCAuth API", "0.0.1"), Paths::new());
-
-        openapi.merge(HealthCheckDoc::openapi());
-
-        app.merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", openapi))
-    } else {
-        app
-    };
-
-    let routes = Router::new().merge(crate::routes::routes());
-    app = app
-        .nest("/api/v1", routes)
-        .layer(logger::create_logger_middleware_http());
+    let app = api_router(config, state)?;
 
     let addr = config.api_addr();
     let socket_addr = SocketAddr::from_str(&addr).expect("Invalid address");
 
     info!(addr = %addr, "🚀 Starting API server");
 
-    let listener = tokio::net::TcpListener::bind(socket_addr).await?;
-    axum::serve(listener, app).await?;
+    let listener = bind_listener(config, socket_addr)?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
 /// Starts the management HTTP server with the specified configuration.
 ///
-/// Validates the configuration, sets up CORS and optional Swagger UI documentation, nests management routes under `/management/v1`, and serves requests on the configured address.
+/// Validates the configuration, sets up CORS and optional Swagger UI documentation, nests management routes under `management_base_path` (`/management/v1` by default), and serves requests on the configured address.
 ///
 /// # Errors
 ///
@@ -106,72 +304,344 @@ pub async fn run_api_server(config: &Config) -> Result<(), Box<dyn Error>> {
 /// # Examples
 ///
 /// ```no_run
+/// # use rcauth_server::{Config, AppState, run_management_server};
 /// let config = Config::default();
 /// tokio::spawn(async move {
-///     run_management_server(&config).await.unwrap();
+///     run_management_server(&config, AppState::default()).await.unwrap();
 /// });
 /// ```
-pub async fn run_management_server(config: &Config) -> Result<(), Box<dyn Error>> {
+pub async fn run_management_server(config: &Config, state: AppState) -> Result<(), Box<dyn Error>> {
     if let Err(err) = config.validate() {
-        return Err(format!("Invalid API server configuration: {}", err).into());
+        return Err(format!("Invalid management server configuration: {}", err).into());
     }
 
-    let mut app = axum::Router::new();
+    let app = management_router(config, state)?;
 
-    if config.enable_cors {
-        let cors = if config.cors_allowed_origins.contains(&"*".to_string()) {
-            warn!(
-                "CORS is configured to allow any origin for management server. This is not recommended for production."
-            );
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any)
-                .expose_headers(Any)
-        } else {
-            let origins = config
-                .cors_allowed_origins
-                .iter()
-                .map(|origin| origin.parse().expect("Invalid CORS origin"))
-                .collect::<Vec<_>>();
-
-            CorsLayer::new()
-                .allow_origin(origins)
-                .allow_methods(Any)
-                .allow_headers(Any)
-                .expose_headers(Any)
-        };
+    let addr = config.management_addr();
+    let socket_addr = SocketAddr::from_str(&addr).expect("Invalid address");
+
+    info!(addr = %addr, "🚀 Starting management server");
+
+    let listener = bind_listener(config, socket_addr)?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
-        app = app.layer(cors);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuthMethods, ConfigBuilder};
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode, header},
+    };
+    use tower::ServiceExt;
+
+    fn preflight() -> Request<Body> {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/health")
+            .header(header::ORIGIN, "https://example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap()
     }
 
-    // Setup OpenAPI documentation if enabled
+    #[tokio::test]
+    async fn preflight_sets_max_age_and_credentials() {
+        let config = crate::ConfigBuilder::default()
+            .cors_allowed_origins(vec!["https://example.com"])
+            .cors_max_age_secs(600)
+            .cors_allow_credentials(true)
+            .build()
+            .unwrap();
+        let app = crate::routes::api_routes(AppState::default(), &AuthMethods::default())
+            .layer(cors_layer(&config.api_cors(), "api").unwrap());
+
+        let response = app.oneshot(preflight()).await.unwrap();
+        let headers = response.headers();
 
-    let mut app = if config.enable_swagger {
-        let mut openapi = utoipa::openapi::OpenApi::new(
-            Info::new("RCAuth Management API", "0.0.1"),
-            Paths::new(),
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://example.com"
         );
+    }
 
-        openapi.merge(HealthCheckDoc::openapi());
+    #[tokio::test]
+    async fn preflight_omits_max_age_by_default() {
+        let app = crate::routes::api_routes(AppState::default(), &AuthMethods::default())
+            .layer(cors_layer(&Config::default().api_cors(), "api").unwrap());
 
-        app.merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", openapi))
-    } else {
-        app
-    };
+        let response = app.oneshot(preflight()).await.unwrap();
+        let headers = response.headers();
 
-    let routes = Router::new().merge(crate::routes::routes());
-    app = app
-        .nest("/management/v1", routes)
-        .layer(logger::create_logger_middleware_http());
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_MAX_AGE));
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    }
 
-    let addr = config.management_addr();
-    let socket_addr = SocketAddr::from_str(&addr).expect("Invalid address");
+    #[tokio::test]
+    async fn servers_enforce_their_own_allowed_origins() {
+        let config = crate::ConfigBuilder::default()
+            .cors_allowed_origins(vec!["https://app.example.com"])
+            .management_cors_allowed_origins(vec!["https://admin.example.com"])
+            .build()
+            .unwrap();
+        let api = api_router(&config, AppState::default()).unwrap();
+        let management = management_router(&config, AppState::default()).unwrap();
+        let preflight = |origin: &str, path: &str| {
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri(path)
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let allowed = |response: axum::response::Response| {
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .cloned()
+        };
 
-    info!(addr = %addr, "🚀 Starting API server");
+        let customer = "https://app.example.com";
+        let admin = "https://admin.example.com";
+        let response = api
+            .clone()
+            .oneshot(preflight(customer, "/api/v1/health"))
+            .await
+            .unwrap();
+        assert_eq!(allowed(response).unwrap(), customer);
+        let response = api
+            .oneshot(preflight(admin, "/api/v1/health"))
+            .await
+            .unwrap();
+        assert!(allowed(response).is_none());
 
-    let listener = tokio::net::TcpListener::bind(socket_addr).await?;
-    axum::serve(listener, app).await?;
+        let response = management
+            .clone()
+            .oneshot(preflight(admin, "/management/v1/health"))
+            .await
+            .unwrap();
+        assert_eq!(allowed(response).unwrap(), admin);
+        let response = management
+            .oneshot(preflight(customer, "/management/v1/health"))
+            .await
+            .unwrap();
+        assert!(allowed(response).is_none());
+    }
 
-    Ok(())
+    #[tokio::test]
+    async fn timestamps_follow_the_configured_format() {
+        let config = crate::ConfigBuilder::default()
+            .timestamp_format(crate::TimestampFormat::Epoch)
+            .build()
+            .unwrap();
+        let request = Request::builder()
+            .uri("/api/v1/health/info")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = api_router(&config, AppState::default())
+            .unwrap()
+            .oneshot(request)
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let info: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(info["build_timestamp"].as_i64().is_some());
+    }
+
+    #[tokio::test]
+    async fn errors_follow_the_configured_envelope() {
+        let config = crate::ConfigBuilder::default()
+            .error_envelope(crate::ErrorEnvelope::Wrapped)
+            .build()
+            .unwrap();
+        let request = Request::builder()
+            .uri("/api/v1/no-such-route")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = api_router(&config, AppState::default())
+            .unwrap()
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"]["code"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn routes_are_served_under_custom_base_path() {
+        let config = crate::ConfigBuilder::default()
+            .api_base_path("/auth/v1")
+            .build()
+            .unwrap();
+        let app = api_router(&config, AppState::default()).unwrap();
+
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app.clone().oneshot(get("/auth/v1/health")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(get("/api/v1/health")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    async fn openapi_document(app: Router) -> serde_json::Value {
+        let request = Request::builder()
+            .uri("/api-docs/openapi.json")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn openapi_document_reflects_configured_info() {
+        let config = crate::ConfigBuilder::default()
+            .openapi_title("Acme Auth")
+            .openapi_version("2.1.0")
+            .openapi_contact_email("platform@example.com")
+            .build()
+            .unwrap();
+
+        let doc = openapi_document(api_router(&config, AppState::default()).unwrap()).await;
+        assert_eq!(doc["info"]["title"], "Acme Auth");
+        assert_eq!(doc["info"]["version"], "2.1.0");
+        assert_eq!(doc["info"]["contact"]["email"], "platform@example.com");
+
+        let doc = openapi_document(management_router(&config, AppState::default()).unwrap()).await;
+        assert_eq!(doc["info"]["title"], "Acme Auth Management");
+    }
+
+    #[tokio::test]
+    async fn openapi_document_falls_back_to_defaults() {
+        let doc =
+            openapi_document(api_router(&Config::default(), AppState::default()).unwrap()).await;
+        assert_eq!(doc["info"]["title"], "RCAuth API");
+        assert_eq!(doc["info"]["version"], "0.0.1");
+        assert!(doc["info"].get("contact").is_none());
+    }
+
+    #[tokio::test]
+    async fn listener_applies_configured_socket_options() {
+        let config = ConfigBuilder::default()
+            .tcp_nodelay(true)
+            .tcp_keepalive_secs(30)
+            .build()
+            .unwrap();
+        let listener = bind_listener(&config, "127.0.0.1:0".parse().unwrap()).unwrap();
+
+        let socket = socket2::SockRef::from(&listener);
+        assert!(socket.tcp_nodelay().unwrap());
+        assert!(socket.keepalive().unwrap());
+
+        let defaults = bind_listener(&Config::default(), "127.0.0.1:0".parse().unwrap()).unwrap();
+        assert!(!socket2::SockRef::from(&defaults).tcp_nodelay().unwrap());
+    }
+
+    /// Accepted sockets only inherit the listener's options on Linux.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn accepted_connections_inherit_nodelay() {
+        let config = ConfigBuilder::default().tcp_nodelay(true).build().unwrap();
+        let listener = bind_listener(&config, "127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let _client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+        assert!(accepted.nodelay().unwrap());
+    }
+
+    async fn error_body(response: axum::response::Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn unknown_path_returns_json_not_found() {
+        let app = api_router(&Config::default(), AppState::default()).unwrap();
+        let request = Request::builder()
+            .uri("/api/v1/nope?token=secret")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = error_body(response).await;
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["details"]["method"], "GET");
+        assert_eq!(body["details"]["path"], "/api/v1/nope");
+    }
+
+    #[tokio::test]
+    async fn wrong_method_returns_json_method_not_allowed() {
+        let app = management_router(&Config::default(), AppState::default()).unwrap();
+        let request = Request::builder()
+            .method(Method::DELETE)
+            .uri("/management/v1/health")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let body = error_body(response).await;
+        assert_eq!(body["details"]["method"], "DELETE");
+        assert_eq!(body["details"]["path"], "/management/v1/health");
+    }
+
+    #[tokio::test]
+    async fn disabled_auth_method_routes_are_not_served_or_documented() {
+        let change_password = || {
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/password/change")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+
+        let app = api_router(&Config::default(), AppState::default()).unwrap();
+        let doc = openapi_document(app.clone()).await;
+        assert!(doc["paths"]["/password/change"].is_object());
+        let response = app.oneshot(change_password()).await.unwrap();
+        assert_ne!(response.status(), StatusCode::NOT_FOUND);
+
+        let config = ConfigBuilder::default()
+            .auth_methods(AuthMethods {
+                password: false,
+                ..AuthMethods::default()
+            })
+            .build()
+            .unwrap();
+        let app = api_router(&config, AppState::default()).unwrap();
+        let doc = openapi_document(app.clone()).await;
+        assert!(doc["paths"].get("/password/change").is_none());
+        let response = app.oneshot(change_password()).await.unwrap();
+        // Absent rather than refused, as if the method did not exist
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(error_body(response).await["code"], "not_found");
+    }
 }
@@ -0,0 +1,513 @@
+use axum::extract::FromRef;
+use jsonwebtoken::jwk::JwkSet;
+use rcauth_core::audit::AuditLog;
+use rcauth_core::error::{Error, ErrorCode};
+use rcauth_core::health::MigrationCheck;
+use rcauth_core::jwt::Jwt;
+use rcauth_core::logger::{LogFilter, redact_field};
+use rcauth_core::password::Hasher;
+use rcauth_core::settings::{self, SettingsRepository};
+use rcauth_core::user::UserRepository;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::health::HealthChecks;
+use crate::metrics::ServerMetrics;
+use crate::routes::idempotency::Idempotency;
+use crate::routes::rate_limit::RateLimit;
+use crate::token_binding::TokenBinding;
+use crate::token_cookies::TokenCookies;
+
+/// Public signing keys served at `/.well-known/jwks.json`.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Jwks;
+/// let jwks = Jwks::default();
+/// assert!(jwks.keys().keys.is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Jwks(Arc<JwkSet>);
+
+impl Jwks {
+    pub fn keys(&self) -> &JwkSet {
+        &self.0
+    }
+}
+
+impl Default for Jwks {
+    /// Returns an empty key set, as published when only shared-secret keys are configured.
+    fn default() -> Self {
+        Self::from(JwkSet { keys: Vec::new() })
+    }
+}
+
+impl From<JwkSet> for Jwks {
+    fn from(set: JwkSet) -> Self {
+        Self(Arc::new(set))
+    }
+}
+
+/// Maintenance-mode switch, shared by both servers: the management server flips it and the API server honours it.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Maintenance;
+/// let maintenance = Maintenance::default();
+/// let api_view = maintenance.clone();
+/// maintenance.enable();
+/// assert!(api_view.is_enabled());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Maintenance(Arc<AtomicBool>);
+
+impl Maintenance {
+    pub fn enable(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn disable(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Shutdown flag, shared by both servers: set once shutdown begins, it fails `/ready` so load balancers drain traffic, while `/health` keeps passing so the process is not killed early.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Draining;
+/// let draining = Draining::default();
+/// let api_view = draining.clone();
+/// draining.start();
+/// assert!(api_view.is_draining());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Draining(Arc<AtomicBool>);
+
+impl Draining {
+    pub fn start(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// User accounts, backed by the store: profiles served at `/userinfo` and the account actions of the management server.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Users;
+/// let users = Users::default();
+/// assert!(!users.is_configured());
+/// ```
+#[derive(Clone, Default)]
+pub struct Users(Option<Arc<dyn UserRepository>>);
+
+impl Users {
+    pub fn new(repository: impl UserRepository + 'static) -> Self {
+        Self(Some(Arc::new(repository)))
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Returns the repository backing the accounts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Unavailable` error if no store was configured.
+    pub fn repository(&self) -> rcauth_core::error::Result<&dyn UserRepository> {
+        self.0.as_deref().ok_or_else(|| {
+            Error::new_simple(ErrorCode::Unavailable, "User accounts are not configured")
+        })
+    }
+}
+
+/// Record of privileged actions, backed by the store, e.g. who impersonated whom.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Audit;
+/// let audit = Audit::default();
+/// assert!(!audit.is_configured());
+/// ```
+#[derive(Clone, Default)]
+pub struct Audit(Option<Arc<dyn AuditLog>>);
+
+impl Audit {
+    pub fn new(log: impl AuditLog + 'static) -> Self {
+        Self(Some(Arc::new(log)))
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Returns the log the entries are recorded in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Unavailable` error if no store was configured.
+    pub fn log(&self) -> rcauth_core::error::Result<&dyn AuditLog> {
+        self.0.as_deref().ok_or_else(|| {
+            Error::new_simple(ErrorCode::Unavailable, "The audit log is not configured")
+        })
+    }
+}
+
+/// Global settings persisted in the store, which keep the maintenance switch across restarts and share them between instances.
+///
+/// Without a store the switch only lives in this process.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Settings;
+/// let settings = Settings::default();
+/// assert!(!settings.is_configured());
+/// ```
+#[derive(Clone, Default)]
+pub struct Settings(Option<Arc<dyn SettingsRepository>>);
+
+impl Settings {
+    pub fn new(repository: impl SettingsRepository + 'static) -> Self {
+        Self(Some(Arc::new(repository)))
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Persists `value` under `key`, or does nothing without a store.
+    ///
+    /// # Errors
+    ///
+    /// Returns the store's error if the value cannot be saved.
+    pub async fn save<T: Serialize + ?Sized + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+    ) -> rcauth_core::error::Result<()> {
+        match &self.0 {
+            Some(repository) => repository.set(key, value).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Applies the persisted switch to `maintenance`, leaving it alone if never persisted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the store's error, or an `Internal` error if the persisted switch is not a `bool`.
+    pub async fn load_switches(&self, maintenance: &Maintenance) -> rcauth_core::error::Result<()> {
+        let Some(repository) = &self.0 else {
+            return Ok(());
+        };
+
+        match repository.get(settings::MAINTENANCE).await? {
+            Some(true) => maintenance.enable(),
+            Some(false) => maintenance.disable(),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Starts reloading the switch every `interval`, so changes made through another instance take effect here.
+    ///
+    /// A failed reload is logged and retried at the next interval. Returns `None` without a store or when `interval` is zero, which disables it.
+    pub fn spawn_sync(
+        &self,
+        maintenance: Maintenance,
+        interval: Duration,
+    ) -> Option<JoinHandle<()>> {
+        if !self.is_configured() || interval.is_zero() {
+            return None;
+        }
+        let settings = self.clone();
+        Some(tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                if let Err(err) = settings.load_switches(&maintenance).await {
+                    warn!("Failed to reload the persisted switches: {}", err);
+                }
+            }
+        }))
+    }
+}
+
+/// Applied and pending migrations of the database, reported at `/health/db`.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Migrations;
+/// let migrations = Migrations::default();
+/// assert!(!migrations.is_configured());
+/// ```
+#[derive(Clone, Default)]
+pub struct Migrations(Option<Arc<dyn MigrationCheck>>);
+
+impl Migrations {
+    pub fn new(check: impl MigrationCheck + 'static) -> Self {
+        Self(Some(Arc::new(check)))
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Returns the check reading the database's migrations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Unavailable` error if no store was configured.
+    pub fn check(&self) -> rcauth_core::error::Result<&dyn MigrationCheck> {
+        self.0
+            .as_deref()
+            .ok_or_else(|| Error::new_simple(ErrorCode::Unavailable, "No database is configured"))
+    }
+}
+
+/// Verifies and hashes the passwords users choose, e.g. at `POST /password/change`, and checks them against the policy.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Passwords;
+/// let passwords = Passwords::default();
+/// assert!(!passwords.is_configured());
+/// ```
+#[derive(Clone, Default)]
+pub struct Passwords(Option<Hasher>);
+
+impl Passwords {
+    pub fn new(hasher: Hasher) -> Self {
+        Self(Some(hasher))
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Returns the hasher holding the configured cost and policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Unavailable` error if password hashing was not configured.
+    pub fn hasher(&self) -> rcauth_core::error::Result<&Hasher> {
+        self.0.as_ref().ok_or_else(|| {
+            Error::new_simple(ErrorCode::Unavailable, "Password hashing is not configured")
+        })
+    }
+}
+
+/// When the server started, reported as uptime by `/health/full`.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::Uptime;
+/// let uptime = Uptime::default();
+/// assert_eq!(uptime.seconds(), 0);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Uptime(Instant);
+
+impl Uptime {
+    pub fn seconds(&self) -> u64 {
+        self.0.elapsed().as_secs()
+    }
+}
+
+impl Default for Uptime {
+    /// Starts counting now.
+    fn default() -> Self {
+        Self(Instant::now())
+    }
+}
+
+/// The configuration the process runs with, served by the management server at `GET /config`.
+///
+/// Sensitive values are masked when it is created, so the document can be served as is. Settings are masked by their own name, and a setting described as `{"value": ..., "source": ...}` keeps its source.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::EffectiveConfig;
+/// let config = EffectiveConfig::new(serde_json::json!({"store": {"password": "hunter2"}}));
+/// assert_eq!(config.document()["store"]["password"], "***");
+/// ```
+#[derive(Clone, Debug)]
+pub struct EffectiveConfig(Arc<serde_json::Value>);
+
+impl EffectiveConfig {
+    pub fn new(mut document: serde_json::Value) -> Self {
+        let sections = document
+            .as_object_mut()
+            .into_iter()
+            .flat_map(|s| s.values_mut());
+        for settings in sections.filter_map(serde_json::Value::as_object_mut) {
+            for (name, setting) in settings {
+                match setting.get_mut("value") {
+                    Some(value) => redact_field(name, value),
+                    None => redact_field(name, setting),
+                }
+            }
+        }
+        Self(Arc::new(document))
+    }
+
+    pub fn document(&self) -> &serde_json::Value {
+        &self.0
+    }
+}
+
+impl Default for EffectiveConfig {
+    /// Returns an empty document, as served when the configuration was not provided.
+    fn default() -> Self {
+        Self::new(serde_json::json!({}))
+    }
+}
+
+/// State shared by the routes of both servers.
+///
+/// Handlers extract the part they need, e.g. `State<HealthChecks>`, rather than the whole struct.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::{AppState, HealthChecks};
+/// let state = AppState {
+///     checks: HealthChecks::default(),
+///     ..AppState::default()
+/// };
+/// ```
+#[derive(Clone, Default)]
+pub struct AppState {
+    /// Checks reported at `/health/full`
+    pub checks: HealthChecks,
+    /// Start of the server, for the uptime reported at `/health/full`
+    pub uptime: Uptime,
+    /// Keys published at `/.well-known/jwks.json`
+    pub jwks: Jwks,
+    /// Verifies the bearer tokens of authenticated routes
+    pub jwt: Option<Jwt>,
+    /// Refuses unbound tokens and bound tokens presented by another client, when `token_binding` is enabled
+    pub token_binding: Option<TokenBinding>,
+    /// Accepts the access token from its cookie, when `token_cookies` is enabled
+    pub token_cookies: Option<TokenCookies>,
+    /// Profiles served at `/userinfo`
+    pub users: Users,
+    /// Records impersonations made through the management server
+    pub audit: Audit,
+    /// Schema version reported at `/health/db`
+    pub migrations: Migrations,
+    /// Checks and hashes the passwords of `POST /password/change`
+    pub passwords: Passwords,
+    /// Replays retries of `POST /password/change` that repeat an `Idempotency-Key`, when set
+    pub idempotency: Option<Idempotency>,
+    /// Limits `POST /password/change` per client IP, when set
+    pub password_change_limit: Option<RateLimit>,
+    /// While enabled, API routes other than health checks return 503
+    pub maintenance: Maintenance,
+    /// Once shutdown begins, `/ready` returns 503
+    pub draining: Draining,
+    /// Persists the maintenance switch
+    pub settings: Settings,
+    /// Connection pool metrics served at `/metrics`
+    pub metrics: ServerMetrics,
+    /// Filter of the process's log subscriber, changed through `PUT /log-level`
+    pub log_filter: Option<LogFilter>,
+    /// Configuration served at `GET /config`
+    pub config: EffectiveConfig,
+}
+
+impl FromRef<AppState> for HealthChecks {
+    fn from_ref(state: &AppState) -> Self {
+        state.checks.clone()
+    }
+}
+
+impl FromRef<AppState> for Uptime {
+    fn from_ref(state: &AppState) -> Self {
+        state.uptime
+    }
+}
+
+impl FromRef<AppState> for Jwks {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwks.clone()
+    }
+}
+
+impl FromRef<AppState> for Settings {
+    fn from_ref(state: &AppState) -> Self {
+        state.settings.clone()
+    }
+}
+
+impl FromRef<AppState> for Audit {
+    fn from_ref(state: &AppState) -> Self {
+        state.audit.clone()
+    }
+}
+
+impl FromRef<AppState> for Users {
+    fn from_ref(state: &AppState) -> Self {
+        state.users.clone()
+    }
+}
+
+impl FromRef<AppState> for Passwords {
+    fn from_ref(state: &AppState) -> Self {
+        state.passwords.clone()
+    }
+}
+
+impl FromRef<AppState> for Migrations {
+    fn from_ref(state: &AppState) -> Self {
+        state.migrations.clone()
+    }
+}
+
+impl FromRef<AppState> for Maintenance {
+    fn from_ref(state: &AppState) -> Self {
+        state.maintenance.clone()
+    }
+}
+
+impl FromRef<AppState> for Draining {
+    fn from_ref(state: &AppState) -> Self {
+        state.draining.clone()
+    }
+}
+
+impl FromRef<AppState> for ServerMetrics {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
+
+impl FromRef<AppState> for EffectiveConfig {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
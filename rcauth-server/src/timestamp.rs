@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// How timestamp fields of API responses are serialized, set with `timestamp_format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// RFC 3339 strings in UTC, e.g. `"2025-08-01T09:00:00Z"`.
+    #[default]
+    Rfc3339,
+    /// Whole seconds since the Unix epoch, e.g. `1754038800`.
+    Epoch,
+}
+
+tokio::task_local! {
+    /// Format of the response being built, installed per request by [`timestamp_format`].
+    static FORMAT: TimestampFormat;
+}
+
+impl TimestampFormat {
+    /// Format in effect for the current request, or the default outside of one.
+    pub fn current() -> Self {
+        FORMAT.try_with(|format| *format).unwrap_or_default()
+    }
+
+    /// Runs `f` with timestamps serialized in this format, e.g. to serialize a response outside a request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_server::TimestampFormat;
+    /// let format = TimestampFormat::Epoch.scope(TimestampFormat::current);
+    /// assert_eq!(format, TimestampFormat::Epoch);
+    /// ```
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        FORMAT.sync_scope(self, f)
+    }
+}
+
+/// Serializes timestamp fields in the request's [`TimestampFormat`], for use with `#[serde(with = "timestamp")]`.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(serde::Serialize)]
+/// struct Session {
+///     #[serde(with = "crate::timestamp")]
+///     created_at: DateTime<Utc>,
+/// }
+/// ```
+pub fn serialize<S: Serializer>(at: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    match TimestampFormat::current() {
+        TimestampFormat::Rfc3339 => {
+            serializer.serialize_str(&at.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+        }
+        TimestampFormat::Epoch => serializer.serialize_i64(at.timestamp()),
+    }
+}
+
+/// [`serialize`] for optional timestamps, which serialize as `null` when absent.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        at: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match at {
+            Some(at) => super::serialize(at, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Serializes the timestamps of the response in `format`.
+///
+/// JSON bodies are serialized while the handler's response is built, so they pick up the format installed here.
+pub async fn timestamp_format(
+    State(format): State<TimestampFormat>,
+    request: Request,
+    next: Next,
+) -> Response {
+    FORMAT.scope(format, next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, body::Body, middleware::from_fn_with_state, routing::get};
+    use tower::ServiceExt;
+
+    #[derive(Serialize)]
+    struct Session {
+        #[serde(with = "crate::timestamp")]
+        created_at: DateTime<Utc>,
+        #[serde(with = "crate::timestamp::option")]
+        revoked_at: Option<DateTime<Utc>>,
+    }
+
+    fn session() -> Session {
+        Session {
+            created_at: DateTime::from_timestamp(1_754_038_800, 0).unwrap(),
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn rfc3339_is_the_default() {
+        let json = serde_json::to_value(session()).unwrap();
+        assert_eq!(json["created_at"], "2025-08-01T09:00:00Z");
+        assert!(json["revoked_at"].is_null());
+    }
+
+    #[test]
+    fn epoch_serializes_whole_seconds() {
+        let json = TimestampFormat::Epoch.scope(|| serde_json::to_value(session()).unwrap());
+        assert_eq!(json["created_at"], 1_754_038_800);
+        assert!(json["revoked_at"].is_null());
+    }
+
+    #[tokio::test]
+    async fn middleware_applies_the_configured_format() {
+        for (format, expected) in [
+            (
+                TimestampFormat::Rfc3339,
+                serde_json::json!("2025-08-01T09:00:00Z"),
+            ),
+            (TimestampFormat::Epoch, serde_json::json!(1_754_038_800)),
+        ] {
+            let app = Router::new()
+                .route("/session", get(|| async { Json(session()) }))
+                .layer(from_fn_with_state(format, timestamp_format));
+            let request = Request::get("/session").body(Body::empty()).unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["created_at"], expected);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use axum::extract::{ConnectInfo, connect_info::MockConnectInfo};
+use http::{HeaderMap, header, request::Parts};
+use ipnet::IpNet;
+use rcauth_core::error::Result;
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use crate::{Config, TrustedProxies};
+
+/// Prefix length of the IPv4 network a token is bound to.
+const IPV4_PREFIX: u8 = 24;
+
+/// Prefix length of the IPv6 network a token is bound to, usually a whole site.
+const IPV6_PREFIX: u8 = 48;
+
+/// Binds access tokens to a coarse fingerprint of the client they were issued to, so a stolen token is refused elsewhere.
+///
+/// The fingerprint hashes the `User-Agent` with the client's network, a /24 for IPv4 and a /48 for IPv6, rather than its exact IP, so mobile clients hopping addresses within their carrier's network keep their tokens. Clients are identified through the trusted proxies.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::{TokenBinding, TrustedProxies};
+/// # use http::HeaderMap;
+/// let binding = TokenBinding::new(TrustedProxies::default());
+/// let headers = HeaderMap::new();
+/// assert_eq!(
+///     binding.fingerprint(&headers, "203.0.113.7".parse().unwrap()),
+///     binding.fingerprint(&headers, "203.0.113.200".parse().unwrap()),
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TokenBinding {
+    proxies: TrustedProxies,
+}
+
+impl TokenBinding {
+    pub fn new(proxies: TrustedProxies) -> Self {
+        Self { proxies }
+    }
+
+    /// Builds the binding when `token_binding` is enabled, identifying clients through the configured trusted proxies.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigurationError` if `trusted_proxies` is malformed.
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        if !config.token_binding {
+            return Ok(None);
+        }
+        Ok(Some(Self::new(TrustedProxies::from_config(config)?)))
+    }
+
+    /// Fingerprint of a client sending `headers` from `peer`, to store in the `fgp` claim of the tokens issued to it.
+    pub fn fingerprint(&self, headers: &HeaderMap, peer: IpAddr) -> String {
+        let network = network(self.proxies.client_ip(headers, peer));
+        let user_agent = headers
+            .get(header::USER_AGENT)
+            .map(|value| value.as_bytes())
+            .unwrap_or_default();
+
+        let mut hasher = Sha256::new();
+        hasher.update(user_agent);
+        hasher.update(b"\n");
+        hasher.update(network.to_string());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Fingerprint of the client that sent the request, grouping clients together when the peer address is unknown.
+    pub(crate) fn fingerprint_of(&self, parts: &Parts) -> String {
+        // Like the `ConnectInfo` extractor, fall back to the address tests mock
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(peer)| peer)
+            .or_else(|| {
+                parts
+                    .extensions
+                    .get::<MockConnectInfo<SocketAddr>>()
+                    .map(|MockConnectInfo(peer)| peer)
+            })
+            .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), SocketAddr::ip);
+        self.fingerprint(&parts.headers, peer)
+    }
+}
+
+/// Network of `ip` that a token is bound to.
+fn network(ip: IpAddr) -> IpNet {
+    let prefix = match ip {
+        IpAddr::V4(_) => IPV4_PREFIX,
+        IpAddr::V6(_) => IPV6_PREFIX,
+    };
+    IpNet::new(ip, prefix)
+        .expect("prefix fits the address family")
+        .trunc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(user_agent: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, user_agent.parse().unwrap());
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn addresses_within_the_network_share_a_fingerprint() {
+        let binding = TokenBinding::default();
+        let phone = headers("rcauth-ios/1.0");
+
+        assert_eq!(
+            binding.fingerprint(&phone, ip("198.51.100.7")),
+            binding.fingerprint(&phone, ip("198.51.100.250"))
+        );
+        assert_eq!(
+            binding.fingerprint(&phone, ip("2001:db8:1:a::1")),
+            binding.fingerprint(&phone, ip("2001:db8:1:ffff::2"))
+        );
+    }
+
+    #[test]
+    fn other_networks_and_user_agents_change_the_fingerprint() {
+        let binding = TokenBinding::default();
+        let phone = headers("rcauth-ios/1.0");
+        let fingerprint = binding.fingerprint(&phone, ip("198.51.100.7"));
+
+        assert_ne!(fingerprint, binding.fingerprint(&phone, ip("198.51.101.7")));
+        assert_ne!(
+            fingerprint,
+            binding.fingerprint(&headers("curl/8.0"), ip("198.51.100.7"))
+        );
+        assert_ne!(
+            binding.fingerprint(&phone, ip("2001:db8:1::1")),
+            binding.fingerprint(&phone, ip("2001:db8:2::1"))
+        );
+    }
+}
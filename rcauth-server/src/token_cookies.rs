@@ -0,0 +1,105 @@
+use http::{HeaderMap, header};
+
+use crate::{Config, TokenCookieSettings};
+
+/// Name of the cookie carrying the access token.
+pub const ACCESS_TOKEN_COOKIE: &str = "rcauth_access_token";
+
+/// Name of the cookie carrying the CSRF token, which scripts read to echo it in [`CSRF_HEADER`].
+pub const CSRF_COOKIE: &str = "rcauth_csrf_token";
+
+/// Header in which browser clients echo the CSRF token of their [`CSRF_COOKIE`].
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Accepts the access token of browser clients from its [`ACCESS_TOKEN_COOKIE`], as configured by the `token_cookies` settings.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::{TokenCookies, TokenCookieSettings};
+/// let cookies = TokenCookies::new(TokenCookieSettings::default());
+/// assert!(cookies.csrf_protection());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TokenCookies {
+    settings: TokenCookieSettings,
+}
+
+impl TokenCookies {
+    pub fn new(settings: TokenCookieSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Builds the cookies when `token_cookies` is enabled.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .token_cookies
+            .enabled
+            .then(|| Self::new(config.token_cookies.clone()))
+    }
+
+    /// Whether mutating requests authenticated by cookie must pass the CSRF check.
+    pub fn csrf_protection(&self) -> bool {
+        self.settings.csrf_protection
+    }
+}
+
+/// Returns the access token of the `Cookie` headers in `headers`, if any.
+pub(crate) fn access_token_of(headers: &HeaderMap) -> Option<&str> {
+    cookie_of(headers, ACCESS_TOKEN_COOKIE)
+}
+
+/// Returns the non-empty value of the cookie called `name` in the `Cookie` headers of `headers`.
+pub(crate) fn cookie_of<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(cookie, _)| *cookie == name)
+        .map(|(_, value)| value.trim())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cookies_are_only_accepted_when_enabled() {
+        assert!(TokenCookies::from_config(&Config::default()).is_none());
+
+        let config = Config {
+            token_cookies: TokenCookieSettings {
+                enabled: true,
+                csrf_protection: false,
+            },
+            ..Config::default()
+        };
+        assert!(
+            !TokenCookies::from_config(&config)
+                .unwrap()
+                .csrf_protection()
+        );
+    }
+
+    #[test]
+    fn access_token_is_found_among_other_cookies() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(access_token_of(&headers), None);
+
+        headers.append(header::COOKIE, "theme=dark".parse().unwrap());
+        headers.append(
+            header::COOKIE,
+            "rcauth_refresh_token=refresh; rcauth_access_token=access"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(access_token_of(&headers), Some("access"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, "rcauth_access_token=".parse().unwrap());
+        assert_eq!(access_token_of(&headers), None);
+    }
+}
@@ -0,0 +1,270 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use http::HeaderMap;
+
+/// Header carrying the W3C trace context, e.g. `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Trace flags of traces started here: sampled.
+const SAMPLED: u8 = 0x01;
+
+tokio::task_local! {
+    /// Trace context of the request being handled, for the outbound calls made while handling it.
+    static CURRENT: TraceContext;
+}
+
+/// W3C trace context of a request: the trace it belongs to, and the id of this server's span within it.
+///
+/// A request carrying a valid `traceparent` continues the upstream trace; any other request starts a new one. Either way this server gets a fresh span id, which outbound calls name as their parent.
+///
+/// # Examples
+///
+/// ```
+/// # use rcauth_server::TraceContext;
+/// # use http::HeaderMap;
+/// let mut headers = HeaderMap::new();
+/// headers.insert(
+///     "traceparent",
+///     "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse().unwrap(),
+/// );
+/// let context = TraceContext::from_headers(&headers);
+/// assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+/// assert_ne!(context.span_id(), "00f067aa0ba902b7");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    flags: u8,
+}
+
+impl Default for TraceContext {
+    /// Starts a new, sampled trace.
+    fn default() -> Self {
+        Self {
+            trace_id: rand::random::<u128>().max(1),
+            span_id: new_span_id(),
+            flags: SAMPLED,
+        }
+    }
+}
+
+impl TraceContext {
+    /// Continues the trace named by the `traceparent` in `headers`, or starts a new one if it is missing or malformed.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(TRACEPARENT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_traceparent)
+            .map_or_else(Self::default, |(trace_id, flags)| Self {
+                trace_id,
+                span_id: new_span_id(),
+                flags,
+            })
+    }
+
+    /// Returns the trace id as 32 lowercase hex digits.
+    pub fn trace_id(&self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+
+    /// Returns this server's span id as 16 lowercase hex digits.
+    pub fn span_id(&self) -> String {
+        format!("{:016x}", self.span_id)
+    }
+
+    /// Renders the `traceparent` to send downstream, naming this server's span as the parent.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            self.trace_id(),
+            self.span_id(),
+            self.flags
+        )
+    }
+
+    /// Returns the context of the request being handled, or `None` outside of a request.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Runs `future` with this context as the [`current`](Self::current) one.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
+
+/// Returns a random, non-zero span id.
+fn new_span_id() -> u64 {
+    rand::random::<u64>().max(1)
+}
+
+/// Parses the trace id and flags of a `traceparent`, or returns `None` if it is malformed.
+///
+/// Versions after `00` may append fields, which are ignored; the all-zero ids and version `ff` are invalid, as the specification requires.
+fn parse_traceparent(value: &str) -> Option<(u128, u8)> {
+    let mut fields = value.splitn(5, '-');
+    let (version, trace_id, parent_id, flags) = (
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+    );
+    let rest = fields.next();
+
+    if version.len() != 2 || !is_lower_hex(version) || version == "ff" {
+        return None;
+    }
+    if version == "00" && rest.is_some() {
+        return None;
+    }
+    if trace_id.len() != 32 || parent_id.len() != 16 || flags.len() != 2 {
+        return None;
+    }
+    if !is_lower_hex(trace_id) || !is_lower_hex(parent_id) || !is_lower_hex(flags) {
+        return None;
+    }
+
+    let trace_id = u128::from_str_radix(trace_id, 16).ok()?;
+    let parent_id = u64::from_str_radix(parent_id, 16).ok()?;
+    if trace_id == 0 || parent_id == 0 {
+        return None;
+    }
+    Some((trace_id, u8::from_str_radix(flags, 16).ok()?))
+}
+
+fn is_lower_hex(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Continues or starts the request's trace, exposing it to the request span through the request's extensions and to outbound calls as the [`current`](TraceContext::current) context.
+///
+/// Installed outside the request logger, so its span can record the trace id.
+pub async fn propagate_trace_context(mut request: Request, next: Next) -> Response {
+    let context = TraceContext::from_headers(request.headers());
+    request.extensions_mut().insert(context.clone());
+    context.scope(next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::logger::create_logger_middleware_http;
+    use axum::{Router, body::Body, routing::get};
+    use rcauth_core::testing::CapturedLogs;
+    use tower::ServiceExt;
+
+    const UPSTREAM: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    fn headers(traceparent: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TRACEPARENT, traceparent.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn upstream_trace_is_continued_with_a_new_span() {
+        let context = TraceContext::from_headers(&headers(UPSTREAM));
+        assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_ne!(context.span_id(), "00f067aa0ba902b7");
+        assert_eq!(
+            context.traceparent(),
+            format!(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-{}-01",
+                context.span_id()
+            )
+        );
+
+        // Later versions may append fields
+        let context = TraceContext::from_headers(&headers(&format!("01{}-extra", &UPSTREAM[2..])));
+        assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+    }
+
+    #[test]
+    fn malformed_traceparent_starts_a_new_trace() {
+        for traceparent in [
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e47-00f067aa0ba902b7-01",
+            "garbage",
+        ] {
+            let context = TraceContext::from_headers(&headers(traceparent));
+            assert_ne!(
+                context.trace_id(),
+                "4bf92f3577b34da6a3ce929d0e0e4736",
+                "{}",
+                traceparent
+            );
+            assert_eq!(context.trace_id().len(), 32);
+        }
+    }
+
+    /// Sends a request, with `traceparent` if given, and returns the trace id the handler saw and the logs.
+    async fn traced_request(traceparent: Option<&str>) -> (String, String) {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route(
+                "/trace",
+                get(|| async { TraceContext::current().unwrap().trace_id() }),
+            )
+            .layer(create_logger_middleware_http())
+            .layer(axum::middleware::from_fn(propagate_trace_context));
+        let mut request = axum::http::Request::builder().uri("/trace");
+        if let Some(traceparent) = traceparent {
+            request = request.header(TRACEPARENT, traceparent);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        let logs = logs.contents();
+        (String::from_utf8(body.to_vec()).unwrap(), logs)
+    }
+
+    #[tokio::test]
+    async fn incoming_trace_id_is_recorded_on_the_request_span() {
+        let (trace_id, logs) = traced_request(Some(UPSTREAM)).await;
+        assert_eq!(trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert!(
+            logs.contains("trace_id=4bf92f3577b34da6a3ce929d0e0e4736"),
+            "{}",
+            logs
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_without_traceparent_get_a_new_trace() {
+        let (first, logs) = traced_request(None).await;
+        assert_eq!(first.len(), 32);
+        assert!(logs.contains(&format!("trace_id={}", first)), "{}", logs);
+
+        let (second, _) = traced_request(None).await;
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn no_context_outside_of_a_request() {
+        assert_eq!(TraceContext::current(), None);
+        let context = TraceContext::default();
+        let inside = context
+            .clone()
+            .scope(async { TraceContext::current() })
+            .await;
+        assert_eq!(inside, Some(context));
+    }
+}
@@ -0,0 +1,11 @@
+create table if not exists idempotency_keys (
+    key text primary key,
+    request_hash text not null,
+    -- null until the request that claimed the key finishes
+    response_status smallint,
+    response_content_type text,
+    response_body bytea,
+    created_at timestamptz not null default now(),
+    expires_at timestamptz not null
+);
+create index if not exists idempotency_keys_expires_at_idx on idempotency_keys (expires_at);
@@ -0,0 +1 @@
+alter table users drop column if exists must_reset_password;
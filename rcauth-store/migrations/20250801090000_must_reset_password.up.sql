@@ -0,0 +1 @@
+alter table users add column if not exists must_reset_password boolean not null default false;
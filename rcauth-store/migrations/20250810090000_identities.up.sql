@@ -0,0 +1,12 @@
+create table if not exists identities (
+    id bigserial primary key,
+    user_id uuid not null references users(id) on delete cascade,
+    -- e.g. google; subject is the provider's stable id for the account
+    provider text not null,
+    subject text not null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+select trigger_updated_at('identities');
+create unique index if not exists identities_provider_subject_idx on identities (provider, subject);
+create index if not exists identities_user_id_idx on identities (user_id);
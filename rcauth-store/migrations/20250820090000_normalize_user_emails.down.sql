@@ -0,0 +1,2 @@
+-- The original case of the emails is not kept, so there is nothing to restore
+select 1;
@@ -0,0 +1,2 @@
+-- Emails are matched ignoring case already, so keeping them lowercased is safe
+update users set email = lower(email) where email <> lower(email);
@@ -0,0 +1,7 @@
+create table if not exists settings (
+    key text primary key,
+    value jsonb not null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+select trigger_updated_at('settings');
@@ -0,0 +1,8 @@
+create table if not exists audit_log (
+    id bigserial primary key,
+    actor text not null,
+    action text not null,
+    target text not null,
+    created_at timestamptz not null default now()
+);
+create index if not exists audit_log_target_idx on audit_log (target);
@@ -0,0 +1,4 @@
+drop index if exists users_deleted_at_idx;
+-- Without the column, soft-deleted users would come back to life, so they are deleted for good
+delete from users where deleted_at is not null;
+alter table users drop column if exists deleted_at;
@@ -0,0 +1,2 @@
+-- Bumped by every versioned update, so concurrent edits of a user are detected instead of overwriting each other
+alter table users add column if not exists version bigint not null default 1;
@@ -0,0 +1,67 @@
+use crate::error::QuerySnafu;
+use crate::store::PgStore;
+use async_trait::async_trait;
+use rcauth_core::audit::{AuditEntry, AuditLog};
+use rcauth_core::error::Result;
+use snafu::ResultExt;
+
+#[async_trait]
+impl AuditLog for PgStore {
+    async fn record(&self, entry: AuditEntry) -> Result<()> {
+        let sql = "INSERT INTO audit_log (actor, action, target) VALUES ($1, $2, $3)";
+        self.timed(
+            sql,
+            sqlx::query(sql)
+                .bind(&entry.actor)
+                .bind(&entry.action)
+                .bind(&entry.target)
+                .execute(&mut *self.acquire().await?),
+        )
+        .await
+        .context(QuerySnafu)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use rcauth_core::audit::IMPERSONATE_USER;
+    use rcauth_core::store::Store;
+    use uuid::Uuid;
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn entries_are_recorded_with_a_timestamp() {
+        let store = crate::store::new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        store.run_migrations().await.unwrap();
+        let target = Uuid::new_v4().to_string();
+
+        store
+            .record(AuditEntry {
+                actor: "admin-1".to_string(),
+                action: IMPERSONATE_USER.to_string(),
+                target: target.clone(),
+            })
+            .await
+            .unwrap();
+
+        let (actor, action, recent): (String, String, bool) = sqlx::query_as(
+            "SELECT actor, action, created_at > now() - interval '1 minute' \
+             FROM audit_log WHERE target = $1",
+        )
+        .bind(&target)
+        .fetch_one(&store.current_pool())
+        .await
+        .unwrap();
+        assert_eq!(
+            (actor.as_str(), action.as_str()),
+            ("admin-1", IMPERSONATE_USER)
+        );
+        assert!(recent);
+    }
+}
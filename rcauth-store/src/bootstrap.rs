@@ -0,0 +1,164 @@
+use crate::error::QuerySnafu;
+use crate::store::PgStore;
+use crate::tenant::ENSURE_TENANT;
+use async_trait::async_trait;
+use rcauth_core::bootstrap::{ADMIN_ROLE, AdminBootstrap};
+use rcauth_core::error::Result;
+use rcauth_core::user::UserProfile;
+use snafu::ResultExt;
+use sqlx::Connection;
+use uuid::Uuid;
+
+#[async_trait]
+impl AdminBootstrap for PgStore {
+    async fn has_users(&self) -> Result<bool> {
+        let sql = "SELECT EXISTS (SELECT 1 FROM users)";
+        let populated: bool = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql).fetch_one(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        Ok(populated)
+    }
+
+    async fn create_first_admin(
+        &self,
+        tenant_slug: &str,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<Option<UserProfile>> {
+        // Only an empty table is worth locking, so starting against a populated database never blocks user writes
+        if self.has_users().await? {
+            return Ok(None);
+        }
+
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await.context(QuerySnafu)?;
+
+        // Holds off concurrent bootstraps and sign-ups until the admin is committed, while allowing reads; another may have won the race since the check above
+        let sql = "LOCK TABLE users IN EXCLUSIVE MODE";
+        self.timed(sql, sqlx::query(sql).execute(&mut *tx))
+            .await
+            .context(QuerySnafu)?;
+        let sql = "SELECT EXISTS (SELECT 1 FROM users)";
+        let populated: bool = self
+            .timed(sql, sqlx::query_scalar(sql).fetch_one(&mut *tx))
+            .await
+            .context(QuerySnafu)?;
+        if populated {
+            return Ok(None);
+        }
+
+        let sql = ENSURE_TENANT;
+        let tenant_id: Uuid = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql)
+                    .bind(tenant_slug)
+                    .fetch_one(&mut *tx),
+            )
+            .await
+            .context(QuerySnafu)?;
+
+        let sql = "INSERT INTO users (tenant_id, email, encrypted_password, role, email_confirmed_at) \
+                   VALUES ($1, $2, $3, $4, now()) RETURNING id";
+        let id: Uuid = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql)
+                    .bind(tenant_id)
+                    .bind(email)
+                    .bind(password_hash)
+                    .bind(ADMIN_ROLE)
+                    .fetch_one(&mut *tx),
+            )
+            .await
+            .context(QuerySnafu)?;
+        tx.commit().await.context(QuerySnafu)?;
+
+        Ok(Some(UserProfile {
+            id: id.to_string(),
+            email: email.to_string(),
+            email_verified: true,
+            name: None,
+            must_reset_password: false,
+            version: 1,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use rcauth_core::store::Store;
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`, whose user may create databases.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn admin_is_created_in_an_empty_database_only() {
+        let config = Config::new().expect("RCAUTH_POSTGRES_* must be set");
+        let admin = crate::store::new(config.clone()).await.unwrap();
+        let database = format!("rcauth_bootstrap_{}", Uuid::new_v4().simple());
+        // The migrations' ICU collation needs UTF8, whatever the server's default encoding
+        sqlx::query(&format!(
+            "CREATE DATABASE {} TEMPLATE template0 ENCODING 'UTF8'",
+            database
+        ))
+        .execute(&admin.current_pool())
+        .await
+        .unwrap();
+
+        let store = crate::store::new(Config {
+            database: database.clone(),
+            ..config
+        })
+        .await
+        .unwrap();
+        store.run_migrations().await.unwrap();
+
+        let created = store
+            .create_first_admin("acme", "admin@example.com", "hash")
+            .await
+            .unwrap()
+            .expect("the database has no users");
+        assert!(created.email_verified);
+        let (role, hash, tenant): (String, String, String) = sqlx::query_as(
+            "SELECT users.role, users.encrypted_password, tenants.slug FROM users \
+             JOIN tenants ON tenants.id = users.tenant_id WHERE users.id = $1",
+        )
+        .bind(Uuid::parse_str(&created.id).unwrap())
+        .fetch_one(&store.current_pool())
+        .await
+        .unwrap();
+        assert_eq!(
+            (role.as_str(), hash.as_str(), tenant.as_str()),
+            (ADMIN_ROLE, "hash", "acme")
+        );
+
+        // The database now has a user, so bootstrapping again changes nothing
+        let rerun = store
+            .create_first_admin("acme", "other@example.com", "other-hash")
+            .await
+            .unwrap();
+        assert_eq!(rerun, None);
+        assert!(store.has_users().await.unwrap());
+        let users: Vec<(String, String)> =
+            sqlx::query_as("SELECT email, encrypted_password FROM users")
+                .fetch_all(&store.current_pool())
+                .await
+                .unwrap();
+        assert_eq!(
+            users,
+            [("admin@example.com".to_string(), "hash".to_string())]
+        );
+
+        store.close().await;
+        sqlx::query(&format!("DROP DATABASE {} WITH (FORCE)", database))
+            .execute(&admin.current_pool())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,271 @@
+use crate::error::QuerySnafu;
+use crate::store::PgStore;
+use rcauth_core::error::Result;
+use snafu::ResultExt;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Rows removed by one purge of expired tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PurgeReport {
+    /// Refresh tokens older than the refresh token lifetime
+    pub refresh_tokens: u64,
+    /// Email confirmation tokens cleared from their users
+    pub confirmation_tokens: u64,
+    /// Password recovery tokens cleared from their users
+    pub recovery_tokens: u64,
+    /// Expired idempotency keys
+    pub idempotency_keys: u64,
+}
+
+impl PurgeReport {
+    pub fn total(&self) -> u64 {
+        self.refresh_tokens
+            + self.confirmation_tokens
+            + self.recovery_tokens
+            + self.idempotency_keys
+    }
+}
+
+/// Deletes the refresh tokens issued before the cutoff.
+const PURGE_REFRESH_TOKENS: &str = "DELETE FROM refresh_tokens WHERE id IN (\
+     SELECT id FROM refresh_tokens \
+     WHERE created_at < now() - make_interval(secs => $1) LIMIT $2)";
+
+/// Clears the confirmation tokens sent before the cutoff.
+const PURGE_CONFIRMATION_TOKENS: &str = "UPDATE users SET confirmation_token = NULL, confirmation_sent_at = NULL WHERE id IN (\
+     SELECT id FROM users WHERE confirmation_token IS NOT NULL \
+     AND confirmation_sent_at < now() - make_interval(secs => $1) LIMIT $2)";
+
+/// Clears the recovery tokens sent before the cutoff.
+const PURGE_RECOVERY_TOKENS: &str = "UPDATE users SET recovery_token = NULL, recovery_sent_at = NULL WHERE id IN (\
+     SELECT id FROM users WHERE recovery_token IS NOT NULL \
+     AND recovery_sent_at < now() - make_interval(secs => $1) LIMIT $2)";
+
+/// Deletes the idempotency keys that expired before the cutoff.
+const PURGE_IDEMPOTENCY_KEYS: &str = "DELETE FROM idempotency_keys WHERE key IN (\
+     SELECT key FROM idempotency_keys \
+     WHERE expires_at < now() - make_interval(secs => $1) LIMIT $2)";
+
+impl PgStore {
+    /// Deletes refresh tokens older than `refresh_token_ttl` and expired idempotency keys, and clears confirmation and recovery tokens past `confirmation_token_ttl_secs` and `recovery_token_ttl_secs`.
+    ///
+    /// Rows are removed `cleanup_batch_size` at a time, each batch in its own statement, so a large backlog never holds locks for long.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DatabaseError` if a statement fails; batches removed before it stay removed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example(store: rcauth_store::store::PgStore) -> rcauth_core::error::Result<()> {
+    /// let report = store.purge_expired(std::time::Duration::from_secs(30 * 86400)).await?;
+    /// println!("purged {} rows", report.total());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn purge_expired(&self, refresh_token_ttl: Duration) -> Result<PurgeReport> {
+        let config = self.config();
+        Ok(PurgeReport {
+            refresh_tokens: self
+                .purge_in_batches(PURGE_REFRESH_TOKENS, refresh_token_ttl)
+                .await?,
+            confirmation_tokens: self
+                .purge_in_batches(
+                    PURGE_CONFIRMATION_TOKENS,
+                    Duration::from_secs(config.confirmation_token_ttl_secs),
+                )
+                .await?,
+            recovery_tokens: self
+                .purge_in_batches(
+                    PURGE_RECOVERY_TOKENS,
+                    Duration::from_secs(config.recovery_token_ttl_secs),
+                )
+                .await?,
+            idempotency_keys: self
+                .purge_in_batches(PURGE_IDEMPOTENCY_KEYS, Duration::ZERO)
+                .await?,
+        })
+    }
+
+    /// Starts purging expired tokens every `cleanup_interval_secs`, logging what each purge removed.
+    ///
+    /// A failed purge is logged and retried at the next interval. Returns `None` when `cleanup_interval_secs` is zero, which disables it.
+    pub fn spawn_cleanup(&self, refresh_token_ttl: Duration) -> Option<JoinHandle<()>> {
+        if self.config().cleanup_interval_secs == 0 {
+            return None;
+        }
+        let store = self.clone();
+        let interval = Duration::from_secs(self.config().cleanup_interval_secs);
+        Some(tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                match store.purge_expired(refresh_token_ttl).await {
+                    Ok(report) if report.total() > 0 => log_purge(&report),
+                    Ok(_) => debug!("🧹 No expired tokens to purge"),
+                    Err(err) => warn!("Failed to purge expired tokens: {}", err),
+                }
+            }
+        }))
+    }
+
+    /// Runs `sql`, which removes at most `$2` rows older than `$1` seconds, until a batch comes up short.
+    async fn purge_in_batches(&self, sql: &str, age: Duration) -> Result<u64> {
+        let batch_size = self.config().cleanup_batch_size;
+        let mut purged = 0;
+        loop {
+            let batch = self
+                .timed(
+                    sql,
+                    sqlx::query(sql)
+                        .bind(age.as_secs_f64())
+                        .bind(i64::from(batch_size))
+                        .execute(&mut *self.acquire().await?),
+                )
+                .await
+                .context(QuerySnafu)?
+                .rows_affected();
+            purged += batch;
+            if batch < u64::from(batch_size) {
+                return Ok(purged);
+            }
+        }
+    }
+}
+
+/// Logs the rows a purge removed, per table.
+pub fn log_purge(report: &PurgeReport) {
+    info!(
+        refresh_tokens = report.refresh_tokens,
+        confirmation_tokens = report.confirmation_tokens,
+        recovery_tokens = report.recovery_tokens,
+        idempotency_keys = report.idempotency_keys,
+        "🧹 Purged {} expired tokens",
+        report.total()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use rcauth_core::store::Store;
+    use uuid::Uuid;
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn expired_rows_are_purged_and_live_ones_kept() {
+        let store = crate::store::new(Config {
+            // Smaller than the backlog, so the purge takes several batches
+            cleanup_batch_size: 2,
+            confirmation_token_ttl_secs: 86400,
+            recovery_token_ttl_secs: 3600,
+            ..Config::new().expect("RCAUTH_POSTGRES_* must be set")
+        })
+        .await
+        .unwrap();
+        store.run_migrations().await.unwrap();
+        let pool = store.current_pool();
+        let tag = Uuid::new_v4().to_string();
+
+        let tenant: Uuid =
+            sqlx::query_scalar("INSERT INTO tenants (name, slug) VALUES ($1, $1) RETURNING id")
+                .bind(&tag)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        // (confirmation sent hours ago, recovery sent hours ago)
+        let mut users = Vec::new();
+        for (confirmation, recovery) in [(48, 2), (1, 0), (30, 5)] {
+            let user: Uuid = sqlx::query_scalar(
+                "INSERT INTO users (tenant_id, email, encrypted_password, role, \
+                     confirmation_token, confirmation_sent_at, recovery_token, recovery_sent_at) \
+                 VALUES ($1, $2, 'hash', 'user', \
+                     'confirm', now() - make_interval(hours => $3), \
+                     'recover', now() - make_interval(hours => $4)) \
+                 RETURNING id",
+            )
+            .bind(tenant)
+            .bind(format!("{}@example.com", Uuid::new_v4()))
+            .bind(confirmation)
+            .bind(recovery)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            users.push(user);
+        }
+        // Five refresh tokens past a 30 day lifetime, one within it
+        for days in [40, 35, 31, 31, 60, 2] {
+            sqlx::query(
+                "INSERT INTO refresh_tokens (tenant_id, session_id, user_id, token, created_at) \
+                 VALUES ($1, $2, $3, $4, now() - make_interval(days => $5))",
+            )
+            .bind(tenant)
+            .bind(Uuid::new_v4())
+            .bind(users[0])
+            .bind(Uuid::new_v4().to_string())
+            .bind(days)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        for (suffix, expires_in_secs) in [("expired", -60.0), ("live", 60.0)] {
+            sqlx::query(
+                "INSERT INTO idempotency_keys (key, request_hash, expires_at) \
+                 VALUES ($1, 'hash', now() + make_interval(secs => $2))",
+            )
+            .bind(format!("{}-{}", tag, suffix))
+            .bind(expires_in_secs)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let report = store
+            .purge_expired(Duration::from_secs(30 * 86400))
+            .await
+            .unwrap();
+        // Other tests leave rows behind, so the report counts at least this test's
+        assert!(report.refresh_tokens >= 5);
+        assert!(report.confirmation_tokens >= 2);
+        assert!(report.recovery_tokens >= 2);
+        assert!(report.idempotency_keys >= 1);
+
+        let tokens: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM refresh_tokens WHERE tenant_id = $1")
+                .bind(tenant)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(tokens, 1);
+        let kept: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT confirmation_token, recovery_token FROM users \
+             WHERE tenant_id = $1 ORDER BY array_position($2, id)",
+        )
+        .bind(tenant)
+        .bind(&users)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            kept,
+            [
+                (None, None),
+                (Some("confirm".to_string()), Some("recover".to_string())),
+                (None, None),
+            ]
+        );
+        let keys: Vec<String> = sqlx::query_scalar(
+            "SELECT key FROM idempotency_keys WHERE key LIKE $1 || '-%' ORDER BY key",
+        )
+        .bind(&tag)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(keys, [format!("{}-live", tag)]);
+    }
+}
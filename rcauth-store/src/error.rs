@@ -1,6 +1,7 @@
 use rcauth_core::error::Error as AppError;
 use snafu::prelude::*;
 use sqlx::postgres::PgDatabaseError;
+use std::fmt;
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub))]
@@ -18,13 +19,57 @@ pub enum Error {
     NotFound,
 
     #[snafu(display("Conflict with existing record: {}", message))]
-    Conflict { message: String },
+    Conflict {
+        message: String,
+        sqlstate: Option<SqlState>,
+    },
 
     #[snafu(display("Database migration error: {}", source))]
     Migration { source: sqlx::migrate::MigrateError },
 
     #[snafu(display("Database serialization error: {}", message))]
-    Serialization { message: String },
+    Serialization {
+        message: String,
+        sqlstate: Option<SqlState>,
+    },
+
+    #[snafu(display("Validation error: {}", message))]
+    Validation {
+        message: String,
+        sqlstate: Option<SqlState>,
+    },
+}
+
+/// Postgres diagnostics of a failed statement, for the logs: its SQLSTATE and the violated constraint, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlState {
+    /// Five-character SQLSTATE, e.g. `23505` for a unique violation
+    pub code: String,
+    /// Name of the violated constraint, e.g. `users_email_key`
+    pub constraint: Option<String>,
+}
+
+impl SqlState {
+    /// Returns the diagnostics of `error`, or `None` if the database did not raise it.
+    pub fn of(error: &sqlx::Error) -> Option<Self> {
+        let sqlx::Error::Database(db_err) = error else {
+            return None;
+        };
+        Some(Self {
+            code: db_err.code()?.into_owned(),
+            constraint: db_err.constraint().map(str::to_string),
+        })
+    }
+}
+
+impl fmt::Display for SqlState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SQLSTATE {}", self.code)?;
+        if let Some(constraint) = &self.constraint {
+            write!(f, ", constraint '{}'", constraint)?;
+        }
+        Ok(())
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -33,41 +78,100 @@ impl Error {
     pub fn conflict(message: impl Into<String>) -> Self {
         Error::Conflict {
             message: message.into(),
+            sqlstate: None,
         }
     }
 
     pub fn serialization_error(message: impl Into<String>) -> Self {
         Error::Serialization {
             message: message.into(),
+            sqlstate: None,
+        }
+    }
+
+    /// Returns true for failures that are safe to retry by re-running the whole transaction.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::Serialization { .. })
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Error::Validation {
+            message: message.into(),
+            sqlstate: None,
         }
     }
+
+    /// Returns the Postgres diagnostics of the failure, if the database raised it.
+    pub fn sqlstate(&self) -> Option<SqlState> {
+        match self {
+            Error::Connection { source }
+            | Error::Query { source }
+            | Error::Transaction { source } => SqlState::of(source),
+            Error::Conflict { sqlstate, .. }
+            | Error::Serialization { sqlstate, .. }
+            | Error::Validation { sqlstate, .. } => sqlstate.clone(),
+            Error::NotFound | Error::Migration { .. } => None,
+        }
+    }
+
+    fn with_sqlstate(mut self, state: Option<SqlState>) -> Self {
+        if let Error::Conflict { sqlstate, .. }
+        | Error::Serialization { sqlstate, .. }
+        | Error::Validation { sqlstate, .. } = &mut self
+        {
+            *sqlstate = state;
+        }
+        self
+    }
 }
 
 // Handle common SQLx error cases
 pub fn handle_sqlx_error(error: sqlx::Error) -> Error {
-    match &error {
+    let sqlstate = SqlState::of(&error);
+    let mapped = match &error {
         sqlx::Error::RowNotFound => Error::NotFound,
-        sqlx::Error::Database(db_err) => {
-            let pg_err = db_err.downcast_ref::<PgDatabaseError>();
-            match pg_err.code() {
-                // Unique violation
-                "23505" => Error::conflict("Record already exists"),
-                // Foreign key violation
-                "23503" => Error::conflict("Related record not found"),
-                // Serialization failure
-                "40001" => Error::serialization_error("Transaction conflict"),
-                _ => Error::Query { source: error },
+        sqlx::Error::Database(db_err) => match db_err.code().as_deref() {
+            // Unique violation
+            Some("23505") => Error::conflict("Record already exists"),
+            // Foreign key violation
+            Some("23503") => Error::conflict("Related record not found"),
+            // Check constraint violation
+            Some("23514") => match db_err.constraint() {
+                Some(constraint) => {
+                    Error::validation(format!("Value violates constraint '{}'", constraint))
+                }
+                None => Error::validation("Value violates a check constraint"),
+            },
+            // Not-null violation
+            Some("23502") => {
+                let column = db_err
+                    .try_downcast_ref::<PgDatabaseError>()
+                    .and_then(|pg_err| pg_err.column());
+                match column {
+                    Some(column) => {
+                        Error::validation(format!("Missing required value for '{}'", column))
+                    }
+                    None => Error::validation("Missing required value"),
+                }
             }
-        }
-        _ => Error::Query { source: error },
-    }
+            // Serialization failure
+            Some("40001") => Error::serialization_error("Transaction conflict"),
+            // Deadlock detected
+            Some("40P01") => Error::serialization_error("Deadlock detected"),
+            _ => return Error::Query { source: error },
+        },
+        _ => return Error::Query { source: error },
+    };
+    mapped.with_sqlstate(sqlstate)
 }
 
 impl From<Error> for AppError {
     fn from(error: Error) -> Self {
         use rcauth_core::error::ErrorCode;
 
-        match error {
+        // Kept in `internal` only, which never reaches the response body
+        let sqlstate = error.sqlstate();
+        let app_error = match error {
             Error::Connection { source } => AppError::new(
                 ErrorCode::DatabaseError,
                 "Database connection failed",
@@ -82,7 +186,7 @@ impl From<Error> for AppError {
                 source,
             ),
             Error::NotFound => AppError::new_simple(ErrorCode::NotFound, "Record not found"),
-            Error::Conflict { message } => {
+            Error::Conflict { message, .. } => {
                 AppError::new_simple(ErrorCode::Conflict, format!("Conflict: {}", message))
             }
             Error::Migration { source } => AppError::new(
@@ -90,11 +194,158 @@ impl From<Error> for AppError {
                 "Database migration failed",
                 source,
             ),
-            Error::Serialization { message } => AppError::new_simple(
+            Error::Serialization { message, .. } => AppError::new_simple(
                 ErrorCode::Conflict,
                 format!("Serialization error: {}", message),
             )
             .with_internal(format!("DB serialization conflict: {}", message)),
+            Error::Validation { message, .. } => {
+                AppError::new_simple(ErrorCode::ValidationError, message)
+            }
+        };
+        match sqlstate {
+            Some(sqlstate) => {
+                let internal = match &app_error.internal {
+                    Some(internal) => format!("{} ({})", internal, sqlstate),
+                    None => sqlstate.to_string(),
+                };
+                app_error.with_internal(internal)
+            }
+            None => app_error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcauth_core::error::{ErrorCode, ErrorResponse};
+    use sqlx::error::{DatabaseError, ErrorKind};
+    use std::borrow::Cow;
+
+    /// Stand-in for a Postgres error; `PgDatabaseError` cannot be constructed outside sqlx.
+    #[derive(Debug)]
+    struct SyntheticDbError {
+        code: &'static str,
+        constraint: Option<&'static str>,
+    }
+
+    impl std::fmt::Display for SyntheticDbError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "synthetic database error {}", self.code)
         }
     }
+
+    impl std::error::Error for SyntheticDbError {}
+
+    impl DatabaseError for SyntheticDbError {
+        fn message(&self) -> &str {
+            "synthetic database error"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.code))
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn constraint(&self) -> Option<&str> {
+            self.constraint
+        }
+
+        fn kind(&self) -> ErrorKind {
+            match self.code {
+                "23514" => ErrorKind::CheckViolation,
+                "23502" => ErrorKind::NotNullViolation,
+                _ => ErrorKind::Other,
+            }
+        }
+    }
+
+    fn db_error(code: &'static str, constraint: Option<&'static str>) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(SyntheticDbError { code, constraint }))
+    }
+
+    #[test]
+    fn check_violation_maps_to_validation() {
+        let error = handle_sqlx_error(db_error("23514", Some("users_email_check")));
+        assert!(
+            matches!(&error, Error::Validation { message, .. } if message.contains("users_email_check"))
+        );
+
+        let app_error = AppError::from(error);
+        assert_eq!(app_error.code, ErrorCode::ValidationError);
+        assert_eq!(app_error.status.as_u16(), 422);
+    }
+
+    #[test]
+    fn not_null_violation_maps_to_validation() {
+        let error = handle_sqlx_error(db_error("23502", None));
+        assert!(matches!(error, Error::Validation { .. }));
+        assert_eq!(AppError::from(error).code, ErrorCode::ValidationError);
+    }
+
+    #[test]
+    fn existing_codes_keep_their_mapping() {
+        assert!(matches!(
+            handle_sqlx_error(db_error("23505", None)),
+            Error::Conflict { .. }
+        ));
+        assert!(matches!(
+            handle_sqlx_error(db_error("40001", None)),
+            Error::Serialization { .. }
+        ));
+        assert!(matches!(
+            handle_sqlx_error(db_error("40P01", None)),
+            Error::Serialization { .. }
+        ));
+        assert!(matches!(
+            handle_sqlx_error(db_error("42P01", None)),
+            Error::Query { .. }
+        ));
+    }
+
+    #[test]
+    fn unique_violation_keeps_its_sqlstate_internal() {
+        let error = handle_sqlx_error(db_error("23505", Some("users_email_key")));
+        assert_eq!(
+            error.sqlstate(),
+            Some(SqlState {
+                code: "23505".to_string(),
+                constraint: Some("users_email_key".to_string()),
+            })
+        );
+
+        let app_error = AppError::from(error);
+        assert_eq!(app_error.code, ErrorCode::Conflict);
+        let internal = app_error.internal.clone().unwrap();
+        assert!(internal.contains("SQLSTATE 23505"), "{}", internal);
+        assert!(internal.contains("'users_email_key'"), "{}", internal);
+
+        let response = ErrorResponse::from_error(&app_error);
+        assert!(!response.message.contains("23505"), "{}", response.message);
+        assert!(response.details.is_none());
+    }
+
+    #[test]
+    fn unmapped_database_errors_carry_their_sqlstate() {
+        let error = Error::Query {
+            source: db_error("42P01", None),
+        };
+        let internal = AppError::from(error).internal.unwrap();
+        assert!(internal.ends_with("(SQLSTATE 42P01)"), "{}", internal);
+
+        assert_eq!(Error::NotFound.sqlstate(), None);
+        assert_eq!(Error::conflict("Email already registered").sqlstate(), None);
+    }
 }
@@ -0,0 +1,194 @@
+use crate::error::QuerySnafu;
+use crate::store::PgStore;
+use async_trait::async_trait;
+use rcauth_core::error::Result;
+use rcauth_core::idempotency::{CachedResponse, Claim, IdempotencyStore};
+use snafu::ResultExt;
+use std::time::Duration;
+
+type StoredRow = (String, Option<i16>, Option<String>, Option<Vec<u8>>);
+
+#[async_trait]
+impl IdempotencyStore for PgStore {
+    async fn claim(&self, key: &str, request_hash: &str, ttl: Duration) -> Result<Claim> {
+        let mut connection = self.acquire().await?;
+        // The key can be released between the insert and the select, so try again once before giving up
+        for _ in 0..2 {
+            // An expired record is taken over as if the key were unused
+            let sql = "INSERT INTO idempotency_keys (key, request_hash, expires_at) \
+                       VALUES ($1, $2, now() + make_interval(secs => $3)) \
+                       ON CONFLICT (key) DO UPDATE SET \
+                           request_hash = excluded.request_hash, \
+                           response_status = NULL, \
+                           response_content_type = NULL, \
+                           response_body = NULL, \
+                           created_at = now(), \
+                           expires_at = excluded.expires_at \
+                       WHERE idempotency_keys.expires_at <= now() \
+                       RETURNING key";
+            let claimed: Option<String> = self
+                .timed(
+                    sql,
+                    sqlx::query_scalar(sql)
+                        .bind(key)
+                        .bind(request_hash)
+                        .bind(ttl.as_secs_f64())
+                        .fetch_optional(&mut *connection),
+                )
+                .await
+                .context(QuerySnafu)?;
+            if claimed.is_some() {
+                return Ok(Claim::Claimed);
+            }
+
+            let sql = "SELECT request_hash, response_status, response_content_type, response_body \
+                       FROM idempotency_keys WHERE key = $1";
+            let existing: Option<StoredRow> = self
+                .timed(
+                    sql,
+                    sqlx::query_as(sql)
+                        .bind(key)
+                        .fetch_optional(&mut *connection),
+                )
+                .await
+                .context(QuerySnafu)?;
+
+            match existing {
+                Some((request_hash, Some(status), content_type, body)) => {
+                    return Ok(Claim::Completed {
+                        request_hash,
+                        response: CachedResponse {
+                            status: status as u16,
+                            content_type,
+                            body: body.unwrap_or_default(),
+                        },
+                    });
+                }
+                Some((request_hash, None, _, _)) => return Ok(Claim::InProgress { request_hash }),
+                None => continue,
+            }
+        }
+
+        Ok(Claim::InProgress {
+            request_hash: request_hash.to_string(),
+        })
+    }
+
+    async fn complete(&self, key: &str, response: &CachedResponse) -> Result<()> {
+        let sql = "UPDATE idempotency_keys \
+                   SET response_status = $2, response_content_type = $3, response_body = $4 \
+                   WHERE key = $1";
+        self.timed(
+            sql,
+            sqlx::query(sql)
+                .bind(key)
+                .bind(response.status as i16)
+                .bind(response.content_type.as_deref())
+                .bind(response.body.as_slice())
+                .execute(&mut *self.acquire().await?),
+        )
+        .await
+        .context(QuerySnafu)?;
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) -> Result<()> {
+        let sql = "DELETE FROM idempotency_keys WHERE key = $1 AND response_status IS NULL";
+        self.timed(
+            sql,
+            sqlx::query(sql)
+                .bind(key)
+                .execute(&mut *self.acquire().await?),
+        )
+        .await
+        .context(QuerySnafu)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    /// Connects and creates the table from its migration, without depending on the others.
+    async fn store() -> PgStore {
+        let store = crate::store::new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        sqlx::raw_sql(include_str!(
+            "../migrations/20250720090000_idempotency_keys.up.sql"
+        ))
+        .execute(&store.current_pool())
+        .await
+        .unwrap();
+        store
+    }
+
+    fn unique_key(name: &str) -> String {
+        format!("{}-{}", name, uuid::Uuid::new_v4())
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn completed_key_returns_cached_response() {
+        let store = store().await;
+        let key = unique_key("completed");
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(
+            store.claim(&key, "hash", ttl).await.unwrap(),
+            Claim::Claimed
+        );
+        assert_eq!(
+            store.claim(&key, "hash", ttl).await.unwrap(),
+            Claim::InProgress {
+                request_hash: "hash".to_string()
+            }
+        );
+
+        let response = CachedResponse {
+            status: 201,
+            content_type: Some("application/json".to_string()),
+            body: br#"{"id":1}"#.to_vec(),
+        };
+        store.complete(&key, &response).await.unwrap();
+        assert_eq!(
+            store.claim(&key, "other", ttl).await.unwrap(),
+            Claim::Completed {
+                request_hash: "hash".to_string(),
+                response
+            }
+        );
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn released_and_expired_keys_can_be_claimed_again() {
+        let store = store().await;
+        let ttl = Duration::from_secs(60);
+
+        let released = unique_key("released");
+        assert_eq!(
+            store.claim(&released, "a", ttl).await.unwrap(),
+            Claim::Claimed
+        );
+        store.release(&released).await.unwrap();
+        assert_eq!(
+            store.claim(&released, "b", ttl).await.unwrap(),
+            Claim::Claimed
+        );
+
+        let expired = unique_key("expired");
+        assert_eq!(
+            store.claim(&expired, "a", Duration::ZERO).await.unwrap(),
+            Claim::Claimed
+        );
+        assert_eq!(
+            store.claim(&expired, "b", ttl).await.unwrap(),
+            Claim::Claimed
+        );
+    }
+}
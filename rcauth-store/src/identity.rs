@@ -0,0 +1,182 @@
+use crate::error::QuerySnafu;
+use crate::store::PgStore;
+use async_trait::async_trait;
+use rcauth_core::error::{Error, ErrorCode, Result};
+use rcauth_core::identity::IdentityRepository;
+use snafu::ResultExt;
+use uuid::Uuid;
+
+#[async_trait]
+impl IdentityRepository for PgStore {
+    async fn find_user(
+        &self,
+        tenant_id: &str,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<String>> {
+        let Ok(tenant_id) = Uuid::parse_str(tenant_id) else {
+            return Ok(None);
+        };
+
+        let sql = "SELECT identities.user_id FROM identities \
+                   JOIN users ON users.id = identities.user_id \
+                   WHERE users.tenant_id = $1 AND identities.provider = $2 AND identities.subject = $3";
+        let user_id: Option<Uuid> = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql)
+                    .bind(tenant_id)
+                    .bind(provider)
+                    .bind(subject)
+                    .fetch_optional(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        Ok(user_id.map(|id| id.to_string()))
+    }
+
+    async fn link(&self, user_id: &str, provider: &str, subject: &str) -> Result<()> {
+        let user_id = Uuid::parse_str(user_id)
+            .map_err(|e| Error::new(ErrorCode::Invalid, "Invalid user id", e))?;
+
+        // The no-op update makes RETURNING yield the owner of an identity that is already linked
+        let sql = "INSERT INTO identities (user_id, provider, subject) VALUES ($1, $2, $3) \
+                   ON CONFLICT (provider, subject) DO UPDATE SET provider = identities.provider \
+                   RETURNING user_id";
+        let owner: Uuid = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql)
+                    .bind(user_id)
+                    .bind(provider)
+                    .bind(subject)
+                    .fetch_one(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+
+        if owner != user_id {
+            return Err(Error::new_simple(
+                ErrorCode::Conflict,
+                format!("{} identity is linked to another user", provider),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use rcauth_core::email::EmailNormalizer;
+    use rcauth_core::identity::{ProviderIdentity, find_or_link};
+    use rcauth_core::store::Store;
+
+    async fn store() -> PgStore {
+        let store = crate::store::new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        store.run_migrations().await.unwrap();
+        store
+    }
+
+    async fn seed_tenant(store: &PgStore) -> String {
+        let id: Uuid =
+            sqlx::query_scalar("INSERT INTO tenants (name, slug) VALUES ($1, $1) RETURNING id")
+                .bind(Uuid::new_v4().to_string())
+                .fetch_one(&store.current_pool())
+                .await
+                .unwrap();
+        id.to_string()
+    }
+
+    fn identity(provider: &str, email: &str) -> ProviderIdentity {
+        ProviderIdentity {
+            provider: provider.to_string(),
+            subject: Uuid::new_v4().to_string(),
+            email: email.to_string(),
+            email_verified: true,
+        }
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn second_provider_links_to_the_existing_user() {
+        let store = store().await;
+        let tenant = seed_tenant(&store).await;
+        let emails = EmailNormalizer::default();
+        let email = format!("{}@example.com", Uuid::new_v4());
+        let google = identity("google", &email);
+        let github = identity("github", &email.to_uppercase());
+
+        let (user, created) = find_or_link(&store, &store, &emails, &tenant, "user", &google)
+            .await
+            .unwrap();
+        assert!(created);
+        let (linked, created) = find_or_link(&store, &store, &emails, &tenant, "user", &github)
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(linked.id, user.id);
+
+        for identity in [&google, &github] {
+            let owner = store
+                .find_user(&tenant, &identity.provider, &identity.subject)
+                .await
+                .unwrap();
+            assert_eq!(owner.as_deref(), Some(user.id.as_str()));
+        }
+        // Identities are scoped to the tenant of their user
+        let other_tenant = seed_tenant(&store).await;
+        assert_eq!(
+            store
+                .find_user(&other_tenant, &google.provider, &google.subject)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn identity_owned_by_another_user_cannot_be_linked() {
+        let store = store().await;
+        let tenant = seed_tenant(&store).await;
+        let emails = EmailNormalizer::default();
+        let google = identity("google", &format!("{}@example.com", Uuid::new_v4()));
+        let (owner, _) = find_or_link(&store, &store, &emails, &tenant, "user", &google)
+            .await
+            .unwrap();
+        let (other, _) = find_or_link(
+            &store,
+            &store,
+            &emails,
+            &tenant,
+            "user",
+            &identity("github", &format!("{}@example.com", Uuid::new_v4())),
+        )
+        .await
+        .unwrap();
+
+        let err = store
+            .link(&other.id, &google.provider, &google.subject)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        // Linking again to the owner is harmless
+        store
+            .link(&owner.id, &google.provider, &google.subject)
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .find_user(&tenant, &google.provider, &google.subject)
+                .await
+                .unwrap(),
+            Some(owner.id)
+        );
+    }
+}
@@ -1,4 +1,15 @@
 #![allow(dead_code)]
+mod audit;
+mod bootstrap;
+pub mod cleanup;
 pub mod config;
 mod error;
+mod idempotency;
+mod identity;
+pub mod pagination;
+mod retry;
+mod settings;
+mod slow_query;
 pub mod store;
+mod tenant;
+mod user;
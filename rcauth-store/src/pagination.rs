@@ -0,0 +1,523 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rcauth_core::error::{Error, Result, ValidationErrors};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+/// Bounds on how many items a list request may return per page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageLimits {
+    /// Page size used when a request does not ask for one
+    pub default_page_size: u32,
+    /// Largest page size served; larger requests are clamped to it
+    pub max_page_size: u32,
+}
+
+/// The `limit` and `offset` a list request asked for, as read from its query string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct PageRequest {
+    #[serde(default)]
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: u64,
+}
+
+/// Offset pagination, with the requested limit resolved against the configured [`PageLimits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Pagination {
+    pub limit: u32,
+    pub offset: u64,
+}
+
+impl Pagination {
+    /// Resolves `request` against `limits`: a missing `limit` falls back to the default page size, and one above the max page size is clamped to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if `limit` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_store::pagination::{PageLimits, PageRequest, Pagination};
+    /// let limits = PageLimits { default_page_size: 20, max_page_size: 100 };
+    ///
+    /// let pagination = Pagination::new(PageRequest::default(), limits).unwrap();
+    /// assert_eq!(pagination.limit, 20);
+    ///
+    /// let request = PageRequest { limit: Some(500), offset: 40 };
+    /// assert_eq!(Pagination::new(request, limits).unwrap().limit, 100);
+    /// ```
+    pub fn new(request: PageRequest, limits: PageLimits) -> Result<Self> {
+        Ok(Self {
+            limit: resolve_limit(request.limit, limits)?,
+            offset: request.offset,
+        })
+    }
+
+    /// Rows to fetch for the page: one more than `limit`, which tells whether another page follows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_store::pagination::Pagination;
+    /// let pagination = Pagination { limit: 20, offset: 0 };
+    /// assert_eq!(pagination.fetch_limit(), 21);
+    /// ```
+    pub fn fetch_limit(&self) -> i64 {
+        i64::from(self.limit) + 1
+    }
+
+    /// `offset` as bound to a query's `OFFSET`.
+    pub fn sql_offset(&self) -> i64 {
+        i64::try_from(self.offset).unwrap_or(i64::MAX)
+    }
+}
+
+/// One page of a list response, reporting the limit and offset that were actually applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub limit: u32,
+    pub offset: u64,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from `rows` fetched with [`Pagination::fetch_limit`], dropping the extra row used to detect a next page.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_store::pagination::{Page, Pagination};
+    /// let pagination = Pagination { limit: 2, offset: 0 };
+    /// let page = Page::new(vec![1, 2, 3], pagination);
+    /// assert_eq!(page.items, vec![1, 2]);
+    /// assert!(page.has_more);
+    /// ```
+    pub fn new(mut rows: Vec<T>, pagination: Pagination) -> Self {
+        let has_more = rows.len() > pagination.limit as usize;
+        rows.truncate(pagination.limit as usize);
+
+        Self {
+            items: rows,
+            limit: pagination.limit,
+            offset: pagination.offset,
+            has_more,
+        }
+    }
+}
+
+/// Applies the default page size to a missing `limit` and clamps an oversized one, rejecting zero.
+fn resolve_limit(limit: Option<u32>, limits: PageLimits) -> Result<u32> {
+    match limit {
+        Some(0) => Err(invalid("limit", "must be greater than zero")),
+        Some(limit) => Ok(limit.min(limits.max_page_size)),
+        None => Ok(limits.default_page_size),
+    }
+}
+
+fn invalid(field: &str, message: &str) -> Error {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, message);
+    Error::validation(errors)
+}
+
+/// Which way a cursor pages from its row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Rows after the cursor
+    Next,
+    /// Rows before the cursor
+    Prev,
+}
+
+/// A position in a list ordered by `(created_at, id)`, handed to clients as an opaque token.
+///
+/// Unlike an offset, the position stays put when rows are inserted or deleted ahead of it, and the keyset lookup uses the `(created_at, id)` index however deep the page is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub direction: Direction,
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    /// Encodes the cursor as an unpadded URL-safe base64 token.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_store::pagination::{Cursor, Direction};
+    /// # use chrono::Utc;
+    /// # use uuid::Uuid;
+    /// let cursor = Cursor { direction: Direction::Next, created_at: Utc::now(), id: Uuid::new_v4() };
+    /// assert_eq!(Cursor::decode(&cursor.encode()).unwrap().id, cursor.id);
+    /// ```
+    pub fn encode(&self) -> String {
+        let direction = match self.direction {
+            Direction::Next => "n",
+            Direction::Prev => "p",
+        };
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}|{}|{}",
+            direction,
+            self.created_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.id
+        ))
+    }
+
+    /// Decodes a token produced by [`Cursor::encode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error on the `cursor` field if the token was not produced by [`Cursor::encode`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_store::pagination::Cursor;
+    /// assert!(Cursor::decode("not-a-cursor").is_err());
+    /// ```
+    pub fn decode(token: &str) -> Result<Self> {
+        let decoded = URL_SAFE_NO_PAD
+            .decode(token)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok());
+        let mut parts = decoded.as_deref().unwrap_or_default().split('|');
+
+        let direction = match parts.next() {
+            Some("n") => Some(Direction::Next),
+            Some("p") => Some(Direction::Prev),
+            _ => None,
+        };
+        let created_at = parts
+            .next()
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok());
+        let id = parts.next().and_then(|value| Uuid::parse_str(value).ok());
+
+        match (direction, created_at, id, parts.next()) {
+            (Some(direction), Some(created_at), Some(id), None) => Ok(Self {
+                direction,
+                created_at: created_at.with_timezone(&Utc),
+                id,
+            }),
+            _ => Err(invalid(
+                "cursor",
+                "is not a valid cursor; use a next_cursor or prev_cursor from a previous page",
+            )),
+        }
+    }
+}
+
+/// The `limit` and `cursor` a cursor-paginated list request asked for, as read from its query string.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct CursorRequest {
+    #[serde(default)]
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Keyset pagination over `(created_at, id)`, the cursor-based alternative to [`Pagination`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorPagination {
+    pub limit: u32,
+    /// Where the page starts; `None` for the first page
+    pub cursor: Option<Cursor>,
+}
+
+impl CursorPagination {
+    /// Resolves `request` against `limits` like [`Pagination::new`], and decodes its cursor.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if `limit` is zero or the cursor is invalid.
+    pub fn new(request: CursorRequest, limits: PageLimits) -> Result<Self> {
+        Ok(Self {
+            limit: resolve_limit(request.limit, limits)?,
+            cursor: request.cursor.as_deref().map(Cursor::decode).transpose()?,
+        })
+    }
+
+    /// Completes a query whose last clause is an open `WHERE` or `AND` with the keyset condition, ordering, and limit of the page.
+    ///
+    /// Rows are fetched in the direction of the cursor, one more than `limit` to tell whether another page follows; pass them to [`CursorPage::new`] as returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rcauth_store::pagination::CursorPagination;
+    /// # use sqlx::{Postgres, QueryBuilder};
+    /// let pagination = CursorPagination { limit: 20, cursor: None };
+    /// let mut query = QueryBuilder::<Postgres>::new("SELECT id, created_at FROM users WHERE ");
+    /// pagination.push_keyset(&mut query);
+    /// assert_eq!(
+    ///     query.sql(),
+    ///     "SELECT id, created_at FROM users WHERE TRUE ORDER BY created_at ASC, id ASC LIMIT $1"
+    /// );
+    /// ```
+    pub fn push_keyset(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        let order = match self.cursor {
+            None => {
+                query.push("TRUE");
+                "ASC"
+            }
+            Some(cursor) => {
+                let (comparison, order) = match cursor.direction {
+                    Direction::Next => (">", "ASC"),
+                    Direction::Prev => ("<", "DESC"),
+                };
+                query
+                    .push(format!("(created_at, id) {comparison} ("))
+                    .push_bind(cursor.created_at)
+                    .push(", ")
+                    .push_bind(cursor.id)
+                    .push(")");
+                order
+            }
+        };
+        query
+            .push(format!(" ORDER BY created_at {order}, id {order} LIMIT "))
+            .push_bind(i64::from(self.limit) + 1);
+    }
+}
+
+/// One page of a cursor-paginated list response, with tokens for the pages on either side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub limit: u32,
+    /// Token for the page after this one, if there is one
+    pub next_cursor: Option<String>,
+    /// Token for the page before this one, if there is one
+    pub prev_cursor: Option<String>,
+}
+
+impl<T> CursorPage<T> {
+    /// Builds a page from `rows` fetched with [`CursorPagination::push_keyset`], where `key` returns a row's `(created_at, id)`.
+    ///
+    /// Items are always in ascending order, whichever way the cursor paged.
+    pub fn new<F>(mut rows: Vec<T>, pagination: CursorPagination, key: F) -> Self
+    where
+        F: Fn(&T) -> (DateTime<Utc>, Uuid),
+    {
+        let limit = pagination.limit as usize;
+        let has_more = rows.len() > limit;
+        rows.truncate(limit);
+
+        let direction = pagination.cursor.map(|cursor| cursor.direction);
+        if direction == Some(Direction::Prev) {
+            rows.reverse();
+        }
+
+        // Paging one way always leaves rows behind in the other, where the cursor came from
+        let (more_after, more_before) = match direction {
+            None => (has_more, false),
+            Some(Direction::Next) => (has_more, true),
+            Some(Direction::Prev) => (true, has_more),
+        };
+        let cursor = |row: Option<&T>, direction| {
+            row.map(|row| {
+                let (created_at, id) = key(row);
+                Cursor {
+                    direction,
+                    created_at,
+                    id,
+                }
+                .encode()
+            })
+        };
+
+        Self {
+            next_cursor: more_after
+                .then(|| cursor(rows.last(), Direction::Next))
+                .flatten(),
+            prev_cursor: more_before
+                .then(|| cursor(rows.first(), Direction::Prev))
+                .flatten(),
+            items: rows,
+            limit: pagination.limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcauth_core::error::ErrorCode;
+
+    const LIMITS: PageLimits = PageLimits {
+        default_page_size: 20,
+        max_page_size: 100,
+    };
+
+    #[test]
+    fn missing_limit_uses_default_page_size() {
+        let request = PageRequest {
+            limit: None,
+            offset: 40,
+        };
+
+        let page = Page::new((0..25).collect(), Pagination::new(request, LIMITS).unwrap());
+
+        assert_eq!(page.limit, 20);
+        assert_eq!(page.offset, 40);
+        assert_eq!(page.items.len(), 20);
+        assert!(page.has_more);
+    }
+
+    #[test]
+    fn oversized_limit_is_clamped_to_max_page_size() {
+        let request = PageRequest {
+            limit: Some(1_000),
+            offset: 0,
+        };
+
+        let pagination = Pagination::new(request, LIMITS).unwrap();
+        assert_eq!(pagination.limit, 100);
+        assert_eq!(pagination.fetch_limit(), 101);
+
+        let page = Page::new((0..100).collect::<Vec<u32>>(), pagination);
+        assert_eq!(page.limit, 100);
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn zero_limit_is_rejected() {
+        let request = PageRequest {
+            limit: Some(0),
+            offset: 0,
+        };
+
+        let err = Pagination::new(request, LIMITS).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ValidationError);
+        assert_eq!(
+            err.data.unwrap()["fields"]["limit"][0],
+            "must be greater than zero"
+        );
+    }
+
+    /// Rows ordered by `(created_at, id)`, one second apart.
+    fn rows(count: u32) -> Vec<(DateTime<Utc>, Uuid)> {
+        let start = DateTime::parse_from_rfc3339("2025-01-01T00:00:00.123456Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        (0..count)
+            .map(|i| {
+                (
+                    start + chrono::Duration::seconds(i64::from(i)),
+                    Uuid::new_v4(),
+                )
+            })
+            .collect()
+    }
+
+    /// Does in memory what the query built by `push_keyset` does in Postgres.
+    fn fetch(
+        rows: &[(DateTime<Utc>, Uuid)],
+        pagination: CursorPagination,
+    ) -> Vec<(DateTime<Utc>, Uuid)> {
+        let fetched = rows.iter().copied();
+        let mut fetched: Vec<_> = match pagination.cursor {
+            None => fetched.collect(),
+            Some(cursor) => fetched
+                .filter(|row| match cursor.direction {
+                    Direction::Next => *row > (cursor.created_at, cursor.id),
+                    Direction::Prev => *row < (cursor.created_at, cursor.id),
+                })
+                .collect(),
+        };
+        if pagination.cursor.map(|c| c.direction) == Some(Direction::Prev) {
+            fetched.reverse();
+        }
+        fetched.truncate(pagination.limit as usize + 1);
+        fetched
+    }
+
+    fn page(
+        rows: &[(DateTime<Utc>, Uuid)],
+        cursor: Option<String>,
+    ) -> CursorPage<(DateTime<Utc>, Uuid)> {
+        let request = CursorRequest {
+            limit: Some(2),
+            cursor,
+        };
+        let pagination = CursorPagination::new(request, LIMITS).unwrap();
+        CursorPage::new(fetch(rows, pagination), pagination, |row| *row)
+    }
+
+    #[test]
+    fn cursors_page_forward_and_back() {
+        let mut rows = rows(5);
+        rows.sort();
+
+        let first = page(&rows, None);
+        assert_eq!(first.items, rows[0..2]);
+        assert!(first.prev_cursor.is_none());
+
+        let second = page(&rows, first.next_cursor);
+        assert_eq!(second.items, rows[2..4]);
+
+        let last = page(&rows, second.next_cursor);
+        assert_eq!(last.items, rows[4..5]);
+        assert!(last.next_cursor.is_none());
+
+        let back = page(&rows, last.prev_cursor);
+        assert_eq!(back.items, rows[2..4]);
+        let start = page(&rows, back.prev_cursor);
+        assert_eq!(start.items, rows[0..2]);
+        assert!(start.prev_cursor.is_none());
+        assert!(start.next_cursor.is_some());
+    }
+
+    #[test]
+    fn invalid_or_tampered_cursor_is_rejected() {
+        let (created_at, id) = rows(1)[0];
+        let token = Cursor {
+            direction: Direction::Next,
+            created_at,
+            id,
+        }
+        .encode();
+        assert_eq!(Cursor::decode(&token).unwrap().created_at, created_at);
+
+        let mut tampered = URL_SAFE_NO_PAD.decode(&token).unwrap();
+        tampered[0] = b'x';
+        for token in [
+            "not base64!".to_string(),
+            URL_SAFE_NO_PAD.encode(tampered),
+            token[..token.len() - 4].to_string(),
+        ] {
+            let request = CursorRequest {
+                limit: None,
+                cursor: Some(token),
+            };
+            let err = CursorPagination::new(request, LIMITS).unwrap_err();
+            assert_eq!(err.code, ErrorCode::ValidationError);
+            assert!(err.data.unwrap()["fields"].get("cursor").is_some());
+        }
+    }
+
+    #[test]
+    fn keyset_condition_follows_cursor_direction() {
+        let (created_at, id) = rows(1)[0];
+        let pagination = CursorPagination {
+            limit: 10,
+            cursor: Some(Cursor {
+                direction: Direction::Prev,
+                created_at,
+                id,
+            }),
+        };
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM users WHERE ");
+        pagination.push_keyset(&mut query);
+
+        assert_eq!(
+            query.sql(),
+            "SELECT * FROM users WHERE (created_at, id) < ($1, $2) ORDER BY created_at DESC, id DESC LIMIT $3"
+        );
+    }
+}
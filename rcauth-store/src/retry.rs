@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::error::Result;
+
+/// Default number of attempts made by [`with_retry`], including the first one.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubled for each further attempt.
+const BASE_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Runs `transaction`, re-running it when it fails with a retryable error.
+///
+/// Serialization failures (40001) and deadlocks (40P01), as classified by [`crate::error::handle_sqlx_error`], are retried up to `max_attempts` attempts in total with an exponential backoff. Any other error is returned immediately. The closure must open and commit its own transaction so that every attempt starts from a clean state.
+///
+/// # Errors
+///
+/// Returns the first non-retryable error, or the last retryable error once `max_attempts` is exhausted.
+///
+/// # Examples
+///
+/// ```ignore
+/// let user = with_retry(DEFAULT_MAX_ATTEMPTS, || async {
+///     let mut tx = pool.begin().await.map_err(handle_sqlx_error)?;
+///     let user = update_user(&mut tx, &changes).await?;
+///     tx.commit().await.map_err(handle_sqlx_error)?;
+///     Ok(user)
+/// })
+/// .await?;
+/// ```
+pub async fn with_retry<T, F, Fut>(max_attempts: u32, mut transaction: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match transaction().await {
+            Err(err) if err.is_retryable() && attempt < max_attempts => {
+                let backoff = BASE_BACKOFF * 2u32.pow(attempt - 1);
+                warn!(
+                    attempt,
+                    ?backoff,
+                    "Retrying transaction after error: {}",
+                    err
+                );
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[tokio::test]
+    async fn retries_serialization_failure_until_success() {
+        let mut attempts = 0;
+        let result = with_retry(DEFAULT_MAX_ATTEMPTS, || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt == 1 {
+                    Err(Error::serialization_error("Transaction conflict"))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn non_retryable_error_propagates_immediately() {
+        let mut attempts = 0;
+        let result: Result<()> = with_retry(DEFAULT_MAX_ATTEMPTS, || {
+            attempts += 1;
+            async { Err(Error::conflict("Record already exists")) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::Conflict { .. })));
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let mut attempts = 0;
+        let result: Result<()> = with_retry(2, || {
+            attempts += 1;
+            async { Err(Error::serialization_error("Deadlock detected")) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::Serialization { .. })));
+        assert_eq!(attempts, 2);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::error::QuerySnafu;
+use crate::store::PgStore;
+use async_trait::async_trait;
+use rcauth_core::error::Result;
+use rcauth_core::settings::SettingsRepository;
+use snafu::ResultExt;
+
+#[async_trait]
+impl SettingsRepository for PgStore {
+    async fn get_raw(&self, key: &str) -> Result<Option<String>> {
+        let sql = "SELECT value::text FROM settings WHERE key = $1";
+        let value = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql)
+                    .bind(key)
+                    .fetch_optional(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        Ok(value)
+    }
+
+    async fn set_raw(&self, key: &str, value: &str) -> Result<()> {
+        let sql = "INSERT INTO settings (key, value) VALUES ($1, $2::jsonb) \
+                   ON CONFLICT (key) DO UPDATE SET value = excluded.value";
+        self.timed(
+            sql,
+            sqlx::query(sql)
+                .bind(key)
+                .bind(value)
+                .execute(&mut *self.acquire().await?),
+        )
+        .await
+        .context(QuerySnafu)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use rcauth_core::error::ErrorCode;
+    use rcauth_core::store::Store;
+    use uuid::Uuid;
+
+    async fn store() -> PgStore {
+        let store = crate::store::new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        store.run_migrations().await.unwrap();
+        store
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn settings_round_trip_and_are_replaced() {
+        let store = store().await;
+        let settings: &dyn SettingsRepository = &store;
+        let key = format!("test-{}", Uuid::new_v4());
+
+        assert_eq!(settings.get::<bool>(&key).await.unwrap(), None);
+        settings.set(&key, &true).await.unwrap();
+        assert_eq!(settings.get(&key).await.unwrap(), Some(true));
+        settings.set(&key, &false).await.unwrap();
+        assert_eq!(settings.get(&key).await.unwrap(), Some(false));
+
+        settings.set(&key, &vec!["a", "b"]).await.unwrap();
+        assert_eq!(
+            settings.get::<Vec<String>>(&key).await.unwrap(),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn values_of_another_type_fail_to_deserialize() {
+        let store = store().await;
+        let settings: &dyn SettingsRepository = &store;
+        let key = format!("test-{}", Uuid::new_v4());
+
+        settings.set(&key, "on").await.unwrap();
+        let err = settings.get::<bool>(&key).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::Internal);
+    }
+}
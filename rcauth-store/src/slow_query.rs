@@ -0,0 +1,105 @@
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Longest query text logged, in characters; longer queries are cut.
+const MAX_LOGGED_SQL_CHARS: usize = 500;
+
+/// Runs `query`, logging `sql` at WARN with how long it took when that exceeds `threshold`. A zero `threshold` logs nothing.
+///
+/// Only the query text is logged, never the bound values, so parameters such as password hashes stay out of the logs.
+///
+/// # Examples
+///
+/// ```ignore
+/// let sql = "SELECT 1";
+/// timed(Duration::from_millis(500), sql, sqlx::query(sql).execute(&pool)).await?;
+/// ```
+pub(crate) async fn timed<F: Future>(threshold: Duration, sql: &str, query: F) -> F::Output {
+    let started = Instant::now();
+    let output = query.await;
+    let elapsed = started.elapsed();
+
+    if !threshold.is_zero() && elapsed > threshold {
+        warn!(
+            duration_ms = elapsed.as_millis() as u64,
+            threshold_ms = threshold.as_millis() as u64,
+            query = %sanitize(sql),
+            "🐢 Slow query"
+        );
+    }
+    output
+}
+
+/// Collapses the whitespace of `sql` onto one line and cuts it to [`MAX_LOGGED_SQL_CHARS`].
+///
+/// ```ignore
+/// assert_eq!(sanitize("SELECT 1\n    FROM users"), "SELECT 1 FROM users");
+/// ```
+fn sanitize(sql: &str) -> String {
+    let mut line = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some((cut, _)) = line.char_indices().nth(MAX_LOGGED_SQL_CHARS) {
+        line.truncate(cut);
+        line.push('…');
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcauth_core::testing::CapturedLogs;
+
+    #[tokio::test(start_paused = true)]
+    async fn only_queries_over_the_threshold_are_logged() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let threshold = Duration::from_millis(100);
+
+        let fast = "SELECT 1";
+        timed(
+            threshold,
+            fast,
+            tokio::time::sleep(Duration::from_millis(50)),
+        )
+        .await;
+        let slow = "SELECT pg_sleep($1)\n    FROM users WHERE email = $2";
+        timed(
+            threshold,
+            slow,
+            tokio::time::sleep(Duration::from_millis(250)),
+        )
+        .await;
+        // A zero threshold turns the log off
+        timed(
+            Duration::ZERO,
+            fast,
+            tokio::time::sleep(Duration::from_secs(5)),
+        )
+        .await;
+
+        let logs = logs.contents();
+        let lines: Vec<&str> = logs.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", logs);
+        assert!(lines[0].contains("WARN"), "{}", logs);
+        assert!(lines[0].contains("duration_ms=250"), "{}", logs);
+        assert!(
+            lines[0].contains("query=SELECT pg_sleep($1) FROM users WHERE email = $2"),
+            "{}",
+            logs
+        );
+    }
+
+    #[test]
+    fn long_queries_are_cut() {
+        let sql = format!("SELECT {}", "x, ".repeat(400));
+        let logged = sanitize(&sql);
+        assert_eq!(logged.chars().count(), MAX_LOGGED_SQL_CHARS + 1);
+        assert!(logged.ends_with('…'));
+    }
+}
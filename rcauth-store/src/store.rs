@@ -1,26 +1,48 @@
-use crate::error::ConnectionSnafu;
+use crate::error::{ConnectionSnafu, QuerySnafu};
 use crate::{config::Config, error::MigrationSnafu};
 use async_trait::async_trait;
-use rcauth_core::{error::Result, store::Store};
+use futures_util::{Stream, StreamExt};
+use rcauth_core::{
+    error::Result,
+    health::{HealthCheck, MigrationCheck, MigrationStatus},
+    metrics::PoolMetrics,
+    store::Store,
+};
 use snafu::ResultExt;
-use sqlx::postgres::PgPoolOptions;
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgListener, PgNotification, PgPoolOptions};
+use sqlx::{Executor, Postgres};
+use std::collections::HashSet;
 use std::path::Path;
-use tracing::{debug, info};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
 
+/// Postgres-backed store. Clones share the same connection pool and its metrics, including when the pool monitor replaces it.
+#[derive(Clone)]
 pub struct PgStore {
-    pool: sqlx::PgPool,
-    migrations_dir: String,
+    pool: Arc<RwLock<sqlx::PgPool>>,
+    config: Arc<Config>,
+    metrics: PoolMetrics,
 }
 
+/// Connects to PostgreSQL, first opening `min_connections` connections when `warm_pool` is set.
+///
+/// # Errors
+///
+/// Returns a `DatabaseError` if the database is unreachable or a warmup connection cannot be opened.
 pub async fn new(config: Config) -> Result<PgStore> {
     info!("🔌 Connecting to PostgreSQL database");
     match PgStore::connect(&config).await {
         Ok(pool) => {
             info!("✅ Successfully connected to PostgreSQL database");
-            Ok(PgStore {
-                pool,
-                migrations_dir: config.migrations_dir().to_string(),
-            })
+            let warm_target = config.warm_pool.then_some(config.min_connections);
+            let store = PgStore::with_pool(pool, config);
+            if let Some(target) = warm_target {
+                store.warm(target).await?;
+            }
+            Ok(store)
         }
         Err(err) => {
             tracing::error!("❌ Failed to connect to PostgreSQL database: {}", err);
@@ -29,15 +51,225 @@ pub async fn new(config: Config) -> Result<PgStore> {
     }
 }
 
+/// Creates a store whose pool connects on first use instead of up front.
+///
+/// Useful for long-running processes that should start, and report the database as unhealthy, while Postgres is unreachable.
+///
+/// # Errors
+///
+/// Returns a `DatabaseError` if the connection options are invalid.
+pub fn new_lazy(config: Config) -> Result<PgStore> {
+    let pool = session_pool_options(&config)
+        .connect_lazy_with(config.connect_options().context(ConnectionSnafu)?);
+
+    Ok(PgStore::with_pool(pool, config))
+}
+
+/// Returns the pool options with the per-connection session setup applied.
+fn session_pool_options(config: &Config) -> PgPoolOptions {
+    let mut options = config.pool_options();
+
+    // Resolve unqualified names in the configured schema on every connection
+    if let Some(schema) = config.schema() {
+        // The schema is validated as a plain identifier, so quoting it is sufficient
+        let set_search_path = format!("SET search_path TO \"{}\"", schema);
+        options = options.after_connect(move |conn, _meta| {
+            let set_search_path = set_search_path.clone();
+            Box::pin(async move {
+                conn.execute(set_search_path.as_str()).await?;
+                Ok(())
+            })
+        });
+    }
+
+    options
+}
+
+impl PgStore {
+    fn with_pool(pool: sqlx::PgPool, config: Config) -> Self {
+        Self {
+            pool: Arc::new(RwLock::new(pool)),
+            config: Arc::new(config),
+            metrics: PoolMetrics::default(),
+        }
+    }
+
+    /// Returns the pool queries should run on; it changes when the pool monitor rebuilds it.
+    pub(crate) fn current_pool(&self) -> sqlx::PgPool {
+        self.pool.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Checks a connection out of the pool, recording in the pool metrics how long it waited for one, or that it gave up.
+    ///
+    /// Queries run on a connection from here rather than on the pool itself, so the metrics see every acquisition, e.g. `sqlx::query(sql).execute(&mut *self.acquire().await?)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DatabaseError` if no connection frees up within `acquire_timeout_secs`, or a new one cannot be opened.
+    pub(crate) async fn acquire(&self) -> Result<PoolConnection<Postgres>> {
+        let started = Instant::now();
+        let connection = self.current_pool().acquire().await;
+        match &connection {
+            Ok(_) => self.metrics.record_acquire(started.elapsed()),
+            Err(sqlx::Error::PoolTimedOut) => self.metrics.record_acquire_timeout(),
+            Err(_) => {}
+        }
+        Ok(connection.context(ConnectionSnafu)?)
+    }
+
+    /// Returns the wait times and timeouts of the pool's acquisitions, e.g. to serve them at `/metrics`.
+    pub fn pool_metrics(&self) -> PoolMetrics {
+        self.metrics.clone()
+    }
+
+    /// Returns the configuration the store was created with.
+    pub(crate) fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Runs `query`, logging its `sql` as slow when it takes longer than `slow_query_threshold_ms`.
+    ///
+    /// Every query of the store goes through here, e.g. `self.timed(sql, sqlx::query(sql).execute(&mut *self.acquire().await?))`.
+    pub(crate) async fn timed<F: Future>(&self, sql: &str, query: F) -> F::Output {
+        let threshold = Duration::from_millis(self.config.slow_query_threshold_ms);
+        crate::slow_query::timed(threshold, sql, query).await
+    }
+
+    /// Starts pinging the database every `pool_monitor_interval_secs`, rebuilding the pool from the configuration after `pool_monitor_failure_threshold` pings in a row fail.
+    ///
+    /// A failover leaves the pool holding connections to a server that is gone; rebuilding it recovers without a restart. The monitor stops once the store is closed. Returns `None` when `pool_monitor_interval_secs` is zero, which disables it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example(config: rcauth_store::config::Config) -> rcauth_core::error::Result<()> {
+    /// let store = rcauth_store::store::new(config).await?;
+    /// let monitor = store.spawn_pool_monitor();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn_pool_monitor(&self) -> Option<JoinHandle<()>> {
+        if self.config.pool_monitor_interval_secs == 0 {
+            return None;
+        }
+        Some(tokio::spawn(monitor_pool(
+            self.clone(),
+            Duration::from_secs(self.config.pool_monitor_interval_secs),
+            self.config.pool_monitor_failure_threshold,
+        )))
+    }
+
+    /// Closes every pooled connection, waiting for checked-out ones to be returned first.
+    ///
+    /// The pool is shared by all clones of this store, and after `close` every one of them is unusable: further queries fail with a `DatabaseError` instead of reconnecting.
+    pub async fn close(&self) {
+        info!("Closing database connections");
+        // The pool monitor may swap in a rebuilt pool while the old one closes; rebuilds are refused once it is marked closed
+        loop {
+            self.current_pool().close().await;
+            if self.current_pool().is_closed() {
+                break;
+            }
+        }
+    }
+
+    /// Subscribes to Postgres notifications on `channels`, e.g. to drop cached state when another instance changes it.
+    ///
+    /// The listener holds its own connection for as long as the stream lives. If that connection drops, it reconnects on the next poll, yielding an error first; notifications sent while disconnected are lost, so treat an error as "anything may have changed".
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DatabaseError` if the listener cannot connect or subscribe.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use futures_util::StreamExt;
+    /// # async fn example(store: rcauth_store::store::PgStore) -> rcauth_core::error::Result<()> {
+    /// let mut notifications = store.listen(&["user_disabled"]).await?;
+    /// while let Some(notification) = notifications.next().await {
+    ///     println!("user {} was disabled", notification?.payload());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn listen(
+        &self,
+        channels: &[&str],
+    ) -> Result<impl Stream<Item = Result<PgNotification>> + Send + Unpin + use<>> {
+        let mut listener = PgListener::connect_with(&self.current_pool())
+            .await
+            .context(ConnectionSnafu)?;
+        listener
+            .listen_all(channels.iter().copied())
+            .await
+            .context(QuerySnafu)?;
+
+        Ok(listener
+            .into_stream()
+            .map(|notification| Ok(notification.context(QuerySnafu)?)))
+    }
+
+    /// Sends `payload` to every listener on `channel`.
+    ///
+    /// Called inside a transaction, the notification is only delivered if the transaction commits.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DatabaseError` if the query fails.
+    pub async fn notify(&self, channel: &str, payload: &str) -> Result<()> {
+        let sql = "SELECT pg_notify($1, $2)";
+        self.timed(
+            sql,
+            sqlx::query(sql)
+                .bind(channel)
+                .bind(payload)
+                .execute(&mut *self.acquire().await?),
+        )
+        .await
+        .context(QuerySnafu)?;
+        Ok(())
+    }
+
+    /// Opens `target` connections at once and returns them to the pool, leaving them idle and ready.
+    ///
+    /// sqlx also tops the pool up to `min_connections`, but only in the background after it is created, so the first requests would still wait for connections.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DatabaseError` if a connection cannot be opened.
+    pub async fn warm(&self, target: u32) -> Result<()> {
+        // Hold each connection until all are open, so every acquire opens a new one
+        let mut held = Vec::with_capacity(target as usize);
+        for _ in 0..target {
+            held.push(self.acquire().await?);
+        }
+        drop(held);
+
+        // Dropped connections are handed back by background tasks; wait briefly for them to land
+        let returned = async {
+            while self.current_pool().num_idle() < target as usize {
+                tokio::task::yield_now().await;
+            }
+        };
+        let _ = tokio::time::timeout(Duration::from_secs(1), returned).await;
+
+        info!(
+            "🔥 Warmed connection pool with {} connections",
+            self.current_pool().num_idle()
+        );
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Store for PgStore {
     type Configuration = Config;
     type Pool = sqlx::PgPool;
 
     async fn connect(config: &Config) -> Result<sqlx::PgPool> {
-        let pool = PgPoolOptions::new()
-            .max_connections(config.pool_size())
-            .connect(&config.connection_string())
+        let pool = session_pool_options(config)
+            .connect_with(config.connect_options().context(ConnectionSnafu)?)
             .await
             .context(ConnectionSnafu)?;
 
@@ -45,14 +277,14 @@ impl Store for PgStore {
     }
 
     async fn run_migrations(&self) -> Result<()> {
-        let migrations_dir = Path::new(self.migrations_dir.as_str());
+        let migrations_dir = Path::new(self.config.migrations_dir());
 
         debug!("Running migrations from directory: {:?}", migrations_dir);
 
         let _ = sqlx::migrate::Migrator::new(migrations_dir)
             .await
             .context(MigrationSnafu)?
-            .run(&self.pool)
+            .run(&self.current_pool())
             .await
             .context(MigrationSnafu)?;
 
@@ -60,6 +292,473 @@ impl Store for PgStore {
     }
 
     async fn pool(&self) -> Result<sqlx::PgPool> {
-        Ok(self.pool.clone())
+        Ok(self.current_pool())
+    }
+}
+
+#[async_trait]
+impl HealthCheck for PgStore {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    async fn check(&self) -> Result<()> {
+        let sql = "SELECT 1";
+        self.timed(sql, sqlx::query(sql).execute(&mut *self.acquire().await?))
+            .await
+            .context(QuerySnafu)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MigrationCheck for PgStore {
+    async fn migration_status(&self) -> Result<MigrationStatus> {
+        let migrator = sqlx::migrate::Migrator::new(Path::new(self.config.migrations_dir()))
+            .await
+            .context(MigrationSnafu)?;
+        let available = migrator
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| migration.version);
+
+        // A database that was never migrated has no migrations table yet
+        let sql = "SELECT to_regclass('_sqlx_migrations') IS NOT NULL";
+        let tracked: bool = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql).fetch_one(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        if !tracked {
+            return Ok(MigrationStatus {
+                pending: count_pending(available, &HashSet::new()),
+                ..MigrationStatus::default()
+            });
+        }
+
+        let sql =
+            "SELECT version, description FROM _sqlx_migrations WHERE success ORDER BY version";
+        let applied: Vec<(i64, String)> = self
+            .timed(
+                sql,
+                sqlx::query_as(sql).fetch_all(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        let versions = applied.iter().map(|(version, _)| *version).collect();
+        let latest = applied.last().cloned();
+
+        Ok(MigrationStatus {
+            pending: count_pending(available, &versions),
+            version: latest.as_ref().map(|(version, _)| *version),
+            description: latest.map(|(_, description)| description),
+        })
+    }
+}
+
+/// Counts the `available` migration versions missing from `applied`.
+fn count_pending(available: impl Iterator<Item = i64>, applied: &HashSet<i64>) -> u64 {
+    available
+        .filter(|version| !applied.contains(version))
+        .count() as u64
+}
+
+/// A pool the monitor can probe and replace.
+#[async_trait]
+trait MonitoredPool: Send + Sync {
+    async fn ping(&self) -> Result<()>;
+
+    /// Replaces the pool with a freshly connected one.
+    async fn rebuild(&self) -> Result<()>;
+
+    fn is_closed(&self) -> bool;
+}
+
+#[async_trait]
+impl MonitoredPool for PgStore {
+    async fn ping(&self) -> Result<()> {
+        self.check().await
+    }
+
+    async fn rebuild(&self) -> Result<()> {
+        let fresh = PgStore::connect(&self.config).await?;
+        let stale = {
+            let mut pool = self.pool.write().unwrap_or_else(|e| e.into_inner());
+            // The store was closed while connecting, so discard the fresh pool instead
+            if pool.is_closed() {
+                fresh
+            } else {
+                std::mem::replace(&mut *pool, fresh)
+            }
+        };
+        // Let queries still running on the discarded pool finish
+        tokio::spawn(async move { stale.close().await });
+        Ok(())
+    }
+
+    fn is_closed(&self) -> bool {
+        self.current_pool().is_closed()
+    }
+}
+
+/// Pings `pool` every `interval`, rebuilding it once `threshold` pings in a row have failed, until it is closed.
+///
+/// A failed rebuild is retried on every following failed ping.
+async fn monitor_pool(pool: impl MonitoredPool, interval: Duration, threshold: u32) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut failures = 0u32;
+
+    loop {
+        ticker.tick().await;
+        if pool.is_closed() {
+            debug!("Database pool closed, stopping its monitor");
+            return;
+        }
+
+        // A dead connection can hang rather than fail, so give up by the next ping
+        let ping = tokio::time::timeout(interval, pool.ping()).await;
+        match ping {
+            Ok(Ok(())) => {
+                if failures > 0 {
+                    info!(failures, "✅ Database connection recovered");
+                }
+                failures = 0;
+            }
+            Ok(Err(err)) => {
+                failures += 1;
+                warn!(failures, error = %err, "Database ping failed");
+            }
+            Err(_) => {
+                failures += 1;
+                warn!(failures, "Database ping timed out");
+            }
+        }
+
+        if failures >= threshold {
+            match pool.rebuild().await {
+                Ok(()) => warn!(failures, "♻️ Rebuilt database connection pool"),
+                Err(err) => {
+                    error!(failures, error = %err, "Failed to rebuild database connection pool")
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    /// A pool that is down until rebuilt, whose first `failing_rebuilds` rebuilds fail.
+    #[derive(Clone, Default)]
+    struct FakePool {
+        up: Arc<AtomicBool>,
+        closed: Arc<AtomicBool>,
+        rebuilds: Arc<AtomicU32>,
+        failing_rebuilds: u32,
+    }
+
+    #[async_trait]
+    impl MonitoredPool for FakePool {
+        async fn ping(&self) -> Result<()> {
+            if self.up.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err(rcauth_core::error::Error::new_simple(
+                    rcauth_core::error::ErrorCode::DatabaseError,
+                    "connection reset",
+                ))
+            }
+        }
+
+        async fn rebuild(&self) -> Result<()> {
+            let attempt = self.rebuilds.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt <= self.failing_rebuilds {
+                return self.ping().await;
+            }
+            self.up.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn is_closed(&self) -> bool {
+            self.closed.load(Ordering::SeqCst)
+        }
+    }
+
+    const INTERVAL: Duration = Duration::from_secs(10);
+
+    #[tokio::test(start_paused = true)]
+    async fn monitor_rebuilds_pool_after_sustained_failure() {
+        let pool = FakePool::default();
+        let monitor = tokio::spawn(monitor_pool(pool.clone(), INTERVAL, 3));
+
+        // Pings at 0s and 10s fail, which is not yet sustained
+        tokio::time::sleep(Duration::from_secs(15)).await;
+        assert_eq!(pool.rebuilds.load(Ordering::SeqCst), 0);
+
+        // The third failure, at 20s, triggers the rebuild
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(pool.rebuilds.load(Ordering::SeqCst), 1);
+        assert!(pool.up.load(Ordering::SeqCst));
+
+        // Healthy pings leave the new pool alone
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(pool.rebuilds.load(Ordering::SeqCst), 1);
+
+        pool.closed.store(true, Ordering::SeqCst);
+        tokio::time::timeout(INTERVAL * 2, monitor)
+            .await
+            .expect("monitor stops once the pool is closed")
+            .unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_rebuild_is_retried_on_the_next_ping() {
+        let pool = FakePool {
+            failing_rebuilds: 1,
+            ..FakePool::default()
+        };
+        tokio::spawn(monitor_pool(pool.clone(), INTERVAL, 2));
+
+        // Rebuilds at 10s, which fails, and at 20s
+        tokio::time::sleep(Duration::from_secs(25)).await;
+        assert_eq!(pool.rebuilds.load(Ordering::SeqCst), 2);
+        assert!(pool.up.load(Ordering::SeqCst));
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(pool.rebuilds.load(Ordering::SeqCst), 2);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn queries_resolve_in_configured_schema() {
+        let config = Config::new().expect("RCAUTH_POSTGRES_* must be set");
+
+        let admin = PgStore::connect(&config).await.unwrap();
+        admin
+            .execute("CREATE SCHEMA IF NOT EXISTS rcauth_schema_test")
+            .await
+            .unwrap();
+        admin
+            .execute(
+                "CREATE TABLE IF NOT EXISTS rcauth_schema_test.marker (id int); \
+                 TRUNCATE rcauth_schema_test.marker; \
+                 INSERT INTO rcauth_schema_test.marker VALUES (42)",
+            )
+            .await
+            .unwrap();
+
+        let scoped = Config {
+            schema: Some("rcauth_schema_test".to_string()),
+            ..config
+        };
+        let pool = PgStore::connect(&scoped).await.unwrap();
+
+        let schema: String = sqlx::query_scalar("SELECT current_schema()")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(schema, "rcauth_schema_test");
+
+        let marker: i32 = sqlx::query_scalar("SELECT id FROM marker")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(marker, 42);
+
+        admin
+            .execute("DROP SCHEMA rcauth_schema_test CASCADE")
+            .await
+            .unwrap();
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn connections_report_application_name() {
+        let config = Config {
+            application_name: "rcauth-test".to_string(),
+            ..Config::new().expect("RCAUTH_POSTGRES_* must be set")
+        };
+        let pool = PgStore::connect(&config).await.unwrap();
+
+        let name: String = sqlx::query_scalar(
+            "SELECT application_name FROM pg_stat_activity WHERE pid = pg_backend_pid()",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(name, "rcauth-test");
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn warm_pool_opens_min_connections_up_front() {
+        let config = Config {
+            pool_size: 5,
+            min_connections: 3,
+            warm_pool: true,
+            ..Config::new().expect("RCAUTH_POSTGRES_* must be set")
+        };
+        let store = new(config).await.unwrap();
+        assert_eq!(store.current_pool().size(), 3);
+        assert_eq!(store.current_pool().num_idle(), 3);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn warm_fills_a_lazy_pool() {
+        let config = Config {
+            pool_size: 5,
+            ..Config::new().expect("RCAUTH_POSTGRES_* must be set")
+        };
+        let store = new_lazy(config).unwrap();
+        assert_eq!(store.current_pool().size(), 0);
+
+        store.warm(2).await.unwrap();
+        assert_eq!(store.current_pool().num_idle(), 2);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn rebuild_after_close_keeps_the_store_closed() {
+        let store = new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        store.close().await;
+
+        store.rebuild().await.unwrap();
+        assert!(store.is_closed());
+        assert!(store.check().await.is_err());
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn listener_receives_notifications() {
+        let store = new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        let mut notifications = store.listen(&["rcauth_listen_test"]).await.unwrap();
+
+        store.notify("rcauth_listen_test", "user-42").await.unwrap();
+        store
+            .notify("rcauth_other_channel", "ignored")
+            .await
+            .unwrap();
+
+        let notification = tokio::time::timeout(Duration::from_secs(5), notifications.next())
+            .await
+            .expect("notification should arrive")
+            .unwrap()
+            .unwrap();
+        assert_eq!(notification.channel(), "rcauth_listen_test");
+        assert_eq!(notification.payload(), "user-42");
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn operations_fail_after_close() {
+        let store = new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        let handle = store.clone();
+        store.check().await.unwrap();
+
+        store.close().await;
+        assert!(store.current_pool().is_closed());
+        assert!(store.check().await.is_err());
+        // Clones share the pool, so they are closed too
+        assert!(handle.check().await.is_err());
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn saturated_pool_records_acquire_waits() {
+        let config = Config {
+            pool_size: 1,
+            ..Config::new().expect("RCAUTH_POSTGRES_* must be set")
+        };
+        let store = new(config).await.unwrap();
+        let metrics = store.pool_metrics();
+
+        let held = store.acquire().await.unwrap();
+        let waited_before = metrics.acquire_wait();
+        let waiting = tokio::spawn({
+            let store = store.clone();
+            async move { store.check().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(held);
+        waiting.await.unwrap().unwrap();
+
+        assert_eq!(metrics.acquires(), 2);
+        assert!(metrics.acquire_wait() - waited_before >= Duration::from_millis(50));
+        assert_eq!(metrics.acquire_timeouts(), 0);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn acquire_timeouts_are_counted() {
+        let config = Config {
+            pool_size: 1,
+            acquire_timeout_secs: 1,
+            ..Config::new().expect("RCAUTH_POSTGRES_* must be set")
+        };
+        let store = new(config).await.unwrap();
+        let metrics = store.pool_metrics();
+
+        let _held = store.acquire().await.unwrap();
+        let err = store.check().await.unwrap_err();
+        assert_eq!(err.code, rcauth_core::error::ErrorCode::DatabaseError);
+        assert_eq!(metrics.acquire_timeouts(), 1);
+        assert_eq!(metrics.acquires(), 1);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn health_check_pings_database() {
+        let store = new_lazy(Config::new().expect("RCAUTH_POSTGRES_* must be set")).unwrap();
+        assert_eq!(store.name(), "database");
+        store.check().await.unwrap();
+    }
+
+    #[test]
+    fn unapplied_migrations_are_pending() {
+        let applied = HashSet::from([1, 2]);
+        assert_eq!(count_pending([1, 2].into_iter(), &applied), 0);
+        assert_eq!(count_pending([1, 2, 3, 4].into_iter(), &applied), 2);
+        assert_eq!(count_pending([1, 2].into_iter(), &HashSet::new()), 2);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn migrated_database_has_no_pending_migrations() {
+        let store = new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        store.run_migrations().await.unwrap();
+
+        let status = store.migration_status().await.unwrap();
+        assert!(status.is_up_to_date());
+        let migrator = sqlx::migrate::Migrator::new(Path::new(store.config.migrations_dir()))
+            .await
+            .unwrap();
+        let latest = migrator.iter().last().unwrap();
+        assert_eq!(status.version, Some(latest.version));
+        assert_eq!(status.description.as_deref(), Some(&*latest.description));
     }
 }
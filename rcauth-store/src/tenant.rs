@@ -0,0 +1,32 @@
+use crate::error::QuerySnafu;
+use crate::store::PgStore;
+use rcauth_core::error::Result;
+use snafu::ResultExt;
+use uuid::Uuid;
+
+/// Creates the tenant with slug `$1` unless it exists, returning its id either way.
+///
+/// The no-op update makes RETURNING yield a tenant that already exists.
+pub(crate) const ENSURE_TENANT: &str = "INSERT INTO tenants (name, slug) VALUES ($1, $1) \
+     ON CONFLICT (lower(slug)) DO UPDATE SET slug = tenants.slug \
+     RETURNING id";
+
+impl PgStore {
+    /// Returns the id of the tenant with `slug`, compared ignoring case, creating the tenant if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `DatabaseError` if the tenant cannot be read or created.
+    pub async fn ensure_tenant(&self, slug: &str) -> Result<String> {
+        let id: Uuid = self
+            .timed(
+                ENSURE_TENANT,
+                sqlx::query_scalar(ENSURE_TENANT)
+                    .bind(slug)
+                    .fetch_one(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        Ok(id.to_string())
+    }
+}
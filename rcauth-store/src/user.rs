@@ -0,0 +1,1116 @@
+use crate::error::{QuerySnafu, handle_sqlx_error};
+use crate::store::PgStore;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rcauth_core::error::{Error, ErrorCode, Result};
+use rcauth_core::user::{
+    BulkCreateReport, NewUser, UserChanges, UserFilter, UserProfile, UserRepository,
+};
+use snafu::ResultExt;
+use sqlx::{Connection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+/// Users inserted per statement by `bulk_create`, keeping each statement well under Postgres' limit of 65535 bound values.
+const BULK_CREATE_CHUNK: usize = 1000;
+
+#[async_trait]
+impl UserRepository for PgStore {
+    async fn profile(&self, id: &str) -> Result<Option<UserProfile>> {
+        // Every user id is a UUID, so anything else names no user
+        let Ok(id) = Uuid::parse_str(id) else {
+            return Ok(None);
+        };
+
+        let sql = "SELECT id, email, email_confirmed_at IS NOT NULL, must_reset_password, version \
+                   FROM users WHERE id = $1 AND deleted_at IS NULL";
+        let row: Option<(Uuid, String, bool, bool, i64)> = self
+            .timed(
+                sql,
+                sqlx::query_as(sql)
+                    .bind(id)
+                    .fetch_optional(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+
+        Ok(row.map(
+            |(id, email, email_verified, must_reset_password, version)| UserProfile {
+                id: id.to_string(),
+                email,
+                email_verified,
+                // Users have no display name yet
+                name: None,
+                must_reset_password,
+                version: version as u64,
+            },
+        ))
+    }
+
+    async fn profile_including_deleted(
+        &self,
+        id: &str,
+    ) -> Result<Option<(UserProfile, Option<DateTime<Utc>>)>> {
+        let Ok(id) = Uuid::parse_str(id) else {
+            return Ok(None);
+        };
+
+        let sql = "SELECT id, email, email_confirmed_at IS NOT NULL, must_reset_password, version, \
+                   deleted_at FROM users WHERE id = $1";
+        let row: Option<(Uuid, String, bool, bool, i64, Option<DateTime<Utc>>)> = self
+            .timed(
+                sql,
+                sqlx::query_as(sql)
+                    .bind(id)
+                    .fetch_optional(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+
+        Ok(row.map(
+            |(id, email, email_verified, must_reset_password, version, deleted_at)| {
+                let profile = UserProfile {
+                    id: id.to_string(),
+                    email,
+                    email_verified,
+                    name: None,
+                    must_reset_password,
+                    version: version as u64,
+                };
+                (profile, deleted_at)
+            },
+        ))
+    }
+
+    async fn delete(&self, id: &str) -> Result<Option<u64>> {
+        let Ok(id) = Uuid::parse_str(id) else {
+            return Ok(None);
+        };
+
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await.context(QuerySnafu)?;
+        let sql = "UPDATE users SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL";
+        let deleted = self
+            .timed(sql, sqlx::query(sql).bind(id).execute(&mut *tx))
+            .await
+            .context(QuerySnafu)?;
+        if deleted.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let sql = "UPDATE refresh_tokens SET revoked = true \
+                   WHERE user_id = $1 AND revoked IS NOT TRUE";
+        let revoked = self
+            .timed(sql, sqlx::query(sql).bind(id).execute(&mut *tx))
+            .await
+            .context(QuerySnafu)?;
+        tx.commit().await.context(QuerySnafu)?;
+
+        Ok(Some(revoked.rows_affected()))
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        expected_version: u64,
+        changes: &UserChanges,
+    ) -> Result<Option<UserProfile>> {
+        let Ok(id) = Uuid::parse_str(id) else {
+            return Ok(None);
+        };
+
+        let mut query = update_query(id, expected_version, changes);
+        let sql = query.sql().to_owned();
+        let row: Option<(Uuid, String, bool, bool, i64)> = self
+            .timed(
+                &sql,
+                query
+                    .build_query_as()
+                    .fetch_optional(&mut *self.acquire().await?),
+            )
+            .await
+            // A unique violation means the new email is taken
+            .map_err(handle_sqlx_error)?;
+        if let Some((id, email, email_verified, must_reset_password, version)) = row {
+            return Ok(Some(UserProfile {
+                id: id.to_string(),
+                email,
+                email_verified,
+                name: None,
+                must_reset_password,
+                version: version as u64,
+            }));
+        }
+
+        // Nothing matched: either there is no such user, or their version moved on
+        let sql = "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL)";
+        let exists: bool = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql)
+                    .bind(id)
+                    .fetch_one(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        if exists {
+            return Err(Error::new_simple(
+                ErrorCode::Conflict,
+                "The user was changed since it was read",
+            ));
+        }
+        Ok(None)
+    }
+
+    async fn purge(&self, deleted_before: DateTime<Utc>) -> Result<u64> {
+        // Sessions and identities go with the user through their cascading foreign keys
+        let sql = "DELETE FROM users WHERE deleted_at < $1";
+        let purged = self
+            .timed(
+                sql,
+                sqlx::query(sql)
+                    .bind(deleted_before)
+                    .execute(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        Ok(purged.rows_affected())
+    }
+
+    async fn force_password_reset(&self, id: &str) -> Result<Option<u64>> {
+        let Ok(id) = Uuid::parse_str(id) else {
+            return Ok(None);
+        };
+
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await.context(QuerySnafu)?;
+        let sql = "UPDATE users SET must_reset_password = true \
+                   WHERE id = $1 AND deleted_at IS NULL";
+        let flagged = self
+            .timed(sql, sqlx::query(sql).bind(id).execute(&mut *tx))
+            .await
+            .context(QuerySnafu)?;
+        if flagged.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let sql = "UPDATE refresh_tokens SET revoked = true \
+                   WHERE user_id = $1 AND revoked IS NOT TRUE";
+        let revoked = self
+            .timed(sql, sqlx::query(sql).bind(id).execute(&mut *tx))
+            .await
+            .context(QuerySnafu)?;
+        tx.commit().await.context(QuerySnafu)?;
+
+        Ok(Some(revoked.rows_affected()))
+    }
+
+    async fn count(&self, filter: &UserFilter) -> Result<u64> {
+        let mut query = count_query(filter);
+        let sql = query.sql().to_owned();
+        let count: i64 = self
+            .timed(
+                &sql,
+                query
+                    .build_query_scalar()
+                    .fetch_one(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        Ok(count as u64)
+    }
+
+    async fn upsert_by_email(&self, user: NewUser) -> Result<(UserProfile, bool)> {
+        let tenant_id = Uuid::parse_str(&user.tenant_id)
+            .map_err(|e| Error::new(ErrorCode::Invalid, "Invalid tenant id", e))?;
+
+        // The no-op update makes RETURNING yield the existing row, which keeps its password hash,
+        // unless the user is deleted, when there is no row.
+        // `xmax` is only zero on a row this statement inserted.
+        let sql = "INSERT INTO users (tenant_id, email, encrypted_password, role, email_confirmed_at) \
+                   VALUES ($1, $2, $3, $4, CASE WHEN $5 THEN now() END) \
+                   ON CONFLICT (tenant_id, lower(email)) DO UPDATE SET email = users.email \
+                   WHERE users.deleted_at IS NULL \
+                   RETURNING id, email, email_confirmed_at IS NOT NULL, must_reset_password, version, \
+                   xmax = 0";
+        let row: Option<(Uuid, String, bool, bool, i64, bool)> = self
+            .timed(
+                sql,
+                sqlx::query_as(sql)
+                    .bind(tenant_id)
+                    .bind(&user.email)
+                    // Provider-only users get an empty hash, which matches no password
+                    .bind(user.password_hash.unwrap_or_default())
+                    .bind(&user.role)
+                    .bind(user.email_verified)
+                    .fetch_optional(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        let Some((id, email, email_verified, must_reset_password, version, created)) = row else {
+            return Err(Error::new_simple(
+                ErrorCode::Conflict,
+                "The email belongs to a deleted user",
+            ));
+        };
+
+        let profile = UserProfile {
+            id: id.to_string(),
+            email,
+            email_verified,
+            name: None,
+            must_reset_password,
+            version: version as u64,
+        };
+        Ok((profile, created))
+    }
+
+    async fn bulk_create(&self, users: &[NewUser]) -> Result<BulkCreateReport> {
+        let tenant_ids = users
+            .iter()
+            .map(|user| Uuid::parse_str(&user.tenant_id))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::new(ErrorCode::Invalid, "Invalid tenant id", e))?;
+
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await.context(QuerySnafu)?;
+        let mut inserted = 0;
+        for (users, tenant_ids) in users
+            .chunks(BULK_CREATE_CHUNK)
+            .zip(tenant_ids.chunks(BULK_CREATE_CHUNK))
+        {
+            let mut query = bulk_insert_query(users, tenant_ids);
+            let sql = query.sql().to_owned();
+            let result = self
+                .timed(&sql, query.build().execute(&mut *tx))
+                .await
+                .context(QuerySnafu)?;
+            inserted += result.rows_affected();
+        }
+        tx.commit().await.context(QuerySnafu)?;
+
+        Ok(BulkCreateReport {
+            inserted,
+            skipped: users.len() as u64 - inserted,
+        })
+    }
+
+    async fn find_by_email(&self, tenant_id: &str, email: &str) -> Result<Option<UserProfile>> {
+        let Ok(tenant_id) = Uuid::parse_str(tenant_id) else {
+            return Ok(None);
+        };
+
+        // Matches the unique (tenant_id, lower(email)) index, which also covers emails stored before normalization
+        let sql = "SELECT id, email, email_confirmed_at IS NOT NULL, must_reset_password, version \
+                   FROM users WHERE tenant_id = $1 AND lower(email) = lower($2) AND deleted_at IS NULL";
+        let row: Option<(Uuid, String, bool, bool, i64)> = self
+            .timed(
+                sql,
+                sqlx::query_as(sql)
+                    .bind(tenant_id)
+                    .bind(email)
+                    .fetch_optional(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+
+        Ok(row.map(
+            |(id, email, email_verified, must_reset_password, version)| UserProfile {
+                id: id.to_string(),
+                email,
+                email_verified,
+                name: None,
+                must_reset_password,
+                version: version as u64,
+            },
+        ))
+    }
+
+    async fn password_hash(&self, id: &str) -> Result<Option<String>> {
+        let Ok(id) = Uuid::parse_str(id) else {
+            return Ok(None);
+        };
+
+        // Provider-only users have an empty hash
+        let sql = "SELECT NULLIF(encrypted_password, '') FROM users \
+                   WHERE id = $1 AND deleted_at IS NULL";
+        let hash: Option<Option<String>> = self
+            .timed(
+                sql,
+                sqlx::query_scalar(sql)
+                    .bind(id)
+                    .fetch_optional(&mut *self.acquire().await?),
+            )
+            .await
+            .context(QuerySnafu)?;
+        Ok(hash.flatten())
+    }
+
+    async fn change_password(
+        &self,
+        id: &str,
+        password_hash: &str,
+        keep_session: Option<&str>,
+    ) -> Result<Option<u64>> {
+        let Ok(id) = Uuid::parse_str(id) else {
+            return Ok(None);
+        };
+        // Session ids are UUIDs, so anything else names no session to keep
+        let keep_session = keep_session.and_then(|session| Uuid::parse_str(session).ok());
+
+        let mut connection = self.acquire().await?;
+        let mut tx = connection.begin().await.context(QuerySnafu)?;
+        let sql = "UPDATE users SET encrypted_password = $2, must_reset_password = false \
+                   WHERE id = $1 AND deleted_at IS NULL";
+        let updated = self
+            .timed(
+                sql,
+                sqlx::query(sql)
+                    .bind(id)
+                    .bind(password_hash)
+                    .execute(&mut *tx),
+            )
+            .await
+            .context(QuerySnafu)?;
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let sql = "UPDATE refresh_tokens SET revoked = true \
+                   WHERE user_id = $1 AND revoked IS NOT TRUE AND session_id IS DISTINCT FROM $2";
+        let revoked = self
+            .timed(
+                sql,
+                sqlx::query(sql)
+                    .bind(id)
+                    .bind(keep_session)
+                    .execute(&mut *tx),
+            )
+            .await
+            .context(QuerySnafu)?;
+        tx.commit().await.context(QuerySnafu)?;
+
+        Ok(Some(revoked.rows_affected()))
+    }
+}
+
+/// Builds one multi-row insert of `users`, each into the tenant at the same position of `tenant_ids`, that skips emails already taken.
+fn bulk_insert_query<'a>(users: &'a [NewUser], tenant_ids: &[Uuid]) -> QueryBuilder<'a, Postgres> {
+    let mut query = QueryBuilder::new(
+        "INSERT INTO users (tenant_id, email, encrypted_password, role, email_confirmed_at) ",
+    );
+    query.push_values(
+        users.iter().zip(tenant_ids),
+        |mut row, (user, tenant_id)| {
+            row.push_bind(*tenant_id)
+                .push_bind(&user.email)
+                // Provider-only users get an empty hash, which matches no password
+                .push_bind(user.password_hash.as_deref().unwrap_or_default())
+                .push_bind(&user.role)
+                .push_bind(user.email_verified.then(Utc::now));
+        },
+    );
+    // Also skips an email repeated within the batch, keeping its first entry, and one of a deleted user
+    query.push(" ON CONFLICT (tenant_id, lower(email)) DO NOTHING");
+    query
+}
+
+/// Builds an update applying `changes` to the user with `id` only at `expected_version`, bumping the version and returning the updated profile.
+fn update_query(
+    id: Uuid,
+    expected_version: u64,
+    changes: &UserChanges,
+) -> QueryBuilder<'_, Postgres> {
+    let mut query = QueryBuilder::new("UPDATE users SET version = version + 1");
+    if let Some(email) = &changes.email {
+        query.push(", email = ").push_bind(email);
+    }
+    if let Some(email_verified) = changes.email_verified {
+        // Keeps the original confirmation time of an email that stays verified
+        query
+            .push(", email_confirmed_at = CASE WHEN ")
+            .push_bind(email_verified)
+            .push(" THEN coalesce(email_confirmed_at, now()) END");
+    }
+    if let Some(role) = &changes.role {
+        query.push(", role = ").push_bind(role);
+    }
+    query
+        .push(" WHERE id = ")
+        .push_bind(id)
+        .push(" AND version = ")
+        .push_bind(expected_version as i64)
+        .push(
+            " AND deleted_at IS NULL \
+             RETURNING id, email, email_confirmed_at IS NOT NULL, must_reset_password, version",
+        );
+    query
+}
+
+/// Builds a single `COUNT(*)` over the users matching `filter`, binding every value.
+fn count_query(filter: &UserFilter) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new("SELECT count(*) FROM users WHERE TRUE");
+    if !filter.include_deleted {
+        query.push(" AND deleted_at IS NULL");
+    }
+    if let Some(verified) = filter.verified {
+        query.push(if verified {
+            " AND email_confirmed_at IS NOT NULL"
+        } else {
+            " AND email_confirmed_at IS NULL"
+        });
+    }
+    if let Some(must_reset_password) = filter.must_reset_password {
+        query
+            .push(" AND must_reset_password = ")
+            .push_bind(must_reset_password);
+    }
+    if let Some(created_after) = filter.created_after {
+        query.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = filter.created_before {
+        query.push(" AND created_at < ").push_bind(created_before);
+    }
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use chrono::Duration;
+    use rcauth_core::email::EmailNormalizer;
+    use rcauth_core::store::Store;
+
+    async fn store() -> PgStore {
+        let store = crate::store::new(Config::new().expect("RCAUTH_POSTGRES_* must be set"))
+            .await
+            .unwrap();
+        store.run_migrations().await.unwrap();
+        store
+    }
+
+    async fn seed_tenant(store: &PgStore) -> Uuid {
+        sqlx::query_scalar("INSERT INTO tenants (name, slug) VALUES ($1, $1) RETURNING id")
+            .bind(Uuid::new_v4().to_string())
+            .fetch_one(&store.current_pool())
+            .await
+            .unwrap()
+    }
+
+    /// Inserts a user with `sessions` live refresh tokens, returning its id.
+    async fn seed_user(store: &PgStore, sessions: usize) -> Uuid {
+        let slug = Uuid::new_v4().to_string();
+        let tenant = seed_tenant(store).await;
+        let user: Uuid = sqlx::query_scalar(
+            "INSERT INTO users (tenant_id, email, encrypted_password, role) \
+             VALUES ($1, $2, 'hash', 'user') RETURNING id",
+        )
+        .bind(tenant)
+        .bind(format!("{}@example.com", slug))
+        .fetch_one(&store.current_pool())
+        .await
+        .unwrap();
+        for _ in 0..sessions {
+            sqlx::query(
+                "INSERT INTO refresh_tokens (tenant_id, session_id, user_id, token) \
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(tenant)
+            .bind(Uuid::new_v4())
+            .bind(user)
+            .bind(Uuid::new_v4().to_string())
+            .execute(&store.current_pool())
+            .await
+            .unwrap();
+        }
+        user
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn emails_are_stored_normalized_and_found_ignoring_case() {
+        let store = store().await;
+        let tenant = seed_tenant(&store).await.to_string();
+        let emails = EmailNormalizer::default();
+        let local = Uuid::new_v4().simple().to_string();
+
+        let (user, created) = store
+            .upsert_by_email(NewUser {
+                tenant_id: tenant.clone(),
+                email: emails
+                    .normalize(&format!(" {}@Example.COM ", local.to_uppercase()))
+                    .unwrap(),
+                password_hash: Some("hash".to_string()),
+                role: "user".to_string(),
+                email_verified: false,
+            })
+            .await
+            .unwrap();
+        assert!(created);
+        let stored: String = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+            .bind(Uuid::parse_str(&user.id).unwrap())
+            .fetch_one(&store.current_pool())
+            .await
+            .unwrap();
+        assert_eq!(stored, format!("{}@example.com", local));
+
+        // Logging in with another casing finds the same user
+        let login = emails
+            .normalize(&format!("{}@EXAMPLE.com", local.to_uppercase()))
+            .unwrap();
+        let found = store.find_by_email(&tenant, &login).await.unwrap();
+        assert_eq!(found, Some(user));
+        let other_tenant = seed_tenant(&store).await.to_string();
+        assert_eq!(
+            store.find_by_email(&other_tenant, &login).await.unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn count_binds_every_filter_value() {
+        assert_eq!(
+            count_query(&UserFilter::default()).sql(),
+            "SELECT count(*) FROM users WHERE TRUE AND deleted_at IS NULL"
+        );
+
+        let filter = UserFilter {
+            verified: Some(false),
+            must_reset_password: Some(true),
+            created_after: Some(Utc::now() - Duration::days(7)),
+            created_before: Some(Utc::now()),
+            include_deleted: true,
+        };
+        assert_eq!(
+            count_query(&filter).sql(),
+            "SELECT count(*) FROM users WHERE TRUE \
+             AND email_confirmed_at IS NULL \
+             AND must_reset_password = $1 \
+             AND created_at >= $2 \
+             AND created_at < $3"
+        );
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn counts_match_each_filter_combination() {
+        let store = store().await;
+        let tenant = seed_tenant(&store).await;
+        let day = |n: i64| {
+            DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+                .unwrap()
+                .to_utc()
+                + Duration::days(n)
+        };
+        // (created on day, verified, must reset password)
+        let seed = [
+            (0, true, false),
+            (1, true, true),
+            (2, false, false),
+            (3, false, true),
+            (4, true, false),
+        ];
+        for (n, verified, must_reset_password) in seed {
+            sqlx::query(
+                "INSERT INTO users (tenant_id, email, encrypted_password, role, \
+                     email_confirmed_at, must_reset_password, created_at) \
+                 VALUES ($1, $2, 'hash', 'user', $3, $4, $5)",
+            )
+            .bind(tenant)
+            .bind(format!("{}@example.com", Uuid::new_v4()))
+            .bind(verified.then(|| day(n)))
+            .bind(must_reset_password)
+            .bind(day(n))
+            .execute(&store.current_pool())
+            .await
+            .unwrap();
+        }
+
+        let cases = [
+            (UserFilter::default(), 5),
+            (
+                UserFilter {
+                    verified: Some(true),
+                    ..UserFilter::default()
+                },
+                3,
+            ),
+            (
+                UserFilter {
+                    verified: Some(false),
+                    ..UserFilter::default()
+                },
+                2,
+            ),
+            (
+                UserFilter {
+                    must_reset_password: Some(true),
+                    ..UserFilter::default()
+                },
+                2,
+            ),
+            (
+                UserFilter {
+                    created_after: Some(day(2)),
+                    ..UserFilter::default()
+                },
+                3,
+            ),
+            (
+                UserFilter {
+                    created_before: Some(day(2)),
+                    ..UserFilter::default()
+                },
+                2,
+            ),
+            (
+                UserFilter {
+                    verified: Some(true),
+                    must_reset_password: Some(false),
+                    created_after: Some(day(1)),
+                    ..UserFilter::default()
+                },
+                1,
+            ),
+        ];
+        for (filter, expected) in cases {
+            // Counting the tenant's users only, so other tests' users do not interfere
+            let mut query = count_query(&filter);
+            query.push(" AND tenant_id = ").push_bind(tenant);
+            let count: i64 = query
+                .build_query_scalar()
+                .fetch_one(&store.current_pool())
+                .await
+                .unwrap();
+            assert_eq!(count, expected, "{:?}", filter);
+        }
+    }
+
+    fn new_user(tenant: Uuid, email: &str, password_hash: Option<&str>) -> NewUser {
+        NewUser {
+            tenant_id: tenant.to_string(),
+            email: email.to_string(),
+            password_hash: password_hash.map(str::to_string),
+            role: "user".to_string(),
+            email_verified: true,
+        }
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn bulk_create_skips_taken_and_repeated_emails() {
+        let store = store().await;
+        let tenant = seed_tenant(&store).await;
+        let existing = format!("{}@example.com", Uuid::new_v4());
+        store
+            .upsert_by_email(new_user(tenant, &existing, Some("original-hash")))
+            .await
+            .unwrap();
+
+        let fresh: Vec<String> = (0..3)
+            .map(|_| format!("{}@example.com", Uuid::new_v4()))
+            .collect();
+        let batch = [
+            new_user(tenant, &fresh[0], Some("hash-0")),
+            new_user(tenant, &existing.to_uppercase(), Some("imported-hash")),
+            new_user(tenant, &fresh[1], None),
+            new_user(tenant, &fresh[0], Some("repeated-hash")),
+            new_user(tenant, &fresh[2], Some("hash-2")),
+        ];
+        let report = store.bulk_create(&batch).await.unwrap();
+        assert_eq!(
+            report,
+            BulkCreateReport {
+                inserted: 3,
+                skipped: 2
+            }
+        );
+
+        let hashes: Vec<(String, String)> = sqlx::query_as(
+            "SELECT email, encrypted_password FROM users WHERE tenant_id = $1 ORDER BY email",
+        )
+        .bind(tenant)
+        .fetch_all(&store.current_pool())
+        .await
+        .unwrap();
+        let hash_of = |email: &str| {
+            hashes
+                .iter()
+                .find(|(stored, _)| stored == email)
+                .map(|(_, hash)| hash.as_str())
+        };
+        assert_eq!(hashes.len(), 4);
+        assert_eq!(hash_of(&existing), Some("original-hash"));
+        assert_eq!(hash_of(&fresh[0]), Some("hash-0"));
+        assert_eq!(hash_of(&fresh[1]), Some(""));
+
+        let err = store
+            .bulk_create(&[NewUser {
+                tenant_id: "not-a-tenant".to_string(),
+                ..new_user(tenant, &fresh[2], None)
+            }])
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Invalid);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn concurrent_upserts_create_one_user() {
+        let store = store().await;
+        let tenant = seed_tenant(&store).await;
+        let email = format!("{}@example.com", Uuid::new_v4());
+
+        let (first, second) = tokio::join!(
+            store.upsert_by_email(new_user(tenant, &email, None)),
+            store.upsert_by_email(new_user(tenant, &email.to_uppercase(), None)),
+        );
+        let (first, first_created) = first.unwrap();
+        let (second, second_created) = second.unwrap();
+        assert_eq!(first.id, second.id);
+        assert!(first_created ^ second_created);
+
+        let users: i64 = sqlx::query_scalar(
+            "SELECT count(*) FROM users WHERE tenant_id = $1 AND lower(email) = lower($2)",
+        )
+        .bind(tenant)
+        .bind(&email)
+        .fetch_one(&store.current_pool())
+        .await
+        .unwrap();
+        assert_eq!(users, 1);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn upsert_keeps_the_password_of_an_existing_user() {
+        let store = store().await;
+        let tenant = seed_tenant(&store).await;
+        let email = format!("{}@example.com", Uuid::new_v4());
+
+        let (user, created) = store
+            .upsert_by_email(new_user(tenant, &email, Some("password-hash")))
+            .await
+            .unwrap();
+        assert!(created);
+        let (linked, created) = store
+            .upsert_by_email(new_user(tenant, &email, None))
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(linked, user);
+
+        let hash: String =
+            sqlx::query_scalar("SELECT encrypted_password FROM users WHERE id = $1::uuid")
+                .bind(&user.id)
+                .fetch_one(&store.current_pool())
+                .await
+                .unwrap();
+        assert_eq!(hash, "password-hash");
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn forced_reset_flags_the_user_and_revokes_their_sessions() {
+        let store = store().await;
+        let user = seed_user(&store, 2).await.to_string();
+
+        assert!(
+            !store
+                .profile(&user)
+                .await
+                .unwrap()
+                .unwrap()
+                .must_reset_password
+        );
+        assert_eq!(store.force_password_reset(&user).await.unwrap(), Some(2));
+        assert!(
+            store
+                .profile(&user)
+                .await
+                .unwrap()
+                .unwrap()
+                .must_reset_password
+        );
+
+        let live: i64 = sqlx::query_scalar(
+            "SELECT count(*) FROM refresh_tokens WHERE user_id = $1::uuid AND revoked IS NOT TRUE",
+        )
+        .bind(&user)
+        .fetch_one(&store.current_pool())
+        .await
+        .unwrap();
+        assert_eq!(live, 0);
+
+        // Nothing is left to revoke the second time
+        assert_eq!(store.force_password_reset(&user).await.unwrap(), Some(0));
+        assert_eq!(
+            store
+                .force_password_reset(&Uuid::new_v4().to_string())
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn deleted_users_are_hidden_until_purged() {
+        let store = store().await;
+        let user = seed_user(&store, 2).await.to_string();
+        let (profile, deleted_at) = store
+            .profile_including_deleted(&user)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deleted_at, None);
+        let tenant: Uuid = sqlx::query_scalar("SELECT tenant_id FROM users WHERE id = $1::uuid")
+            .bind(&user)
+            .fetch_one(&store.current_pool())
+            .await
+            .unwrap();
+        let tenant_users = |include_deleted| {
+            let mut query = count_query(&UserFilter {
+                include_deleted,
+                ..UserFilter::default()
+            });
+            query.push(" AND tenant_id = ").push_bind(tenant);
+            query
+        };
+
+        assert_eq!(store.delete(&user).await.unwrap(), Some(2));
+        assert_eq!(store.profile(&user).await.unwrap(), None);
+        assert_eq!(
+            store
+                .find_by_email(&tenant.to_string(), &profile.email)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(store.password_hash(&user).await.unwrap(), None);
+        assert_eq!(store.force_password_reset(&user).await.unwrap(), None);
+        assert_eq!(
+            store
+                .change_password(&user, "new-hash", None)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(store.delete(&user).await.unwrap(), None);
+        for (include_deleted, expected) in [(false, 0), (true, 1)] {
+            let count: i64 = tenant_users(include_deleted)
+                .build_query_scalar()
+                .fetch_one(&store.current_pool())
+                .await
+                .unwrap();
+            assert_eq!(count, expected, "include_deleted: {}", include_deleted);
+        }
+        let (deleted, deleted_at) = store
+            .profile_including_deleted(&user)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deleted, profile);
+        assert!(deleted_at.is_some());
+
+        // The email stays taken until the user is purged
+        let err = store
+            .upsert_by_email(new_user(tenant, &profile.email, None))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        let report = store
+            .bulk_create(&[new_user(tenant, &profile.email, None)])
+            .await
+            .unwrap();
+        assert_eq!(report.skipped, 1);
+
+        // Backdated so that purging leaves other tests' deleted users alone
+        let deleted_on = DateTime::parse_from_rfc3339("2000-01-01T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        sqlx::query("UPDATE users SET deleted_at = $2 WHERE id = $1::uuid")
+            .bind(&user)
+            .bind(deleted_on)
+            .execute(&store.current_pool())
+            .await
+            .unwrap();
+        assert_eq!(store.purge(deleted_on).await.unwrap(), 0);
+        assert_eq!(
+            store.purge(deleted_on + Duration::days(1)).await.unwrap(),
+            1
+        );
+        assert_eq!(store.profile_including_deleted(&user).await.unwrap(), None);
+        let sessions: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM refresh_tokens WHERE user_id = $1::uuid")
+                .bind(&user)
+                .fetch_one(&store.current_pool())
+                .await
+                .unwrap();
+        assert_eq!(sessions, 0);
+        let (_, created) = store
+            .upsert_by_email(new_user(tenant, &profile.email, None))
+            .await
+            .unwrap();
+        assert!(created);
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn updates_apply_only_at_the_expected_version() {
+        let store = store().await;
+        let tenant = seed_tenant(&store).await;
+        let (ada, _) = store
+            .upsert_by_email(new_user(
+                tenant,
+                &format!("{}@example.com", Uuid::new_v4()),
+                None,
+            ))
+            .await
+            .unwrap();
+        let (grace, _) = store
+            .upsert_by_email(new_user(
+                tenant,
+                &format!("{}@example.com", Uuid::new_v4()),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(ada.version, 1);
+
+        let email = format!("{}@example.com", Uuid::new_v4());
+        let changes = UserChanges {
+            email: Some(email.clone()),
+            email_verified: Some(false),
+            role: Some("admin".to_string()),
+        };
+        let updated = store
+            .update(&ada.id, ada.version, &changes)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.email, email);
+        assert!(!updated.email_verified);
+        assert_eq!(updated.version, 2);
+        assert_eq!(store.profile(&ada.id).await.unwrap(), Some(updated.clone()));
+        let role: String = sqlx::query_scalar("SELECT role FROM users WHERE id = $1::uuid")
+            .bind(&ada.id)
+            .fetch_one(&store.current_pool())
+            .await
+            .unwrap();
+        assert_eq!(role, "admin");
+
+        // A second admin still holding the first version must not overwrite the edit
+        let stale = UserChanges {
+            role: Some("user".to_string()),
+            ..UserChanges::default()
+        };
+        let err = store
+            .update(&ada.id, ada.version, &stale)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        assert_eq!(store.profile(&ada.id).await.unwrap(), Some(updated.clone()));
+
+        let taken = UserChanges {
+            email: Some(grace.email.to_uppercase()),
+            ..UserChanges::default()
+        };
+        let err = store
+            .update(&ada.id, updated.version, &taken)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+
+        assert_eq!(
+            store
+                .update(&Uuid::new_v4().to_string(), 1, &stale)
+                .await
+                .unwrap(),
+            None
+        );
+        store.delete(&ada.id).await.unwrap();
+        assert_eq!(
+            store
+                .update(&ada.id, updated.version, &stale)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    /// Requires a reachable Postgres configured through `RCAUTH_POSTGRES_*`.
+    #[tokio::test]
+    #[ignore = "requires a running Postgres; run with --ignored"]
+    async fn password_change_keeps_only_the_current_session() {
+        let store = store().await;
+        let user = seed_user(&store, 3).await.to_string();
+        store.force_password_reset(&user).await.unwrap();
+        // Reinstate the sessions the forced reset revoked
+        sqlx::query("UPDATE refresh_tokens SET revoked = false WHERE user_id = $1::uuid")
+            .bind(&user)
+            .execute(&store.current_pool())
+            .await
+            .unwrap();
+        let current: Uuid = sqlx::query_scalar(
+            "SELECT session_id FROM refresh_tokens WHERE user_id = $1::uuid LIMIT 1",
+        )
+        .bind(&user)
+        .fetch_one(&store.current_pool())
+        .await
+        .unwrap();
+        assert_eq!(
+            store.password_hash(&user).await.unwrap().as_deref(),
+            Some("hash")
+        );
+
+        let revoked = store
+            .change_password(&user, "new-hash", Some(&current.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(revoked, Some(2));
+        assert_eq!(
+            store.password_hash(&user).await.unwrap().as_deref(),
+            Some("new-hash")
+        );
+        assert!(
+            !store
+                .profile(&user)
+                .await
+                .unwrap()
+                .unwrap()
+                .must_reset_password
+        );
+        let live: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT session_id FROM refresh_tokens WHERE user_id = $1::uuid AND revoked IS NOT TRUE",
+        )
+        .bind(&user)
+        .fetch_all(&store.current_pool())
+        .await
+        .unwrap();
+        assert_eq!(live, [current]);
+
+        // Without a session to keep, every session goes
+        assert_eq!(
+            store
+                .change_password(&user, "newer-hash", None)
+                .await
+                .unwrap(),
+            Some(1)
+        );
+        let missing = Uuid::new_v4().to_string();
+        assert_eq!(store.password_hash(&missing).await.unwrap(), None);
+        assert_eq!(
+            store.change_password(&missing, "hash", None).await.unwrap(),
+            None
+        );
+    }
+}